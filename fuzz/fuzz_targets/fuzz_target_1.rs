@@ -7,18 +7,23 @@ fuzz_target!(|data: &[u8]| {
 });
 extern crate task_hero;
 
-use task_hero::gamification::{PriorityLevel, Task, Gamification};
+use task_hero::clock::Clock;
+use task_hero::dates::DueDate;
+use task_hero::gamification::{Gamification, PriorityLevel, Task};
 
 fn fuzz_target(data: &[u8]) {
     // Try to construct a Task with fuzzed data
     if let Ok(name) = std::str::from_utf8(data) {
         let task = Task {
-            name: name.to_string(),
             description: "Fuzz description".to_string(),
-            due_date: "2024-11-22".to_string(),
-            priority: PriorityLevel::High, // Fixed priority for testing
+            due_date: Some(DueDate::on(
+                chrono::NaiveDate::from_ymd_opt(2024, 11, 22).unwrap(),
+            )),
             completed: true,
-            completed_date: Some("2024-11-22".to_string()),
+            completed_date: Some(
+                Clock::fixed_on(chrono::NaiveDate::from_ymd_opt(2024, 11, 22).unwrap()).now(),
+            ),
+            ..Task::new(name, PriorityLevel::High) // Fixed priority for testing
         };
 
         // Calculate points and ensure no panics
@@ -3,30 +3,32 @@ use libfuzzer_sys::fuzz_target;
 
 extern crate task_hero;
 
-use task_hero::gamification::{PriorityLevel, Task, Gamification};
+use task_hero::clock::Clock;
+use task_hero::dates::parse_due_date;
+use task_hero::gamification::{Gamification, PriorityLevel, Task};
 
 // Generate random Task and Gamification instances and test methods
 fuzz_target!(|data: &[u8]| {
     // Convert `data` to a string if possible
     if let Ok(random_string) = std::str::from_utf8(data) {
         // Create a list of tasks using random strings
-        let tasks = vec![
-            Task {
-                name: random_string.to_string(),
-                description: random_string.to_string(),
-                due_date: random_string.to_string(),
-                priority: PriorityLevel::Low, // Randomize later for more thorough fuzzing
-                completed: false,
-                completed_date: None,
-            }
-        ];
+        let tasks = vec![Task {
+            description: random_string.to_string(),
+            due_date: parse_due_date(
+                random_string,
+                chrono::NaiveDate::from_ymd_opt(2024, 11, 23).unwrap(),
+            )
+            .ok(),
+            ..Task::new(random_string, PriorityLevel::Low) // Randomize the priority later for more thorough fuzzing
+        }];
 
         // Initialize a Gamification instance
         let mut gamification = Gamification::new();
 
-        // Call methods with fuzzed data
-        gamification.check_challenges(&tasks);
-        gamification.daily_reward(&tasks);
-        gamification.weekly_challenge(&tasks);
+        // Call methods with fuzzed data, on a fixed day so every run checks the same thing
+        let today = Clock::fixed_on(chrono::NaiveDate::from_ymd_opt(2024, 11, 23).unwrap()).today();
+        gamification.check_challenges(&tasks, today);
+        gamification.daily_reward(&tasks, today);
+        gamification.weekly_challenge(&tasks, today);
     }
 });
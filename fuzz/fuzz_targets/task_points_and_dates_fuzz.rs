@@ -1,23 +1,27 @@
 #![no_main]
-use libfuzzer_sys::fuzz_target;
-use task_hero::gamification::{Task, Gamification, PriorityLevel};
 use chrono::NaiveDate;
+use libfuzzer_sys::fuzz_target;
+use task_hero::clock::Clock;
+use task_hero::dates::{parse_due_date, DueDate};
+use task_hero::gamification::{Gamification, PriorityLevel, Task};
 
 fuzz_target!(|data: &[u8]| {
     // Try converting input to a UTF-8 string for date testing
     if let Ok(random_string) = std::str::from_utf8(data) {
+        // Run on a fixed day so every run checks the same thing
+        let clock = Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap());
         // Test points calculation with different priorities
+        let priority = match random_string.chars().next() {
+            Some('H') => PriorityLevel::High,
+            Some('M') => PriorityLevel::Medium,
+            _ => PriorityLevel::Low,
+        };
         let task = Task {
-            name: "Test Task".to_string(),
             description: "Description".to_string(),
-            due_date: "2024-11-23".to_string(),
-            priority: match random_string.chars().next() {
-                Some('H') => PriorityLevel::High,
-                Some('M') => PriorityLevel::Medium,
-                _ => PriorityLevel::Low,
-            },
+            due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap())),
             completed: true,
-            completed_date: Some(random_string.to_string()),
+            completed_date: Some(clock.now()),
+            ..Task::new("Test Task", priority)
         };
 
         // Call Task::points() and ensure no panics
@@ -27,20 +31,25 @@ fuzz_target!(|data: &[u8]| {
         let mut gamification = Gamification::new();
 
         // Fuzz daily_reward with potentially invalid dates
-        gamification.daily_reward(&[task.clone()]);
+        gamification.daily_reward(&[task.clone()], clock.today());
 
         // Fuzz weekly_challenge with extreme or invalid date ranges
         let malformed_task = Task {
-            name: "Malformed Task".to_string(),
             description: "Description".to_string(),
-            due_date: "invalid-date".to_string(), // Inject invalid date
-            priority: PriorityLevel::Low,
+            due_date: parse_due_date(random_string, clock.today()).ok(), // Inject fuzzed date
             completed: true,
-            completed_date: Some(random_string.to_string()),
+            completed_date: Some(clock.now()),
+            ..Task::new("Malformed Task", PriorityLevel::Low)
         };
-        gamification.weekly_challenge(&[malformed_task]);
+        gamification.weekly_challenge(&[malformed_task], clock.today());
 
         // Check how NaiveDate parsing handles edge cases
         let _ = NaiveDate::parse_from_str(random_string, "%Y-%m-%d").ok();
+
+        // The tolerant due date parser must never panic, and whatever it accepts must round-trip
+        let today = clock.today();
+        if let Ok(due_date) = parse_due_date(random_string, today) {
+            assert_eq!(parse_due_date(&due_date.to_string(), today), Ok(due_date));
+        }
     }
 });
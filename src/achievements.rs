@@ -0,0 +1,456 @@
+//! The achievement engine.
+//!
+//! Achievements are rules rather than code: each one has a [`Condition`] over the completed
+//! tasks, the points, the current [streak](crate::streaks), the priorities and tags of the tasks and a window of
+//! days, and a kind saying what reaching it means. A trophy is unlocked once and kept in the
+//! trophy case with the time it was unlocked. The bronze, silver and gold levels are unlocked
+//! the same way, and the highest level currently reached is the achievement message. A daily
+//! reward pays its points on every day its condition holds, the highest of the day winning.
+//!
+//! The standard rules are read from `achievements.json`. Other rules can be loaded from a JSON
+//! file in the same format with [`parse_rules`], replacing the standard ones.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Local, NaiveDate};
+
+use crate::gamification::{Gamification, PriorityLevel, Task};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+/// What reaching an achievement means
+pub enum AchievementKind {
+    #[default]
+    Trophy, // unlocked once and kept in the trophy case
+    Level, // unlocked like a trophy, and the highest level reached is the achievement message
+    Daily, // pays its points for every day the condition holds
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+/// The days a condition counts completions on
+pub enum Window {
+    #[default]
+    AllTime, // every completed task
+    Today,     // tasks completed today
+    Days(u32), // tasks completed in the last number of days, today included
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+/// One of the goals set in "Customize Your Goals"
+pub enum Goal {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// A condition an achievement is reached on
+pub enum Condition {
+    Completions {
+        count: usize,
+        #[serde(default)]
+        window: Window,
+        #[serde(default)]
+        min_priority: Option<PriorityLevel>, // only count tasks of this priority or higher
+        #[serde(default)]
+        tag: Option<String>, // only count tasks with this tag
+    },
+    Points {
+        at_least: u32,
+    },
+    Streak {
+        days: u32,
+    }, // a current streak of at least this many days
+    Goal {
+        goal: Goal,
+    }, // completed as many tasks as the goal
+    All {
+        conditions: Vec<Condition>,
+    },
+    Any {
+        conditions: Vec<Condition>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// An achievement and what it takes to reach it
+pub struct Achievement {
+    pub id: String, // stays the same when the name changes, so an unlocked trophy stays unlocked
+    pub name: String,
+    #[serde(default)]
+    pub description: String, // what to do to reach it
+    #[serde(default)]
+    pub message: String, // shown when it's reached, the name is used when empty
+    #[serde(default)]
+    pub kind: AchievementKind,
+    #[serde(default)]
+    pub points: u32, // the points a daily reward pays
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// An achievement in the trophy case
+pub struct Unlocked {
+    pub id: String,
+    pub name: String, // the name when it was unlocked, in case its rule is removed later
+    pub at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Errors returned when achievement rules can't be loaded
+pub enum RulesError {
+    Json(String),        // the file is not valid JSON or not a list of achievements
+    EmptyId,             // an achievement has no ID
+    DuplicateId(String), // two achievements have the same ID
+}
+
+impl std::fmt::Display for RulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RulesError::Json(message) => {
+                write!(f, "The achievement rules are not valid: {message}")
+            }
+            RulesError::EmptyId => write!(f, "Every achievement needs an ID."),
+            RulesError::DuplicateId(id) => {
+                write!(f, "There is more than one achievement with the ID {id:?}.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+/// What the conditions of the achievements are checked against
+pub struct Progress<'a> {
+    tasks: &'a [Task],
+    points: u32,
+    streak: u32,     // the current streak, in days
+    goals: [u32; 3], // bronze, silver and gold
+    today: NaiveDate,
+}
+
+/// Implementation of Progress with the checks of every condition
+impl<'a> Progress<'a> {
+    /// Helper function to get the progress of the tasks, with the points, streak and goals of the gamification system
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - A slice of Task structs representing the user's tasks
+    /// * `gamification` - The points, streak and goals
+    /// * `today` - The current date, from the app's clock
+    pub fn new(tasks: &'a [Task], gamification: &Gamification, today: NaiveDate) -> Self {
+        let goals = [
+            gamification.bronze_goal,
+            gamification.silver_goal,
+            gamification.gold_goal,
+        ];
+        Progress {
+            tasks,
+            points: gamification.points,
+            streak: gamification.streak.current,
+            goals,
+            today,
+        }
+    }
+
+    /// Helper function to check whether a condition holds
+    pub fn meets(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Completions {
+                count,
+                window,
+                min_priority,
+                tag,
+            } => {
+                let completed = self.tasks.iter().filter(|task| {
+                    task.completed
+                        && self.in_window(task, *window)
+                        && min_priority
+                            .map_or(true, |priority| task.priority as u8 >= priority as u8)
+                        && tag.as_ref().map_or(true, |tag| {
+                            task.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+                        })
+                });
+                completed.count() >= *count
+            }
+            Condition::Points { at_least } => self.points >= *at_least,
+            Condition::Streak { days } => self.streak >= *days,
+            Condition::Goal { goal } => {
+                let goal = self.goals[*goal as usize];
+                self.tasks.iter().filter(|task| task.completed).count() >= goal as usize
+            }
+            Condition::All { conditions } => {
+                conditions.iter().all(|condition| self.meets(condition))
+            }
+            Condition::Any { conditions } => {
+                conditions.iter().any(|condition| self.meets(condition))
+            }
+        }
+    }
+
+    /// Helper function to check whether a completed task was completed in a window
+    fn in_window(&self, task: &Task, window: Window) -> bool {
+        let days = match window {
+            Window::AllTime => return true,
+            Window::Today => 1,
+            Window::Days(days) => days as i64,
+        };
+        task.completed_date.is_some_and(|completed_date| {
+            (0..days).contains(&(self.today - completed_date.date_naive()).num_days())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+/// Achievements struct with the rules in use and the achievements unlocked so far
+pub struct Achievements {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    custom_rules: Option<Vec<Achievement>>, // rules loaded by the user, None for the standard rules
+    unlocked: Vec<Unlocked>, // oldest first
+}
+
+/// Implementation of Achievements with methods to evaluate the rules and unlock achievements
+impl Achievements {
+    /// Helper function to get the rules in use
+    pub fn rules(&self) -> &[Achievement] {
+        self.custom_rules
+            .as_deref()
+            .unwrap_or_else(|| standard_rules())
+    }
+
+    /// Helper function to get the rules loaded by the user, None when the standard rules are used
+    pub fn custom_rules(&self) -> Option<&[Achievement]> {
+        self.custom_rules.as_deref()
+    }
+
+    /// Helper function to replace the rules, or to go back to the standard rules with None.
+    /// Achievements already unlocked stay unlocked.
+    pub fn set_custom_rules(&mut self, rules: Option<Vec<Achievement>>) {
+        self.custom_rules = rules;
+    }
+
+    /// Helper function to get the unlocked achievements, oldest first
+    pub fn unlocked(&self) -> &[Unlocked] {
+        &self.unlocked
+    }
+
+    /// Helper function to check whether an achievement is unlocked
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.iter().any(|unlocked| unlocked.id == id)
+    }
+
+    /// Helper function to add an unlocked achievement, such as when rebuilding them from the journal
+    pub fn push(&mut self, unlocked: Unlocked) {
+        if !self.is_unlocked(&unlocked.id) {
+            self.unlocked.push(unlocked);
+        }
+    }
+
+    /// Helper function to put back the unlocked achievements, such as after undo restored an
+    /// earlier state. Achievements are never taken back, so they are carried over instead.
+    pub fn set_unlocked(&mut self, unlocked: Vec<Unlocked>) {
+        self.unlocked = unlocked;
+    }
+
+    /// Helper function to get the highest achievement of a kind that is reached, the one
+    /// listed last in the rules
+    pub fn highest(&self, kind: AchievementKind, progress: &Progress<'_>) -> Option<&Achievement> {
+        self.rules()
+            .iter()
+            .rev()
+            .find(|rule| rule.kind == kind && progress.meets(&rule.condition))
+    }
+
+    /// Helper function to unlock every trophy and level reached that isn't unlocked yet
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - What the conditions are checked against
+    /// * `now` - When the achievements are unlocked
+    ///
+    /// # Returns
+    ///
+    /// The number of achievements unlocked.
+    pub fn unlock(&mut self, progress: &Progress<'_>, now: DateTime<Local>) -> usize {
+        let reached: Vec<Unlocked> = self
+            .rules()
+            .iter()
+            .filter(|rule| {
+                rule.kind != AchievementKind::Daily
+                    && !self.is_unlocked(&rule.id)
+                    && progress.meets(&rule.condition)
+            })
+            .map(|rule| Unlocked {
+                id: rule.id.clone(),
+                name: rule.name.clone(),
+                at: now,
+            })
+            .collect();
+        let count = reached.len();
+        self.unlocked.extend(reached);
+        count
+    }
+}
+
+/// Implementation of Achievement with the message shown when it's reached
+impl Achievement {
+    /// Helper function to get the message shown when the achievement is reached
+    pub fn message(&self) -> &str {
+        if self.message.is_empty() {
+            &self.name
+        } else {
+            &self.message
+        }
+    }
+}
+
+/// Helper function to get the standard rules, read from `achievements.json`
+pub fn standard_rules() -> &'static [Achievement] {
+    static RULES: OnceLock<Vec<Achievement>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        parse_rules(include_str!("achievements.json"))
+            .expect("the standard achievement rules are valid")
+    })
+}
+
+/// Helper function to read achievement rules from JSON
+///
+/// # Arguments
+///
+/// * `json` - A JSON list of achievements, in the format of `achievements.json`
+///
+/// # Returns
+///
+/// The rules, or an error if the JSON is invalid or two achievements have the same ID.
+pub fn parse_rules(json: &str) -> Result<Vec<Achievement>, RulesError> {
+    let rules: Vec<Achievement> =
+        serde_json::from_str(json).map_err(|err| RulesError::Json(err.to_string()))?;
+    let mut ids = HashSet::new();
+    for rule in &rules {
+        if rule.id.trim().is_empty() {
+            return Err(RulesError::EmptyId);
+        }
+        if !ids.insert(rule.id.as_str()) {
+            return Err(RulesError::DuplicateId(rule.id.clone()));
+        }
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::streaks::Streak;
+    use crate::task_list::TaskId;
+    use chrono::Duration;
+
+    fn task(
+        id: u64,
+        priority: PriorityLevel,
+        completed_date: Option<DateTime<Local>>,
+        tags: &[&str],
+    ) -> Task {
+        Task {
+            id: TaskId(id),
+            completed: completed_date.is_some(),
+            completed_date,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Task::new(format!("Task {id}"), priority)
+        }
+    }
+
+    #[test]
+    fn test_conditions() {
+        let now = Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap()).now();
+        let days_ago = |days| Some(now - Duration::days(days));
+        let tasks = [
+            task(1, PriorityLevel::High, days_ago(0), &["backend"]),
+            task(2, PriorityLevel::Low, days_ago(1), &[]),
+            task(3, PriorityLevel::Medium, days_ago(2), &["Backend"]),
+            task(4, PriorityLevel::High, days_ago(10), &[]),
+            task(5, PriorityLevel::High, None, &["backend"]),
+        ];
+        let mut gamification = Gamification::new();
+        gamification.points = 60;
+        gamification.streak = Streak::track(&tasks, &[], &[], now.date_naive());
+        let progress = Progress::new(&tasks, &gamification, now.date_naive());
+        let completions = |count, window, min_priority, tag: Option<&str>| Condition::Completions {
+            count,
+            window,
+            min_priority,
+            tag: tag.map(str::to_string),
+        };
+
+        assert!(progress.meets(&completions(4, Window::AllTime, None, None)));
+        assert!(!progress.meets(&completions(5, Window::AllTime, None, None)));
+        assert!(progress.meets(&completions(1, Window::Today, None, None)));
+        assert!(!progress.meets(&completions(2, Window::Today, None, None)));
+        assert!(progress.meets(&completions(3, Window::Days(3), None, None)));
+        assert!(progress.meets(&completions(
+            2,
+            Window::AllTime,
+            Some(PriorityLevel::High),
+            None
+        )));
+        assert!(progress.meets(&completions(
+            3,
+            Window::Days(7),
+            Some(PriorityLevel::Low),
+            None
+        )));
+        assert!(progress.meets(&completions(2, Window::AllTime, None, Some("backend"))));
+        assert!(!progress.meets(&completions(3, Window::AllTime, None, Some("backend"))));
+        assert!(progress.meets(&Condition::Streak { days: 3 }));
+        assert!(!progress.meets(&Condition::Goal { goal: Goal::Bronze })); // 4 of the 5 tasks the goal asks for
+        let both = Condition::All {
+            conditions: vec![
+                Condition::Points { at_least: 50 },
+                Condition::Goal { goal: Goal::Silver },
+            ],
+        };
+        assert!(!progress.meets(&both));
+        assert!(progress.meets(&Condition::Any {
+            conditions: vec![both, Condition::Points { at_least: 60 }]
+        }));
+    }
+
+    #[test]
+    fn test_rules_and_unlocking() {
+        assert!(standard_rules()
+            .iter()
+            .any(|rule| rule.id == "gold" && rule.kind == AchievementKind::Level));
+        let json = r#"[{"id": "tagged", "name": "Tagged", "condition": {"type": "completions", "count": 1, "tag": "home"}}]"#;
+        let rules = parse_rules(json).unwrap();
+        assert_eq!(rules[0].kind, AchievementKind::Trophy);
+        assert_eq!(rules[0].message(), "Tagged");
+        assert!(matches!(parse_rules("{}"), Err(RulesError::Json(_))));
+        let twice = format!("[{0}, {0}]", &json[1..json.len() - 1]);
+        assert_eq!(
+            parse_rules(&twice),
+            Err(RulesError::DuplicateId("tagged".to_string()))
+        );
+
+        let now = Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap()).now();
+        let mut achievements = Achievements::default();
+        achievements.set_custom_rules(Some(rules));
+        let gamification = Gamification::new();
+        let tasks = [task(1, PriorityLevel::Low, Some(now), &["home"])];
+        let progress = Progress::new(&tasks, &gamification, now.date_naive());
+        assert_eq!(achievements.unlock(&progress, now), 1);
+        assert_eq!(achievements.unlock(&progress, now + Duration::days(1)), 0);
+        assert_eq!(
+            achievements.unlocked(),
+            [Unlocked {
+                id: "tagged".to_string(),
+                name: "Tagged".to_string(),
+                at: now
+            }]
+        );
+    }
+}
@@ -1,21 +1,183 @@
 // In gamification.rs
-use crate::gamification::{Gamification, PriorityLevel, Task};
-#[derive(serde::Deserialize, serde::Serialize)]
-#[serde(default)]
+use chrono::{NaiveDate, Weekday};
+
+use crate::achievements::{self, AchievementKind};
+use crate::clock::Clock;
+use crate::csv_transfer::{self, ColumnMapping, CsvFile, CsvPreview, TaskField};
+use crate::dates;
+use crate::gamification::{Gamification, PriorityLevel, Task, UNBLOCK_BONUS};
+use crate::history::History;
+use crate::ical::{self, IcsComponent};
+use crate::journal::{Event, Journal};
+use crate::levels;
+use crate::query::{SortKey, Status, TaskQuery};
+use crate::query_language::QUERY_HINT;
+use crate::recurrence::Recurrence;
+use crate::scoring::{Difficulty, Formula, ScoringModel};
+use crate::storage::{self, Backup};
+use crate::store::{NewTask, StoreError, TaskStore, TaskUpdate};
+use crate::streaks::{DayStatus, FREEZE_COST, MAX_FREEZES};
+use crate::tags;
+use crate::task_list::TaskId;
+use crate::todotxt;
+use crate::transfer::{self, ImportMode};
+
+/// Keyboard shortcut that undoes the last command
+const UNDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+
+/// Keyboard shortcut that redoes the last undone command
+const REDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::Z,
+);
 
 /// This struct represents the main application state.
-/// It contains the list of tasks, the selected task index, and the fields for creating a new task.
+/// It contains the task store, the selected task ID, and the fields for creating a new task.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct TemplateApp {
-    tasks: Vec<Task>,                 // List of tasks
-    selected_task: Option<usize>,     // Index of selected task
-    new_task_name: String,            // Fields for creating a new task
-    new_task_description: String,     // Description of the new task
-    new_task_due_date: String,        // Due date of the new task
+    store: TaskStore, // Tasks and gamification system
+    history: History, // Commands that can be undone and redone, saved only when kept
+    journal: Journal, // Every change ever made, from which the tasks and goals are rebuilt
+    #[serde(skip)]
+    clock: Clock, // Where the app gets the time from, another date while simulating one
+    #[serde(skip)]
+    simulate_date: String, // Date to simulate, as typed by the user
+    #[serde(skip)]
+    simulate_date_error: Option<String>, // Validation error for the date to simulate
+    selected_task: Option<TaskId>, // ID of selected task
+    new_task_name: String, // Fields for creating a new task
+    new_task_description: String, // Description of the new task
+    new_task_due_date: String, // Due date of the new task, as typed by the user
+    #[serde(skip)]
+    new_task_due_date_error: Option<String>, // Validation error for the new task's due date
+    new_task_recurrence: String, // How often the new task repeats, as typed by the user
+    #[serde(skip)]
+    new_task_recurrence_error: Option<String>, // Validation error for the new task's repeat rule
     new_task_priority: PriorityLevel, // Priority level of the new task
-    new_task_completed: bool,         // Flag for new task completion
-    is_editing: bool,                 // Flag for editing task mode
-    gamification: Gamification,       // Gamification system
-    details_report_viewable: bool,    // Flag for viewing the details report
+    new_task_completed: bool, // Flag for new task completion
+    new_task_project: String, // Project of the new task, empty for none
+    new_task_tags: String, // Tags of the new task, separated by commas
+    #[serde(skip)]
+    new_task_parent: Option<TaskId>, // Task the new task is added to as a subtask, None for a top-level task
+    #[serde(skip)]
+    new_checklist_item: String, // Text of the checklist item being added to the selected task
+    query: TaskQuery,         // Search, filters and sort order of the task list
+    query_due_after: String,  // "Due after" filter, as typed by the user
+    query_due_before: String, // "Due before" filter, as typed by the user
+    #[serde(skip)]
+    query_error: Option<String>, // Validation error for the due date filters
+    #[serde(skip)]
+    filter_name: String, // Name to save the current query under
+    group_by_project: bool,   // Flag for listing the tasks under their project
+    #[serde(skip)]
+    completion_warning: Option<String>, // Shown after completing a task whose prerequisites were still open
+    is_editing: bool, // Flag for editing task mode
+    #[serde(skip)]
+    edit_name: String, // Name of the task being edited
+    #[serde(skip)]
+    edit_description: String, // Description of the task being edited
+    #[serde(skip)]
+    edit_due_date: String, // Due date of the task being edited, as typed by the user
+    #[serde(skip)]
+    edit_recurrence: String, // Repeat rule of the task being edited, as typed by the user
+    #[serde(skip)]
+    edit_priority: PriorityLevel, // Priority level of the task being edited
+    #[serde(skip)]
+    edit_project: String, // Project of the task being edited, empty for none
+    #[serde(skip)]
+    edit_tags: String, // Tags of the task being edited, separated by commas
+    #[serde(skip)]
+    edit_effort: String, // Estimated effort in hours of the task being edited, empty for none
+    #[serde(skip)]
+    edit_difficulty: Difficulty, // Difficulty of the task being edited
+    #[serde(skip)]
+    edit_points: String, // Points set by hand for the task being edited, empty for the scoring formula
+    #[serde(skip)]
+    edit_error: Option<String>, // Validation error for the edited task
+    #[serde(skip)]
+    goal_project: String, // Project whose goal is being set
+    #[serde(skip)]
+    goal_target: u32, // Number of tasks to complete in that project
+    #[serde(skip)]
+    adjust_amount: i64, // Points to add by hand, negative to take them away
+    #[serde(skip)]
+    adjust_reason: String, // Why the points are changed by hand
+    #[serde(skip)]
+    adjust_error: Option<String>, // Validation error for the manual adjustment
+    #[serde(skip)]
+    streak_error: Option<String>, // Shown when a streak freeze can't be bought
+    #[serde(skip)]
+    scoring_draft: Option<ScoringModel>, // Scoring settings being changed, applied with a button
+    #[serde(skip)]
+    scoring_tag: String, // Tag whose multiplier is being set
+    #[serde(skip)]
+    scoring_multiplier: f64, // Multiplier for the points of tasks with that tag
+    #[serde(skip)]
+    level_up: Option<u32>, // Level just reached, celebrated until dismissed
+    details_report_viewable: bool, // Flag for viewing the details report
+    report_listed_only: bool,      // Flag for reporting on the listed tasks instead of every task
+    #[serde(skip)]
+    load_warning: Option<String>, // Shown when the saved state could not be loaded
+    #[serde(skip)]
+    pending_backup: Option<Backup>, // Unreadable saved state, written back on the next save
+    #[serde(skip)]
+    transfer_window: Option<TransferWindow>, // Open File > Export/Import window
+}
+
+#[derive(Clone, Copy, PartialEq)]
+/// What the File > Export/Import window does
+enum TransferKind {
+    ExportJson,       // Export every task and the goals as JSON
+    ImportJson,       // Import a JSON export
+    ExportCsv,        // Export every task as CSV
+    ExportReportCsv,  // Export the Tasks Report metrics as CSV
+    ImportCsv,        // Import tasks from a spreadsheet
+    ExportIcs,        // Export tasks to a calendar
+    ImportIcs,        // Import the to-dos of a calendar
+    ExportTodoTxt,    // Export every task as todo.txt
+    ImportTodoTxt,    // Import the tasks of a todo.txt file
+    LoadAchievements, // Load the achievement rules from a JSON file
+    LoadLevels,       // Load the level curve and XP sources from a JSON file
+}
+
+/// State of the File > Export/Import window
+struct TransferWindow {
+    kind: TransferKind,                              // What the window does
+    path: String,                                    // Path of the file
+    mode: ImportMode, // How imported JSON tasks are combined with the existing ones
+    csv_file: Option<CsvFile>, // CSV file loaded for import
+    csv_mapping: ColumnMapping, // CSV column each task field is read from
+    csv_preview: Option<Result<CsvPreview, String>>, // Tasks and failing rows for the current mapping
+    ics_component: IcsComponent, // Whether tasks are exported as to-dos or events
+    message: Option<String>,     // Result of the last export or import
+}
+
+/// Implementation of TransferWindow with a method to open it
+impl TransferWindow {
+    /// Helper function to open the window for an export or import
+    fn new(kind: TransferKind) -> Self {
+        let path = match kind {
+            TransferKind::ExportJson | TransferKind::ImportJson => "taskhero-export.json",
+            TransferKind::ExportCsv | TransferKind::ImportCsv => "taskhero-tasks.csv",
+            TransferKind::ExportReportCsv => "taskhero-report.csv",
+            TransferKind::ExportIcs | TransferKind::ImportIcs => "taskhero-tasks.ics",
+            TransferKind::ExportTodoTxt | TransferKind::ImportTodoTxt => "todo.txt",
+            TransferKind::LoadAchievements => "achievements.json",
+            TransferKind::LoadLevels => "levels.json",
+        };
+        TransferWindow {
+            kind,
+            path: String::from(path),
+            mode: ImportMode::Merge,
+            csv_file: None,
+            csv_mapping: ColumnMapping::default(),
+            csv_preview: None,
+            ics_component: IcsComponent::Todo,
+            message: None,
+        }
+    }
 }
 
 /// Implement the Default trait for TemplateApp to provide a default state.
@@ -28,16 +190,59 @@ impl Default for TemplateApp {
     /// 
     fn default() -> Self {
         Self {
-            tasks: Vec::new(),                     // Initialize tasks list
-            selected_task: None,                   // Initialize selected task index
+            store: TaskStore::new(),               // Initialize tasks and gamification
+            history: History::default(),           // Initialize undo history
+            journal: Journal::default(),           // Initialize event journal
+            clock: Clock::System,                  // Initialize clock
+            simulate_date: String::new(),          // Initialize simulated date
+            simulate_date_error: None,             // Initialize simulated date
+            selected_task: None,                   // Initialize selected task ID
             new_task_name: String::new(),          // Initialize new task fields
             new_task_description: String::new(),   // Initialize new task fields
             new_task_due_date: String::new(),      // Initialize new task fields
+            new_task_due_date_error: None,         // Initialize new task fields
+            new_task_recurrence: String::new(),    // Initialize new task fields
+            new_task_recurrence_error: None,       // Initialize new task fields
             new_task_priority: PriorityLevel::Low, // Initialize new task fields
             new_task_completed: false,             // Initialize new task fields
+            new_task_project: String::new(),       // Initialize new task fields
+            new_task_tags: String::new(),          // Initialize new task fields
+            new_task_parent: None,                 // Initialize new task fields
+            new_checklist_item: String::new(),     // Initialize checklist field
+            query: TaskQuery::default(),           // Initialize task list query
+            query_due_after: String::new(),        // Initialize task list query
+            query_due_before: String::new(),       // Initialize task list query
+            query_error: None,                     // Initialize task list query
+            filter_name: String::new(),            // Initialize task list query
+            group_by_project: false,               // Initialize task list grouping
+            completion_warning: None,              // Initialize completion warning
             is_editing: false,                     // Initialize editing mode
-            gamification: Gamification::new(),     // Initialize gamification
+            edit_name: String::new(),              // Initialize editing fields
+            edit_description: String::new(),       // Initialize editing fields
+            edit_due_date: String::new(),          // Initialize editing fields
+            edit_recurrence: String::new(),        // Initialize editing fields
+            edit_priority: PriorityLevel::Low,     // Initialize editing fields
+            edit_project: String::new(),           // Initialize editing fields
+            edit_tags: String::new(),              // Initialize editing fields
+            edit_effort: String::new(),            // Initialize editing fields
+            edit_difficulty: Difficulty::Normal,   // Initialize editing fields
+            edit_points: String::new(),            // Initialize editing fields
+            edit_error: None,                      // Initialize editing fields
+            goal_project: String::new(),           // Initialize project goal fields
+            goal_target: 5,                        // Initialize project goal fields
+            adjust_amount: 0,                      // Initialize points adjustment fields
+            adjust_reason: String::new(),          // Initialize points adjustment fields
+            adjust_error: None,                    // Initialize points adjustment fields
+            streak_error: None,                    // Initialize streak freeze error
+            scoring_draft: None,                   // Initialize scoring settings being changed
+            scoring_tag: String::new(),            // Initialize tag multiplier fields
+            scoring_multiplier: 2.0,               // Initialize tag multiplier fields
+            level_up: None,                        // Initialize level-up celebration
             details_report_viewable: false,        // Initialize details report viewable flag
+            report_listed_only: false,             // Initialize report scope
+            load_warning: None,                    // Initialize load warning
+            pending_backup: None,                  // Initialize pending backup
+            transfer_window: None,                 // Initialize export/import window
         }
     }
 }
@@ -47,8 +252,10 @@ impl Default for TemplateApp {
 impl TemplateApp {
 
     /// This function creates a new instance of the TemplateApp struct.
-    /// It initializes the app state and loads the state from storage if available.
-    /// 
+    /// It initializes the app state and loads the state from storage if available,
+    /// upgrading states saved by older versions. If the saved state can't be read,
+    /// the app starts empty and keeps a backup of the unreadable data.
+    ///
     /// # Arguments
     /// 
     /// * `cc` - The eframe::CreationContext containing the storage for the app state.
@@ -59,194 +266,1775 @@ impl TemplateApp {
     /// 
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let mut app = Self::default();
-        // if let Some(storage) = cc.storage {
-        //     return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        // }
-        // Default::default()
         if let Some(storage) = cc.storage {
-            app = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            let loaded = storage::load::<Self>(storage);
+            app = loaded.state;
+            app.load_warning = loaded.warning;
+            app.pending_backup = loaded.backup;
         };
 
-        // Trigger achievements check when app is first loaded
-        app.update_achievements();
+        // Rebuild the tasks from the journal, which also checks the achievements
+        app.rebuild_from_journal();
 
         app
     }
 
-    /// This function adds a new task to the task list.
-    /// It checks if the task name, description, and due date are not empty before adding the task.
-    /// 
+    /// This function returns the task store holding the tasks and gamification state.
+    pub fn store(&self) -> &TaskStore {
+        &self.store
+    }
+
+    /// This function returns the task store for changing tasks outside the GUI, such as from the CLI.
+    /// Changes made through it can't be undone, use `apply` for those.
+    pub fn store_mut(&mut self) -> &mut TaskStore {
+        &mut self.store
+    }
+
+    /// This function returns the undo history.
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// This function returns the undo history, to choose whether it is kept across restarts.
+    pub fn history_mut(&mut self) -> &mut History {
+        &mut self.history
+    }
+
+    /// This function returns the event journal with every change made so far.
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// This function returns the clock the app gets the time from.
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// This function chooses the clock the app gets the time from, such as a fixed clock in tests
+    /// or an offset clock to simulate another date, and checks the achievements for its date.
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The clock to use.
+    ///
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+        self.store.set_clock(clock);
+        self.update_achievements();
+    }
+
+    /// This function rebuilds the task store from the event journal.
+    /// Changes the journal doesn't know about yet, such as everything saved by a version
+    /// without a journal, are recorded first, so nothing saved is lost.
+    ///
+    /// # Notes
+    ///
+    /// This function modifies the app state by replacing the task store and checking the achievements.
+    ///
+    pub fn rebuild_from_journal(&mut self) {
+        let replayed = self.journal.replay();
+        self.journal
+            .record(&replayed, &self.store, self.clock.now());
+        self.store = self.journal.replay();
+        self.store.set_clock(self.clock);
+        let previous = self.store.gamification().clone();
+        let recorded = self.store.ledger().len();
+        self.store.evaluate();
+        self.record_points(recorded);
+        self.record_rewards(&previous);
+        if self.store.gamification().best_level > previous.best_level {
+            self.level_up = Some(self.store.gamification().best_level);
+        }
+    }
+
+    /// This function changes the task store as one command that can be undone.
+    /// A change that fails is rolled back completely and not recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - What the command does, shown next to Undo and Redo.
+    /// * `change` - The change to make to the store.
+    ///
+    /// # Returns
+    ///
+    /// The result of the change.
+    ///
+    pub fn apply<T, E>(
+        &mut self,
+        description: impl Into<String>,
+        change: impl FnOnce(&mut TaskStore) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let before = self.store.clone();
+        let result = change(&mut self.store);
+        match result {
+            Ok(_) => self.record(description, before),
+            Err(_) => self.store = before,
+        }
+        result
+    }
+
+    /// This function records a change that was already made to the task store, so it can be undone.
+    /// The change is also written to the journal.
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - What the command did, shown next to Undo and Redo.
+    /// * `before` - The task store as it was before the change.
+    ///
+    pub fn record(&mut self, description: impl Into<String>, before: TaskStore) {
+        self.journal.record(&before, &self.store, self.clock.now());
+        self.history.record(description, &before, &self.store);
+    }
+
+    /// This function undoes the last command and updates the achievements.
+    ///
+    /// # Returns
+    ///
+    /// The description of the undone command, or None if there was nothing to undo.
+    ///
+    pub fn undo(&mut self) -> Option<String> {
+        let before = self.store.clone();
+        let description = self.history.undo(&mut self.store)?;
+        // points and achievements are never taken back, a reopened task records a penalty instead
+        self.store.keep_earned(&before);
+        self.journal.record(&before, &self.store, self.clock.now());
+        self.update_achievements();
+        Some(description)
+    }
+
+    /// This function redoes the last undone command and updates the achievements.
+    ///
+    /// # Returns
+    ///
+    /// The description of the redone command, or None if there was nothing to redo.
+    ///
+    pub fn redo(&mut self) -> Option<String> {
+        let before = self.store.clone();
+        let description = self.history.redo(&mut self.store)?;
+        // points and achievements are never taken back, a reopened task records a penalty instead
+        self.store.keep_earned(&before);
+        self.journal.record(&before, &self.store, self.clock.now());
+        self.update_achievements();
+        Some(description)
+    }
+
+    /// This function adds a new task to the task list, as a subtask when one is being added.
+    /// It checks if the task name, description, and due date are not empty before adding the task,
+    /// and that the due date can be understood.
+    ///
     /// # Notes
     /// 
     /// This function modifies the app state by adding a new task to the task list.
-    /// It also clears the input fields after adding the task.
-    /// 
+    /// It also clears the input fields after adding the task, or records a validation
+    /// error for the due date field if it could not be parsed.
+    ///
     fn add_task(&mut self) {
         if !self.new_task_name.is_empty()
             && !self.new_task_description.is_empty()
             && !self.new_task_due_date.is_empty()
         {
-            self.tasks.push(Task {
+            let due_date = match dates::parse_due_date(&self.new_task_due_date, self.clock.today())
+            {
+                Ok(due_date) => due_date,
+                Err(err) => {
+                    self.new_task_due_date_error = Some(err.to_string());
+                    return;
+                }
+            };
+            self.new_task_due_date_error = None;
+            // An empty repeat rule means the task doesn't repeat
+            let recurrence = match Recurrence::parse_optional(&self.new_task_recurrence) {
+                Ok(recurrence) => recurrence,
+                Err(err) => {
+                    self.new_task_recurrence_error = Some(err);
+                    return;
+                }
+            };
+            self.new_task_recurrence_error = None;
+
+            let new_task = NewTask {
                 name: self.new_task_name.clone(),
                 description: self.new_task_description.clone(),
-                due_date: self.new_task_due_date.clone(),
+                due_date: Some(due_date),
                 priority: self.new_task_priority,
-                completed: false,
-                completed_date: None,
+                recurrence,
+            };
+            let parent = self.new_task_parent;
+            let update = TaskUpdate {
+                tags: Some(tags::parse_tags(&self.new_task_tags)),
+                project: Some(Some(self.new_task_project.clone())),
+                ..Default::default()
+            };
+            let organized = self.apply(format!("Add task \"{}\"", self.new_task_name), |store| {
+                let id = match parent {
+                    Some(parent) => store.create_subtask(parent, new_task),
+                    None => store.create(new_task),
+                }?;
+                store.update(id, update)
             });
+            if let Err(err) = organized {
+                self.new_task_due_date_error = Some(err.to_string());
+                return;
+            }
 
             // Clear inputs after adding task
             self.new_task_name.clear();
             self.new_task_description.clear();
             self.new_task_due_date.clear();
+            self.new_task_recurrence.clear();
             self.new_task_priority = PriorityLevel::Low;
             self.new_task_completed = false;
+            self.new_task_project.clear();
+            self.new_task_tags.clear();
+            self.new_task_parent = None;
         }
     }
 
     /// This function updates the achievements based on the task list.
-    /// It uses the gamification system to check challenges and daily rewards.
-    /// 
+    /// It uses the gamification system to check challenges and daily rewards,
+    /// and records newly reached levels and rewards in the journal.
+    ///
     /// # Notes
     /// 
     /// This function modifies the app state by updating the achievement messages and points.
-    /// 
-    fn update_achievements(&mut self) {
-        self.gamification.check_challenges(&self.tasks); // Use gamification system to check challenges
-        // check the daily goal
-        self.gamification.daily_reward(&self.tasks);
+    ///
+    pub fn update_achievements(&mut self) {
+        let previous = self.store.gamification().clone();
+        let recorded = self.store.ledger().len();
+        self.store.evaluate(); // Use gamification system to check challenges and the daily goal
+        self.record_points(recorded);
+        self.record_rewards(&previous);
     }
 
-    /// This function handles the logic for the left panel of the UI.
-    /// It contains the task creation form, task list, and task details.
-    /// 
+    /// This function writes the trophies and levels unlocked since the achievements were last
+    /// checked to the journal. The points earned are recorded by the ledger.
+    ///
     /// # Arguments
-    /// 
+    ///
+    /// * `previous` - The gamification state as it was when the achievements were last checked.
+    ///
+    pub fn record_rewards(&mut self, previous: &Gamification) {
+        let recorded = previous.achievements.unlocked().len();
+        for unlocked in self
+            .store
+            .gamification()
+            .achievements
+            .unlocked()
+            .iter()
+            .skip(recorded)
+        {
+            self.journal.push(
+                Event::AchievementUnlocked {
+                    unlocked: unlocked.clone(),
+                },
+                unlocked.at,
+            );
+        }
+        let level = self.store.gamification().best_level;
+        if level > previous.best_level {
+            self.journal
+                .push(Event::LevelReached { level }, self.clock.now());
+        }
+    }
+
+    /// This function writes the transactions added to the points ledger to the journal.
+    ///
+    /// # Arguments
+    ///
+    /// * `recorded` - The number of transactions the ledger had before, which are already in the journal.
+    ///
+    pub fn record_points(&mut self, recorded: usize) {
+        for transaction in self.store.ledger().transactions().iter().skip(recorded) {
+            self.journal.push(
+                Event::PointsRecorded {
+                    transaction: transaction.clone(),
+                },
+                transaction.at,
+            );
+        }
+    }
+
+    /// This function changes the points by hand. Adjustments are kept in the ledger and can't be
+    /// undone; another adjustment corrects one.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The points to add, or to take away when negative.
+    /// * `reason` - Why the points were changed.
+    ///
+    /// # Returns
+    ///
+    /// An error if the amount is 0 or the reason is empty.
+    ///
+    pub fn adjust_points(&mut self, amount: i64, reason: &str) -> Result<(), StoreError> {
+        let recorded = self.store.ledger().len();
+        self.store.adjust_points(amount, reason)?;
+        self.record_points(recorded);
+        Ok(())
+    }
+
+    /// This function spends points on a streak freeze. Like the points, it can't be undone.
+    ///
+    /// # Returns
+    ///
+    /// An error if there aren't enough points or the most freezes are already held.
+    ///
+    pub fn buy_streak_freeze(&mut self) -> Result<(), StoreError> {
+        let recorded = self.store.ledger().len();
+        self.store.buy_streak_freeze()?;
+        self.record_points(recorded);
+        Ok(())
+    }
+
+    /// This function saves the changes made in the task editing form.
+    /// It stays in editing mode and shows an error if the changes are not valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task being edited.
+    ///
+    fn save_edits(&mut self, id: TaskId) {
+        // An empty due date removes it, anything else has to parse
+        let due_date = if self.edit_due_date.trim().is_empty() {
+            Ok(None)
+        } else {
+            dates::parse_due_date(&self.edit_due_date, self.clock.today()).map(Some)
+        };
+        let due_date = match due_date {
+            Ok(due_date) => due_date,
+            Err(err) => {
+                self.edit_error = Some(err.to_string());
+                return;
+            }
+        };
+        let recurrence = match Recurrence::parse_optional(&self.edit_recurrence) {
+            Ok(recurrence) => recurrence,
+            Err(err) => {
+                self.edit_error = Some(err);
+                return;
+            }
+        };
+        // Empty effort and points leave them to the scoring formula
+        let parse_number = |text: &str, what: &str| match text.trim() {
+            "" => Ok(None),
+            text => text
+                .parse::<u32>()
+                .map(Some)
+                .map_err(|_| format!("The {what} has to be a whole number, not {text:?}.")),
+        };
+        let (effort, points_override) = match (
+            parse_number(&self.edit_effort, "effort"),
+            parse_number(&self.edit_points, "points"),
+        ) {
+            (Ok(effort), Ok(points)) => (effort, points),
+            (Err(err), _) | (_, Err(err)) => {
+                self.edit_error = Some(err);
+                return;
+            }
+        };
+
+        let update = TaskUpdate {
+            name: Some(self.edit_name.clone()),
+            description: Some(self.edit_description.clone()),
+            due_date: Some(due_date),
+            priority: Some(self.edit_priority),
+            recurrence: Some(recurrence),
+            tags: Some(tags::parse_tags(&self.edit_tags)),
+            project: Some(Some(self.edit_project.clone())),
+            effort: Some(effort),
+            difficulty: Some(self.edit_difficulty),
+            points_override: Some(points_override),
+            ..Default::default()
+        };
+        match self.apply(format!("Edit task \"{}\"", self.edit_name), |store| {
+            store.update(id, update)
+        }) {
+            Ok(()) => {
+                self.edit_error = None;
+                self.is_editing = false; // Exit editing mode
+            }
+            Err(err) => self.edit_error = Some(err.to_string()),
+        }
+    }
+
+    /// This function writes an export to a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write.
+    /// * `contents` - The exported data.
+    ///
+    /// # Returns
+    ///
+    /// A message describing the result, shown in the export window.
+    ///
+    fn write_export(&self, path: &str, contents: String) -> String {
+        match std::fs::write(path, contents) {
+            Ok(()) => format!("Exported {} task(s) to {path}.", self.store.tasks().len()),
+            Err(err) => format!("Failed to write {path}: {err}"),
+        }
+    }
+
+    /// This function imports tasks from a JSON file written by File > Export Tasks.
+    /// Nothing is changed if any record in the file is invalid.
+    ///
+    /// # Returns
+    ///
+    /// A message describing the result, or listing the invalid records, shown in the import window.
+    ///
+    fn import_tasks(&mut self, path: &str, mode: ImportMode) -> String {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(err) => return format!("Failed to read {path}: {err}"),
+        };
+        match self.apply(format!("Import {path}"), |store| {
+            transfer::import(store, &json, mode)
+        }) {
+            Ok(summary) => {
+                // the selected task may have been replaced
+                if self
+                    .selected_task
+                    .is_some_and(|id| !self.store.tasks().contains(id))
+                {
+                    self.selected_task = None;
+                    self.is_editing = false;
+                }
+                summary.to_string()
+            }
+            Err(err) => err.to_string(),
+        }
+    }
+
+    /// This function imports the to-dos of an iCalendar file, skipping tasks that already exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the .ics file.
+    ///
+    /// # Returns
+    ///
+    /// A message describing the result of the import.
+    ///
+    fn import_ics(&mut self, path: &str) -> String {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => return format!("Failed to read {path}: {err}"),
+        };
+        match ical::parse_todos(&text) {
+            Ok(todos) => {
+                let skipped = todos.skipped.clone();
+                let before = self.store.clone();
+                let summary = ical::import(&mut self.store, todos);
+                if summary.added > 0 {
+                    self.record(format!("Import {path}"), before);
+                }
+                let mut message = summary.to_string();
+                for reason in skipped {
+                    message.push_str(&format!("\nSkipped {reason}"));
+                }
+                message
+            }
+            Err(err) => format!("Failed to read {path}: {err}"),
+        }
+    }
+
+    /// This function imports the tasks of a todo.txt file, skipping tasks that already exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the todo.txt file.
+    ///
+    /// # Returns
+    ///
+    /// A message describing the result of the import.
+    ///
+    fn import_todo_txt(&mut self, path: &str) -> String {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => return format!("Failed to read {path}: {err}"),
+        };
+        let file = todotxt::parse(&text);
+        let skipped = file.skipped.clone();
+        let before = self.store.clone();
+        let summary = todotxt::import(&mut self.store, file);
+        if summary.added > 0 {
+            self.record(format!("Import {path}"), before);
+        }
+        let mut message = summary.to_string();
+        for reason in skipped {
+            message.push_str(&format!("\nSkipped {reason}"));
+        }
+        message
+    }
+
+    /// This function replaces the achievement rules with those of a JSON file, or goes back to the
+    /// standard rules. Achievements already unlocked stay in the trophy case.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the JSON file, or None for the standard rules.
+    ///
+    /// # Returns
+    ///
+    /// A message describing the result.
+    ///
+    fn load_achievement_rules(&mut self, path: Option<&str>) -> String {
+        let Some(path) = path else {
+            if self
+                .store
+                .gamification()
+                .achievements
+                .custom_rules()
+                .is_some()
+            {
+                let before = self.store.clone();
+                self.store.set_achievement_rules(None);
+                self.record("Use the standard achievement rules", before);
+            }
+            return "Using the standard achievement rules.".to_string();
+        };
+        let rules = match std::fs::read_to_string(path) {
+            Ok(json) => achievements::parse_rules(&json),
+            Err(err) => return format!("Failed to read {path}: {err}"),
+        };
+        match rules {
+            Ok(rules) => {
+                let message = format!("Loaded {} achievement rule(s).", rules.len());
+                let before = self.store.clone();
+                self.store.set_achievement_rules(Some(rules));
+                self.record(format!("Load the achievement rules of {path}"), before);
+                message
+            }
+            Err(err) => err.to_string(),
+        }
+    }
+
+    /// This function replaces the level curve and XP sources with those of a JSON file, or goes
+    /// back to the standard ones. Levels already reached aren't celebrated again.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the JSON file, or None for the standard level curve.
+    ///
+    /// # Returns
+    ///
+    /// A message describing the result.
+    ///
+    fn load_level_config(&mut self, path: Option<&str>) -> String {
+        let Some(path) = path else {
+            if self.store.gamification().level_config.is_some() {
+                let before = self.store.clone();
+                self.store.set_level_config(None);
+                self.record("Use the standard level curve", before);
+            }
+            return "Using the standard level curve.".to_string();
+        };
+        let config = match std::fs::read_to_string(path) {
+            Ok(json) => levels::parse_config(&json),
+            Err(err) => return format!("Failed to read {path}: {err}"),
+        };
+        match config {
+            Ok(config) => {
+                let before = self.store.clone();
+                self.store.set_level_config(Some(config));
+                self.record(format!("Load the level curve of {path}"), before);
+                format!(
+                    "Loaded the level curve, you are level {}.",
+                    self.store.gamification().level.level
+                )
+            }
+            Err(err) => err.to_string(),
+        }
+    }
+
+    /// This function loads a CSV file for import and guesses its column mapping.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The import window to load the file into.
+    /// * `today` - The date that relative due dates in the file are read against.
+    ///
+    fn load_csv(window: &mut TransferWindow, today: NaiveDate) {
+        window.csv_file = None;
+        window.csv_preview = None;
+        match std::fs::read_to_string(&window.path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| CsvFile::parse(&text))
+        {
+            Ok(file) => {
+                window.csv_mapping = file.guess_mapping();
+                window.csv_preview = Some(file.preview(&window.csv_mapping, today));
+                window.csv_file = Some(file);
+                window.message = None;
+            }
+            Err(err) => window.message = Some(format!("Failed to read {}: {err}", window.path)),
+        }
+    }
+
+    /// This function shows the column mapping and the preview of a loaded CSV file,
+    /// and imports the rows that parse when the user confirms.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the import window.
+    /// * `window` - The import window with the loaded file.
+    ///
+    fn csv_import_logic(&mut self, ui: &mut egui::Ui, window: &mut TransferWindow) {
+        let Some(file) = &window.csv_file else {
+            return;
+        };
+
+        // choose the column each task field is read from
+        ui.separator();
+        ui.label("Columns:");
+        let mut mapping_changed = false;
+        egui::Grid::new("csv_mapping").show(ui, |ui| {
+            for field in TaskField::ALL {
+                ui.label(field.column_name());
+                let mut column = window.csv_mapping.get(field);
+                let selected_text = column.map_or("(none)", |column| file.headers[column].as_str());
+                egui::ComboBox::from_id_source(field.column_name())
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut column, None, "(none)");
+                        for (index, header) in file.headers.iter().enumerate() {
+                            ui.selectable_value(&mut column, Some(index), header);
+                        }
+                    });
+                if column != window.csv_mapping.get(field) {
+                    window.csv_mapping.set(field, column);
+                    mapping_changed = true;
+                }
+                ui.end_row();
+            }
+        });
+        if mapping_changed {
+            window.csv_preview =
+                Some(file.preview(&window.csv_mapping, self.store.clock().today()));
+        }
+
+        // preview the rows that fail to parse
+        ui.separator();
+        match &window.csv_preview {
+            Some(Ok(preview)) => {
+                ui.label(format!(
+                    "{} row(s) ready to import, {} row(s) will be skipped.",
+                    preview.tasks.len(),
+                    preview.failures.len()
+                ));
+                if !preview.failures.is_empty() {
+                    egui::ScrollArea::vertical()
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            for failure in &preview.failures {
+                                ui.colored_label(egui::Color32::RED, failure.to_string());
+                            }
+                        });
+                }
+                if ui
+                    .add_enabled(
+                        !preview.tasks.is_empty(),
+                        egui::Button::new(format!("Import {} Task(s)", preview.tasks.len())),
+                    )
+                    .clicked()
+                {
+                    if let Some(Ok(preview)) = window.csv_preview.take() {
+                        let before = self.store.clone();
+                        let added = csv_transfer::import(&mut self.store, preview);
+                        if added > 0 {
+                            self.record(format!("Import {}", window.path), before);
+                        }
+                        window.message = Some(format!("Imported {added} task(s)."));
+                        window.csv_file = None;
+                    }
+                }
+            }
+            Some(Err(err)) => {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+            None => {}
+        }
+    }
+
+    /// This function shows the File > Export/Import window while it is open.
+    ///
+    /// # Arguments
+    ///
     /// * `ctx` - The egui::Context for the UI.
-    /// 
-    fn left_panel_logic(&mut self, ctx: &egui::Context) {
-        egui::SidePanel::left("left_panel").show(ctx, |ui| {
-            ui.heading("Add a Task");
+    ///
+    fn transfer_window_logic(&mut self, ctx: &egui::Context) {
+        let Some(mut window) = self.transfer_window.take() else {
+            return;
+        };
+        let mut open = true;
+        let title = match window.kind {
+            TransferKind::ExportJson => "Export Tasks (JSON)",
+            TransferKind::ImportJson => "Import Tasks (JSON)",
+            TransferKind::ExportCsv => "Export Tasks (CSV)",
+            TransferKind::ExportReportCsv => "Export Tasks Report (CSV)",
+            TransferKind::ImportCsv => "Import Tasks (CSV)",
+            TransferKind::ExportIcs => "Export Tasks (iCalendar)",
+            TransferKind::ImportIcs => "Import Tasks (iCalendar)",
+            TransferKind::ExportTodoTxt => "Export Tasks (todo.txt)",
+            TransferKind::ImportTodoTxt => "Import Tasks (todo.txt)",
+            TransferKind::LoadAchievements => "Load Achievement Rules",
+            TransferKind::LoadLevels => "Load Level Curve",
+        };
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("File: ");
+                    ui.text_edit_singleline(&mut window.path);
+                });
+                match window.kind {
+                    TransferKind::ExportJson => {
+                        if ui.button("Export").clicked() {
+                            window.message = Some(
+                                self.write_export(&window.path, transfer::export(&self.store)),
+                            );
+                        }
+                    }
+                    TransferKind::ExportCsv => {
+                        if ui.button("Export").clicked() {
+                            window.message = Some(self.write_export(
+                                &window.path,
+                                csv_transfer::export_tasks(&self.store),
+                            ));
+                        }
+                    }
+                    TransferKind::ExportReportCsv => {
+                        if ui.button("Export").clicked() {
+                            let report = self.store.report(self.clock.today());
+                            window.message =
+                                Some(self.write_export(
+                                    &window.path,
+                                    csv_transfer::export_report(&report),
+                                ));
+                        }
+                    }
+                    TransferKind::ImportJson => {
+                        ui.radio_value(
+                            &mut window.mode,
+                            ImportMode::Merge,
+                            "Merge with my tasks (skip duplicates)",
+                        );
+                        ui.radio_value(
+                            &mut window.mode,
+                            ImportMode::Replace,
+                            "Replace all my tasks and goals",
+                        );
+                        if ui.button("Import").clicked() {
+                            window.message = Some(self.import_tasks(&window.path, window.mode));
+                        }
+                    }
+                    TransferKind::ImportCsv => {
+                        if ui.button("Load").clicked() {
+                            Self::load_csv(&mut window, self.store.clock().today());
+                        }
+                        self.csv_import_logic(ui, &mut window);
+                    }
+                    TransferKind::ExportIcs => {
+                        ui.radio_value(
+                            &mut window.ics_component,
+                            IcsComponent::Todo,
+                            "As to-dos (VTODO), every task",
+                        );
+                        ui.radio_value(
+                            &mut window.ics_component,
+                            IcsComponent::Event,
+                            "As events (VEVENT), tasks with a due date",
+                        );
+                        if ui.button("Export").clicked() {
+                            window.message = Some(self.write_export(
+                                &window.path,
+                                ical::export(&self.store, window.ics_component),
+                            ));
+                        }
+                    }
+                    TransferKind::ImportIcs => {
+                        if ui.button("Import").clicked() {
+                            window.message = Some(self.import_ics(&window.path));
+                        }
+                    }
+                    TransferKind::ExportTodoTxt => {
+                        if ui.button("Export").clicked() {
+                            window.message =
+                                Some(self.write_export(&window.path, todotxt::export(&self.store)));
+                        }
+                    }
+                    TransferKind::ImportTodoTxt => {
+                        if ui.button("Import").clicked() {
+                            window.message = Some(self.import_todo_txt(&window.path));
+                        }
+                    }
+                    TransferKind::LoadAchievements => {
+                        ui.horizontal(|ui| {
+                            if ui.button("Load").clicked() {
+                                window.message =
+                                    Some(self.load_achievement_rules(Some(&window.path)));
+                            }
+                            if ui.button("Use Standard Rules").clicked() {
+                                window.message = Some(self.load_achievement_rules(None));
+                            }
+                        });
+                    }
+                    TransferKind::LoadLevels => {
+                        ui.horizontal(|ui| {
+                            if ui.button("Load").clicked() {
+                                window.message = Some(self.load_level_config(Some(&window.path)));
+                            }
+                            if ui.button("Use Standard Curve").clicked() {
+                                window.message = Some(self.load_level_config(None));
+                            }
+                        });
+                    }
+                }
+                if let Some(message) = &window.message {
+                    ui.separator();
+                    ui.label(message);
+                }
+            });
+        if open {
+            self.transfer_window = Some(window);
+        }
+    }
+
+    /// This function handles the logic for the left panel of the UI.
+    /// It contains the task creation form, task list, and task details.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `ctx` - The egui::Context for the UI.
+    /// 
+    fn left_panel_logic(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::left("left_panel").show(ctx, |ui| {
+            ui.heading("Add a Task");
+            // Drop the parent if it no longer exists
+            if self.new_task_parent.is_some_and(|id| self.store.task(id).is_none()) {
+                self.new_task_parent = None;
+            }
+            if let Some(parent) = self.new_task_parent.and_then(|id| self.store.task(id)) {
+                let parent_name = parent.name.clone();
+                ui.horizontal(|ui| {
+                    ui.label(format!("Subtask of: {parent_name}"));
+                    if ui.button("Make Top-level").clicked() {
+                        self.new_task_parent = None;
+                    }
+                });
+            }
+
+            // Task input fields
+            ui.horizontal(|ui| {
+                ui.label("Task Name: ");
+                ui.text_edit_singleline(&mut self.new_task_name);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Description: ");
+                ui.text_edit_singleline(&mut self.new_task_description);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Due Date: ");
+                ui.text_edit_singleline(&mut self.new_task_due_date)
+                    .on_hover_text("e.g. 2024-11-23, 11/23/2024 2:30pm or tomorrow");
+            });
+            if let Some(err) = &self.new_task_due_date_error {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+            ui.horizontal(|ui| {
+                ui.label("Repeats: ");
+                ui.text_edit_singleline(&mut self.new_task_recurrence)
+                    .on_hover_text("Leave empty for a one-off task, or e.g. daily, weekdays, every 2 weeks, monthly on the 15th");
+            });
+            if let Some(err) = &self.new_task_recurrence_error {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+            ui.horizontal(|ui| {
+                ui.label("Priority: ");
+                let mut priority_val = self.new_task_priority as u8;
+                ui.add(egui::Slider::new(&mut priority_val, 1..=3).text("Level"));
+                self.new_task_priority = match priority_val {
+                    1 => PriorityLevel::Low,
+                    2 => PriorityLevel::Medium,
+                    3 => PriorityLevel::High,
+                    _ => PriorityLevel::Low,
+                };
+            });
+            Self::organize_fields_logic(ui, &self.store, &mut self.new_task_project, &mut self.new_task_tags);
+
+            // Add task button
+            if ui.button("Add Task").clicked() {
+                self.add_task();
+            }
+
+            ui.separator();
+
+            // Display tasks and achievements
+            ui.heading("Tasks");
+            self.query_bar_logic(ui);
+            ui.checkbox(&mut self.group_by_project, "Group by project");
+            let mut toggled_task = None;
+            let listed = self.query.run(&self.store, self.clock.today());
+            if listed.is_empty() && !self.store.tasks().is_empty() {
+                ui.label("No tasks match the search.");
+            }
+            let groups: Vec<tags::ProjectGroup<'_>> = if self.group_by_project {
+                tags::group_by_project(listed)
+                    .into_iter()
+                    .map(|(project, tasks)| (Some(project.unwrap_or("No Project")), tasks))
+                    .collect()
+            } else {
+                vec![(None, listed)]
+            };
+            for (heading, tasks) in groups {
+                if let Some(heading) = heading {
+                    ui.label(egui::RichText::new(heading).strong());
+                }
+                for (depth, task) in tasks {
+                    ui.horizontal(|ui| {
+                        // indent subtasks under their parent
+                        ui.add_space(depth as f32 * 16.0);
+                        // completing or reopening the task is applied through the store after the loop;
+                        // a task with open subtasks or checklist items can't be completed yet
+                        let mut completed = task.completed;
+                        let can_toggle = task.completed || self.store.children_done(task.id);
+                        let checkbox = ui.add_enabled(can_toggle, egui::Checkbox::new(&mut completed, ""));
+                        if checkbox.on_disabled_hover_text("Finish its subtasks and checklist first").changed() {
+                            toggled_task = Some((task.id, completed));
+                        }
+                        let (done, total) = task.checklist_progress();
+                        if total > 0 {
+                            ui.label(format!("{} ({done}/{total})", task.name));
+                        } else {
+                            ui.label(&task.name);
+                        }
+                        let blocked_by = self.store.blocked_by(task.id);
+                        if !task.completed && !blocked_by.is_empty() {
+                            ui.weak("(blocked)").on_hover_text(format!("Waiting for {}", Self::task_names(&self.store, &blocked_by)));
+                        }
+                        if !task.tags.is_empty() {
+                            ui.weak(tags::format_tags(&task.tags));
+                        }
+                        if ui.button("Details").clicked() {
+                            self.selected_task = Some(task.id);
+                        }
+                    });
+                }
+            }
+            if let Some((id, completed)) = toggled_task {
+                let blocked_by = self.store.blocked_by(id);
+                let name = self.store.task(id).map(|task| task.name.clone()).unwrap_or_default();
+                let verb = if completed { "Complete" } else { "Reopen" };
+                match self.apply(format!("{verb} task \"{name}\""), |store| store.set_completed(id, completed)) {
+                    Ok(()) if completed && !blocked_by.is_empty() => {
+                        self.completion_warning =
+                            Some(format!("Completed a task that was still waiting for {}.", Self::task_names(&self.store, &blocked_by)));
+                    }
+                    Ok(()) => self.completion_warning = None,
+                    Err(err) => log::warn!("Failed to update task {id}: {err}"),
+                }
+            }
+            if let Some(warning) = &self.completion_warning {
+                ui.colored_label(ui.visuals().warn_fg_color, warning);
+            }
+
+            // Drop the selection if the selected task no longer exists
+            if self.selected_task.is_some_and(|id| self.store.task(id).is_none()) {
+                self.selected_task = None;
+                self.is_editing = false;
+            }
+
+            // Check if a task is selected and display its details
+            if let Some(selected_task) = self.selected_task.and_then(|id| self.store.task(id)).cloned() {
+
+                ui.separator();
+                ui.heading("Task Details");
+
+                // Editable fields for the selected task
+                if self.is_editing {
+                    ui.label("Editing Task:");
+                    ui.horizontal(|ui| {
+                        ui.label("Name: ");
+                        ui.text_edit_singleline(&mut self.edit_name);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Description: ");
+                        ui.text_edit_multiline(&mut self.edit_description);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Due Date: ");
+                        ui.text_edit_singleline(&mut self.edit_due_date); // Parsed when the changes are saved
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Repeats: ");
+                        ui.text_edit_singleline(&mut self.edit_recurrence); // Empty for a one-off task
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Priority: ");
+                        let mut priority_val = self.edit_priority as u8;
+                        ui.add(egui::Slider::new(&mut (priority_val), 1..=3).text("Level"));
+                        self.edit_priority = match priority_val {
+                            1 => PriorityLevel::Low,
+                            2 => PriorityLevel::Medium,
+                            3 => PriorityLevel::High,
+                            _ => PriorityLevel::Low,
+                        };
+                    });
+                    Self::organize_fields_logic(ui, &self.store, &mut self.edit_project, &mut self.edit_tags);
+                    ui.horizontal(|ui| {
+                        ui.label("Effort (hours): ");
+                        ui.text_edit_singleline(&mut self.edit_effort); // Empty for no estimate
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Difficulty: ");
+                        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+                            ui.radio_value(&mut self.edit_difficulty, difficulty, difficulty.to_string());
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Points: ");
+                        ui.text_edit_singleline(&mut self.edit_points); // Empty for the scoring formula
+                    });
+                    if let Some(err) = &self.edit_error {
+                        ui.colored_label(ui.visuals().error_fg_color, err);
+                    }
+
+                    // Save Changes button
+                    if ui.button("Save Changes").clicked() {
+                        self.save_edits(selected_task.id);
+                    }
+
+                    // Cancel Edits button
+                    if ui.button("Cancel Edits").clicked() {
+                        self.edit_error = None;
+                        self.is_editing = false; // Exit editing mode
+                    }
+                } else {
+                    // Display read-only fields for the selected task
+                    ui.label(format!("Name: {}", selected_task.name));
+                    ui.label(format!("Description: {}", selected_task.description));
+                    ui.label(format!("Due Date: {}", selected_task.due_date.map_or_else(|| "None".to_string(), |due| due.to_string())));
+                    if let Some(recurrence) = &selected_task.recurrence {
+                        ui.label(format!("Repeats: {recurrence}"));
+                    }
+                    ui.label(format!("Priority: {:?}", selected_task.priority));
+                    if let Some(project) = &selected_task.project {
+                        ui.label(format!("Project: {project}"));
+                    }
+                    if !selected_task.tags.is_empty() {
+                        ui.label(format!("Tags: {}", tags::format_tags(&selected_task.tags)));
+                    }
+                    if let Some(effort) = selected_task.effort {
+                        ui.label(format!("Effort: {effort} h"));
+                    }
+                    ui.label(format!("Difficulty: {}", selected_task.difficulty));
+                    ui.label(format!("Completed: {}", selected_task.completed));
+                    self.score_logic(ui, &selected_task);
+                    if let Some(parent) = selected_task.parent.and_then(|id| self.store.task(id)) {
+                        ui.label(format!("Subtask of: {}", parent.name));
+                    }
+                    self.subtasks_logic(ui, &selected_task);
+                    self.dependencies_logic(ui, &selected_task);
+
+                    // Everything that happened to the task, from the journal
+                    egui::CollapsingHeader::new("History").id_source(("task_history", selected_task.id)).show(ui, |ui| {
+                        for line in self.journal.log(Some(selected_task.id)).iter().rev() {
+                            ui.label(format!("{}  {}", line.at.format("%Y-%m-%d %H:%M"), line.text));
+                        }
+                    });
+
+                    // Delete Task button, which also deletes the subtasks
+                    if ui.button("Delete Task").clicked() {
+                        let description = format!("Delete task \"{}\"", selected_task.name);
+                        if let Err(err) = self.apply(description, |store| store.delete(selected_task.id)) {
+                            log::warn!("Failed to delete task {}: {err}", selected_task.id);
+                        }
+                    }
+
+                    // Edit Task button
+                    if ui.button("Edit Task").clicked() {
+                        self.edit_name = selected_task.name.clone();
+                        self.edit_description = selected_task.description.clone();
+                        self.edit_due_date = selected_task.due_date.map(|due| due.to_string()).unwrap_or_default();
+                        self.edit_recurrence = selected_task.recurrence.as_ref().map(|rule| rule.to_string()).unwrap_or_default();
+                        self.edit_priority = selected_task.priority;
+                        self.edit_project = selected_task.project.clone().unwrap_or_default();
+                        self.edit_tags = selected_task.tags.join(", ");
+                        self.edit_effort = selected_task.effort.map(|effort| effort.to_string()).unwrap_or_default();
+                        self.edit_difficulty = selected_task.difficulty;
+                        self.edit_points = selected_task.points_override.map(|points| points.to_string()).unwrap_or_default();
+                        self.edit_error = None;
+                        self.is_editing = true; // Enter editing mode
+                    }
+                }
+
+                ui.separator();
+
+                // Clear all tasks button
+                if ui.button("Clear All Tasks").clicked() {
+                    let before = self.store.clone();
+                    self.store.clear();
+                    self.record("Clear all tasks", before);
+                    self.selected_task = None;
+                    self.is_editing = false;
+                }
+            }
+            // Check for achievements
+            self.update_achievements();
+        });
+    }
+
+    /// This function shows the subtasks and checklist of the selected task, with controls
+    /// to check off and add checklist items, add a subtask, and choose whether the task
+    /// completes itself once everything under it is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the task details.
+    /// * `task` - The selected task.
+    ///
+    fn subtasks_logic(&mut self, ui: &mut egui::Ui, task: &Task) {
+        let subtasks: Vec<bool> = self
+            .store
+            .tasks()
+            .children(task.id)
+            .map(|subtask| subtask.completed)
+            .collect();
+        if !subtasks.is_empty() {
+            let done = subtasks.iter().filter(|&&completed| completed).count();
+            ui.label(format!("Subtasks: {done}/{} done", subtasks.len()));
+        }
+
+        // checklist changes are applied through the store after the loop
+        let mut checked_item = None;
+        let mut removed_item = None;
+        if !task.checklist.is_empty() {
+            ui.label("Checklist:");
+        }
+        for (index, item) in task.checklist.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let mut done = item.done;
+                if ui.checkbox(&mut done, &item.text).changed() {
+                    checked_item = Some((index, done));
+                }
+                if ui.small_button("Remove").clicked() {
+                    removed_item = Some(index);
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_checklist_item);
+            if ui.button("Add Item").clicked() {
+                let description = format!("Add checklist item to \"{}\"", task.name);
+                let text = self.new_checklist_item.clone();
+                match self.apply(description, |store| {
+                    store.add_checklist_item(task.id, &text)
+                }) {
+                    Ok(()) => self.new_checklist_item.clear(),
+                    Err(err) => {
+                        log::warn!("Failed to add a checklist item to task {}: {err}", task.id)
+                    }
+                }
+            }
+        });
+        let result = match (checked_item, removed_item) {
+            (Some((index, done)), _) => {
+                let verb = if done { "Check" } else { "Uncheck" };
+                self.apply(
+                    format!("{verb} \"{}\"", task.checklist[index].text),
+                    |store| store.set_checklist_item(task.id, index, done),
+                )
+            }
+            (None, Some(index)) => {
+                let description =
+                    format!("Remove checklist item \"{}\"", task.checklist[index].text);
+                self.apply(description, |store| {
+                    store.remove_checklist_item(task.id, index).map(|_| ())
+                })
+            }
+            (None, None) => Ok(()),
+        };
+        if let Err(err) = result {
+            log::warn!("Failed to update the checklist of task {}: {err}", task.id);
+        }
+
+        let mut auto_complete = task.auto_complete;
+        if ui
+            .checkbox(
+                &mut auto_complete,
+                "Complete when all subtasks and checklist items are done",
+            )
+            .changed()
+        {
+            let update = TaskUpdate {
+                auto_complete: Some(auto_complete),
+                ..Default::default()
+            };
+            if let Err(err) = self.apply(format!("Edit task \"{}\"", task.name), |store| {
+                store.update(task.id, update)
+            }) {
+                log::warn!("Failed to update task {}: {err}", task.id);
+            }
+        }
+
+        // Add Subtask button, which points the Add a Task form at this task
+        if ui.button("Add Subtask").clicked() {
+            self.new_task_parent = Some(task.id);
+        }
+    }
+
+    /// This function shows the query bar above the task list: a search field, filters for
+    /// the status, priority, tag, project and due date, the sort order, and saved filters.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the left panel.
+    ///
+    fn query_bar_logic(&mut self, ui: &mut egui::Ui) {
+        let tasks = self.store.tasks().as_slice();
+        ui.horizontal(|ui| {
+            ui.label("Search: ");
+            ui.text_edit_singleline(&mut self.query.text)
+                .on_hover_text(QUERY_HINT);
+            if ui
+                .add_enabled(
+                    self.query != TaskQuery::default(),
+                    egui::Button::new("Clear"),
+                )
+                .clicked()
+            {
+                self.query = TaskQuery::default();
+                self.query_due_after.clear();
+                self.query_due_before.clear();
+                self.query_error = None;
+            }
+        });
+        // text that isn't a valid expression is still searched for as it is
+        if let Err(err) = self.query.expression(self.clock.today()) {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Searching for the text as written: {err}"),
+            );
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            egui::ComboBox::from_id_source("query_status")
+                .selected_text(format!("Status: {}", self.query.status.name()))
+                .show_ui(ui, |ui| {
+                    for status in Status::ALL {
+                        ui.selectable_value(&mut self.query.status, status, status.name());
+                    }
+                });
+            egui::ComboBox::from_id_source("query_priority")
+                .selected_text(match self.query.priority {
+                    Some(priority) => format!("Priority: {priority:?}"),
+                    None => "Any priority".to_string(),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.query.priority, None, "Any priority");
+                    for priority in [
+                        PriorityLevel::High,
+                        PriorityLevel::Medium,
+                        PriorityLevel::Low,
+                    ] {
+                        ui.selectable_value(
+                            &mut self.query.priority,
+                            Some(priority),
+                            format!("{priority:?}"),
+                        );
+                    }
+                });
+            egui::ComboBox::from_id_source("query_tag")
+                .selected_text(
+                    self.query
+                        .tag
+                        .as_ref()
+                        .map_or("Any tag".to_string(), |tag| format!("#{tag}")),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.query.tag, None, "Any tag");
+                    for tag in tags::all_tags(tasks) {
+                        let label = format!("#{tag}");
+                        ui.selectable_value(&mut self.query.tag, Some(tag), label);
+                    }
+                });
+            egui::ComboBox::from_id_source("query_project")
+                .selected_text(
+                    self.query
+                        .project
+                        .clone()
+                        .unwrap_or_else(|| "Any project".to_string()),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.query.project, None, "Any project");
+                    for project in tags::all_projects(tasks) {
+                        let label = project.clone();
+                        ui.selectable_value(&mut self.query.project, Some(project), label);
+                    }
+                });
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Due after: ");
+            let after =
+                ui.add(egui::TextEdit::singleline(&mut self.query_due_after).desired_width(80.0));
+            ui.label("before: ");
+            let before =
+                ui.add(egui::TextEdit::singleline(&mut self.query_due_before).desired_width(80.0));
+            if after.changed() || before.changed() {
+                self.apply_due_filters();
+            }
+            ui.checkbox(&mut self.query.overdue, "Overdue");
+        });
+        if let Some(err) = &self.query_error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("query_sort")
+                .selected_text(format!("Sort by: {}", self.query.sort.name()))
+                .show_ui(ui, |ui| {
+                    for key in SortKey::ALL {
+                        ui.selectable_value(&mut self.query.sort, key, key.name());
+                    }
+                });
+            ui.checkbox(&mut self.query.reverse, "Reverse");
+        });
+
+        // saved filters are applied or deleted through the store after the menu
+        let mut loaded = None;
+        let mut deleted = None;
+        ui.horizontal_wrapped(|ui| {
+            if !self.store.filters().is_empty() {
+                egui::ComboBox::from_id_source("saved_filters")
+                    .selected_text("Saved filters")
+                    .show_ui(ui, |ui| {
+                        for (name, query) in self.store.filters() {
+                            if ui.selectable_label(false, name).clicked() {
+                                loaded = Some((name.clone(), query.clone()));
+                            }
+                        }
+                    });
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut self.filter_name)
+                    .hint_text("Filter name")
+                    .desired_width(100.0),
+            );
+            if ui.button("Save Filter").clicked() {
+                let (name, query) = (self.filter_name.clone(), self.query.clone());
+                if let Err(err) = self.apply(format!("Save filter \"{}\"", name.trim()), |store| {
+                    store.save_filter(&name, query)
+                }) {
+                    self.query_error = Some(err.to_string());
+                }
+            }
+            if self.store.filters().contains_key(self.filter_name.trim())
+                && ui.button("Delete Filter").clicked()
+            {
+                deleted = Some(self.filter_name.clone());
+            }
+        });
+        if let Some((name, query)) = loaded {
+            self.query_due_after = query
+                .due_after
+                .map(|date| date.to_string())
+                .unwrap_or_default();
+            self.query_due_before = query
+                .due_before
+                .map(|date| date.to_string())
+                .unwrap_or_default();
+            self.query = query;
+            self.query_error = None;
+            self.filter_name = name;
+        }
+        if let Some(name) = deleted {
+            let before = self.store.clone();
+            if self.store.remove_filter(&name).is_some() {
+                self.record(format!("Delete filter \"{}\"", name.trim()), before);
+            }
+        }
+    }
+
+    /// This function reads the due date filters typed in the query bar. An empty field
+    /// removes its filter, and a date that can't be parsed is shown as an error and
+    /// leaves the filter as it was.
+    ///
+    fn apply_due_filters(&mut self) {
+        let today = self.clock.today();
+        let parse = |text: &str| {
+            if text.trim().is_empty() {
+                Ok(None)
+            } else {
+                dates::parse_due_date(text, today).map(|due_date| Some(due_date.local_date()))
+            }
+        };
+        self.query_error = None;
+        match parse(&self.query_due_after) {
+            Ok(date) => self.query.due_after = date,
+            Err(err) => self.query_error = Some(err.to_string()),
+        }
+        match parse(&self.query_due_before) {
+            Ok(date) => self.query.due_before = date,
+            Err(err) => self.query_error = Some(err.to_string()),
+        }
+    }
+
+    /// This function shows the project and tags fields of a task form, with buttons that
+    /// complete a project or tag from the ones already in use.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the form.
+    /// * `store` - The task store, for the projects and tags already in use.
+    /// * `project` - The project field.
+    /// * `tags_text` - The tags field, with tags separated by commas.
+    ///
+    fn organize_fields_logic(
+        ui: &mut egui::Ui,
+        store: &TaskStore,
+        project: &mut String,
+        tags_text: &mut String,
+    ) {
+        let tasks = store.tasks().as_slice();
+        ui.horizontal(|ui| {
+            ui.label("Project: ");
+            ui.text_edit_singleline(project)
+                .on_hover_text("Leave empty for no project");
+        });
+        if !project.trim().is_empty() {
+            let known_projects = tags::all_projects(tasks);
+            let suggestions = tags::suggest(project, &known_projects, &[]);
+            if let Some(suggestion) = Self::suggestions_logic(ui, &suggestions) {
+                *project = suggestion;
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Tags: ");
+            ui.text_edit_singleline(tags_text)
+                .on_hover_text("Separate tags with commas, e.g. backend, urgent");
+        });
+        let (typed, typing) = tags::split_last_tag(tags_text);
+        if !typing.is_empty() {
+            let known_tags = tags::all_tags(tasks);
+            let suggestions = tags::suggest(typing, &known_tags, &typed);
+            if let Some(suggestion) = Self::suggestions_logic(ui, &suggestions) {
+                *tags_text = tags::complete_last_tag(tags_text, &suggestion);
+            }
+        }
+    }
+
+    /// This function shows suggestions as a row of small buttons.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui to show the suggestions in.
+    /// * `suggestions` - The suggested names.
+    ///
+    /// # Returns
+    ///
+    /// The suggestion that was clicked, if any.
+    ///
+    fn suggestions_logic(ui: &mut egui::Ui, suggestions: &[&str]) -> Option<String> {
+        let mut chosen = None;
+        if !suggestions.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.weak("Suggestions:");
+                for &suggestion in suggestions {
+                    if ui.small_button(suggestion).clicked() {
+                        chosen = Some(suggestion.to_string());
+                    }
+                }
+            });
+        }
+        chosen
+    }
+
+    /// This function lists tasks by name for messages, such as "Design" and "Build".
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The task store holding the tasks.
+    /// * `ids` - The IDs of the tasks to name.
+    ///
+    fn task_names(store: &TaskStore, ids: &[TaskId]) -> String {
+        let names: Vec<String> = ids
+            .iter()
+            .filter_map(|&id| store.task(id))
+            .map(|task| format!("\"{}\"", task.name))
+            .collect();
+        names.join(", ")
+    }
 
-            // Task input fields
+    /// This function shows the tasks the selected task waits for and the tasks waiting for it,
+    /// with controls to add and remove prerequisites.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the task details.
+    /// * `task` - The selected task.
+    ///
+    fn dependencies_logic(&mut self, ui: &mut egui::Ui, task: &Task) {
+        // dependency changes are applied through the store after the loop
+        let mut removed = None;
+        if !task.depends_on.is_empty() {
+            ui.label("Waits for:");
+        }
+        for &prerequisite in &task.depends_on {
+            let Some(other) = self.store.task(prerequisite) else {
+                continue;
+            };
             ui.horizontal(|ui| {
-                ui.label("Task Name: ");
-                ui.text_edit_singleline(&mut self.new_task_name);
+                ui.label(format!(
+                    "{} {}",
+                    if other.completed { "✔" } else { "⏳" },
+                    other.name
+                ));
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(prerequisite);
+                }
             });
-            ui.horizontal(|ui| {
-                ui.label("Description: ");
-                ui.text_edit_singleline(&mut self.new_task_description);
+        }
+        let dependents = self.store.dependents(task.id);
+        if !dependents.is_empty() {
+            ui.label(format!(
+                "Blocks: {}",
+                Self::task_names(&self.store, &dependents)
+            ));
+        }
+
+        let mut added = None;
+        egui::ComboBox::from_id_source("add_prerequisite")
+            .selected_text("Add a task to wait for")
+            .show_ui(ui, |ui| {
+                for other in self.store.tasks() {
+                    if other.id != task.id
+                        && !task.depends_on.contains(&other.id)
+                        && ui.selectable_label(false, &other.name).clicked()
+                    {
+                        added = Some(other.id);
+                    }
+                }
             });
+        let result = match (added, removed) {
+            (Some(prerequisite), _) => self.apply(
+                format!("Make \"{}\" wait for another task", task.name),
+                |store| store.add_dependency(task.id, prerequisite),
+            ),
+            (None, Some(prerequisite)) => {
+                let description = format!("Stop \"{}\" waiting for another task", task.name);
+                self.apply(description, |store| {
+                    store.remove_dependency(task.id, prerequisite).map(|_| ())
+                })
+            }
+            (None, None) => Ok(()),
+        };
+        if let Err(err) = result {
+            self.completion_warning = Some(err.to_string());
+        }
+    }
+
+    /// This function shows the progress toward each project goal, with controls to set
+    /// and remove goals.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the right panel.
+    ///
+    fn project_goals_logic(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Project Goals");
+        // goal changes are applied through the store after the loop
+        let mut changed_goal = None;
+        for goal in self
+            .store
+            .gamification()
+            .project_goal_progress(self.store.tasks().as_slice())
+        {
             ui.horizontal(|ui| {
-                ui.label("Due Date: ");
-                ui.text_edit_singleline(&mut self.new_task_due_date);
+                let completed = goal.completed.min(goal.goal as usize);
+                ui.add(
+                    egui::ProgressBar::new(completed as f32 / goal.goal as f32)
+                        .text(format!("{}: {completed}/{}", goal.project, goal.goal)),
+                );
+                if ui.small_button("Remove").clicked() {
+                    changed_goal = Some((goal.project.clone(), 0));
+                }
             });
+            if goal.reached() {
+                ui.label(format!(
+                    "Congrats! You reached your goal for {}!",
+                    goal.project
+                ));
+            }
+        }
+
+        let projects = tags::all_projects(self.store.tasks().as_slice());
+        if projects.is_empty() {
+            ui.label("Give tasks a project to set goals for it.");
+        } else {
             ui.horizontal(|ui| {
-                ui.label("Priority: ");
-                let mut priority_val = self.new_task_priority as u8;
-                ui.add(egui::Slider::new(&mut priority_val, 1..=3).text("Level"));
-                self.new_task_priority = match priority_val {
-                    1 => PriorityLevel::Low,
-                    2 => PriorityLevel::Medium,
-                    3 => PriorityLevel::High,
-                    _ => PriorityLevel::Low,
-                };
+                egui::ComboBox::from_id_source("goal_project")
+                    .selected_text(if self.goal_project.is_empty() {
+                        "Choose a project"
+                    } else {
+                        &self.goal_project
+                    })
+                    .show_ui(ui, |ui| {
+                        for project in &projects {
+                            ui.selectable_value(&mut self.goal_project, project.clone(), project);
+                        }
+                    });
+                ui.add(
+                    egui::DragValue::new(&mut self.goal_target)
+                        .speed(1)
+                        .range(1..=100),
+                );
+                if ui
+                    .add_enabled(!self.goal_project.is_empty(), egui::Button::new("Set Goal"))
+                    .clicked()
+                {
+                    changed_goal = Some((self.goal_project.clone(), self.goal_target));
+                }
             });
-
-            // Add task button
-            if ui.button("Add Task").clicked() {
-                self.add_task();
+        }
+        if let Some((project, goal)) = changed_goal {
+            let description = if goal == 0 {
+                format!("Remove the goal of {project}")
+            } else {
+                format!("Set the goal of {project}")
+            };
+            if let Err(err) =
+                self.apply(description, |store| store.set_project_goal(&project, goal))
+            {
+                log::warn!("Failed to update the goal of project {project}: {err}");
             }
+        }
+    }
 
-            ui.separator();
-
-            // Display tasks and achievements
-            ui.heading("Tasks");
-            for (i, task) in self.tasks.iter_mut().enumerate() {
-
-                ui.horizontal(|ui| {
-                    ui.checkbox(&mut task.completed, "");
-                    // if the task checkbox is checked, update the completed date, otherwise set it to None
-                    if task.completed {
-                        task.completed_date = Some(chrono::Local::now().format("%Y-%m-%d").to_string().to_string());
-                    } else {
-                        task.completed_date = None;
-                    }
-                    ui.label(&task.name);
-                    if ui.button("Details").clicked() {
-                        self.selected_task = Some(i);
-                    }
+    /// This function shows the Edit menu, with Undo and Redo naming the command they change
+    /// and a setting to keep the history after a restart.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the menu bar.
+    ///
+    fn edit_menu_logic(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Edit", |ui| {
+            let undo = self
+                .history
+                .next_undo()
+                .map_or("Undo".to_string(), |description| {
+                    format!("Undo: {description}")
                 });
-
+            let button =
+                egui::Button::new(undo).shortcut_text(ui.ctx().format_shortcut(&UNDO_SHORTCUT));
+            if ui
+                .add_enabled(self.history.next_undo().is_some(), button)
+                .clicked()
+            {
+                self.undo();
+                ui.close_menu();
             }
+            let redo = self
+                .history
+                .next_redo()
+                .map_or("Redo".to_string(), |description| {
+                    format!("Redo: {description}")
+                });
+            let button =
+                egui::Button::new(redo).shortcut_text(ui.ctx().format_shortcut(&REDO_SHORTCUT));
+            if ui
+                .add_enabled(self.history.next_redo().is_some(), button)
+                .clicked()
+            {
+                self.redo();
+                ui.close_menu();
+            }
+            ui.separator();
+            let mut persist = self.history.is_persistent();
+            if ui
+                .checkbox(&mut persist, "Keep undo history after restarting")
+                .changed()
+            {
+                self.history.set_persistent(persist);
+            }
+        });
+    }
 
-            // Check if a task is selected and display its details
-            if let Some(selected_index) = self.selected_task {
-                let selected_task = &mut self.tasks[selected_index]; // Get the selected task
-
-                ui.separator();
-                ui.heading("Task Details");
-
-                // Editable fields for the selected task
-                if self.is_editing {
-                    ui.label("Editing Task:");
-                    ui.horizontal(|ui| {
-                        ui.label("Name: ");
-                        ui.text_edit_singleline(&mut selected_task.name);
-                    });
-                    ui.horizontal(|ui| {
-                        ui.label("Description: ");
-                        ui.text_edit_multiline(&mut selected_task.description);
-                    });
-                    ui.horizontal(|ui| {
-                        ui.label("Due Date: ");
-                        ui.text_edit_singleline(&mut selected_task.due_date); // Assuming due_date is a String
-                    });
-                    ui.horizontal(|ui| {
-                        ui.label("Priority: ");
-                        let mut priority_val = selected_task.priority as u8;
-                        ui.add(egui::Slider::new(&mut (priority_val), 1..=3).text("Level"));
-                        selected_task.priority = match priority_val {
-                            1 => PriorityLevel::Low,
-                            2 => PriorityLevel::Medium,
-                            3 => PriorityLevel::High,
-                            _ => PriorityLevel::Low,
-                        };
-                    });
-
-                    // Save Changes button
-                    if ui.button("Save Changes").clicked() {
-                        self.is_editing = false; // Exit editing mode
-                    }
-
-                    // Cancel Edits button
-                    if ui.button("Cancel Edits").clicked() {
-                        self.is_editing = false; // Exit editing mode
-                    }
-                } else {
-                    // Display read-only fields for the selected task
-                    ui.label(format!("Name: {}", selected_task.name));
-                    ui.label(format!("Description: {}", selected_task.description));
-                    ui.label(format!("Due Date: {}", selected_task.due_date));
-                    ui.label(format!("Priority: {:?}", selected_task.priority));
-                    ui.label(format!("Completed: {}", selected_task.completed));
-
-                    // Edit Task button
-                    if ui.button("Edit Task").clicked() {
-                        self.is_editing = true; // Enter editing mode
+    /// This function shows the Debug menu, which simulates another date so the daily reward,
+    /// the weekly challenge, completion times and the Tasks Report can be checked for any day.
+    /// The menu is only shown in debug builds.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the menu bar.
+    ///
+    fn debug_menu_logic(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Debug", |ui| {
+            ui.label("Simulate date:");
+            ui.text_edit_singleline(&mut self.simulate_date);
+            if let Some(err) = &self.simulate_date_error {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Simulate").clicked() {
+                    match dates::parse_due_date(&self.simulate_date, Clock::System.today()) {
+                        Ok(due_date) => {
+                            self.set_clock(Clock::simulating(due_date.local_date()));
+                            self.simulate_date_error = None;
+                            ui.close_menu();
+                        }
+                        Err(err) => self.simulate_date_error = Some(err.to_string()),
                     }
                 }
-
-                ui.separator();
-
-                // Clear all tasks button
-                if ui.button("Clear All Tasks").clicked() {
-                    self.tasks.clear();
+                if ui
+                    .add_enabled(
+                        self.clock.is_simulated(),
+                        egui::Button::new("Use the real date"),
+                    )
+                    .clicked()
+                {
+                    self.set_clock(Clock::System);
+                    self.simulate_date.clear();
+                    ui.close_menu();
                 }
-            }
-            // Check for achievements
-            self.update_achievements();
+            });
         });
+        if self.clock.is_simulated() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Simulating {}", self.clock.today()),
+            );
+        }
+    }
+
+    /// This function shows the level badge at the right of the menu bar, with the progress toward
+    /// the next level.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the menu bar, laid out from the right.
+    ///
+    fn level_badge_logic(&self, ui: &mut egui::Ui) {
+        let level = self.store.gamification().level;
+        let hover = if level.next > level.start {
+            format!(
+                "{} XP, the next level starts at {} XP",
+                level.xp, level.next
+            )
+        } else {
+            format!("{} XP, the highest level", level.xp)
+        };
+        ui.add(
+            egui::ProgressBar::new(level.progress())
+                .desired_width(100.0)
+                .text(format!("{} XP", level.xp)),
+        )
+        .on_hover_text(&hover);
+        ui.strong(format!("⭐ Level {}", level.level))
+            .on_hover_text(hover);
     }
 
     /// This function handles the logic for the right panel of the UI.
@@ -260,20 +2048,61 @@ impl TemplateApp {
         egui::SidePanel::right("right_panel").show(ctx, |ui| {
             ui.heading("Achievements");
             ui.separator();
-            ui.label(&self.gamification.achievement_message); // Display the achievement message
+            let gamification = self.store.gamification();
+            ui.label(&gamification.achievement_message); // Display the achievement message
+            egui::CollapsingHeader::new(format!(
+                "Trophy Case ({})",
+                gamification.achievements.unlocked().len()
+            ))
+            .id_source("trophy_case")
+            .show(ui, |ui| self.trophy_case_logic(ui));
+            let gamification = self.store.gamification();
             ui.separator();
 
             // Display task progress toward goals
-            let completed_tasks = self.tasks.iter().filter(|task| task.completed).count();
+            let completed_tasks = self.store.query(|task| task.completed).count();
             ui.separator();
-            // add a progress bar for the bronze goal 
-            ui.add(egui::ProgressBar::new(completed_tasks as f32 / self.gamification.bronze_goal as f32).text(format!("Bronze Goal: {}/{}", if completed_tasks <= self.gamification.bronze_goal as usize { completed_tasks } else {self.gamification.bronze_goal as usize}, self.gamification.bronze_goal)));
+            // add a progress bar for the bronze goal
+            ui.add(
+                egui::ProgressBar::new(completed_tasks as f32 / gamification.bronze_goal as f32)
+                    .text(format!(
+                        "Bronze Goal: {}/{}",
+                        if completed_tasks <= gamification.bronze_goal as usize {
+                            completed_tasks
+                        } else {
+                            gamification.bronze_goal as usize
+                        },
+                        gamification.bronze_goal
+                    )),
+            );
             ui.separator();
             // add a progress bar for the silver goal
-            ui.add(egui::ProgressBar::new(completed_tasks as f32 / self.gamification.silver_goal as f32).text(format!("Silver Goal: {}/{}", if completed_tasks <= self.gamification.silver_goal as usize { completed_tasks } else {self.gamification.silver_goal as usize}, self.gamification.silver_goal)));
+            ui.add(
+                egui::ProgressBar::new(completed_tasks as f32 / gamification.silver_goal as f32)
+                    .text(format!(
+                        "Silver Goal: {}/{}",
+                        if completed_tasks <= gamification.silver_goal as usize {
+                            completed_tasks
+                        } else {
+                            gamification.silver_goal as usize
+                        },
+                        gamification.silver_goal
+                    )),
+            );
             ui.separator();
             // add a progress bar for the gold goal
-            ui.add(egui::ProgressBar::new(completed_tasks as f32 / self.gamification.gold_goal as f32).text(format!("Gold Goal: {}/{}", if completed_tasks <= self.gamification.gold_goal as usize { completed_tasks } else {self.gamification.gold_goal as usize}, self.gamification.gold_goal)));
+            ui.add(
+                egui::ProgressBar::new(completed_tasks as f32 / gamification.gold_goal as f32)
+                    .text(format!(
+                        "Gold Goal: {}/{}",
+                        if completed_tasks <= gamification.gold_goal as usize {
+                            completed_tasks
+                        } else {
+                            gamification.gold_goal as usize
+                        },
+                        gamification.gold_goal
+                    )),
+            );
 
             ui.separator();
 
@@ -281,23 +2110,302 @@ impl TemplateApp {
             ui.heading("Customize Your Goals");
 
             // Input fields to set bronze, silver, and gold task goals
+            let (mut bronze_goal, mut silver_goal, mut gold_goal) = (
+                gamification.bronze_goal,
+                gamification.silver_goal,
+                gamification.gold_goal,
+            );
             ui.horizontal(|ui| {
                 ui.label("Bronze Goal: ");
-                ui.add(egui::DragValue::new(&mut self.gamification.bronze_goal).speed(1).range(1..=self.gamification.silver_goal-1));
+                ui.add(
+                    egui::DragValue::new(&mut bronze_goal)
+                        .speed(1)
+                        .range(1..=silver_goal - 1),
+                );
             });
 
             ui.horizontal(|ui| {
                 ui.label("Silver Goal: ");
-                ui.add(egui::DragValue::new(&mut self.gamification.silver_goal).speed(1).range(self.gamification.bronze_goal+1..=self.gamification.gold_goal-1));
+                ui.add(
+                    egui::DragValue::new(&mut silver_goal)
+                        .speed(1)
+                        .range(bronze_goal + 1..=gold_goal - 1),
+                );
             });
 
             ui.horizontal(|ui| {
                 ui.label("Gold Goal: ");
-                ui.add(egui::DragValue::new(&mut self.gamification.gold_goal).speed(1).range(self.gamification.silver_goal+1..=100));
+                ui.add(
+                    egui::DragValue::new(&mut gold_goal)
+                        .speed(1)
+                        .range(silver_goal + 1..=100),
+                );
             });
 
+            let current_bonus = gamification.unblock_bonus;
+            let mut unblock_bonus = current_bonus;
+            ui.checkbox(
+                &mut unblock_bonus,
+                format!(
+                    "Earn {UNBLOCK_BONUS} bonus points for every task a completed task unblocks"
+                ),
+            );
+            if gamification.unblock_bonus_points > 0 {
+                ui.label(format!(
+                    "Unblock Bonus: {} points",
+                    gamification.unblock_bonus_points
+                ));
+            }
+
+            // Apply changed goals through the store, which checks that they stay in order
+            if (bronze_goal, silver_goal, gold_goal)
+                != (
+                    gamification.bronze_goal,
+                    gamification.silver_goal,
+                    gamification.gold_goal,
+                )
+            {
+                // dragging a goal changes it every frame, which is undone in one step
+                let before = self.store.clone();
+                match self.store.set_goals(bronze_goal, silver_goal, gold_goal) {
+                    Ok(()) => {
+                        self.journal.record(&before, &self.store, self.clock.now());
+                        self.history
+                            .record_coalescing("Change goals", &before, &self.store);
+                    }
+                    Err(err) => log::warn!("Failed to update goals: {err}"),
+                }
+            }
+            if unblock_bonus != current_bonus {
+                let before = self.store.clone();
+                self.store.set_unblock_bonus(unblock_bonus);
+                self.record(
+                    if unblock_bonus {
+                        "Turn on the unblock bonus"
+                    } else {
+                        "Turn off the unblock bonus"
+                    },
+                    before,
+                );
+                self.update_achievements();
+            }
+
+            egui::CollapsingHeader::new("Scoring").show(ui, |ui| self.scoring_logic(ui));
+
             ui.separator();
+            self.project_goals_logic(ui);
+
+            ui.separator();
+            egui::CollapsingHeader::new("Points Ledger").show(ui, |ui| self.ledger_logic(ui));
+        });
+    }
+
+    /// This function shows how the points of a task are worked out, step by step. For a completed
+    /// task these are the points it was paid when it was completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui of the task details.
+    /// * `task` - The selected task.
+    ///
+    fn score_logic(&self, ui: &mut egui::Ui, task: &Task) {
+        let score = task.score_with(&self.store.gamification().scoring);
+        let title = if task.completed {
+            format!("Points paid: {}", score.points)
+        } else {
+            format!("Points: {}", score.points)
+        };
+        egui::CollapsingHeader::new(title)
+            .id_source(("task_score", task.id))
+            .show(ui, |ui| {
+                egui::Grid::new(("score_steps", task.id))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for step in &score.steps {
+                            ui.label(&step.label);
+                            ui.label(step.effect.to_string());
+                            ui.end_row();
+                        }
+                        ui.strong("Total");
+                        ui.strong(score.points.to_string());
+                        ui.end_row();
+                    });
+            });
+    }
+
+    /// This function handles the logic for the scoring settings in the right panel.
+    /// It chooses the formula the points of a task are worked out with, and the weights of the
+    /// weighted formula. The changes are applied together with a button and score the tasks that
+    /// are still open; completed tasks keep the points they were paid.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui to draw the settings in.
+    ///
+    fn scoring_logic(&mut self, ui: &mut egui::Ui) {
+        let mut scoring = self
+            .scoring_draft
+            .take()
+            .unwrap_or_else(|| self.store.gamification().scoring.clone());
+        ui.radio_value(
+            &mut scoring.formula,
+            Formula::Priority,
+            "Priority: 10, 20 or 30 points",
+        );
+        ui.radio_value(
+            &mut scoring.formula,
+            Formula::Weighted,
+            "Weighted: effort, difficulty, timing and tags",
+        );
+        ui.add_enabled_ui(scoring.formula == Formula::Weighted, |ui| {
+            ui.add(
+                egui::DragValue::new(&mut scoring.effort_points)
+                    .range(0..=100)
+                    .suffix(" points an hour of effort"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut scoring.early_multiplier)
+                    .range(0.0..=10.0)
+                    .speed(0.05)
+                    .prefix("Early ×"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut scoring.late_multiplier)
+                    .range(0.0..=10.0)
+                    .speed(0.05)
+                    .prefix("Late ×"),
+            );
+            let mut removed = None;
+            for (tag, multiplier) in &scoring.tag_multipliers {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Tag {tag} ×{multiplier}"));
+                    if ui.small_button("Remove").clicked() {
+                        removed = Some(tag.clone());
+                    }
+                });
+            }
+            if let Some(tag) = removed {
+                scoring.set_tag_multiplier(&tag, 1.0);
+            }
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.scoring_tag)
+                        .hint_text("tag")
+                        .desired_width(80.0),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.scoring_multiplier)
+                        .range(0.0..=10.0)
+                        .speed(0.05)
+                        .prefix("×"),
+                );
+                if ui
+                    .add_enabled(
+                        !self.scoring_tag.trim().is_empty(),
+                        egui::Button::new("Set"),
+                    )
+                    .clicked()
+                {
+                    scoring.set_tag_multiplier(self.scoring_tag.trim(), self.scoring_multiplier);
+                    self.scoring_tag.clear();
+                }
+            });
+        });
+        if scoring == self.store.gamification().scoring {
+            return;
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Apply").clicked() {
+                let before = self.store.clone();
+                self.store.set_scoring(scoring.clone());
+                self.record("Change the scoring", before);
+                self.update_achievements();
+            } else if !ui.button("Discard").clicked() {
+                self.scoring_draft = Some(scoring.clone());
+            }
+        });
+    }
+
+    /// This function handles the logic for the trophy case in the right panel.
+    /// It lists the unlocked achievements with when they were unlocked, then the ones still to reach.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui to draw the trophy case in.
+    ///
+    fn trophy_case_logic(&self, ui: &mut egui::Ui) {
+        let achievements = &self.store.gamification().achievements;
+        for unlocked in achievements.unlocked() {
+            let description = achievements
+                .rules()
+                .iter()
+                .find(|rule| rule.id == unlocked.id)
+                .map_or("", |rule| rule.description.as_str());
+            ui.label(format!(
+                "🏆 {} ({})",
+                unlocked.name,
+                unlocked.at.format("%Y-%m-%d")
+            ))
+            .on_hover_text(description);
+        }
+        for rule in achievements.rules().iter().filter(|rule| {
+            rule.kind != AchievementKind::Daily && !achievements.is_unlocked(&rule.id)
+        }) {
+            ui.weak(format!("🔒 {}: {}", rule.name, rule.description));
+        }
+    }
+
+    /// This function handles the logic for the points ledger in the right panel.
+    /// It lists every change to the points, newest first, and changes them by hand.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui to draw the ledger in.
+    ///
+    fn ledger_logic(&mut self, ui: &mut egui::Ui) {
+        let ledger = self.store.ledger();
+        ui.label(format!("Balance: {} points", ledger.balance()));
+        egui::ScrollArea::vertical()
+            .id_source("points_ledger")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                if ledger.is_empty() {
+                    ui.label("No points earned yet.");
+                }
+                for transaction in ledger.transactions().iter().rev() {
+                    ui.label(format!(
+                        "{} {:+} {}: {}",
+                        transaction.at.format("%Y-%m-%d %H:%M"),
+                        transaction.amount,
+                        transaction.kind,
+                        transaction.reason
+                    ))
+                    .on_hover_text(&transaction.key);
+                }
+            });
+
+        // Adjustments are kept like every other transaction, so they can't be undone
+        ui.horizontal(|ui| {
+            ui.label("Adjust by: ");
+            ui.add(egui::DragValue::new(&mut self.adjust_amount).speed(1));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Reason: ");
+            ui.text_edit_singleline(&mut self.adjust_reason);
         });
+        if let Some(err) = &self.adjust_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        if ui.button("Adjust Points").clicked() {
+            match self.adjust_points(self.adjust_amount, &self.adjust_reason.clone()) {
+                Ok(()) => {
+                    self.adjust_amount = 0;
+                    self.adjust_reason.clear();
+                    self.adjust_error = None;
+                }
+                Err(err) => self.adjust_error = Some(err.to_string()),
+            }
+        }
     }
 
     /// This function handles the logic for the central panel of the UI.
@@ -312,8 +2420,10 @@ impl TemplateApp {
             // The central panel the region left after adding TopPanel's and SidePanel's
 
             egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.heading(format!("Challenges (Points: {})", self.gamification.points));
-
+                ui.heading(format!(
+                    "Challenges (Points: {})",
+                    self.store.gamification().points
+                ));
             });
             ui.separator();
             // Display the status on daily rewards
@@ -323,9 +2433,12 @@ impl TemplateApp {
             ui.label("Complete 5, 10, or 15 tasks in a day to earn rewards!");
             ui.separator();
             // Display the daily reward message
-            ui.label(&self.gamification.daily_reward_message);
+            ui.label(&self.store.gamification().daily_reward_message);
             // Display the daily reward points
-            ui.label(format!("Daily Reward Points: {}", self.gamification.daily_reward));
+            ui.label(format!(
+                "Daily Reward Points: {}",
+                self.store.gamification().daily_reward
+            ));
             // Display a message saying that daily points reset at the end of the day
             ui.label("Daily points reset at the end of the day.");
 
@@ -333,9 +2446,111 @@ impl TemplateApp {
             // Display the weekly challenge message
             ui.heading("Weekly Challenge");
             ui.separator();
-            ui.label(&self.gamification.weekly_challenge_message);
+            ui.label(&self.store.gamification().weekly_challenge_message);
+
+            ui.separator();
+            self.streak_logic(ui);
+        });
+    }
+
+    /// This function handles the logic for the streak in the central panel.
+    /// It shows the current and longest streak and a calendar of the last weeks, chooses the
+    /// rest days and buys streak freezes.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The egui::Ui to draw the streak in.
+    ///
+    fn streak_logic(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Streak");
+        ui.separator();
+        let gamification = self.store.gamification();
+        let streak = &gamification.streak;
+        match streak.start {
+            Some(start) => ui.label(format!(
+                "Current streak: {} day(s), since {}",
+                streak.current,
+                start.format("%b %-d")
+            )),
+            None => ui.label("Complete a task today to start a streak!"),
+        };
+        ui.label(format!("Longest streak: {} day(s)", streak.longest));
+
+        // a week per row from Monday, colored by what each day was
+        egui::Grid::new("streak_calendar")
+            .spacing([4.0, 4.0])
+            .show(ui, |ui| {
+                for name in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"] {
+                    ui.weak(name);
+                }
+                ui.end_row();
+                for week in streak.calendar(5) {
+                    for (day, status) in week {
+                        let (color, description) = match status {
+                            DayStatus::Completed => {
+                                (egui::Color32::from_rgb(60, 160, 80), "Completed a task")
+                            }
+                            DayStatus::Frozen => (
+                                egui::Color32::from_rgb(90, 160, 220),
+                                "Protected by a streak freeze",
+                            ),
+                            DayStatus::Rest => (egui::Color32::GRAY, "Rest day"),
+                            DayStatus::Missed => (egui::Color32::from_rgb(200, 80, 80), "Missed"),
+                            DayStatus::Upcoming => (ui.visuals().weak_text_color(), ""),
+                        };
+                        let label = ui.colored_label(color, day.format("%-d").to_string());
+                        if !description.is_empty() {
+                            label.on_hover_text(format!(
+                                "{}: {description}",
+                                day.format("%a %b %-d")
+                            ));
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
 
+        // rest days neither count toward the streak nor break it
+        let mut rest_days = gamification.rest_days.clone();
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Rest days: ");
+            for day in [
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+                Weekday::Sat,
+                Weekday::Sun,
+            ] {
+                let mut rest = rest_days.contains(&day);
+                if ui.checkbox(&mut rest, day.to_string()).changed() {
+                    rest_days.retain(|other| *other != day);
+                    if rest {
+                        rest_days.push(day);
+                    }
+                }
+            }
+        });
+        if rest_days != gamification.rest_days {
+            let before = self.store.clone();
+            self.store.set_rest_days(rest_days);
+            self.record("Change the rest days", before);
+        }
+
+        let freezes = self.store.gamification().streak.freezes;
+        ui.horizontal(|ui| {
+            ui.label(format!("Streak freezes: {freezes}/{MAX_FREEZES}"));
+            if ui
+                .button(format!("Buy a Streak Freeze ({FREEZE_COST} points)"))
+                .clicked()
+            {
+                self.streak_error = self.buy_streak_freeze().err().map(|err| err.to_string());
+            }
         });
+        if let Some(err) = &self.streak_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
     }
 
     /// This function handles the logic for the bottom panel of the UI.
@@ -351,77 +2566,20 @@ impl TemplateApp {
             if ui.button("Tasks Report").clicked() {self.details_report_viewable = true;}
             if self.details_report_viewable {
                 // put a report of metrics of tasks, including total number, % completed, and % of each priority level on the screen
-                let total_tasks = self.tasks.len();
-                let completed_tasks = self.tasks.iter().filter(|task| task.completed).count();
-                let low_priority_tasks = self.tasks.iter().filter(|task| task.priority == PriorityLevel::Low).count();
-                let medium_priority_tasks = self.tasks.iter().filter(|task| task.priority == PriorityLevel::Medium).count();
-                let high_priority_tasks = self.tasks.iter().filter(|task| task.priority == PriorityLevel::High).count();
-                let completed_percentage = (completed_tasks as f32 / total_tasks as f32) * 100.0;
-                let low_priority_percentage = (low_priority_tasks as f32 / total_tasks as f32) * 100.0;
-                let medium_priority_percentage = (medium_priority_tasks as f32 / total_tasks as f32) * 100.0;
-                let high_priority_percentage = (high_priority_tasks as f32 / total_tasks as f32) * 100.0;
-                // put the above metrics on the screen
-                //To add new details about the tasks like Total Points Earned,Incomplete Tasks,Average Task Priority,Most Common Task Priority,Upcoming Tasks
-                 // 1. Total Points Earned
-                let total_points: u32 = self.tasks.iter()
-                .filter(|task| task.completed)
-                .map(|task| task.points())
-                .sum();
-                // 2. Incomplete Tasks
-                let incomplete_tasks = total_tasks - completed_tasks;
-                let incomplete_percentage = if total_tasks > 0 {
-                    (incomplete_tasks as f32 / total_tasks as f32) * 100.0
+                ui.checkbox(
+                    &mut self.report_listed_only,
+                    "Only the tasks matching the search",
+                );
+                let today = self.clock.today();
+                let report = if self.report_listed_only {
+                    self.store.report_matching(&self.query, today)
                 } else {
-                    0.0
+                    self.store.report(today)
                 };
-                // 3. Average Task Priority
-                let total_priority: u32 = self.tasks.iter()
-                .map(|task| task.priority as u32)
-                .sum();
-                let avg_priority = if total_tasks > 0 {
-                    total_priority as f32 / total_tasks as f32
-                } else {
-                    0.0
-                };
-                 // 4. Most Common Task Priority
-                let mut priority_counts = [0; 3]; // For Low, Medium, High
-                for task in &self.tasks {
-                    match task.priority {
-                        PriorityLevel::Low => priority_counts[0] += 1,
-                        PriorityLevel::Medium => priority_counts[1] += 1,
-                        PriorityLevel::High => priority_counts[2] += 1,
-                    }
-                }
-                let most_common_priority = if priority_counts[0] >= priority_counts[1] && priority_counts[0] >= priority_counts[2] {
-                    "Low"
-                } else if priority_counts[1] >= priority_counts[0] && priority_counts[1] >= priority_counts[2] {
-                    "Medium"
-                } else {
-                    "High"
-                };
-                 // 5. Upcoming Tasks (with future due dates)
-                let upcoming_tasks = self.tasks.iter()
-                 .filter(|task| {
-                     // Parse the due date in the format MM.DD.YYYY
-                     if let Ok(due_date) = chrono::NaiveDate::parse_from_str(&task.due_date, "%m.%d.%Y") {
-                         due_date > chrono::Local::now().naive_local().date() // Compare the due date with the current date
-                     } else {
-                         false // If the date parsing fails, it's not an upcoming task
-                     }
-                })
-                 .count();
-                ui.label(format!("Total Tasks: {}", total_tasks));
-                ui.label(format!("Completed Tasks: {} ({:.2}%)", completed_tasks, completed_percentage));
-                ui.label(format!("Low Priority Tasks: {} ({:.2}%)", low_priority_tasks, low_priority_percentage));
-                ui.label(format!("Medium Priority Tasks: {} ({:.2}%)", medium_priority_tasks, medium_priority_percentage));
-                ui.label(format!("High Priority Tasks: {} ({:.2}%)", high_priority_tasks, high_priority_percentage));
-                //New metric
-                ui.label(format!("Total Points Earned: {}", total_points));
-                ui.label(format!("Incomplete Tasks: {} ({:.2}%)", incomplete_tasks, incomplete_percentage));
-                ui.label(format!("Average Task Priority: {:.2}", avg_priority));
-                ui.label(format!("Most Common Task Priority: {}", most_common_priority));
-                ui.label(format!("Upcoming Tasks: {}", upcoming_tasks));
-                 //add a button to close the report
+                for line in report.lines() {
+                    ui.label(line);
+                }
+                //add a button to close the report
                 if ui.button("Close Report").clicked() {
                     self.details_report_viewable = false;
                 }
@@ -437,14 +2595,18 @@ impl TemplateApp {
 /// Implement the eframe::App trait for the TemplateApp struct.
 /// This trait provides the necessary methods to run the app and update the UI.
 impl eframe::App for TemplateApp {
-    /// Save the app state to storage.
-    /// 
+    /// Save the app state to storage in the versioned format.
+    /// A backup of saved data that could not be loaded is written first, so it is never lost.
+    ///
     /// # Arguments
     /// 
     /// * `storage` - The eframe::Storage to save the app state.
     /// 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+        if let Some(backup) = self.pending_backup.take() {
+            storage::write_backup(storage, &backup);
+        }
+        storage::save(storage, self);
     }
 
     /// Update the app state and UI.
@@ -455,6 +2617,15 @@ impl eframe::App for TemplateApp {
     /// * `frame` - The eframe::Frame for the UI.
     /// 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Ctrl+Z and Ctrl+Shift+Z undo and redo, unless a text field is using them
+        if !ctx.wants_keyboard_input() {
+            // the redo shortcut is checked first, since Ctrl+Z also matches with Shift held
+            if ctx.input_mut(|input| input.consume_shortcut(&REDO_SHORTCUT)) {
+                self.redo();
+            } else if ctx.input_mut(|input| input.consume_shortcut(&UNDO_SHORTCUT)) {
+                self.undo();
+            }
+        }
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             // The top panel is often a good place for a menu bar:
@@ -464,15 +2635,72 @@ impl eframe::App for TemplateApp {
                 let is_web = cfg!(target_arch = "wasm32");
                 if !is_web {
                     ui.menu_button("File", |ui| {
+                        for (label, kind) in [
+                            ("Export Tasks (JSON)…", TransferKind::ExportJson),
+                            ("Import Tasks (JSON)…", TransferKind::ImportJson),
+                            ("Export Tasks (CSV)…", TransferKind::ExportCsv),
+                            ("Export Tasks Report (CSV)…", TransferKind::ExportReportCsv),
+                            ("Import Tasks (CSV)…", TransferKind::ImportCsv),
+                            ("Export Tasks (iCalendar)…", TransferKind::ExportIcs),
+                            ("Import Tasks (iCalendar)…", TransferKind::ImportIcs),
+                            ("Export Tasks (todo.txt)…", TransferKind::ExportTodoTxt),
+                            ("Import Tasks (todo.txt)…", TransferKind::ImportTodoTxt),
+                            ("Load Achievement Rules…", TransferKind::LoadAchievements),
+                            ("Load Level Curve…", TransferKind::LoadLevels),
+                        ] {
+                            if ui.button(label).clicked() {
+                                self.transfer_window = Some(TransferWindow::new(kind));
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
                         if ui.button("Quit").clicked() {
                             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                         }
                     });
                     ui.add_space(16.0);
                 }
+                self.edit_menu_logic(ui);
+                if cfg!(debug_assertions) {
+                    self.debug_menu_logic(ui);
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    self.level_badge_logic(ui);
+                });
             });
         });
 
+        // celebrate a level reached for the first time
+        if let Some(level) = self.level_up {
+            egui::Window::new("Level Up!")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.heading(format!("🎉 You reached level {level}!"));
+                    let current = self.store.gamification().level;
+                    if current.next > current.start {
+                        ui.label(format!("The next level starts at {} XP.", current.next));
+                    }
+                    if ui.button("OK").clicked() {
+                        self.level_up = None;
+                    }
+                });
+        }
+
+        // warn the user if their saved tasks could not be loaded
+        if let Some(warning) = self.load_warning.clone() {
+            egui::Window::new("Saved data could not be loaded")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(warning);
+                    if ui.button("OK").clicked() {
+                        self.load_warning = None;
+                    }
+                });
+        }
+
+        // export/import window
+        self.transfer_window_logic(ctx);
+
         // task creation/ info UI
         self.left_panel_logic(ctx);
 
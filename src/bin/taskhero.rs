@@ -0,0 +1,2724 @@
+//! Command-line interface for TaskHero.
+//!
+//! `taskhero` works on the same saved data as the GUI, through the same TaskStore, so completing
+//! a task from the shell awards the same points and achievement messages the app shows.
+//! Run without a command to open the full-screen terminal UI.
+//! Changes made while the GUI is open are overwritten when the GUI saves, so close it first.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    cli::main()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cli {
+    use std::fmt::Write as _;
+    use std::path::{Path, PathBuf};
+    use std::process::ExitCode;
+
+    use chrono::{NaiveDate, Weekday};
+    use clap::{Args, Parser, Subcommand};
+    use task_hero::achievements::{self, AchievementKind, RulesError};
+    use task_hero::clock::Clock;
+    use task_hero::csv_transfer::{self, CsvFile, TaskField};
+    use task_hero::dates::{self, DueDate};
+    use task_hero::gamification::{PriorityLevel, Task};
+    use task_hero::ical::{self, IcsComponent};
+    use task_hero::levels::{self, LevelConfigError};
+    use task_hero::query::{SortKey, Status, TaskQuery};
+    use task_hero::query_language::{Expr, QUERY_HINT};
+    use task_hero::recurrence::Recurrence;
+    use task_hero::scoring::{self, Difficulty, Formula};
+    use task_hero::storage::{self, FileStorage};
+    use task_hero::store::{NewTask, StoreError, TaskStore, TaskUpdate};
+    use task_hero::streaks::{self, DayStatus};
+    use task_hero::tags;
+    use task_hero::task_list::TaskId;
+    use task_hero::todotxt;
+    use task_hero::transfer::{self, ImportError, ImportMode};
+    use task_hero::TemplateApp;
+
+    #[derive(Parser)]
+    #[command(
+        name = "taskhero",
+        version,
+        about = "Manage your TaskHero tasks from the terminal"
+    )]
+    /// Command line arguments
+    struct Cli {
+        /// Directory holding app.ron, defaults to the directory the GUI saves to
+        #[arg(long, global = true)]
+        data_dir: Option<PathBuf>,
+        /// Run as if it were another day, to see the rewards and reports of that day
+        #[arg(long, global = true, value_name = "DATE", value_parser = parse_date)]
+        simulate_date: Option<NaiveDate>,
+        /// Run without a command to open the full-screen terminal UI
+        #[command(subcommand)]
+        command: Option<Command>,
+    }
+
+    #[derive(Subcommand, Debug, Clone, PartialEq)]
+    /// The subcommands of the CLI
+    pub enum Command {
+        /// Add a task
+        Add {
+            name: String,
+            #[arg(short, long, default_value = "")]
+            description: String,
+            /// Due date, such as 2024-11-23, 11/23/2024 14:30 or tomorrow
+            #[arg(long, value_parser = parse_due_date)]
+            due: Option<String>,
+            #[arg(short, long, default_value = "low")]
+            priority: PriorityLevel,
+            /// Repeat the task, such as daily, weekdays, "every 2 weeks", "monthly on the 15th" or an RRULE
+            #[arg(long)]
+            repeat: Option<Recurrence>,
+            /// Add the task as a subtask of another task
+            #[arg(long)]
+            parent: Option<TaskId>,
+            /// A task that has to be completed before this one can start, can be repeated
+            #[arg(long = "depends-on", value_name = "ID")]
+            depends_on: Vec<TaskId>,
+            /// The project the task belongs to
+            #[arg(long)]
+            project: Option<String>,
+            /// A tag for the task, can be repeated
+            #[arg(long = "tag", value_name = "TAG")]
+            tags: Vec<String>,
+        },
+        /// List tasks, with subtasks indented under their parent
+        List {
+            #[command(flatten)]
+            query: QueryArgs,
+            /// List the tasks under their project
+            #[arg(long)]
+            group: bool,
+            /// Save the search, filters and sort order under a name, to use again with --filter
+            #[arg(long, value_name = "NAME")]
+            save: Option<String>,
+        },
+        /// Mark a task as completed
+        Done { id: TaskId },
+        /// Change the fields of a task
+        Edit {
+            id: TaskId,
+            #[arg(short, long)]
+            name: Option<String>,
+            #[arg(short, long)]
+            description: Option<String>,
+            #[arg(long, value_parser = parse_due_date, conflicts_with = "no_due")]
+            due: Option<String>,
+            /// Remove the due date
+            #[arg(long)]
+            no_due: bool,
+            #[arg(short, long)]
+            priority: Option<PriorityLevel>,
+            #[arg(long, conflicts_with = "no_repeat")]
+            repeat: Option<Recurrence>,
+            /// Stop repeating the task
+            #[arg(long)]
+            no_repeat: bool,
+            /// Move the task under another task
+            #[arg(long, conflicts_with = "no_parent")]
+            parent: Option<TaskId>,
+            /// Move the task to the top level
+            #[arg(long)]
+            no_parent: bool,
+            /// Whether the task completes itself once all its subtasks and checklist items are done
+            #[arg(long, value_name = "true|false")]
+            auto_complete: Option<bool>,
+            /// Move the task to a project
+            #[arg(long, conflicts_with = "no_project")]
+            project: Option<String>,
+            /// Take the task out of its project
+            #[arg(long)]
+            no_project: bool,
+            /// Add a tag, can be repeated
+            #[arg(long = "tag", value_name = "TAG")]
+            tags: Vec<String>,
+            /// Remove a tag, can be repeated
+            #[arg(long = "untag", value_name = "TAG")]
+            untags: Vec<String>,
+            /// Estimated effort in hours, scored by the weighted formula
+            #[arg(long, value_name = "HOURS", conflicts_with = "no_effort")]
+            effort: Option<u32>,
+            /// Remove the estimated effort
+            #[arg(long)]
+            no_effort: bool,
+            /// Easy, normal or hard, scored by the weighted formula
+            #[arg(long)]
+            difficulty: Option<Difficulty>,
+            /// Set the points of the task by hand, whatever the scoring formula
+            #[arg(long, value_name = "POINTS", conflicts_with = "no_points")]
+            points: Option<u32>,
+            /// Work out the points of the task with the scoring formula again
+            #[arg(long)]
+            no_points: bool,
+        },
+        /// Delete a task and its subtasks
+        Rm { id: TaskId },
+        /// Add an item to a task's checklist
+        ItemAdd { id: TaskId, text: String },
+        /// Check off a checklist item, numbered from 1 as in `list`
+        ItemDone {
+            id: TaskId,
+            #[arg(value_parser = parse_item_number)]
+            number: usize,
+        },
+        /// Uncheck a checklist item
+        ItemUndo {
+            id: TaskId,
+            #[arg(value_parser = parse_item_number)]
+            number: usize,
+        },
+        /// Remove a checklist item
+        ItemRm {
+            id: TaskId,
+            #[arg(value_parser = parse_item_number)]
+            number: usize,
+        },
+        /// Make a task wait until another task is completed
+        DepAdd { id: TaskId, prerequisite: TaskId },
+        /// Stop a task waiting for another task
+        DepRm { id: TaskId, prerequisite: TaskId },
+        /// Show the Tasks Report metrics
+        Report {
+            /// Only report on the tasks that match a saved filter
+            #[arg(long, value_name = "NAME")]
+            filter: Option<String>,
+        },
+        /// List the saved filters
+        Filters,
+        /// Delete a saved filter
+        FilterRm { name: String },
+        /// Set how many tasks to complete in a project, 0 removes the goal
+        Goal { project: String, goal: u32 },
+        /// Undo the last change to the tasks, goals or saved filters
+        Undo,
+        /// Redo the last undone change
+        Redo,
+        /// List the changes that can be undone, most recent first
+        History {
+            /// Keep the undo history between runs, so `undo` can undo the changes of earlier commands
+            #[arg(long, value_name = "true|false")]
+            keep: Option<bool>,
+        },
+        /// Show the journal of every change made, oldest first
+        Log {
+            /// Only show the changes to one task, including after it was deleted
+            #[arg(long)]
+            task: Option<TaskId>,
+            /// Count the completions of every day instead, including tasks reopened or deleted since
+            #[arg(long, conflicts_with = "task")]
+            completions: bool,
+        },
+        /// Show points, rewards and progress toward the goals
+        Points {
+            /// Turn the bonus for completing tasks that other tasks wait for on or off
+            #[arg(long, value_name = "true|false")]
+            unblock_bonus: Option<bool>,
+        },
+        /// Show every change to the points, or change them by hand
+        Ledger {
+            /// Add points by hand, or take them away with a negative number
+            #[arg(long, allow_negative_numbers = true, requires = "reason")]
+            adjust: Option<i64>,
+            /// Why the points are changed
+            #[arg(long, requires = "adjust")]
+            reason: Option<String>,
+        },
+        /// Show the streak and its calendar, choose rest days or buy a streak freeze
+        Streak {
+            /// Days of the week that don't break a streak, such as sat,sun, or "" for none
+            #[arg(long, value_name = "DAYS", value_parser = streaks::parse_rest_days)]
+            rest_days: Option<std::vec::Vec<Weekday>>, // the full path keeps clap from reading it as a repeated argument
+            /// Spend points on a streak freeze, which protects the streak on a day without a completion
+            #[arg(long)]
+            buy_freeze: bool,
+            /// Number of weeks of the calendar to show
+            #[arg(long, default_value_t = 4)]
+            weeks: u32,
+        },
+        /// Show the trophy case, or load the achievement rules from a JSON file
+        Achievements {
+            /// JSON file with the rules, in the format of the standard rules
+            #[arg(long, conflicts_with = "standard")]
+            rules: Option<PathBuf>,
+            /// Go back to the standard rules
+            #[arg(long)]
+            standard: bool,
+        },
+        /// Show or change how the points of a task are worked out
+        Scoring {
+            /// Priority (10, 20 or 30 points by priority) or weighted (with effort, difficulty, timing and tags)
+            #[arg(long)]
+            formula: Option<Formula>,
+            /// Points for every hour of estimated effort
+            #[arg(long, value_name = "POINTS")]
+            effort_points: Option<u32>,
+            /// Multiplier for a task completed before the day it was due
+            #[arg(long, value_name = "MULTIPLIER", value_parser = scoring::parse_multiplier)]
+            early: Option<f64>,
+            /// Multiplier for a task completed after the day it was due
+            #[arg(long, value_name = "MULTIPLIER", value_parser = scoring::parse_multiplier)]
+            late: Option<f64>,
+            /// Multiply the points of tasks with a tag, such as urgent=2; 1 removes it; can be repeated
+            #[arg(long = "tag-multiplier", value_name = "TAG=MULTIPLIER", value_parser = scoring::parse_tag_multiplier)]
+            tag_multipliers: Vec<(String, f64)>,
+            /// Show how the points of a task are worked out, or were paid for a completed task
+            #[arg(long, value_name = "ID")]
+            task: Option<TaskId>,
+        },
+        /// Show the level and XP, or load the level curve from a JSON file
+        Level {
+            /// JSON file with the level curve and XP sources, in the format of the standard ones
+            #[arg(long, value_name = "FILE", conflicts_with = "standard")]
+            config: Option<PathBuf>,
+            /// Go back to the standard level curve
+            #[arg(long)]
+            standard: bool,
+        },
+        /// Export every task and the goals as JSON
+        Export {
+            /// File to write, defaults to standard output
+            #[arg(short, long)]
+            output: Option<PathBuf>,
+        },
+        /// Import tasks from a JSON export
+        Import {
+            file: PathBuf,
+            /// Replace every task and the goals instead of merging
+            #[arg(long)]
+            replace: bool,
+        },
+        /// Export every task, or the Tasks Report metrics, as CSV
+        ExportCsv {
+            /// Export the Tasks Report metrics instead of the tasks
+            #[arg(long)]
+            report: bool,
+            /// File to write, defaults to standard output
+            #[arg(short, long)]
+            output: Option<PathBuf>,
+        },
+        /// Import tasks from a CSV file with a header row
+        ImportCsv {
+            file: PathBuf,
+            /// Read a task field from a column, such as `name=Title` or `due_date=3` (columns count from 1).
+            /// Columns are otherwise matched to fields by their header.
+            #[arg(long = "map", value_name = "FIELD=COLUMN", value_parser = parse_column_mapping)]
+            mappings: Vec<(TaskField, String)>,
+            /// Only show which rows would be imported and which fail to parse
+            #[arg(long)]
+            dry_run: bool,
+        },
+        /// Export tasks as an iCalendar (.ics) file for calendar clients
+        ExportIcs {
+            /// Export tasks with a due date as events instead of every task as a to-do
+            #[arg(long)]
+            events: bool,
+            /// File to write, defaults to standard output
+            #[arg(short, long)]
+            output: Option<PathBuf>,
+        },
+        /// Import the to-dos (VTODO) of an iCalendar file as tasks, skipping duplicates
+        ImportIcs { file: PathBuf },
+        /// Export every task as a todo.txt file
+        ExportTodotxt {
+            /// File to write, defaults to standard output
+            #[arg(short, long)]
+            output: Option<PathBuf>,
+        },
+        /// Import the tasks of a todo.txt file, skipping duplicates
+        ImportTodotxt { file: PathBuf },
+        /// Merge a todo.txt file into your tasks and write your tasks back to it
+        SyncTodotxt {
+            file: PathBuf,
+            /// Keep syncing until interrupted, carrying over every later change to the file or your tasks
+            #[arg(long)]
+            watch: bool,
+        },
+    }
+
+    #[derive(Args, Debug, Clone, PartialEq)]
+    /// The search, filter and sort options of `list`
+    pub struct QueryArgs {
+        /// Start from a saved filter; the other options change it
+        #[arg(long, value_name = "NAME")]
+        filter: Option<String>,
+        /// Only show tasks whose name or description contains the words, or that match a
+        /// query; a search that isn't a valid query looks for the text as written
+        #[arg(short, long)]
+        search: Option<String>,
+        /// Only show tasks that match a query such as
+        /// `priority>=medium and due<+3d and not completed and tag:backend`
+        #[arg(short = 'w', long = "where", value_name = "QUERY", value_parser = parse_query, conflicts_with = "search", long_help = QUERY_HINT)]
+        query: Option<String>,
+        /// Only show completed tasks
+        #[arg(long, conflicts_with_all = ["pending", "ready", "blocked"])]
+        completed: bool,
+        /// Only show tasks that are not completed
+        #[arg(long, conflicts_with_all = ["ready", "blocked"])]
+        pending: bool,
+        /// Only show tasks ready to work on: not completed and not waiting for another task
+        #[arg(long, conflicts_with = "blocked")]
+        ready: bool,
+        /// Only show open tasks that are waiting for another task
+        #[arg(long)]
+        blocked: bool,
+        /// Only show tasks of a priority
+        #[arg(short, long)]
+        priority: Option<PriorityLevel>,
+        /// Only show the tasks of a project
+        #[arg(long)]
+        project: Option<String>,
+        /// Only show tasks with a tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only show tasks due before a day
+        #[arg(long, value_parser = parse_due_date, value_name = "DATE")]
+        due_before: Option<String>,
+        /// Only show tasks due after a day
+        #[arg(long, value_parser = parse_due_date, value_name = "DATE")]
+        due_after: Option<String>,
+        /// Only show open tasks that were due before today
+        #[arg(long)]
+        overdue: bool,
+        /// Sort by tree (the default, with subtasks under their parent), due, priority, created or points
+        #[arg(long)]
+        sort: Option<SortKey>,
+        /// Reverse the sort order
+        #[arg(long)]
+        reverse: bool,
+    }
+
+    /// Implementation of QueryArgs with a method to turn the options into a query
+    impl QueryArgs {
+        /// Helper function to build the query, starting from the saved filter if one is named;
+        /// relative due dates are read against `today`
+        fn into_query(self, store: &TaskStore, today: NaiveDate) -> Result<TaskQuery, CliError> {
+            let mut query = match &self.filter {
+                Some(name) => store
+                    .filters()
+                    .get(name.trim())
+                    .cloned()
+                    .ok_or_else(|| CliError::UnknownFilter(name.clone()))?,
+                None => TaskQuery::default(),
+            };
+            if let Some(text) = self.search.or(self.query) {
+                query.text = text;
+            }
+            for (set, status) in [
+                (self.completed, Status::Completed),
+                (self.pending, Status::Open),
+                (self.ready, Status::Ready),
+                (self.blocked, Status::Blocked),
+            ] {
+                if set {
+                    query.status = status;
+                }
+            }
+            query.priority = self.priority.or(query.priority);
+            query.project = self.project.or(query.project);
+            query.tag = self.tag.or(query.tag);
+            query.due_before = self
+                .due_before
+                .map(|input| resolve_due_date(&input, today).local_date())
+                .or(query.due_before);
+            query.due_after = self
+                .due_after
+                .map(|input| resolve_due_date(&input, today).local_date())
+                .or(query.due_after);
+            query.overdue |= self.overdue;
+            query.sort = self.sort.unwrap_or(query.sort);
+            query.reverse ^= self.reverse;
+            Ok(query)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    /// Errors returned by CLI commands
+    pub enum CliError {
+        Store(StoreError),
+        Import(ImportError),
+        Io(String),
+        UnknownFilter(String),
+        Rules(RulesError),
+        Levels(LevelConfigError),
+    }
+
+    impl std::fmt::Display for CliError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                CliError::Store(err) => write!(f, "{err}"),
+                CliError::Import(err) => write!(f, "{err}"),
+                CliError::Io(message) => write!(f, "{message}"),
+                CliError::UnknownFilter(name) => {
+                    write!(f, "There is no saved filter named {name:?}.")
+                }
+                CliError::Rules(err) => write!(f, "{err}"),
+                CliError::Levels(err) => write!(f, "{err}"),
+            }
+        }
+    }
+
+    impl From<StoreError> for CliError {
+        fn from(err: StoreError) -> Self {
+            CliError::Store(err)
+        }
+    }
+
+    impl From<ImportError> for CliError {
+        fn from(err: ImportError) -> Self {
+            CliError::Import(err)
+        }
+    }
+
+    impl From<RulesError> for CliError {
+        fn from(err: RulesError) -> Self {
+            CliError::Rules(err)
+        }
+    }
+
+    impl From<LevelConfigError> for CliError {
+        fn from(err: LevelConfigError) -> Self {
+            CliError::Levels(err)
+        }
+    }
+
+    /// Helper function to check a due date argument with the same parser as the GUI forms.
+    /// Relative dates such as "tomorrow" are read against the simulated date once it is known,
+    /// by resolve_due_date.
+    fn parse_due_date(input: &str) -> Result<String, String> {
+        dates::parse_due_date(input, Clock::System.today()).map_err(|err| err.to_string())?;
+        Ok(input.to_string())
+    }
+
+    /// Helper function to read a due date argument that parse_due_date already checked
+    fn resolve_due_date(input: &str, today: NaiveDate) -> DueDate {
+        dates::parse_due_date(input, today).expect("the due date was checked")
+    }
+
+    /// Helper function to parse the date to simulate, relative to the real date
+    fn parse_date(input: &str) -> Result<NaiveDate, String> {
+        dates::parse_due_date(input, Clock::System.today())
+            .map(|due_date| due_date.local_date())
+            .map_err(|err| err.to_string())
+    }
+
+    /// Helper function to check a query argument, so a mistake in it is reported instead of
+    /// being searched for as text
+    fn parse_query(input: &str) -> Result<String, String> {
+        Expr::parse(input, Clock::System.today()).map_err(|err| err.to_string())?;
+        Ok(input.to_string())
+    }
+
+    /// Helper function to parse a checklist item number, which counts from 1
+    fn parse_item_number(input: &str) -> Result<usize, String> {
+        match input.trim().parse() {
+            Ok(number) if number >= 1 => Ok(number),
+            _ => Err(format!(
+                "invalid item number {input:?}, items are numbered from 1"
+            )),
+        }
+    }
+
+    /// Helper function to parse a `FIELD=COLUMN` column mapping argument
+    fn parse_column_mapping(input: &str) -> Result<(TaskField, String), String> {
+        let (field, column) = input.split_once('=').ok_or("expected FIELD=COLUMN")?;
+        Ok((field.parse()?, column.trim().to_string()))
+    }
+
+    /// Helper function to write a task as a line of `list`, followed by its checklist items
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to write to
+    /// * `store` - The store, to find the tasks the task waits for
+    /// * `depth` - How deep the task is nested under other tasks
+    /// * `task` - The task to write
+    /// * `show_project` - Whether to write the project, which a grouped list shows as a heading
+    fn write_task(
+        output: &mut String,
+        store: &TaskStore,
+        depth: usize,
+        task: &Task,
+        show_project: bool,
+    ) {
+        let indent = "  ".repeat(depth);
+        write!(
+            output,
+            "{:>4} [{}] {:<6} {indent}{}",
+            task.id.to_string(),
+            if task.completed { "x" } else { " " },
+            format!("{:?}", task.priority),
+            task.name
+        )
+        .unwrap();
+        if !task.tags.is_empty() {
+            write!(output, " {}", tags::format_tags(&task.tags)).unwrap();
+        }
+        if let Some(project) = task.project.as_ref().filter(|_| show_project) {
+            write!(output, " (project {project})").unwrap();
+        }
+        if let Some(due_date) = task.due_date {
+            write!(output, " (due {due_date})").unwrap();
+        }
+        if let Some(recurrence) = &task.recurrence {
+            write!(output, " (repeats {recurrence})").unwrap();
+        }
+        let blocked_by = store.blocked_by(task.id);
+        if !task.completed && !blocked_by.is_empty() {
+            let ids: Vec<String> = blocked_by.iter().map(TaskId::to_string).collect();
+            write!(output, " (waiting for {})", ids.join(", ")).unwrap();
+        }
+        writeln!(output).unwrap();
+        for (index, item) in task.checklist.iter().enumerate() {
+            writeln!(
+                output,
+                "{:16}{indent}  {}. [{}] {}",
+                "",
+                index + 1,
+                if item.done { "x" } else { " " },
+                item.text
+            )
+            .unwrap();
+        }
+    }
+
+    /// Helper function to read a file named on the command line
+    fn read_file(path: &Path) -> Result<String, CliError> {
+        std::fs::read_to_string(path)
+            .map_err(|err| CliError::Io(format!("failed to read {}: {err}", path.display())))
+    }
+
+    /// Helper function to write a file named on the command line
+    fn write_file(path: &Path, contents: &str) -> Result<(), CliError> {
+        std::fs::write(path, contents)
+            .map_err(|err| CliError::Io(format!("failed to write {}: {err}", path.display())))
+    }
+
+    /// Helper function to run the CLI and turn failures into an exit code
+    pub fn main() -> ExitCode {
+        env_logger::init(); // Log to stderr
+        let cli = Cli::parse();
+        match run_with_storage(cli) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(message) => {
+                eprintln!("taskhero: {message}");
+                ExitCode::FAILURE
+            }
+        }
+    }
+
+    /// Helper function to load the saved app state, run a command and save the result
+    fn run_with_storage(cli: Cli) -> Result<(), String> {
+        let (mut file_storage, mut app) = load(cli.data_dir.as_deref())?;
+        if let Some(date) = cli.simulate_date {
+            app.set_clock(Clock::simulating(date));
+        }
+
+        let Some(command) = cli.command else {
+            // Save after every change so the other TaskHero tools see it right away
+            task_hero::tui::run(app, |app| {
+                storage::save(&mut file_storage, app);
+                file_storage.write().map_err(|err| err.to_string())
+            })
+            .map_err(|err| format!("terminal error: {err}"))?;
+            return Ok(());
+        };
+
+        let watch = match &command {
+            Command::SyncTodotxt { file, watch: true } => Some(file.clone()),
+            _ => None,
+        };
+        let today = app.clock().today();
+        let (output, changed) = run(command, &mut app, today).map_err(|err| err.to_string())?;
+        print!("{output}");
+
+        if changed {
+            save(&mut file_storage, &app)?;
+        }
+        match watch {
+            Some(file) => watch_todotxt(cli.data_dir.as_deref(), file_storage, app, &file),
+            None => Ok(()),
+        }
+    }
+
+    /// Helper function to open the saved app state
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir` - The directory holding app.ron, or `None` for the one the GUI uses
+    fn load(data_dir: Option<&Path>) -> Result<(FileStorage, TemplateApp), String> {
+        let file_storage = match data_dir {
+            Some(dir) => FileStorage::open(dir),
+            None => FileStorage::open_default(),
+        }
+        .map_err(|err| format!("failed to open the saved tasks: {err}"))?;
+
+        // Refuse to touch data that couldn't be loaded, the GUI will back it up on its next start
+        let loaded = storage::load::<TemplateApp>(&file_storage);
+        if let Some(warning) = loaded.warning {
+            return Err(warning);
+        }
+        let mut app = loaded.state;
+        app.rebuild_from_journal();
+        Ok((file_storage, app))
+    }
+
+    /// Helper function to save the app state
+    fn save(file_storage: &mut FileStorage, app: &TemplateApp) -> Result<(), String> {
+        storage::save(file_storage, app);
+        file_storage
+            .write()
+            .map_err(|err| format!("failed to save {}: {err}", file_storage.path().display()))
+    }
+
+    /// Helper function to keep a todo.txt file and the saved tasks in sync until the process is
+    /// interrupted. Both are checked for changes once a second.
+    ///
+    /// Edits to the file are applied to the tasks, including deleted lines, and changes saved by
+    /// the GUI or other taskhero commands are written to the file. When both change at once the
+    /// file is merged without deleting anything, so no task is lost.
+    fn watch_todotxt(
+        data_dir: Option<&Path>,
+        mut file_storage: FileStorage,
+        mut app: TemplateApp,
+        path: &Path,
+    ) -> Result<(), String> {
+        let modified = |path: &Path| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        };
+        let mut saved_at = modified(file_storage.path());
+        let mut written = todotxt::export(app.store());
+        println!(
+            "Watching {} and your tasks for changes, press Ctrl+C to stop.",
+            path.display()
+        );
+        loop {
+            std::thread::sleep(std::time::Duration::from_secs(1));
+
+            let tasks_changed = modified(file_storage.path()) != saved_at;
+            if tasks_changed {
+                let clock = app.clock();
+                (file_storage, app) = load(data_dir)?;
+                app.set_clock(clock);
+                saved_at = modified(file_storage.path());
+            }
+            // a deleted file is written again rather than read as "delete every task"
+            let text = std::fs::read_to_string(path).ok();
+            if let Some(text) = text.as_ref().filter(|&text| *text != written) {
+                let before = app.store().clone();
+                match todotxt::sync(app.store_mut(), todotxt::parse(text), !tasks_changed) {
+                    Ok(summary) if summary == todotxt::SyncSummary::default() => {}
+                    Ok(summary) => {
+                        app.record(format!("Sync {}", path.display()), before);
+                        save(&mut file_storage, &app)?;
+                        saved_at = modified(file_storage.path());
+                        println!("{}: {summary}", path.display());
+                    }
+                    Err(err) => {
+                        // wait for the line to be fixed instead of overwriting it
+                        eprintln!("taskhero: not syncing {}:\n{err}", path.display());
+                        written = text.clone();
+                        continue;
+                    }
+                }
+            }
+            let export = todotxt::export(app.store());
+            if text.as_ref() != Some(&export) {
+                write_file(path, &export).map_err(|err| err.to_string())?;
+            }
+            written = export;
+        }
+    }
+
+    /// Helper function to run a command against the app state
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to run
+    /// * `app` - The app state to read and change
+    /// * `today` - The current date, used by the report
+    ///
+    /// # Returns
+    ///
+    /// The text to print and whether the app state changed and needs saving.
+    pub fn run(
+        command: Command,
+        app: &mut TemplateApp,
+        today: NaiveDate,
+    ) -> Result<(String, bool), CliError> {
+        // changes are recorded so they can be undone, except undo and redo themselves
+        let description = describe(&command, app.store());
+        let before = app.store().clone();
+        let store = app.store_mut();
+        let mut output = String::new();
+        let mut changed = match command {
+            Command::Add {
+                name,
+                description,
+                due,
+                priority,
+                repeat,
+                parent,
+                depends_on,
+                project,
+                tags,
+            } => {
+                // check the prerequisites first so a failed command adds nothing
+                if let Some(&missing) = depends_on
+                    .iter()
+                    .find(|&&prerequisite| store.task(prerequisite).is_none())
+                {
+                    return Err(StoreError::TaskNotFound(missing).into());
+                }
+                let new_task = NewTask {
+                    name,
+                    description,
+                    due_date: due.map(|input| resolve_due_date(&input, today)),
+                    priority,
+                    recurrence: repeat,
+                };
+                let id = match parent {
+                    Some(parent) => store.create_subtask(parent, new_task)?,
+                    None => store.create(new_task)?,
+                };
+                for prerequisite in depends_on {
+                    store.add_dependency(id, prerequisite)?;
+                }
+                store.update(
+                    id,
+                    TaskUpdate {
+                        tags: Some(tags),
+                        project: Some(project),
+                        ..Default::default()
+                    },
+                )?;
+                writeln!(output, "Added task {id}.").unwrap();
+                true
+            }
+            Command::List { query, group, save } => {
+                let query = query.into_query(store, today)?;
+                let changed = match save {
+                    Some(name) => {
+                        store.save_filter(&name, query.clone())?;
+                        writeln!(output, "Saved filter {:?}: {query}.", name.trim()).unwrap();
+                        true
+                    }
+                    None => false,
+                };
+                let listed = query.run(store, today);
+                if listed.is_empty() {
+                    writeln!(output, "No tasks.").unwrap();
+                }
+                if group {
+                    for (project, tasks) in tags::group_by_project(listed) {
+                        writeln!(output, "{}:", project.unwrap_or("No project")).unwrap();
+                        for (depth, task) in tasks {
+                            write_task(&mut output, store, depth, task, false);
+                        }
+                    }
+                } else {
+                    for (depth, task) in listed {
+                        write_task(&mut output, store, depth, task, true);
+                    }
+                }
+                changed
+            }
+            Command::Done { id } => {
+                let was_completed = store
+                    .task(id)
+                    .ok_or(StoreError::TaskNotFound(id))?
+                    .completed;
+                let best_level = store.gamification().best_level;
+                let open_parents: Vec<TaskId> = store
+                    .ancestors(id)
+                    .into_iter()
+                    .filter(|&parent| store.task(parent).is_some_and(|task| !task.completed))
+                    .collect();
+                let blocked_by = if was_completed {
+                    Vec::new()
+                } else {
+                    store.blocked_by(id)
+                };
+                let waiting: Vec<TaskId> = store
+                    .dependents(id)
+                    .into_iter()
+                    .filter(|&dependent| store.is_blocked(dependent))
+                    .collect();
+                let next = store.complete(id)?;
+                store.evaluate();
+                let task = store.task(id).ok_or(StoreError::TaskNotFound(id))?;
+                let scoring = &store.gamification().scoring;
+                if was_completed {
+                    writeln!(output, "Task {id} \"{}\" is already completed.", task.name).unwrap();
+                } else {
+                    writeln!(
+                        output,
+                        "Completed task {id} \"{}\" (+{} points).",
+                        task.name,
+                        task.score_with(scoring).points
+                    )
+                    .unwrap();
+                }
+                // auto-completing parents whose last open subtask this was
+                for parent in open_parents
+                    .into_iter()
+                    .filter_map(|parent| store.task(parent))
+                    .filter(|task| task.completed)
+                {
+                    writeln!(
+                        output,
+                        "Also completed task {} \"{}\" (+{} points).",
+                        parent.id,
+                        parent.name,
+                        parent.score_with(scoring).points
+                    )
+                    .unwrap();
+                }
+                for prerequisite in blocked_by
+                    .into_iter()
+                    .filter_map(|prerequisite| store.task(prerequisite))
+                {
+                    writeln!(
+                        output,
+                        "Warning: it was still waiting for task {} \"{}\".",
+                        prerequisite.id, prerequisite.name
+                    )
+                    .unwrap();
+                }
+                for dependent in waiting
+                    .into_iter()
+                    .filter(|&dependent| store.is_ready(dependent))
+                    .filter_map(|dependent| store.task(dependent))
+                {
+                    writeln!(
+                        output,
+                        "Task {} \"{}\" is now ready to work on.",
+                        dependent.id, dependent.name
+                    )
+                    .unwrap();
+                }
+                if let Some(next) = next.and_then(|next| store.task(next)) {
+                    let due_date = next
+                        .due_date
+                        .map(|due_date| format!(", due {due_date}"))
+                        .unwrap_or_default();
+                    writeln!(output, "Next occurrence: task {}{due_date}.", next.id).unwrap();
+                }
+                let gamification = store.gamification();
+                writeln!(output, "{}", gamification.achievement_message).unwrap();
+                writeln!(output, "{}", gamification.daily_reward_message).unwrap();
+                if gamification.best_level > best_level {
+                    writeln!(
+                        output,
+                        "Level up! You reached level {}.",
+                        gamification.best_level
+                    )
+                    .unwrap();
+                }
+                !was_completed
+            }
+            Command::Edit {
+                id,
+                name,
+                description,
+                due,
+                no_due,
+                priority,
+                repeat,
+                no_repeat,
+                parent,
+                no_parent,
+                auto_complete,
+                project,
+                no_project,
+                tags,
+                untags,
+                effort,
+                no_effort,
+                difficulty,
+                points,
+                no_points,
+            } => {
+                let due_date = if no_due {
+                    Some(None)
+                } else {
+                    due.map(|input| Some(resolve_due_date(&input, today)))
+                };
+                let recurrence = if no_repeat {
+                    Some(None)
+                } else {
+                    repeat.map(Some)
+                };
+                let project = if no_project {
+                    Some(None)
+                } else {
+                    project.map(Some)
+                };
+                let effort = if no_effort {
+                    Some(None)
+                } else {
+                    effort.map(Some)
+                };
+                let points_override = if no_points {
+                    Some(None)
+                } else {
+                    points.map(Some)
+                };
+                let tags = if tags.is_empty() && untags.is_empty() {
+                    None
+                } else {
+                    let mut own = store
+                        .task(id)
+                        .ok_or(StoreError::TaskNotFound(id))?
+                        .tags
+                        .clone();
+                    own.retain(|tag| {
+                        !untags
+                            .iter()
+                            .any(|untag| untag.trim_start_matches('#').eq_ignore_ascii_case(tag))
+                    });
+                    own.extend(tags);
+                    Some(own)
+                };
+                if parent.is_some() || no_parent {
+                    store.set_parent(id, parent)?;
+                }
+                let update = TaskUpdate {
+                    name,
+                    description,
+                    due_date,
+                    priority,
+                    tags,
+                    project,
+                    recurrence,
+                    auto_complete,
+                    effort,
+                    difficulty,
+                    points_override,
+                };
+                store.update(id, update)?;
+                store.evaluate();
+                writeln!(output, "Updated task {id}.").unwrap();
+                true
+            }
+            Command::Rm { id } => {
+                let subtasks = store.descendants(id).len();
+                let task = store.delete(id)?;
+                store.evaluate();
+                write!(output, "Deleted task {id} \"{}\"", task.name).unwrap();
+                if subtasks > 0 {
+                    write!(output, " and {subtasks} subtask(s)").unwrap();
+                }
+                writeln!(output, ".").unwrap();
+                true
+            }
+            Command::ItemAdd { id, text } => {
+                store.add_checklist_item(id, &text)?;
+                store.evaluate();
+                let number = store.task(id).map_or(0, |task| task.checklist.len());
+                writeln!(output, "Added item {number} to task {id}.").unwrap();
+                true
+            }
+            Command::ItemDone { id, number } => {
+                store.set_checklist_item(id, number - 1, true)?;
+                store.evaluate();
+                let task = store.task(id).ok_or(StoreError::TaskNotFound(id))?;
+                let (done, total) = task.checklist_progress();
+                write!(
+                    output,
+                    "Checked item {number} of task {id} ({done}/{total} done)"
+                )
+                .unwrap();
+                if task.completed {
+                    write!(output, ", which completed the task").unwrap();
+                }
+                writeln!(output, ".").unwrap();
+                true
+            }
+            Command::ItemUndo { id, number } => {
+                store.set_checklist_item(id, number - 1, false)?;
+                store.evaluate();
+                writeln!(output, "Unchecked item {number} of task {id}.").unwrap();
+                true
+            }
+            Command::ItemRm { id, number } => {
+                let item = store.remove_checklist_item(id, number - 1)?;
+                store.evaluate();
+                writeln!(output, "Removed item \"{}\" from task {id}.", item.text).unwrap();
+                true
+            }
+            Command::Report { filter } => {
+                let report = match filter {
+                    Some(name) => {
+                        let query = store
+                            .filters()
+                            .get(name.trim())
+                            .ok_or(CliError::UnknownFilter(name.clone()))?;
+                        writeln!(output, "Tasks matching {:?}: {query}", name.trim()).unwrap();
+                        store.report_matching(query, today)
+                    }
+                    None => store.report(today),
+                };
+                for line in report.lines() {
+                    writeln!(output, "{line}").unwrap();
+                }
+                false
+            }
+            Command::Filters => {
+                for (name, query) in store.filters() {
+                    writeln!(output, "{name}: {query}").unwrap();
+                }
+                if output.is_empty() {
+                    writeln!(
+                        output,
+                        "No saved filters. Save one with `list --save NAME`."
+                    )
+                    .unwrap();
+                }
+                false
+            }
+            Command::FilterRm { name } => {
+                store
+                    .remove_filter(&name)
+                    .ok_or(CliError::UnknownFilter(name.clone()))?;
+                writeln!(output, "Deleted filter {:?}.", name.trim()).unwrap();
+                true
+            }
+            Command::Goal { project, goal } => {
+                store.set_project_goal(&project, goal)?;
+                if goal == 0 {
+                    writeln!(output, "Removed the goal of project {}.", project.trim()).unwrap();
+                } else {
+                    writeln!(
+                        output,
+                        "Set the goal of project {} to {goal} task(s).",
+                        project.trim()
+                    )
+                    .unwrap();
+                }
+                true
+            }
+            Command::DepAdd { id, prerequisite } => {
+                store.add_dependency(id, prerequisite)?;
+                store.evaluate();
+                writeln!(output, "Task {id} now waits for task {prerequisite}.").unwrap();
+                true
+            }
+            Command::DepRm { id, prerequisite } => {
+                let removed = store.remove_dependency(id, prerequisite)?;
+                store.evaluate();
+                if removed {
+                    writeln!(output, "Task {id} no longer waits for task {prerequisite}.").unwrap();
+                } else {
+                    writeln!(output, "Task {id} wasn't waiting for task {prerequisite}.").unwrap();
+                }
+                removed
+            }
+            Command::Undo => match app.undo() {
+                Some(description) => {
+                    writeln!(output, "Undid: {description}.").unwrap();
+                    true
+                }
+                None => {
+                    write!(output, "Nothing to undo.").unwrap();
+                    if !app.history().is_persistent() {
+                        write!(output, " Run `taskhero history --keep true` to keep the undo history between runs.").unwrap();
+                    }
+                    writeln!(output).unwrap();
+                    false
+                }
+            },
+            Command::Redo => match app.redo() {
+                Some(description) => {
+                    writeln!(output, "Redid: {description}.").unwrap();
+                    true
+                }
+                None => {
+                    writeln!(output, "Nothing to redo.").unwrap();
+                    false
+                }
+            },
+            Command::History { keep } => {
+                if let Some(keep) = keep {
+                    app.history_mut().set_persistent(keep);
+                }
+                let history = app.history();
+                let kept = if history.is_persistent() {
+                    "kept"
+                } else {
+                    "not kept"
+                };
+                writeln!(output, "The undo history is {kept} between runs.").unwrap();
+                for (number, description) in history.undoable().enumerate() {
+                    writeln!(output, "{}. {description}", number + 1).unwrap();
+                }
+                if history.next_undo().is_none() {
+                    writeln!(output, "Nothing to undo.").unwrap();
+                }
+                keep.is_some()
+            }
+            Command::Log {
+                task,
+                completions: false,
+            } => {
+                for line in app.journal().log(task) {
+                    writeln!(
+                        output,
+                        "{}  {}",
+                        line.at.format("%Y-%m-%d %H:%M"),
+                        line.text
+                    )
+                    .unwrap();
+                }
+                if output.is_empty() {
+                    writeln!(output, "Nothing has been recorded yet.").unwrap();
+                }
+                false
+            }
+            Command::Log {
+                completions: true, ..
+            } => {
+                for (day, count) in app.journal().completions_by_day() {
+                    writeln!(output, "{day}: {count} completed").unwrap();
+                }
+                if output.is_empty() {
+                    writeln!(output, "No task has been completed yet.").unwrap();
+                }
+                false
+            }
+            Command::Points { unblock_bonus } => {
+                if let Some(enabled) = unblock_bonus {
+                    store.set_unblock_bonus(enabled);
+                }
+                store.evaluate();
+                let completed_tasks = store.query(|task| task.completed).count();
+                let gamification = store.gamification();
+                writeln!(output, "Points: {}", gamification.points).unwrap();
+                if gamification.unblock_bonus {
+                    writeln!(
+                        output,
+                        "Unblock Bonus: {} of those points",
+                        gamification.unblock_bonus_points
+                    )
+                    .unwrap();
+                }
+                writeln!(output, "{}", gamification.achievement_message).unwrap();
+                writeln!(output, "Daily Reward Points: {}", gamification.daily_reward).unwrap();
+                writeln!(output, "{}", gamification.daily_reward_message).unwrap();
+                writeln!(output, "{}", gamification.weekly_challenge_message).unwrap();
+                for (level, goal) in [
+                    ("Bronze", gamification.bronze_goal),
+                    ("Silver", gamification.silver_goal),
+                    ("Gold", gamification.gold_goal),
+                ] {
+                    writeln!(
+                        output,
+                        "{level} Goal: {}/{goal}",
+                        completed_tasks.min(goal as usize)
+                    )
+                    .unwrap();
+                }
+                for goal in gamification.project_goal_progress(store.tasks().as_slice()) {
+                    write!(
+                        output,
+                        "Project {} Goal: {}/{}",
+                        goal.project,
+                        goal.completed.min(goal.goal as usize),
+                        goal.goal
+                    )
+                    .unwrap();
+                    writeln!(
+                        output,
+                        "{}",
+                        if goal.reached() { " (reached!)" } else { "" }
+                    )
+                    .unwrap();
+                }
+                unblock_bonus.is_some()
+            }
+            Command::Streak {
+                rest_days,
+                buy_freeze,
+                weeks,
+            } => {
+                if let Some(days) = &rest_days {
+                    store.set_rest_days(days.clone());
+                }
+                if buy_freeze {
+                    store.buy_streak_freeze()?;
+                    writeln!(
+                        output,
+                        "Bought a streak freeze for {} points.",
+                        streaks::FREEZE_COST
+                    )
+                    .unwrap();
+                }
+                store.evaluate();
+                let gamification = store.gamification();
+                let streak = &gamification.streak;
+                write!(output, "Current streak: {} day(s)", streak.current).unwrap();
+                match streak.start {
+                    Some(start) => writeln!(output, " since {start}"),
+                    None => writeln!(output),
+                }
+                .unwrap();
+                writeln!(output, "Longest streak: {} day(s)", streak.longest).unwrap();
+                writeln!(
+                    output,
+                    "Streak freezes: {}/{}",
+                    streak.freezes,
+                    streaks::MAX_FREEZES
+                )
+                .unwrap();
+                writeln!(
+                    output,
+                    "Rest days: {}",
+                    streaks::format_rest_days(&gamification.rest_days)
+                )
+                .unwrap();
+                // a week per line from Monday: # completed, * frozen, - rest day, . missed
+                for week in streak.calendar(weeks) {
+                    let days: Vec<&str> = week
+                        .iter()
+                        .map(|(_, status)| match status {
+                            DayStatus::Completed => "#",
+                            DayStatus::Frozen => "*",
+                            DayStatus::Rest => "-",
+                            DayStatus::Missed => ".",
+                            DayStatus::Upcoming => " ",
+                        })
+                        .collect();
+                    writeln!(output, "{}  {}", week[0].0, days.join(" ").trim_end()).unwrap();
+                }
+                rest_days.is_some()
+            }
+            Command::Ledger {
+                adjust: Some(amount),
+                reason,
+            } => {
+                store.adjust_points(amount, reason.as_deref().unwrap_or_default())?;
+                writeln!(
+                    output,
+                    "Adjusted the points by {amount:+}, to {}.",
+                    store.gamification().points
+                )
+                .unwrap();
+                true
+            }
+            Command::Ledger { adjust: None, .. } => {
+                store.evaluate();
+                for transaction in store.ledger().transactions() {
+                    let at = transaction.at.format("%Y-%m-%d %H:%M");
+                    writeln!(
+                        output,
+                        "{at}  {:>+5}  {}: {}",
+                        transaction.amount, transaction.kind, transaction.reason
+                    )
+                    .unwrap();
+                }
+                writeln!(output, "Balance: {} points", store.ledger().balance()).unwrap();
+                false
+            }
+            Command::Achievements {
+                rules: Some(file), ..
+            } => {
+                let json = std::fs::read_to_string(&file).map_err(|err| {
+                    CliError::Io(format!("failed to read {}: {err}", file.display()))
+                })?;
+                let rules = achievements::parse_rules(&json)?;
+                writeln!(output, "Loaded {} achievement rule(s).", rules.len()).unwrap();
+                store.set_achievement_rules(Some(rules));
+                true
+            }
+            Command::Achievements { standard: true, .. } => {
+                let changed = store.gamification().achievements.custom_rules().is_some();
+                store.set_achievement_rules(None);
+                writeln!(output, "Using the standard achievement rules.").unwrap();
+                changed
+            }
+            Command::Achievements { .. } => {
+                store.evaluate();
+                let achievements = &store.gamification().achievements;
+                for rule in achievements
+                    .rules()
+                    .iter()
+                    .filter(|rule| rule.kind != AchievementKind::Daily)
+                {
+                    match achievements
+                        .unlocked()
+                        .iter()
+                        .find(|unlocked| unlocked.id == rule.id)
+                    {
+                        Some(unlocked) => writeln!(
+                            output,
+                            "[x] {}: {} (unlocked {})",
+                            rule.name,
+                            rule.description,
+                            unlocked.at.format("%Y-%m-%d")
+                        ),
+                        None => writeln!(output, "[ ] {}: {}", rule.name, rule.description),
+                    }
+                    .unwrap();
+                }
+                let unlocked = achievements.unlocked().len();
+                writeln!(output, "{unlocked} achievement(s) unlocked.").unwrap();
+                false
+            }
+            Command::Scoring { task: Some(id), .. } => {
+                let task = store.task(id).ok_or(StoreError::TaskNotFound(id))?;
+                let score = task.score_with(&store.gamification().scoring);
+                for step in &score.steps {
+                    writeln!(output, "{:<24}{}", step.label, step.effect).unwrap();
+                }
+                writeln!(output, "{:<24}{}", "Points", score.points).unwrap();
+                false
+            }
+            Command::Scoring {
+                formula,
+                effort_points,
+                early,
+                late,
+                tag_multipliers,
+                task: None,
+            } => {
+                let mut model = store.gamification().scoring.clone();
+                model.formula = formula.unwrap_or(model.formula);
+                model.effort_points = effort_points.unwrap_or(model.effort_points);
+                model.early_multiplier = early.unwrap_or(model.early_multiplier);
+                model.late_multiplier = late.unwrap_or(model.late_multiplier);
+                for (tag, multiplier) in tag_multipliers {
+                    model.set_tag_multiplier(&tag, multiplier);
+                }
+                let changed = model != store.gamification().scoring;
+                store.set_scoring(model);
+                store.evaluate();
+                let model = &store.gamification().scoring;
+                writeln!(output, "Formula: {}", model.formula).unwrap();
+                if model.formula == Formula::Weighted {
+                    writeln!(output, "Effort: {} points an hour", model.effort_points).unwrap();
+                    writeln!(
+                        output,
+                        "Completed early: ×{}, late: ×{}",
+                        model.early_multiplier, model.late_multiplier
+                    )
+                    .unwrap();
+                    for (tag, multiplier) in &model.tag_multipliers {
+                        writeln!(output, "Tag {tag}: ×{multiplier}").unwrap();
+                    }
+                }
+                changed
+            }
+            Command::Level {
+                config: Some(file), ..
+            } => {
+                let json = std::fs::read_to_string(&file).map_err(|err| {
+                    CliError::Io(format!("failed to read {}: {err}", file.display()))
+                })?;
+                store.set_level_config(Some(levels::parse_config(&json)?));
+                writeln!(output, "Loaded the level curve of {}.", file.display()).unwrap();
+                true
+            }
+            Command::Level { standard: true, .. } => {
+                let changed = store.gamification().level_config.is_some();
+                store.set_level_config(None);
+                writeln!(output, "Using the standard level curve.").unwrap();
+                changed
+            }
+            Command::Level { .. } => {
+                store.evaluate();
+                let level = store.gamification().level;
+                writeln!(output, "Level {} ({} XP)", level.level, level.xp).unwrap();
+                if level.next > level.start {
+                    writeln!(
+                        output,
+                        "Next level: {} XP to go, at {} XP",
+                        level.next - level.xp,
+                        level.next
+                    )
+                    .unwrap();
+                } else {
+                    writeln!(output, "This is the highest level.").unwrap();
+                }
+                false
+            }
+            Command::Export { output: None } => {
+                writeln!(output, "{}", transfer::export(store)).unwrap();
+                false
+            }
+            Command::Export { output: Some(path) } => {
+                std::fs::write(&path, transfer::export(store)).map_err(|err| {
+                    CliError::Io(format!("failed to write {}: {err}", path.display()))
+                })?;
+                writeln!(
+                    output,
+                    "Exported {} task(s) to {}.",
+                    store.tasks().len(),
+                    path.display()
+                )
+                .unwrap();
+                false
+            }
+            Command::ExportCsv {
+                report,
+                output: path,
+            } => {
+                let csv = if report {
+                    csv_transfer::export_report(&store.report(today))
+                } else {
+                    csv_transfer::export_tasks(store)
+                };
+                match path {
+                    None => write!(output, "{csv}").unwrap(),
+                    Some(path) => {
+                        std::fs::write(&path, csv).map_err(|err| {
+                            CliError::Io(format!("failed to write {}: {err}", path.display()))
+                        })?;
+                        writeln!(output, "Exported to {}.", path.display()).unwrap();
+                    }
+                }
+                false
+            }
+            Command::ImportCsv {
+                file,
+                mappings,
+                dry_run,
+            } => {
+                let text = std::fs::read_to_string(&file).map_err(|err| {
+                    CliError::Io(format!("failed to read {}: {err}", file.display()))
+                })?;
+                let csv = CsvFile::parse(&text).map_err(CliError::Io)?;
+                let mut mapping = csv.guess_mapping();
+                for (field, column) in mappings {
+                    // a column is given by its header or its number
+                    let index = csv
+                        .headers
+                        .iter()
+                        .position(|header| header.eq_ignore_ascii_case(&column))
+                        .or_else(|| {
+                            column
+                                .parse::<usize>()
+                                .ok()
+                                .filter(|&number| number >= 1 && number <= csv.headers.len())
+                                .map(|number| number - 1)
+                        })
+                        .ok_or_else(|| {
+                            CliError::Io(format!(
+                                "there is no column {column:?} in {}",
+                                file.display()
+                            ))
+                        })?;
+                    mapping.set(field, Some(index));
+                }
+                let preview = csv.preview(&mapping, today).map_err(CliError::Io)?;
+                for failure in &preview.failures {
+                    writeln!(output, "Skipping {failure}").unwrap();
+                }
+                if dry_run {
+                    writeln!(output, "{} row(s) would be imported.", preview.tasks.len()).unwrap();
+                    false
+                } else {
+                    let added = csv_transfer::import(store, preview);
+                    writeln!(output, "Imported {added} task(s).").unwrap();
+                    added > 0
+                }
+            }
+            Command::ExportIcs {
+                events,
+                output: path,
+            } => {
+                let component = if events {
+                    IcsComponent::Event
+                } else {
+                    IcsComponent::Todo
+                };
+                let ics = ical::export(store, component);
+                match path {
+                    None => write!(output, "{ics}").unwrap(),
+                    Some(path) => {
+                        std::fs::write(&path, ics).map_err(|err| {
+                            CliError::Io(format!("failed to write {}: {err}", path.display()))
+                        })?;
+                        writeln!(output, "Exported to {}.", path.display()).unwrap();
+                    }
+                }
+                false
+            }
+            Command::ImportIcs { file } => {
+                let text = std::fs::read_to_string(&file).map_err(|err| {
+                    CliError::Io(format!("failed to read {}: {err}", file.display()))
+                })?;
+                let todos = ical::parse_todos(&text).map_err(|err| {
+                    CliError::Io(format!("failed to read {}: {err}", file.display()))
+                })?;
+                for reason in &todos.skipped {
+                    writeln!(output, "Skipping {reason}").unwrap();
+                }
+                let summary = ical::import(store, todos);
+                writeln!(output, "{summary}").unwrap();
+                summary.added > 0
+            }
+            Command::ExportTodotxt { output: None } => {
+                write!(output, "{}", todotxt::export(store)).unwrap();
+                false
+            }
+            Command::ExportTodotxt { output: Some(path) } => {
+                write_file(&path, &todotxt::export(store))?;
+                writeln!(
+                    output,
+                    "Exported {} task(s) to {}.",
+                    store.tasks().len(),
+                    path.display()
+                )
+                .unwrap();
+                false
+            }
+            Command::ImportTodotxt { file } => {
+                let todo_txt = todotxt::parse(&read_file(&file)?);
+                for reason in &todo_txt.skipped {
+                    writeln!(output, "Skipping {reason}").unwrap();
+                }
+                let summary = todotxt::import(store, todo_txt);
+                writeln!(output, "{summary}").unwrap();
+                summary.added > 0
+            }
+            Command::SyncTodotxt { file, watch: _ } => {
+                // a file that doesn't exist yet is created from the tasks
+                let text = if file.exists() {
+                    read_file(&file)?
+                } else {
+                    String::new()
+                };
+                let summary =
+                    todotxt::sync(store, todotxt::parse(&text), false).map_err(CliError::Io)?;
+                write_file(&file, &todotxt::export(store))?;
+                writeln!(output, "Synced {}: {summary}", file.display()).unwrap();
+                summary != todotxt::SyncSummary::default()
+            }
+            Command::Import { file, replace } => {
+                let json = std::fs::read_to_string(&file).map_err(|err| {
+                    CliError::Io(format!("failed to read {}: {err}", file.display()))
+                })?;
+                let mode = if replace {
+                    ImportMode::Replace
+                } else {
+                    ImportMode::Merge
+                };
+                let summary = transfer::import(store, &json, mode)?;
+                writeln!(output, "{summary}").unwrap();
+                summary.added > 0 || summary.removed > 0 || summary.goals_changed
+            }
+        };
+        let unlocked = |store: &TaskStore| store.gamification().achievements.unlocked().len();
+        let best_level = |store: &TaskStore| store.gamification().best_level;
+        if let Some(description) = description.filter(|_| changed) {
+            // the points and achievements earned are journaled with the change
+            app.store_mut().evaluate();
+            app.record(description, before);
+        } else if app.store().ledger().len() > before.ledger().len()
+            || unlocked(app.store()) > unlocked(&before)
+            || best_level(app.store()) > best_level(&before)
+        {
+            // points, achievements and levels earned without changing the tasks are kept, but can't be undone
+            app.record_points(before.ledger().len());
+            app.record_rewards(before.gamification());
+            changed = true;
+        }
+        Ok((output, changed))
+    }
+
+    /// Helper function to describe the change a command makes, for the undo history
+    ///
+    /// # Returns
+    ///
+    /// The description, or None for the commands that aren't undone themselves.
+    fn describe(command: &Command, store: &TaskStore) -> Option<String> {
+        let task = |id: &TaskId| {
+            store
+                .task(*id)
+                .map_or(id.to_string(), |task| format!("{:?}", task.name))
+        };
+        Some(match command {
+            Command::Undo | Command::Redo | Command::History { .. } => return None,
+            Command::Add { name, .. } => format!("Add task {name:?}"),
+            Command::List { save, .. } => format!(
+                "Save filter {:?}",
+                save.as_deref().unwrap_or_default().trim()
+            ),
+            Command::Done { id } => format!("Complete task {}", task(id)),
+            Command::Edit { id, .. } => format!("Edit task {}", task(id)),
+            Command::Rm { id } => format!("Delete task {}", task(id)),
+            Command::ItemAdd { id, .. } => format!("Add checklist item to {}", task(id)),
+            Command::ItemDone { id, number } => format!("Check item {number} of {}", task(id)),
+            Command::ItemUndo { id, number } => format!("Uncheck item {number} of {}", task(id)),
+            Command::ItemRm { id, number } => format!("Remove item {number} of {}", task(id)),
+            Command::DepAdd { id, .. } => format!("Make {} wait for another task", task(id)),
+            Command::DepRm { id, .. } => format!("Stop {} waiting for another task", task(id)),
+            Command::FilterRm { name } => format!("Delete filter {:?}", name.trim()),
+            Command::Goal { project, goal: 0 } => format!("Remove the goal of {}", project.trim()),
+            Command::Goal { project, .. } => format!("Set the goal of {}", project.trim()),
+            Command::Points {
+                unblock_bonus: Some(true),
+            } => "Turn on the unblock bonus".to_string(),
+            Command::Points {
+                unblock_bonus: Some(false),
+            } => "Turn off the unblock bonus".to_string(),
+            Command::Points {
+                unblock_bonus: None,
+            } => return None,
+            Command::Achievements {
+                rules: Some(file), ..
+            } => format!("Load the achievement rules of {}", file.display()),
+            Command::Streak {
+                rest_days: Some(_), ..
+            } => "Change the rest days".to_string(),
+            Command::Streak { .. } => return None,
+            Command::Achievements { standard: true, .. } => {
+                "Use the standard achievement rules".to_string()
+            }
+            Command::Scoring { task: Some(_), .. } => return None,
+            Command::Scoring { .. } => "Change the scoring".to_string(),
+            Command::Level {
+                config: Some(file), ..
+            } => format!("Load the level curve of {}", file.display()),
+            Command::Level { standard: true, .. } => "Use the standard level curve".to_string(),
+            Command::Import { file, .. }
+            | Command::ImportCsv { file, .. }
+            | Command::ImportIcs { file }
+            | Command::ImportTodotxt { file } => format!("Import {}", file.display()),
+            Command::SyncTodotxt { file, .. } => format!("Sync {}", file.display()),
+            // these only read the tasks, so they are never recorded
+            Command::Report { .. }
+            | Command::Log { .. }
+            | Command::Ledger { .. }
+            | Command::Achievements {
+                rules: None,
+                standard: false,
+            }
+            | Command::Level {
+                config: None,
+                standard: false,
+            }
+            | Command::Filters
+            | Command::Export { .. }
+            | Command::ExportCsv { .. }
+            | Command::ExportIcs { .. }
+            | Command::ExportTodotxt { .. } => return None,
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn run_args(app: &mut TemplateApp, args: &[&str]) -> Result<(String, bool), CliError> {
+            let cli = Cli::try_parse_from(std::iter::once("taskhero").chain(args.iter().copied()))
+                .unwrap();
+            run(
+                cli.command.unwrap(),
+                app,
+                NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(),
+            )
+        }
+
+        #[test]
+        fn test_add_edit_done_rm() {
+            let mut app = TemplateApp::default();
+            let (output, changed) = run_args(
+                &mut app,
+                &["add", "Write report", "-p", "high", "--due", "2024-11-23"],
+            )
+            .unwrap();
+            assert_eq!(output, "Added task #1.\n");
+            assert!(changed);
+
+            run_args(
+                &mut app,
+                &["edit", "#1", "--name", "Write the report", "--no-due"],
+            )
+            .unwrap();
+            let task = app.store().task(TaskId(1)).unwrap();
+            assert_eq!(task.name, "Write the report");
+            assert_eq!(task.due_date, None);
+
+            let (output, changed) = run_args(&mut app, &["done", "1"]).unwrap();
+            assert!(output.starts_with("Completed task #1 \"Write the report\" (+30 points).\n"));
+            assert!(changed);
+            assert_eq!(app.store().gamification().points, 30);
+            assert!(!run_args(&mut app, &["done", "1"]).unwrap().1);
+
+            let (output, _) = run_args(&mut app, &["list", "--completed"]).unwrap();
+            assert_eq!(output, "  #1 [x] High   Write the report\n");
+            assert_eq!(
+                run_args(&mut app, &["list", "--pending"]).unwrap().0,
+                "No tasks.\n"
+            );
+
+            run_args(&mut app, &["rm", "1"]).unwrap();
+            assert!(app.store().tasks().is_empty());
+            assert_eq!(
+                run_args(&mut app, &["rm", "1"]).unwrap_err(),
+                StoreError::TaskNotFound(TaskId(1)).into()
+            );
+        }
+
+        #[test]
+        fn test_repeating_task() {
+            let mut app = TemplateApp::default();
+            run_args(
+                &mut app,
+                &[
+                    "add",
+                    "Standup notes",
+                    "--due",
+                    "2024-11-22",
+                    "--repeat",
+                    "weekdays",
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                run_args(&mut app, &["list"]).unwrap().0,
+                "  #1 [ ] Low    Standup notes (due 2024-11-22) (repeats weekdays)\n"
+            );
+
+            let (output, _) = run_args(&mut app, &["done", "1"]).unwrap();
+            assert!(output.starts_with(
+                "Completed task #1 \"Standup notes\" (+10 points).\nNext occurrence: task #2, due "
+            ));
+            assert_eq!(
+                app.store().task(TaskId(2)).unwrap().recurrence,
+                Some(Recurrence::weekdays())
+            );
+            assert_eq!(app.store().task(TaskId(1)).unwrap().recurrence, None);
+
+            run_args(&mut app, &["edit", "2", "--no-repeat"]).unwrap();
+            assert!(!run_args(&mut app, &["done", "2"])
+                .unwrap()
+                .0
+                .contains("Next occurrence"));
+            assert_eq!(app.store().gamification().points, 20);
+            assert!(
+                Cli::try_parse_from(["taskhero", "add", "Task", "--repeat", "sometimes"]).is_err()
+            );
+        }
+
+        #[test]
+        fn test_subtasks_and_checklist() {
+            let mut app = TemplateApp::default();
+            run_args(&mut app, &["add", "Plan the trip", "-p", "high"]).unwrap();
+            run_args(&mut app, &["add", "Book flights", "--parent", "1"]).unwrap();
+            run_args(&mut app, &["add", "Groceries"]).unwrap();
+            run_args(&mut app, &["item-add", "2", "Compare prices"]).unwrap();
+            run_args(&mut app, &["edit", "1", "--auto-complete", "true"]).unwrap();
+            assert_eq!(
+                run_args(&mut app, &["list"]).unwrap().0,
+                "  #1 [ ] High   Plan the trip\n  #2 [ ] Low      Book flights\n                    1. [ ] Compare prices\n  #3 [ ] Low    Groceries\n"
+            );
+
+            let err = run_args(&mut app, &["done", "2"]).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Task #2 still has open subtasks or checklist items."
+            );
+            assert_eq!(
+                run_args(&mut app, &["item-done", "2", "1"]).unwrap().0,
+                "Checked item 1 of task #2 (1/1 done).\n"
+            );
+            let (output, _) = run_args(&mut app, &["done", "2"]).unwrap();
+            assert!(output.starts_with("Completed task #2 \"Book flights\" (+10 points).\nAlso completed task #1 \"Plan the trip\" (+30 points).\n"));
+            assert_eq!(app.store().gamification().points, 40);
+
+            assert!(run_args(&mut app, &["edit", "1", "--parent", "2"]).is_err());
+            assert!(Cli::try_parse_from(["taskhero", "item-done", "2", "0"]).is_err());
+            assert_eq!(
+                run_args(&mut app, &["rm", "1"]).unwrap().0,
+                "Deleted task #1 \"Plan the trip\" and 1 subtask(s).\n"
+            );
+            assert_eq!(app.store().tasks().len(), 1);
+        }
+
+        #[test]
+        fn test_dependencies() {
+            let mut app = TemplateApp::default();
+            run_args(&mut app, &["add", "Design", "-p", "medium"]).unwrap();
+            run_args(&mut app, &["add", "Build", "--depends-on", "1"]).unwrap();
+            assert!(run_args(&mut app, &["add", "Ship", "--depends-on", "9"]).is_err());
+            assert_eq!(app.store().tasks().len(), 2);
+            assert_eq!(
+                run_args(&mut app, &["dep-add", "1", "2"])
+                    .unwrap_err()
+                    .to_string(),
+                "Task #1 can't depend on #2, since #2 already waits for #1."
+            );
+
+            assert_eq!(
+                run_args(&mut app, &["list"]).unwrap().0,
+                "  #1 [ ] Medium Design\n  #2 [ ] Low    Build (waiting for #1)\n"
+            );
+            assert_eq!(
+                run_args(&mut app, &["list", "--ready"]).unwrap().0,
+                "  #1 [ ] Medium Design\n"
+            );
+
+            run_args(&mut app, &["points", "--unblock-bonus", "true"]).unwrap();
+            let (output, _) = run_args(&mut app, &["done", "1"]).unwrap();
+            assert!(output.starts_with("Completed task #1 \"Design\" (+20 points).\nTask #2 \"Build\" is now ready to work on.\n"));
+            assert_eq!(app.store().gamification().points, 25);
+
+            run_args(&mut app, &["add", "Ship", "--depends-on", "2"]).unwrap();
+            run_args(&mut app, &["dep-rm", "2", "1"]).unwrap();
+            let (output, _) = run_args(&mut app, &["done", "3"]).unwrap();
+            assert!(output.contains("Warning: it was still waiting for task #2 \"Build\".\n"));
+        }
+
+        #[test]
+        fn test_projects_and_tags() {
+            let mut app = TemplateApp::default();
+            run_args(
+                &mut app,
+                &[
+                    "add",
+                    "Fix login",
+                    "--project",
+                    "Website",
+                    "--tag",
+                    "backend",
+                    "--tag",
+                    "#urgent",
+                ],
+            )
+            .unwrap();
+            run_args(&mut app, &["add", "Water plants", "--tag", "@home"]).unwrap();
+            run_args(
+                &mut app,
+                &[
+                    "edit",
+                    "2",
+                    "--project",
+                    "Garden",
+                    "--tag",
+                    "weekly",
+                    "--untag",
+                    "@home",
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                run_args(&mut app, &["list"]).unwrap().0,
+                "  #1 [ ] Low    Fix login #backend #urgent (project Website)\n  #2 [ ] Low    Water plants #weekly (project Garden)\n"
+            );
+            assert_eq!(
+                run_args(&mut app, &["list", "--group"]).unwrap().0,
+                "Garden:\n  #2 [ ] Low    Water plants #weekly\nWebsite:\n  #1 [ ] Low    Fix login #backend #urgent\n"
+            );
+            assert_eq!(
+                run_args(&mut app, &["list", "--tag", "#Backend"])
+                    .unwrap()
+                    .0
+                    .lines()
+                    .count(),
+                1
+            );
+            assert_eq!(
+                run_args(&mut app, &["list", "--project", "Garden"])
+                    .unwrap()
+                    .0
+                    .lines()
+                    .count(),
+                1
+            );
+
+            run_args(&mut app, &["goal", "Website", "1"]).unwrap();
+            run_args(&mut app, &["done", "1"]).unwrap();
+            assert!(run_args(&mut app, &["points"])
+                .unwrap()
+                .0
+                .ends_with("Project Website Goal: 1/1 (reached!)\n"));
+            assert!(run_args(&mut app, &["report"])
+                .unwrap()
+                .0
+                .contains("Project Website: 1/1 completed (100.00%), 10 points\n"));
+            assert_eq!(
+                run_args(&mut app, &["goal", " ", "1"])
+                    .unwrap_err()
+                    .to_string(),
+                "A project needs a name."
+            );
+
+            run_args(&mut app, &["edit", "2", "--no-project"]).unwrap();
+            assert_eq!(app.store().task(TaskId(2)).unwrap().project, None);
+        }
+
+        #[test]
+        fn test_search_sort_and_saved_filters() {
+            let mut app = TemplateApp::default();
+            run_args(
+                &mut app,
+                &[
+                    "add",
+                    "Write report",
+                    "-p",
+                    "medium",
+                    "--due",
+                    "2024-11-18",
+                    "-d",
+                    "Quarterly numbers",
+                ],
+            )
+            .unwrap();
+            run_args(
+                &mut app,
+                &[
+                    "add",
+                    "Email team",
+                    "-p",
+                    "high",
+                    "--due",
+                    "2024-11-25",
+                    "-d",
+                    "About the report",
+                ],
+            )
+            .unwrap();
+            run_args(&mut app, &["add", "Plan sprint"]).unwrap();
+            let listed = |app: &mut TemplateApp, args: &[&str]| -> Vec<String> {
+                let output = run_args(app, args).unwrap().0;
+                output
+                    .lines()
+                    .map(|line| line[..4].trim().to_string())
+                    .collect()
+            };
+            assert_eq!(
+                listed(&mut app, &["list", "--search", "REPORT"]),
+                ["#1", "#2"]
+            );
+            assert_eq!(
+                listed(&mut app, &["list", "--sort", "priority"]),
+                ["#2", "#1", "#3"]
+            );
+            assert_eq!(
+                listed(&mut app, &["list", "--sort", "due", "--reverse"]),
+                ["#3", "#2", "#1"]
+            );
+            assert_eq!(listed(&mut app, &["list", "--overdue"]), ["#1"]);
+            assert_eq!(
+                listed(&mut app, &["list", "--due-after", "2024-11-20"]),
+                ["#2"]
+            );
+            assert_eq!(
+                run_args(&mut app, &["list", "--search", "nothing"])
+                    .unwrap()
+                    .0,
+                "No tasks.\n"
+            );
+
+            let (output, changed) = run_args(
+                &mut app,
+                &["list", "--pending", "--sort", "due", "--save", "Next up"],
+            )
+            .unwrap();
+            assert!(output.starts_with("Saved filter \"Next up\": open, sorted by due.\n"));
+            assert!(changed);
+            assert_eq!(
+                run_args(&mut app, &["filters"]).unwrap().0,
+                "Next up: open, sorted by due\n"
+            );
+            run_args(&mut app, &["done", "1"]).unwrap();
+            assert_eq!(
+                listed(&mut app, &["list", "--filter", "Next up"]),
+                ["#2", "#3"]
+            );
+            assert_eq!(
+                listed(&mut app, &["list", "--filter", "Next up", "-p", "low"]),
+                ["#3"]
+            );
+            let report = run_args(&mut app, &["report", "--filter", "Next up"])
+                .unwrap()
+                .0;
+            assert!(report
+                .starts_with("Tasks matching \"Next up\": open, sorted by due\nTotal Tasks: 2\n"));
+
+            run_args(&mut app, &["filter-rm", "Next up"]).unwrap();
+            assert_eq!(
+                run_args(&mut app, &["list", "--filter", "Next up"])
+                    .unwrap_err()
+                    .to_string(),
+                "There is no saved filter named \"Next up\"."
+            );
+            assert!(Cli::try_parse_from(["taskhero", "list", "--sort", "size"]).is_err());
+        }
+
+        #[test]
+        fn test_query_language() {
+            let mut app = TemplateApp::default();
+            run_args(
+                &mut app,
+                &["add", "Write report", "-p", "medium", "--due", "2024-11-21"],
+            )
+            .unwrap();
+            run_args(
+                &mut app,
+                &[
+                    "add",
+                    "Fix login",
+                    "-p",
+                    "high",
+                    "--due",
+                    "2024-11-22",
+                    "--tag",
+                    "backend",
+                ],
+            )
+            .unwrap();
+            run_args(
+                &mut app,
+                &[
+                    "add",
+                    "Plan sprint",
+                    "--due",
+                    "2024-12-20",
+                    "--tag",
+                    "backend",
+                ],
+            )
+            .unwrap();
+            run_args(&mut app, &["done", "1"]).unwrap();
+            let listed = |app: &mut TemplateApp, args: &[&str]| -> Vec<String> {
+                let output = run_args(app, args).unwrap().0;
+                output
+                    .lines()
+                    .map(|line| line[..4].trim().to_string())
+                    .collect()
+            };
+            assert_eq!(
+                listed(
+                    &mut app,
+                    &[
+                        "list",
+                        "--where",
+                        "priority>=medium and due<+3d and not completed and tag:backend"
+                    ]
+                ),
+                ["#2"]
+            );
+            assert_eq!(
+                listed(&mut app, &["list", "-w", "done or due>+1w"]),
+                ["#1", "#3"]
+            );
+            assert_eq!(
+                listed(
+                    &mut app,
+                    &[
+                        "list",
+                        "--search",
+                        "fix or plan",
+                        "--sort",
+                        "due",
+                        "--reverse"
+                    ]
+                ),
+                ["#3", "#2"]
+            );
+
+            // saved views keep the query, so relative dates count from the day they are run
+            run_args(&mut app, &["list", "-w", "due<+3d", "--save", "Soon"]).unwrap();
+            assert_eq!(
+                run_args(&mut app, &["filters"]).unwrap().0,
+                "Soon: \"due<+3d\"\n"
+            );
+            assert_eq!(
+                listed(&mut app, &["list", "--filter", "Soon"]),
+                ["#1", "#2"]
+            );
+
+            let err = Cli::try_parse_from(["taskhero", "list", "--where", "priority>=urgent"])
+                .err()
+                .unwrap()
+                .to_string();
+            assert!(
+                err.contains(
+                    "unknown priority `urgent`, expected low, medium or high (at column 11)"
+                ),
+                "{err}"
+            );
+            assert!(
+                Cli::try_parse_from(["taskhero", "list", "-w", "open", "-s", "report"]).is_err()
+            );
+        }
+
+        #[test]
+        fn test_undo_and_redo() {
+            let mut app = TemplateApp::default();
+            run_args(&mut app, &["add", "Write report", "-p", "high"]).unwrap();
+            run_args(&mut app, &["done", "1"]).unwrap();
+            run_args(&mut app, &["goal", "Garden", "3"]).unwrap();
+            run_args(&mut app, &["list"]).unwrap();
+            assert_eq!(
+                run_args(&mut app, &["history"]).unwrap().0,
+                "The undo history is not kept between runs.\n1. Set the goal of Garden\n2. Complete task \"Write report\"\n3. Add task \"Write report\"\n"
+            );
+
+            run_args(&mut app, &["undo"]).unwrap();
+            assert_eq!(
+                run_args(&mut app, &["undo"]).unwrap(),
+                ("Undid: Complete task \"Write report\".\n".to_string(), true)
+            );
+            let task = app.store().task(TaskId(1)).unwrap();
+            assert!(!task.completed && task.completed_date.is_none());
+            assert_eq!(app.store().gamification().points, 0);
+            assert!(app.store().gamification().project_goals.is_empty());
+
+            assert_eq!(
+                run_args(&mut app, &["redo"]).unwrap().0,
+                "Redid: Complete task \"Write report\".\n"
+            );
+            assert!(app.store().task(TaskId(1)).unwrap().completed);
+            run_args(&mut app, &["rm", "1"]).unwrap();
+            assert_eq!(
+                run_args(&mut app, &["redo"]).unwrap(),
+                ("Nothing to redo.\n".to_string(), false)
+            );
+
+            run_args(&mut app, &["undo"]).unwrap();
+            run_args(&mut app, &["undo"]).unwrap();
+            run_args(&mut app, &["undo"]).unwrap();
+            assert!(app.store().tasks().is_empty());
+            let (output, changed) = run_args(&mut app, &["undo"]).unwrap();
+            assert_eq!(output, "Nothing to undo. Run `taskhero history --keep true` to keep the undo history between runs.\n");
+            assert!(!changed);
+            assert!(
+                run_args(&mut app, &["history", "--keep", "true"])
+                    .unwrap()
+                    .1
+            );
+            assert!(app.history().is_persistent());
+        }
+
+        #[test]
+        fn test_log() {
+            let mut app = TemplateApp::default();
+            app.set_clock(Clock::fixed_on(
+                NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(),
+            ));
+            // tasks saved before the journal existed are recorded when the app is loaded
+            app.store_mut()
+                .create(NewTask {
+                    name: "Plan sprint".to_string(),
+                    description: String::new(),
+                    due_date: None,
+                    priority: PriorityLevel::Low,
+                    recurrence: None,
+                })
+                .unwrap();
+            app.rebuild_from_journal();
+            assert_eq!(app.store().tasks().len(), 1);
+
+            run_args(&mut app, &["add", "Write report"]).unwrap();
+            run_args(&mut app, &["edit", "2", "-p", "high"]).unwrap();
+            run_args(&mut app, &["done", "2"]).unwrap();
+            run_args(&mut app, &["undo"]).unwrap();
+            run_args(&mut app, &["done", "2"]).unwrap();
+            run_args(&mut app, &["rm", "2"]).unwrap();
+            let (output, changed) = run_args(&mut app, &["log", "--task", "2"]).unwrap();
+            assert!(!changed);
+            let lines: Vec<&str> = output
+                .lines()
+                .map(|line| line.strip_prefix("2024-11-20 12:00  ").unwrap())
+                .collect();
+            assert_eq!(
+                lines,
+                [
+                    "Created task #2 \"Write report\"",
+                    "task #2 \"Write report\": priority changed from Low to High",
+                    "Completed task #2 \"Write report\"",
+                    "Reopened task #2 \"Write report\"",
+                    "Completed task #2 \"Write report\"",
+                    "Deleted task #2 \"Write report\"",
+                ]
+            );
+            // both completions count, even though the task was deleted
+            assert_eq!(
+                run_args(&mut app, &["log", "--completions"]).unwrap().0,
+                "2024-11-20: 2 completed\n"
+            );
+
+            // the tasks rebuilt from the journal are the tasks as they are now
+            let names = |app: &TemplateApp| {
+                app.store()
+                    .tasks()
+                    .iter()
+                    .map(|task| task.name.clone())
+                    .collect::<Vec<_>>()
+            };
+            let before = names(&app);
+            app.rebuild_from_journal();
+            assert_eq!(names(&app), before);
+            assert_eq!(
+                run_args(&mut app, &["add", "Groceries"]).unwrap().0,
+                "Added task #3.\n"
+            );
+        }
+
+        #[test]
+        fn test_simulate_date() {
+            let cli = Cli::try_parse_from(["taskhero", "points", "--simulate-date", "2024-11-21"])
+                .unwrap();
+            assert_eq!(cli.simulate_date, NaiveDate::from_ymd_opt(2024, 11, 21));
+
+            // five tasks completed on a day earn that day's reward, but not the next day's
+            let mut app = TemplateApp::default();
+            let day = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap();
+            app.set_clock(Clock::fixed_on(day));
+            for n in 1..=5 {
+                run_args(&mut app, &["add", &format!("Task {n}")]).unwrap();
+                run_args(&mut app, &["done", &n.to_string()]).unwrap();
+            }
+            assert!(run_args(&mut app, &["points"])
+                .unwrap()
+                .0
+                .contains("Daily Reward Points: 25\n"));
+            app.set_clock(Clock::fixed_on(day.succ_opt().unwrap()));
+            assert!(run_args(&mut app, &["points"])
+                .unwrap()
+                .0
+                .contains("Daily Reward Points: 0\n"));
+            assert_eq!(
+                app.store().task(TaskId(1)).unwrap().completed_date,
+                Some(Clock::fixed_on(day).now())
+            );
+
+            // relative due dates are read against the simulated date, not the real one
+            run_args(&mut app, &["add", "Call back", "--due", "tomorrow"]).unwrap();
+            assert_eq!(
+                app.store().task(TaskId(6)).unwrap().due_date,
+                Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 21).unwrap()))
+            );
+            assert!(run_args(&mut app, &["list", "--due-after", "today"])
+                .unwrap()
+                .0
+                .contains("Call back"));
+        }
+
+        #[test]
+        fn test_ledger() {
+            let mut app = TemplateApp::default();
+            app.set_clock(Clock::fixed_on(
+                NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(),
+            ));
+            run_args(&mut app, &["add", "Write report"]).unwrap();
+            run_args(&mut app, &["done", "1"]).unwrap();
+            run_args(&mut app, &["undo"]).unwrap();
+            let (output, changed) = run_args(
+                &mut app,
+                &["ledger", "--adjust", "-3", "--reason", "Fixed a typo"],
+            )
+            .unwrap();
+            assert_eq!(output, "Adjusted the points by -3, to 0.\n");
+            assert!(changed);
+            assert!(Cli::try_parse_from(["taskhero", "ledger", "--adjust", "5"]).is_err());
+
+            let (output, changed) = run_args(&mut app, &["ledger"]).unwrap();
+            assert!(!changed);
+            let lines: Vec<&str> = output
+                .lines()
+                .map(|line| line.strip_prefix("2024-11-20 12:00  ").unwrap_or(line))
+                .collect();
+            assert_eq!(
+                lines,
+                [
+                    "  +10  Completion: Completed \"Write report\"",
+                    "  -10  Penalty: Reopened \"Write report\"",
+                    "   -3  Adjustment: Fixed a typo",
+                    "Balance: -3 points",
+                ]
+            );
+            // the adjustment is kept when the tasks are rebuilt from the journal
+            app.rebuild_from_journal();
+            assert_eq!(app.store().ledger().balance(), -3);
+        }
+
+        #[test]
+        fn test_streak() {
+            let mut app = TemplateApp::default();
+            let mut clock = Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap()); // a Wednesday
+            for day in 0..3 {
+                app.set_clock(clock);
+                run_args(&mut app, &["add", &format!("Task {day}"), "-p", "high"]).unwrap();
+                run_args(&mut app, &["done", &(day + 1).to_string()]).unwrap();
+                clock.advance(chrono::Duration::days(1));
+            }
+            // nothing on Saturday the 23rd, then a task on Sunday
+            clock.advance(chrono::Duration::days(1));
+            app.set_clock(clock);
+            run_args(&mut app, &["add", "Task 3"]).unwrap();
+            run_args(&mut app, &["done", "4"]).unwrap();
+            let (output, changed) = run_args(&mut app, &["streak", "--weeks", "1"]).unwrap();
+            assert!(!changed);
+            assert_eq!(output, "Current streak: 1 day(s) since 2024-11-24\nLongest streak: 3 day(s)\nStreak freezes: 0/2\nRest days: none\n2024-11-18  . . # # # . #\n");
+
+            let (output, changed) = run_args(
+                &mut app,
+                &["streak", "--rest-days", "sat,sun", "--weeks", "0"],
+            )
+            .unwrap();
+            assert!(changed);
+            assert_eq!(output, "Current streak: 4 day(s) since 2024-11-20\nLongest streak: 4 day(s)\nStreak freezes: 0/2\nRest days: Sat, Sun\n");
+            assert!(
+                Cli::try_parse_from(["taskhero", "streak", "--rest-days", "caturday"]).is_err()
+            );
+            let cli =
+                Cli::try_parse_from(["taskhero", "streak", "--rest-days", "sun, sat"]).unwrap();
+            assert!(
+                matches!(cli.command, Some(Command::Streak { rest_days: Some(days), .. }) if days == [Weekday::Sat, Weekday::Sun])
+            );
+            let (output, _) =
+                run_args(&mut app, &["streak", "--rest-days", "", "--weeks", "0"]).unwrap();
+            assert!(output.contains("Rest days: none\n"));
+
+            // 100 points buy two freezes, and no more
+            assert!(run_args(&mut app, &["streak", "--buy-freeze"])
+                .unwrap()
+                .0
+                .starts_with("Bought a streak freeze for 50 points.\n"));
+            run_args(&mut app, &["streak", "--buy-freeze"]).unwrap();
+            assert_eq!(
+                run_args(&mut app, &["streak", "--buy-freeze"]),
+                Err(CliError::Store(StoreError::TooManyFreezes))
+            );
+            assert_eq!(app.store().gamification().points, 0);
+            app.rebuild_from_journal();
+            assert_eq!(app.store().gamification().streak.freezes, 2);
+        }
+
+        #[test]
+        fn test_achievements() {
+            let path = std::env::temp_dir().join(format!(
+                "taskhero_cli_achievements_{}.json",
+                std::process::id()
+            ));
+            let mut app = TemplateApp::default();
+            app.set_clock(Clock::fixed_on(
+                NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(),
+            ));
+            run_args(&mut app, &["add", "Write report"]).unwrap();
+            run_args(&mut app, &["done", "1"]).unwrap();
+            let (output, changed) = run_args(&mut app, &["achievements"]).unwrap();
+            assert!(!changed);
+            assert!(output
+                .contains("[x] First Steps: Complete your first task (unlocked 2024-11-20)\n"));
+            assert!(output.contains("[ ] Bronze: Earn 50 points and reach your bronze goal\n"));
+
+            std::fs::write(&path, r#"[{"id": "home", "name": "Homebody", "description": "Complete a task tagged home",
+                                     "condition": {"type": "completions", "count": 1, "tag": "home"}}]"#).unwrap();
+            assert_eq!(
+                run_args(
+                    &mut app,
+                    &["achievements", "--rules", path.to_str().unwrap()]
+                )
+                .unwrap(),
+                ("Loaded 1 achievement rule(s).\n".to_string(), true)
+            );
+            run_args(&mut app, &["edit", "1", "--tag", "home"]).unwrap();
+            let (output, _) = run_args(&mut app, &["achievements"]).unwrap();
+            assert_eq!(output, "[x] Homebody: Complete a task tagged home (unlocked 2024-11-20)\n2 achievement(s) unlocked.\n");
+            // unlocked achievements stay unlocked after undo, and are rebuilt from the journal
+            run_args(&mut app, &["undo"]).unwrap();
+            app.rebuild_from_journal();
+            assert_eq!(app.store().gamification().achievements.unlocked().len(), 2);
+
+            std::fs::write(&path, "[{\"id\": \"\"}]").unwrap();
+            assert!(matches!(
+                run_args(
+                    &mut app,
+                    &["achievements", "--rules", path.to_str().unwrap()]
+                ),
+                Err(CliError::Rules(_))
+            ));
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_scoring() {
+            let mut app = TemplateApp::default();
+            app.set_clock(Clock::fixed_on(
+                NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(),
+            ));
+            run_args(
+                &mut app,
+                &[
+                    "add",
+                    "Write report",
+                    "--priority",
+                    "high",
+                    "--due",
+                    "2024-11-22",
+                    "--tag",
+                    "urgent",
+                ],
+            )
+            .unwrap();
+            run_args(
+                &mut app,
+                &["edit", "1", "--effort", "2", "--difficulty", "hard"],
+            )
+            .unwrap();
+            // the priority formula ignores the effort and difficulty
+            assert_eq!(
+                run_args(&mut app, &["scoring", "--task", "1"]).unwrap().0,
+                "High priority           30\nPoints                  30\n"
+            );
+
+            let (output, changed) = run_args(
+                &mut app,
+                &[
+                    "scoring",
+                    "--formula",
+                    "weighted",
+                    "--tag-multiplier",
+                    "urgent=2",
+                ],
+            )
+            .unwrap();
+            assert!(changed);
+            assert_eq!(output, "Formula: Weighted\nEffort: 5 points an hour\nCompleted early: ×1.25, late: ×0.75\nTag urgent: ×2\n");
+            // (30 + 2 * 5) * 1.5 for a hard task, * 1.25 for completing it two days early, * 2 for the tag
+            let (output, _) = run_args(&mut app, &["done", "1"]).unwrap();
+            assert!(
+                output.starts_with("Completed task #1 \"Write report\" (+150 points).\n"),
+                "{output}"
+            );
+            assert_eq!(app.store().gamification().points, 150);
+            let (output, _) = run_args(&mut app, &["scoring", "--task", "1"]).unwrap();
+            assert!(output.contains("Completed early         ×1.25\nTag urgent              ×2\nPoints                  150\n"), "{output}");
+
+            // points set by hand win, and the ledger settles the difference
+            run_args(&mut app, &["edit", "1", "--points", "40"]).unwrap();
+            assert_eq!(app.store().gamification().points, 40);
+            run_args(&mut app, &["undo"]).unwrap();
+            // a completed task keeps the points it was paid when the formula changes, also in the journal
+            run_args(&mut app, &["scoring", "--formula", "priority"]).unwrap();
+            app.rebuild_from_journal();
+            assert_eq!(
+                app.store().gamification().scoring.formula,
+                Formula::Priority
+            );
+            assert_eq!(app.store().gamification().points, 150);
+            assert!(run_args(&mut app, &["scoring", "--task", "1"])
+                .unwrap()
+                .0
+                .ends_with("Points                  150\n"));
+            assert!(
+                Cli::try_parse_from(["taskhero", "scoring", "--tag-multiplier", "urgent"]).is_err()
+            );
+            assert!(Cli::try_parse_from(["taskhero", "scoring", "--late=-1"]).is_err());
+        }
+
+        #[test]
+        fn test_level() {
+            let path = std::env::temp_dir()
+                .join(format!("taskhero_cli_levels_{}.json", std::process::id()));
+            let mut app = TemplateApp::default();
+            app.set_clock(Clock::fixed_on(
+                NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(),
+            ));
+            assert_eq!(
+                run_args(&mut app, &["level"]).unwrap(),
+                (
+                    "Level 1 (0 XP)\nNext level: 100 XP to go, at 100 XP\n".to_string(),
+                    false
+                )
+            );
+
+            std::fs::write(&path, r#"{"curve": {"type": "linear", "per_level": 10}, "xp": {"task_points": 1, "bonuses": 1, "streak_day": 0}}"#).unwrap();
+            run_args(&mut app, &["level", "--config", path.to_str().unwrap()]).unwrap();
+            run_args(&mut app, &["add", "Write report", "--priority", "high"]).unwrap();
+            let (output, _) = run_args(&mut app, &["done", "1"]).unwrap();
+            assert!(
+                output.ends_with("Level up! You reached level 4.\n"),
+                "{output}"
+            );
+            // levels reached are kept after undo and going back to the standard curve, and rebuilt from the journal
+            run_args(&mut app, &["undo"]).unwrap();
+            run_args(&mut app, &["level", "--standard"]).unwrap();
+            app.rebuild_from_journal();
+            assert_eq!(app.store().gamification().best_level, 4);
+            assert_eq!(app.store().gamification().level.level, 1);
+
+            std::fs::write(&path, "{}").unwrap();
+            assert!(matches!(
+                run_args(&mut app, &["level", "--config", path.to_str().unwrap()]),
+                Err(CliError::Levels(_))
+            ));
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_export_then_import() {
+            let path = std::env::temp_dir()
+                .join(format!("taskhero_cli_export_{}.json", std::process::id()));
+            let mut app = TemplateApp::default();
+            run_args(&mut app, &["add", "Write report"]).unwrap();
+            run_args(&mut app, &["export", "--output", path.to_str().unwrap()]).unwrap();
+
+            let (output, changed) =
+                run_args(&mut app, &["import", path.to_str().unwrap()]).unwrap();
+            assert_eq!(output, "Imported 0 task(s), skipped 1 duplicate(s).\n");
+            assert!(!changed);
+
+            let mut other = TemplateApp::default();
+            assert!(
+                run_args(&mut other, &["import", "--replace", path.to_str().unwrap()])
+                    .unwrap()
+                    .1
+            );
+            assert_eq!(other.store().task(TaskId(1)).unwrap().name, "Write report");
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_import_csv_with_mapping() {
+            let path = std::env::temp_dir()
+                .join(format!("taskhero_cli_import_{}.csv", std::process::id()));
+            std::fs::write(
+                &path,
+                "Summary,Level,When\nWrite report,H,2024-11-23\nBroken,urgent,\n",
+            )
+            .unwrap();
+            let mut app = TemplateApp::default();
+
+            let (output, changed) = run_args(
+                &mut app,
+                &[
+                    "import-csv",
+                    path.to_str().unwrap(),
+                    "--map",
+                    "priority=Level",
+                    "--map",
+                    "due_date=3",
+                    "--dry-run",
+                ],
+            )
+            .unwrap();
+            assert_eq!(output, "Skipping line 3: invalid priority: unknown priority \"urgent\", expected low, medium or high (or L/M/H, 1/2/3)\n1 row(s) would be imported.\n");
+            assert!(!changed);
+            assert!(app.store().tasks().is_empty());
+
+            run_args(
+                &mut app,
+                &[
+                    "import-csv",
+                    path.to_str().unwrap(),
+                    "--map",
+                    "priority=Level",
+                    "--map",
+                    "due_date=3",
+                ],
+            )
+            .unwrap();
+            let task = app.store().task(TaskId(1)).unwrap();
+            assert_eq!(task.priority, PriorityLevel::High);
+            assert_eq!(
+                task.due_date,
+                dates::parse_due_date("2024-11-23", NaiveDate::from_ymd_opt(2024, 11, 20).unwrap())
+                    .ok()
+            );
+            assert!(run_args(
+                &mut app,
+                &["import-csv", path.to_str().unwrap(), "--map", "due_date=9"]
+            )
+            .is_err());
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_export_then_import_ics() {
+            let path = std::env::temp_dir()
+                .join(format!("taskhero_cli_export_{}.ics", std::process::id()));
+            let mut app = TemplateApp::default();
+            run_args(
+                &mut app,
+                &["add", "Write report", "-p", "high", "--due", "2024-11-23"],
+            )
+            .unwrap();
+            run_args(&mut app, &["add", "Someday"]).unwrap();
+            let (output, _) = run_args(&mut app, &["export-ics", "--events"]).unwrap();
+            assert_eq!(output.matches("BEGIN:VEVENT").count(), 1);
+            run_args(&mut app, &["export-ics", "-o", path.to_str().unwrap()]).unwrap();
+
+            let mut other = TemplateApp::default();
+            let (output, changed) =
+                run_args(&mut other, &["import-ics", path.to_str().unwrap()]).unwrap();
+            assert_eq!(output, "Imported 2 task(s).\n");
+            assert!(changed);
+            assert_eq!(
+                other.store().task(TaskId(1)).unwrap().priority,
+                PriorityLevel::High
+            );
+            assert!(
+                !run_args(&mut other, &["import-ics", path.to_str().unwrap()])
+                    .unwrap()
+                    .1
+            );
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_sync_todotxt() {
+            let path =
+                std::env::temp_dir().join(format!("taskhero_cli_sync_{}.txt", std::process::id()));
+            std::fs::write(
+                &path,
+                "(A) Write report +backend due:2024-11-23\nx 2024-11-19 Call the bank\n",
+            )
+            .unwrap();
+            let mut app = TemplateApp::default();
+            run_args(&mut app, &["add", "Existing"]).unwrap();
+
+            let (output, changed) =
+                run_args(&mut app, &["sync-todotxt", path.to_str().unwrap()]).unwrap();
+            assert_eq!(
+                output,
+                format!(
+                    "Synced {}: Added 2 task(s), updated 0, removed 0.\n",
+                    path.display()
+                )
+            );
+            assert!(changed);
+            assert_eq!(
+                std::fs::read_to_string(&path).unwrap(),
+                "(C) Existing taskhero:1\n(A) Write report +backend due:2024-11-23 taskhero:2\nx 2024-11-19 Call the bank pri:C taskhero:3\n"
+            );
+
+            // completing a task in the file completes it in TaskHero
+            std::fs::write(&path, "(C) Existing taskhero:1\nx 2024-11-20 Write report +backend due:2024-11-23 pri:A taskhero:2\n").unwrap();
+            assert!(
+                run_args(&mut app, &["sync-todotxt", path.to_str().unwrap()])
+                    .unwrap()
+                    .1
+            );
+            assert!(app.store().task(TaskId(2)).unwrap().completed);
+            assert_eq!(app.store().tasks().len(), 3); // lines are only deleted in watch mode
+
+            let (output, changed) =
+                run_args(&mut app, &["import-todotxt", path.to_str().unwrap()]).unwrap();
+            assert_eq!(output, "Imported 0 task(s), skipped 3 duplicate(s).\n");
+            assert!(!changed);
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_invalid_arguments_are_rejected() {
+            assert!(Cli::try_parse_from(["taskhero", "add", "Task", "--due", "someday"]).is_err());
+            assert!(Cli::try_parse_from(["taskhero", "add", "Task", "-p", "urgent"]).is_err());
+            assert!(Cli::try_parse_from(["taskhero", "done", "0"]).is_err());
+        }
+    }
+}
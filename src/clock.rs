@@ -0,0 +1,96 @@
+//! The clock every date-dependent part of TaskHero reads the time from.
+//!
+//! Completion times, the daily reward, the weekly challenge, the Tasks Report and relative due
+//! dates all ask a [`Clock`] instead of reading the system time themselves. The system clock is
+//! used normally; tests and fuzz targets use a fixed clock so they give the same result on any
+//! day, and the "simulate date" debug mode uses an offset clock to see what the app does on
+//! another day while the time keeps running.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+
+use crate::dates;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// Where the current time comes from
+pub enum Clock {
+    #[default]
+    System, // the system time
+    Fixed(DateTime<Local>), // always the same moment
+    Offset(Duration),       // the system time moved by a fixed amount
+}
+
+/// Implementation of Clock with the current time and date
+impl Clock {
+    /// Helper function to get a fixed clock at noon on a date, which is on that date in every time zone
+    pub fn fixed_on(date: NaiveDate) -> Self {
+        let noon = date.and_hms_opt(12, 0, 0).expect("noon is a valid time");
+        Clock::Fixed(
+            Local
+                .from_local_datetime(&noon)
+                .earliest()
+                .unwrap_or_else(|| dates::start_of_day(date)),
+        )
+    }
+
+    /// Helper function to get a clock that runs as usual but on another date
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - The date it is today according to the clock
+    pub fn simulating(date: NaiveDate) -> Self {
+        let today = Local::now().date_naive();
+        if date == today {
+            Clock::System
+        } else {
+            Clock::Offset(date - today)
+        }
+    }
+
+    /// Helper function to get the current time
+    pub fn now(&self) -> DateTime<Local> {
+        match *self {
+            Clock::System => Local::now(),
+            Clock::Fixed(now) => now,
+            Clock::Offset(offset) => Local::now() + offset,
+        }
+    }
+
+    /// Helper function to get the current local date
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    /// Helper function to check whether the clock tells another time than the system
+    pub fn is_simulated(&self) -> bool {
+        *self != Clock::System
+    }
+
+    /// Helper function to move a fixed clock forward, such as to the next day in a test.
+    /// Other clocks follow the system time, so they aren't moved.
+    pub fn advance(&mut self, by: Duration) {
+        if let Clock::Fixed(now) = self {
+            *now += by;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clocks() {
+        let date = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap();
+        let mut clock = Clock::fixed_on(date);
+        assert_eq!(clock.today(), date);
+        assert_eq!(clock.now(), clock.now());
+        clock.advance(Duration::days(1));
+        assert_eq!(clock.today(), date + Duration::days(1));
+
+        let simulated = Clock::simulating(date);
+        assert_eq!(simulated.today(), date);
+        assert!(simulated.is_simulated());
+        assert_eq!(Clock::simulating(Local::now().date_naive()), Clock::System);
+        assert!(!Clock::default().is_simulated());
+    }
+}
@@ -0,0 +1,561 @@
+//! CSV import and export for spreadsheets.
+//!
+//! Importing is done in two steps. [`CsvFile::parse`] reads the file, then
+//! [`CsvFile::preview`] maps its columns onto `Task` fields and reports the rows that fail to
+//! parse, so they can be shown before [`import`] adds the rows that parsed. The column mapping
+//! is guessed from the header row and can be changed before previewing.
+//!
+//! Exporting writes either the tasks, with the same columns the importer guesses, or the
+//! metrics of the Tasks Report. Tags are written to one column, separated by commas.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDate};
+
+use crate::dates;
+use crate::gamification::{PriorityLevel, Task};
+use crate::report::TaskReport;
+use crate::store::TaskStore;
+use crate::tags;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Task fields that CSV columns can be mapped onto
+pub enum TaskField {
+    Name,
+    Description,
+    DueDate,
+    Priority,
+    Completed,
+    CompletedDate,
+    Project,
+    Tags,
+}
+
+/// Implementation of TaskField with the list of fields and their column names
+impl TaskField {
+    /// Every field, in the order of the exported columns
+    pub const ALL: [TaskField; 8] = [
+        TaskField::Name,
+        TaskField::Description,
+        TaskField::DueDate,
+        TaskField::Priority,
+        TaskField::Completed,
+        TaskField::CompletedDate,
+        TaskField::Project,
+        TaskField::Tags,
+    ];
+
+    /// Helper function to get the column name the field is exported under
+    pub fn column_name(self) -> &'static str {
+        match self {
+            TaskField::Name => "name",
+            TaskField::Description => "description",
+            TaskField::DueDate => "due_date",
+            TaskField::Priority => "priority",
+            TaskField::Completed => "completed",
+            TaskField::CompletedDate => "completed_date",
+            TaskField::Project => "project",
+            TaskField::Tags => "tags",
+        }
+    }
+
+    /// Helper function to get the other header names that are recognized for the field
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            TaskField::Name => &["name", "task", "title", "task name", "summary"],
+            TaskField::Description => &["description", "notes", "details"],
+            TaskField::DueDate => &["due_date", "due date", "due", "deadline"],
+            TaskField::Priority => &["priority", "importance"],
+            TaskField::Completed => &["completed", "done", "complete", "status"],
+            TaskField::CompletedDate => &[
+                "completed_date",
+                "completed date",
+                "completed at",
+                "completed on",
+                "done date",
+            ],
+            TaskField::Project => &["project", "list"],
+            TaskField::Tags => &["tags", "tag", "labels", "categories"],
+        }
+    }
+}
+
+impl FromStr for TaskField {
+    type Err = String;
+
+    /// Helper function to parse a field by its column name or one of its aliases
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        TaskField::ALL
+            .into_iter()
+            .find(|field| field.aliases().contains(&input.as_str()))
+            .ok_or_else(|| format!("unknown task field {input:?}"))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// ColumnMapping struct with the CSV column, if any, that each task field is read from
+pub struct ColumnMapping {
+    columns: [Option<usize>; 8], // indexed by the position of the field in TaskField::ALL
+}
+
+/// Implementation of ColumnMapping with methods to guess and change the mapping
+impl ColumnMapping {
+    /// Helper function to guess the mapping from the header row, matching header names
+    /// such as "Title", "Due Date" or "Done" in any case
+    pub fn from_headers(headers: &[String]) -> Self {
+        let mut mapping = ColumnMapping::default();
+        for (column, header) in headers.iter().enumerate() {
+            if let Ok(field) = header.parse::<TaskField>() {
+                if mapping.get(field).is_none() {
+                    mapping.set(field, Some(column));
+                }
+            }
+        }
+        mapping
+    }
+
+    /// Helper function to get the column a field is read from
+    pub fn get(&self, field: TaskField) -> Option<usize> {
+        self.columns[field as usize]
+    }
+
+    /// Helper function to change the column a field is read from; None leaves the field empty
+    pub fn set(&mut self, field: TaskField, column: Option<usize>) {
+        self.columns[field as usize] = column;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A row of a CSV file that failed to parse
+pub struct RowError {
+    pub line: u64, // line number in the file, starting at 1 for the header row
+    pub message: String,
+}
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A row of a CSV file, before it is mapped onto a task
+struct CsvRow {
+    line: u64,
+    fields: Result<Vec<String>, String>, // Err when the row itself could not be read
+}
+
+/// CsvFile struct with the header and rows of a CSV file
+pub struct CsvFile {
+    pub headers: Vec<String>,
+    rows: Vec<CsvRow>,
+}
+
+/// CsvPreview struct with the result of mapping every row of a CSV file onto a task
+pub struct CsvPreview {
+    pub tasks: Vec<Task>,        // rows that parsed, in file order
+    pub failures: Vec<RowError>, // rows that did not parse
+}
+
+/// Implementation of CsvFile with methods to read a file and preview its tasks
+impl CsvFile {
+    /// Helper function to read a CSV file with a header row
+    ///
+    /// # Returns
+    ///
+    /// The file, or an error if the header row can't be read.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true) // rows with missing trailing columns are allowed
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+        let headers = reader
+            .headers()
+            .map_err(|err| format!("the header row could not be read: {err}"))?
+            .iter()
+            .map(str::to_string)
+            .collect();
+
+        let rows = reader
+            .records()
+            .map(|record| match record {
+                Ok(record) => CsvRow {
+                    line: record.position().map_or(0, |position| position.line()),
+                    fields: Ok(record.iter().map(str::to_string).collect()),
+                },
+                Err(err) => CsvRow {
+                    line: err.position().map_or(0, |position| position.line()),
+                    fields: Err(err.to_string()),
+                },
+            })
+            .collect();
+        Ok(CsvFile { headers, rows })
+    }
+
+    /// Helper function to guess the column mapping from the header row
+    pub fn guess_mapping(&self) -> ColumnMapping {
+        ColumnMapping::from_headers(&self.headers)
+    }
+
+    /// Helper function to map every row onto a task
+    ///
+    /// # Arguments
+    ///
+    /// * `mapping` - The column each task field is read from
+    /// * `today` - The date that relative dates such as "tomorrow" are read against
+    ///
+    /// # Returns
+    ///
+    /// The tasks and the rows that failed to parse, or an error if no column is mapped to the name.
+    pub fn preview(&self, mapping: &ColumnMapping, today: NaiveDate) -> Result<CsvPreview, String> {
+        if mapping.get(TaskField::Name).is_none() {
+            return Err("No column is mapped to the task name.".to_string());
+        }
+        let mut preview = CsvPreview {
+            tasks: Vec::new(),
+            failures: Vec::new(),
+        };
+        for row in &self.rows {
+            match row
+                .fields
+                .as_ref()
+                .map_err(String::clone)
+                .and_then(|fields| parse_row(fields, mapping, today))
+            {
+                Ok(task) => preview.tasks.push(task),
+                Err(message) => preview.failures.push(RowError {
+                    line: row.line,
+                    message,
+                }),
+            }
+        }
+        Ok(preview)
+    }
+}
+
+/// Helper function to map one row onto a task
+fn parse_row(fields: &[String], mapping: &ColumnMapping, today: NaiveDate) -> Result<Task, String> {
+    // Missing columns in short rows read as empty
+    let field = |field: TaskField| {
+        mapping
+            .get(field)
+            .and_then(|column| fields.get(column))
+            .map_or("", String::as_str)
+    };
+
+    let name = field(TaskField::Name);
+    if name.is_empty() {
+        return Err("the name is empty".to_string());
+    }
+    let due_date = match field(TaskField::DueDate) {
+        "" => None,
+        text => Some(
+            dates::parse_due_date(text, today)
+                .map_err(|err| format!("invalid due date {text:?}: {err}"))?,
+        ),
+    };
+    let priority = match field(TaskField::Priority) {
+        "" => PriorityLevel::Low,
+        text => text
+            .parse()
+            .map_err(|err| format!("invalid priority: {err}"))?,
+    };
+    let completed_date = match field(TaskField::CompletedDate) {
+        "" => None,
+        text => Some(
+            dates::parse_completed_date(text, today)
+                .ok_or_else(|| format!("invalid completed date {text:?}"))?,
+        ),
+    };
+    let completed = match field(TaskField::Completed) {
+        "" => completed_date.is_some(),
+        text => parse_completed(text)
+            .ok_or_else(|| format!("invalid completed value {text:?}, expected yes or no"))?,
+    };
+    if completed_date.is_some() && !completed {
+        return Err("a completed date is set but the task is not completed".to_string());
+    }
+
+    Ok(Task {
+        description: field(TaskField::Description).to_string(),
+        due_date,
+        completed,
+        completed_date,
+        tags: tags::parse_tags(field(TaskField::Tags)),
+        project: tags::normalize_project(Some(field(TaskField::Project).to_string())),
+        ..Task::new(name, priority) // the ID is assigned when the task is added
+    })
+}
+
+/// Helper function to parse the spellings of a completed flag that spreadsheets use
+fn parse_completed(text: &str) -> Option<bool> {
+    match text.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "x" | "done" | "completed" => Some(true),
+        "false" | "no" | "n" | "0" | "todo" | "open" | "pending" => Some(false),
+        _ => None,
+    }
+}
+
+/// Helper function to add the tasks of a preview to the store
+///
+/// # Returns
+///
+/// The number of tasks added.
+pub fn import(store: &mut TaskStore, preview: CsvPreview) -> usize {
+    let added = preview.tasks.len();
+    for task in preview.tasks {
+        store.insert(task);
+    }
+    store.evaluate();
+    added
+}
+
+/// Helper function to export every task as CSV, with an `id` column followed by the
+/// columns of TaskField::ALL
+pub fn export_tasks(store: &TaskStore) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut header = vec!["id"];
+    header.extend(TaskField::ALL.map(TaskField::column_name));
+    writer
+        .write_record(header)
+        .expect("writing to memory can't fail");
+
+    for task in store.tasks() {
+        writer
+            .write_record([
+                task.id.0.to_string(),
+                task.name.clone(),
+                task.description.clone(),
+                task.due_date
+                    .map(|due_date| due_date.to_string())
+                    .unwrap_or_default(),
+                format!("{:?}", task.priority),
+                task.completed.to_string(),
+                task.completed_date
+                    .map(|date: DateTime<Local>| date.to_rfc3339())
+                    .unwrap_or_default(),
+                task.project.clone().unwrap_or_default(),
+                task.tags.join(", "),
+            ])
+            .expect("writing to memory can't fail");
+    }
+    into_string(writer)
+}
+
+/// Helper function to export the Tasks Report metrics as CSV with `metric`, `value` and
+/// `percentage` columns. The percentage is empty for metrics that are not counts of tasks.
+pub fn export_report(report: &TaskReport) -> String {
+    let count = |count: usize| {
+        (
+            count.to_string(),
+            format!("{:.2}", report.percentage(count)),
+        )
+    };
+    let mut rows = vec![
+        (
+            "Total Tasks".to_string(),
+            (report.total_tasks.to_string(), String::new()),
+        ),
+        ("Completed Tasks".to_string(), count(report.completed_tasks)),
+        (
+            "Incomplete Tasks".to_string(),
+            count(report.incomplete_tasks),
+        ),
+        (
+            "Low Priority Tasks".to_string(),
+            count(report.low_priority_tasks),
+        ),
+        (
+            "Medium Priority Tasks".to_string(),
+            count(report.medium_priority_tasks),
+        ),
+        (
+            "High Priority Tasks".to_string(),
+            count(report.high_priority_tasks),
+        ),
+        (
+            "Total Points Earned".to_string(),
+            (report.total_points.to_string(), String::new()),
+        ),
+        (
+            "Average Task Priority".to_string(),
+            (format!("{:.2}", report.average_priority), String::new()),
+        ),
+        (
+            "Most Common Task Priority".to_string(),
+            (format!("{:?}", report.most_common_priority), String::new()),
+        ),
+        ("Upcoming Tasks".to_string(), count(report.upcoming_tasks)),
+    ];
+    // Each project and tag gets its number of tasks, the part of them that is completed and its points
+    for (kind, breakdowns) in [("Project", &report.projects), ("Tag", &report.tags)] {
+        for breakdown in breakdowns {
+            let name = format!("{kind} {}", breakdown.name);
+            rows.push((format!("{name}: Tasks"), count(breakdown.total_tasks)));
+            rows.push((
+                format!("{name}: Completed Tasks"),
+                (
+                    breakdown.completed_tasks.to_string(),
+                    format!("{:.2}", breakdown.completed_percentage()),
+                ),
+            ));
+            rows.push((
+                format!("{name}: Points Earned"),
+                (breakdown.total_points.to_string(), String::new()),
+            ));
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(["metric", "value", "percentage"])
+        .expect("writing to memory can't fail");
+    for (metric, (value, percentage)) in rows {
+        writer
+            .write_record([&metric, &value, &percentage])
+            .expect("writing to memory can't fail");
+    }
+    into_string(writer)
+}
+
+/// Helper function to get the text written to an in-memory CSV writer
+fn into_string(writer: csv::Writer<Vec<u8>>) -> String {
+    let bytes = writer.into_inner().expect("writing to memory can't fail");
+    String::from_utf8(bytes).expect("the CSV writer only writes UTF-8 input")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::dates::DueDate;
+    use chrono::NaiveDate;
+
+    /// Helper function to get the date the tests run on
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 11, 20).unwrap()
+    }
+
+    #[test]
+    fn test_guessed_mapping_and_tolerant_values() {
+        let csv = "Title,Notes,Deadline,Importance,Done\n\
+                   Write report,Quarterly numbers,2024-11-23,H,yes\n\
+                   Email team,,11/30/2024,2,no\n\
+                   Plan sprint,,,low\n";
+        let file = CsvFile::parse(csv).unwrap();
+        let mapping = file.guess_mapping();
+        assert_eq!(mapping.get(TaskField::Name), Some(0));
+        assert_eq!(mapping.get(TaskField::Completed), Some(4));
+        assert_eq!(mapping.get(TaskField::CompletedDate), None);
+
+        let preview = file.preview(&mapping, today()).unwrap();
+        assert!(preview.failures.is_empty());
+        let priorities: Vec<_> = preview.tasks.iter().map(|task| task.priority).collect();
+        assert_eq!(
+            priorities,
+            [
+                PriorityLevel::High,
+                PriorityLevel::Medium,
+                PriorityLevel::Low
+            ]
+        );
+        assert_eq!(
+            preview.tasks[1].due_date,
+            Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 30).unwrap()))
+        );
+        assert!(preview.tasks[0].completed && !preview.tasks[2].completed);
+
+        let mut store = TaskStore::new();
+        assert_eq!(import(&mut store, preview), 3);
+        assert_eq!(store.gamification().points, 30);
+    }
+
+    #[test]
+    fn test_preview_reports_failing_rows() {
+        let csv = "name,due,priority,completed date\n\
+                   Fine,,,\n\
+                   ,2024-11-23,,\n\
+                   Bad due,someday,,\n\
+                   Bad priority,,urgent,\n\
+                   Done,,,2024-11-22T18:45:10-05:00\n";
+        let file = CsvFile::parse(csv).unwrap();
+        let preview = file.preview(&file.guess_mapping(), today()).unwrap();
+        assert_eq!(preview.tasks.len(), 2);
+        // A completed date without a completed column marks the task as completed
+        assert!(preview.tasks[1].completed);
+        let lines: Vec<_> = preview
+            .failures
+            .iter()
+            .map(|failure| failure.line)
+            .collect();
+        assert_eq!(lines, [3, 4, 5]);
+        assert!(preview.failures[1]
+            .to_string()
+            .starts_with("line 4: invalid due date \"someday\""));
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let file = CsvFile::parse("a,b\nFirst,3\n").unwrap();
+        assert!(file.preview(&file.guess_mapping(), today()).is_err());
+
+        let mut mapping = ColumnMapping::default();
+        mapping.set("task".parse().unwrap(), Some(0));
+        mapping.set(TaskField::Priority, Some(1));
+        let preview = file.preview(&mapping, today()).unwrap();
+        assert_eq!(preview.tasks[0].name, "First");
+        assert_eq!(preview.tasks[0].priority, PriorityLevel::High);
+    }
+
+    #[test]
+    fn test_relative_dates_follow_the_clock() {
+        let mut store = TaskStore::new();
+        store.set_clock(Clock::fixed_on(today()));
+        let file =
+            CsvFile::parse("name,due,completed date\nCall back,tomorrow,\nFiled,,yesterday\n")
+                .unwrap();
+        let preview = file
+            .preview(&file.guess_mapping(), store.clock().today())
+            .unwrap();
+        assert_eq!(
+            preview.tasks[0].due_date,
+            Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 21).unwrap()))
+        );
+        assert_eq!(
+            preview.tasks[1]
+                .completed_date
+                .map(|date| date.date_naive()),
+            NaiveDate::from_ymd_opt(2024, 11, 19)
+        );
+    }
+
+    #[test]
+    fn test_exports_round_trip() {
+        let file = CsvFile::parse(
+            "name,description,priority,completed,project,labels\n\"Write, report\",\"Line \"\"one\"\"\",medium,true,Work,\"#urgent, q4\"\n",
+        )
+        .unwrap();
+        let mut store = TaskStore::new();
+        import(
+            &mut store,
+            file.preview(&file.guess_mapping(), today()).unwrap(),
+        );
+
+        let exported = export_tasks(&store);
+        let file = CsvFile::parse(&exported).unwrap();
+        assert_eq!(file.headers[0], "id");
+        let task = &file.preview(&file.guess_mapping(), today()).unwrap().tasks[0];
+        assert_eq!(task.name, "Write, report");
+        assert_eq!(task.description, "Line \"one\"");
+        assert_eq!(task.priority, PriorityLevel::Medium);
+        assert!(task.completed);
+        assert_eq!(task.project.as_deref(), Some("Work"));
+        assert_eq!(task.tags, ["urgent", "q4"]);
+
+        let report = export_report(&store.report(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap()));
+        assert!(report
+            .starts_with("metric,value,percentage\nTotal Tasks,1,\nCompleted Tasks,1,100.00\n"));
+        assert!(report.contains("\nProject Work: Tasks,1,100.00\nProject Work: Completed Tasks,1,100.00\nProject Work: Points Earned,20,\n"));
+        assert!(report.ends_with("\nTag urgent: Points Earned,20,\n"));
+    }
+}
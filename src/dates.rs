@@ -0,0 +1,519 @@
+use crate::clock::Clock;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Timelike,
+};
+
+/// Date formats accepted by the tolerant parser, tried in order.
+/// Month-first formats are tried before day-first ones, so `01/02/2024` is January 2nd.
+const DATE_FORMATS: [&str; 18] = [
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%Y.%m.%d",
+    "%m/%d/%Y",
+    "%m.%d.%Y",
+    "%m-%d-%Y",
+    "%d/%m/%Y",
+    "%d.%m.%Y",
+    "%d-%m-%Y",
+    "%b %d %Y",
+    "%b %d, %Y",
+    "%d %b %Y",
+    "%B %d %Y",
+    "%B %d, %Y",
+    "%d %B %Y",
+    "%m/%d/%y",
+    "%m.%d.%y",
+    "%d/%m/%y",
+];
+
+/// Time formats accepted by the tolerant parser, tried in order
+const TIME_FORMATS: [&str; 6] = ["%H:%M", "%H:%M:%S", "%I:%M%p", "%I:%M %p", "%I%p", "%I %p"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Errors returned when a date typed by the user can't be understood
+pub enum DateParseError {
+    Empty,
+    Unrecognized(String),
+}
+
+impl std::fmt::Display for DateParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateParseError::Empty => write!(f, "Please enter a due date."),
+            DateParseError::Unrecognized(input) => write!(
+                f,
+                "Could not understand the date \"{input}\". Try YYYY-MM-DD, MM/DD/YYYY, \"Nov 23 2024\" or \"tomorrow\", optionally followed by a time like 14:30."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DateParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// DueDate struct with the calendar date a task is due, plus an optional time of day
+/// and an optional UTC offset (in minutes) for that time. Without an offset the time
+/// is in the user's local time zone.
+pub struct DueDate {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+    pub utc_offset_minutes: Option<i32>,
+}
+
+/// Implementation of DueDate with constructors, the tolerant parser, and local-time helpers
+impl DueDate {
+    /// Helper function to create a due date without a time of day
+    pub fn on(date: NaiveDate) -> Self {
+        DueDate {
+            date,
+            time: None,
+            utc_offset_minutes: None,
+        }
+    }
+
+    /// Helper function to get the moment the task is due in the local time zone.
+    /// Tasks without a time of day are due at the end of the day.
+    pub fn to_local(&self) -> DateTime<Local> {
+        let time = self
+            .time
+            .unwrap_or_else(|| NaiveTime::from_hms_opt(23, 59, 59).expect("valid time"));
+        let naive = self.date.and_time(time);
+        match self
+            .utc_offset_minutes
+            .and_then(|minutes| FixedOffset::east_opt(minutes * 60))
+        {
+            Some(offset) => match offset.from_local_datetime(&naive).single() {
+                Some(due) => due.with_timezone(&Local),
+                None => local_from_naive(naive),
+            },
+            None => local_from_naive(naive),
+        }
+    }
+
+    /// Helper function to get the calendar date the task is due in the local time zone
+    pub fn local_date(&self) -> NaiveDate {
+        if self.utc_offset_minutes.is_some() && self.time.is_some() {
+            self.to_local().date_naive()
+        } else {
+            self.date
+        }
+    }
+}
+
+impl std::fmt::Display for DueDate {
+    /// Format the due date in its canonical form, e.g. `2024-11-23 14:30 +02:00`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.date.format("%Y-%m-%d"))?;
+        if let Some(time) = self.time {
+            if time.second() == 0 {
+                write!(f, " {}", time.format("%H:%M"))?;
+            } else {
+                write!(f, " {}", time.format("%H:%M:%S"))?;
+            }
+        }
+        if let Some(minutes) = self.utc_offset_minutes {
+            let sign = if minutes < 0 { '-' } else { '+' };
+            write!(
+                f,
+                " {}{:02}:{:02}",
+                sign,
+                minutes.abs() / 60,
+                minutes.abs() % 60
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl serde::Serialize for DueDate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for DueDate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        // saved dates are written in full, so the day only matters for hand-edited files
+        parse_due_date(&input, Clock::System.today()).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Helper function to interpret a naive date and time in the local time zone,
+/// picking the earlier moment when a daylight saving change makes it ambiguous
+fn local_from_naive(naive: NaiveDateTime) -> DateTime<Local> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&naive))
+}
+
+/// Helper function to get the start of a calendar day in the local time zone
+pub fn start_of_day(date: NaiveDate) -> DateTime<Local> {
+    local_from_naive(date.and_time(NaiveTime::MIN))
+}
+
+/// Helper function to parse a due date in any of the common formats users type
+///
+/// # Arguments
+///
+/// * `input` - The text typed by the user, e.g. `2024-11-23`, `11/23/2024 2:30pm`,
+///   `Nov 23 2024 14:30 +02:00` or `tomorrow`
+/// * `today` - The date that relative words such as "today" and "tomorrow" refer to
+pub fn parse_due_date(input: &str, today: NaiveDate) -> Result<DueDate, DateParseError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(DateParseError::Empty);
+    }
+    let unrecognized = || DateParseError::Unrecognized(input.to_string());
+
+    // Full RFC 3339 timestamps, e.g. 2024-11-23T14:30:00+02:00
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(input) {
+        return Ok(DueDate {
+            date: timestamp.date_naive(),
+            time: timestamp.time().with_nanosecond(0),
+            utc_offset_minutes: Some(timestamp.offset().local_minus_utc() / 60),
+        });
+    }
+
+    let (rest, utc_offset_minutes) = split_utc_offset(input);
+    let mut due = parse_date_and_time(rest, today).ok_or_else(unrecognized)?;
+    if utc_offset_minutes.is_some() && due.time.is_none() {
+        // An offset only means something for a time of day
+        return Err(unrecognized());
+    }
+    due.utc_offset_minutes = utc_offset_minutes;
+    Ok(due)
+}
+
+/// Helper function to split a trailing UTC offset (`Z`, `UTC`, `+02:00`, `-0500`, ...) off the input
+fn split_utc_offset(input: &str) -> (&str, Option<i32>) {
+    if let Some(rest) = input.strip_suffix(['Z', 'z']) {
+        if rest.ends_with(|c: char| c.is_ascii_digit()) {
+            return (rest, Some(0));
+        }
+    }
+    let Some((rest, suffix)) = input.rsplit_once(' ') else {
+        return (input, None);
+    };
+    if suffix.eq_ignore_ascii_case("utc") || suffix.eq_ignore_ascii_case("gmt") {
+        return (rest.trim_end(), Some(0));
+    }
+    let sign = match suffix.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return (input, None),
+    };
+    let digits: String = suffix[1..].chars().filter(|&c| c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return (input, None);
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok(), Some(0)),
+        4 => (
+            digits[..2].parse::<i32>().ok(),
+            digits[2..].parse::<i32>().ok(),
+        ),
+        _ => (None, None),
+    };
+    match (hours, minutes) {
+        (Some(hours), Some(minutes)) if hours < 24 && minutes < 60 => {
+            (rest.trim_end(), Some(sign * (hours * 60 + minutes)))
+        }
+        _ => (input, None),
+    }
+}
+
+/// Helper function to parse a date with an optional time of day after it
+fn parse_date_and_time(input: &str, today: NaiveDate) -> Option<DueDate> {
+    if let Some(date) = parse_date(input, today) {
+        return Some(DueDate::on(date));
+    }
+    // Try every place the date could end and the time could begin
+    input
+        .char_indices()
+        .filter(|&(_, c)| c == ' ' || c == 'T')
+        .find_map(|(split, _)| {
+            let date = parse_date(input[..split].trim_end(), today)?;
+            let time = parse_time(input[split + 1..].trim_start())?;
+            Some(DueDate {
+                date,
+                time: Some(time),
+                utc_offset_minutes: None,
+            })
+        })
+}
+
+/// Helper function to parse a calendar date in any of the accepted formats
+fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match input.to_ascii_lowercase().as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.checked_add_signed(Duration::days(1)),
+        "yesterday" => return today.checked_sub_signed(Duration::days(1)),
+        _ => {}
+    }
+    DATE_FORMATS
+        .iter()
+        .filter_map(|format| NaiveDate::parse_from_str(input, format).ok())
+        // `%Y` happily reads "24" as the year 24, leave two-digit years to `%y`
+        .find(|date| (1000..=9999).contains(&date.year()))
+}
+
+/// Helper function to parse a time of day in any of the accepted formats
+fn parse_time(input: &str) -> Option<NaiveTime> {
+    let input = input.to_ascii_uppercase();
+    TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(&input, format).ok())
+        .or_else(|| {
+            // `%I%p` without minutes is not supported by chrono's parser, add them
+            let (hour, meridiem) = input.split_at(input.find(['A', 'P'])?);
+            NaiveTime::parse_from_str(&format!("{}:00 {}", hour.trim(), meridiem), "%I:%M %p").ok()
+        })
+}
+
+/// Helper function to parse a completion timestamp, which is either an RFC 3339 timestamp
+/// or, in app states saved before timestamps were recorded, a bare date
+///
+/// # Arguments
+///
+/// * `input` - The timestamp or date
+/// * `today` - The date that relative words such as "yesterday" refer to
+pub fn parse_completed_date(input: &str, today: NaiveDate) -> Option<DateTime<Local>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(input) {
+        return Some(timestamp.with_timezone(&Local));
+    }
+    parse_due_date(input, today).ok().map(|due| match due.time {
+        Some(_) => due.to_local(),
+        None => start_of_day(due.date),
+    })
+}
+
+/// Serde helpers for `Task::due_date` that also accept the free-form strings
+/// saved before due dates were typed
+pub mod serde_due_date {
+    use super::*;
+
+    /// Deserialize an optional due date. A legacy free-form string that can't be
+    /// understood is an error rather than being dropped, so the text is never lost unnoticed.
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DueDate>, D::Error> {
+        deserializer.deserialize_any(LegacyStringVisitor(|input: &str| {
+            if input.trim().is_empty() {
+                return Ok(None);
+            }
+            // saved dates are written in full, so the day only matters for legacy free-form strings
+            parse_due_date(input, Clock::System.today())
+                .map(Some)
+                .map_err(|err| format!("invalid due date {input:?}: {err}"))
+        }))
+    }
+}
+
+/// Serde helpers for `Task::completed_date` that also accept the `YYYY-MM-DD`
+/// strings saved before completion timestamps were recorded
+pub mod serde_completed_date {
+    use super::*;
+
+    /// Deserialize an optional completion timestamp
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Local>>, D::Error> {
+        deserializer.deserialize_any(LegacyStringVisitor(|input: &str| {
+            let parsed = parse_completed_date(input, Clock::System.today());
+            if parsed.is_none() {
+                log::warn!("Dropping completion date {input:?} that could not be understood");
+            }
+            Ok(parsed)
+        }))
+    }
+}
+
+/// Visitor that reads an optional value stored either as `Some("...")`/`None` or as a bare string
+struct LegacyStringVisitor<F>(F);
+
+impl<'de, T, F: Fn(&str) -> Result<Option<T>, String>> serde::de::Visitor<'de>
+    for LegacyStringVisitor<F>
+{
+    type Value = Option<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("an optional date string")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        (self.0)(value).map_err(E::custom)
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn hm(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_common_date_formats() {
+        let today = ymd(2024, 11, 1);
+        let expected = ymd(2024, 11, 23);
+        for input in [
+            "2024-11-23",
+            "2024/11/23",
+            "11/23/2024",
+            "11.23.2024",
+            "23/11/2024",
+            "23.11.2024",
+            "Nov 23 2024",
+            "Nov 23, 2024",
+            "November 23, 2024",
+            "23 Nov 2024",
+            "11/23/24",
+            "  2024-11-23  ",
+        ] {
+            assert_eq!(
+                parse_due_date(input, today),
+                Ok(DueDate::on(expected)),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_month_first_when_ambiguous() {
+        let due = parse_due_date("01/02/2024", ymd(2024, 1, 1)).unwrap();
+        assert_eq!(due.date, ymd(2024, 1, 2));
+    }
+
+    #[test]
+    fn test_parse_relative_dates() {
+        let today = ymd(2024, 12, 31);
+        assert_eq!(parse_due_date("today", today).unwrap().date, today);
+        assert_eq!(
+            parse_due_date("Tomorrow", today).unwrap().date,
+            ymd(2025, 1, 1)
+        );
+    }
+
+    #[test]
+    fn test_parse_times_and_offsets() {
+        let today = ymd(2024, 11, 1);
+
+        let due = parse_due_date("2024-11-23 14:30", today).unwrap();
+        assert_eq!((due.time, due.utc_offset_minutes), (Some(hm(14, 30)), None));
+
+        let due = parse_due_date("11/23/2024 2:30pm", today).unwrap();
+        assert_eq!(due.time, Some(hm(14, 30)));
+
+        let due = parse_due_date("Nov 23 2024 9am", today).unwrap();
+        assert_eq!(due.time, Some(hm(9, 0)));
+
+        let due = parse_due_date("2024-11-23 14:30 +02:00", today).unwrap();
+        assert_eq!(due.utc_offset_minutes, Some(120));
+
+        let due = parse_due_date("2024-11-23 14:30 -0530", today).unwrap();
+        assert_eq!(due.utc_offset_minutes, Some(-330));
+
+        let due = parse_due_date("2024-11-23T14:30:00Z", today).unwrap();
+        assert_eq!(
+            (due.time, due.utc_offset_minutes),
+            (Some(hm(14, 30)), Some(0))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let today = ymd(2024, 11, 1);
+        assert_eq!(parse_due_date("   ", today), Err(DateParseError::Empty));
+        for input in [
+            "next friday",
+            "2024-13-45",
+            "11/23",
+            "2024-11-23 +02:00",
+            "2024-11-23 25:00",
+        ] {
+            assert_eq!(
+                parse_due_date(input, today),
+                Err(DateParseError::Unrecognized(input.to_string())),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let today = ymd(2024, 11, 1);
+        for input in [
+            "2024-11-23",
+            "2024-11-23 14:30",
+            "2024-11-23 14:30:15",
+            "2024-11-23 14:30 +02:00",
+            "2024-11-23 08:05 -05:30",
+        ] {
+            let due = parse_due_date(input, today).unwrap();
+            assert_eq!(due.to_string(), input);
+            assert_eq!(parse_due_date(&due.to_string(), today), Ok(due));
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Record {
+        #[serde(default, deserialize_with = "serde_due_date::deserialize")]
+        due_date: Option<DueDate>,
+        #[serde(default, deserialize_with = "serde_completed_date::deserialize")]
+        completed_date: Option<DateTime<Local>>,
+    }
+
+    #[test]
+    fn test_legacy_strings_are_migrated() {
+        let record: Record =
+            ron::from_str(r#"(due_date: "11.23.2024", completed_date: Some("2024-11-22"))"#)
+                .unwrap();
+        assert_eq!(record.due_date, Some(DueDate::on(ymd(2024, 11, 23))));
+        assert_eq!(record.completed_date, Some(start_of_day(ymd(2024, 11, 22))));
+
+        let record: Record = ron::from_str(r#"(due_date: "", completed_date: None)"#).unwrap();
+        assert_eq!(record.due_date, None);
+        assert_eq!(record.completed_date, None);
+
+        // a due date that can't be understood fails the load instead of being dropped
+        let err = ron::from_str::<Record>(r#"(due_date: "whenever", completed_date: None)"#)
+            .err()
+            .expect("the due date is invalid");
+        assert!(err.to_string().contains("invalid due date \"whenever\""));
+    }
+
+    #[test]
+    fn test_typed_dates_are_loaded() {
+        let completed = Local.with_ymd_and_hms(2024, 11, 22, 18, 45, 10).unwrap();
+        let saved = format!(
+            r#"(due_date: Some("2024-11-23 14:30 +02:00"), completed_date: Some("{}"))"#,
+            completed.to_rfc3339()
+        );
+        let record: Record = ron::from_str(&saved).unwrap();
+        assert_eq!(record.due_date.unwrap().utc_offset_minutes, Some(120));
+        assert_eq!(record.completed_date, Some(completed));
+    }
+}
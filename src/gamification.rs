@@ -1,3 +1,15 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local, NaiveDate, Weekday};
+
+use crate::achievements::{AchievementKind, Achievements, Progress};
+use crate::dates::DueDate;
+use crate::levels::{self, Level, LevelConfig};
+use crate::recurrence::Recurrence;
+use crate::scoring::{Difficulty, Score, ScoringModel};
+use crate::streaks::Streak;
+use crate::task_list::TaskId;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 /// Priority levels for tasks
 pub enum PriorityLevel {
@@ -6,20 +18,101 @@ pub enum PriorityLevel {
     High = 3,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
-/// Task struct with fields for name, description, due date, priority level, and completion status
+impl std::str::FromStr for PriorityLevel {
+    type Err = String;
+
+    /// Helper function to parse a priority level in any case, either as a name ("high"),
+    /// its first letter ("H") or its number ("3")
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "low" | "l" | "1" => Ok(PriorityLevel::Low),
+            "medium" | "med" | "m" | "2" => Ok(PriorityLevel::Medium),
+            "high" | "h" | "3" => Ok(PriorityLevel::High),
+            _ => Err(format!(
+                "unknown priority {input:?}, expected low, medium or high (or L/M/H, 1/2/3)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize, Clone, PartialEq)]
+/// Task struct with fields for ID, name, description, due date, priority level, and completion status
 pub struct Task {
+    #[serde(default)]
+    pub id: TaskId, // assigned by TaskList, missing in app states saved before IDs existed
     pub name: String,
     pub description: String,
-    pub due_date: String,
+    #[serde(
+        default,
+        deserialize_with = "crate::dates::serde_due_date::deserialize"
+    )]
+    pub due_date: Option<DueDate>,
     pub priority: PriorityLevel,
     pub completed: bool,
-    pub completed_date: Option<String>, // add when the task was completed
+    #[serde(
+        default,
+        deserialize_with = "crate::dates::serde_completed_date::deserialize"
+    )]
+    pub completed_date: Option<DateTime<Local>>, // add when the task was completed
+    #[serde(default)]
+    pub tags: Vec<String>, // labels for organizing tasks, with todo.txt contexts keeping their `@`
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>, // when the next occurrence is due, for repeating tasks
+    #[serde(default)]
+    pub parent: Option<TaskId>, // the task this is a subtask of, None for a top-level task
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>, // lightweight steps that don't need a task of their own
+    #[serde(default)]
+    pub auto_complete: bool, // complete the task as soon as all its subtasks and checklist items are done
+    #[serde(default)]
+    pub depends_on: Vec<TaskId>, // tasks that have to be completed before this one can start
+    #[serde(default)]
+    pub project: Option<String>, // the project the task belongs to, if any
+    #[serde(default)]
+    pub effort: Option<u32>, // estimated effort in hours, scored by the weighted formula
+    #[serde(default)]
+    pub difficulty: Difficulty, // how hard the task is, scored by the weighted formula
+    #[serde(default)]
+    pub points_override: Option<u32>, // points set by hand, whatever the scoring formula
+    #[serde(default)]
+    pub score: Option<Score>, // the score paid when the task was completed, kept when the scoring changes
 }
 
-/// Implementation of Task struct with a method to calculate points based on priority level
+#[derive(Debug, serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq)]
+/// ChecklistItem struct with the text of a checklist step and whether it is done
+pub struct ChecklistItem {
+    pub text: String,
+    pub done: bool,
+}
+
+/// Implementation of Task struct with methods to calculate points based on priority level
 impl Task {
-    /// Helper function to calculate points based on priority level
+    /// Helper function to create an open task with a name and a priority and every other field empty.
+    /// The ID is assigned when the task is added to a TaskList.
+    pub fn new(name: impl Into<String>, priority: PriorityLevel) -> Self {
+        Task {
+            id: TaskId::default(),
+            name: name.into(),
+            description: String::new(),
+            due_date: None,
+            priority,
+            completed: false,
+            completed_date: None,
+            tags: Vec::new(),
+            recurrence: None,
+            parent: None,
+            checklist: Vec::new(),
+            auto_complete: false,
+            depends_on: Vec::new(),
+            project: None,
+            effort: None,
+            difficulty: Difficulty::Normal,
+            points_override: None,
+            score: None,
+        }
+    }
+
+    /// Helper function to calculate points based on priority level, which every scoring formula starts from
     pub fn points(&self) -> u32 {
         match self.priority {
             PriorityLevel::Low => 10,
@@ -27,9 +120,52 @@ impl Task {
             PriorityLevel::High => 30,
         }
     }
+
+    /// Helper function to get the score of the task: the one paid when it was completed, or the one
+    /// the scoring model gives it now for an open task and a completed task that wasn't scored yet
+    ///
+    /// # Arguments
+    ///
+    /// * `scoring` - The scoring model that works out the points of the task
+    pub fn score_with(&self, scoring: &ScoringModel) -> Score {
+        match (&self.score, self.completed) {
+            (Some(score), true) => score.clone(),
+            _ => scoring.score(self),
+        }
+    }
+
+    /// Helper function to calculate the points earned so far.
+    /// A completed task earns all its points; an open task earns an equal share of its points
+    /// for every checklist item that is done, rounded down.
+    ///
+    /// # Arguments
+    ///
+    /// * `scoring` - The scoring model that works out the points of the task
+    pub fn earned_points(&self, scoring: &ScoringModel) -> u32 {
+        let points = self.score_with(scoring).points;
+        if self.completed {
+            return points;
+        }
+        let (done, total) = self.checklist_progress();
+        if total == 0 {
+            0
+        } else {
+            points * done as u32 / total as u32
+        }
+    }
+
+    /// Helper function to count the checklist items
+    ///
+    /// # Returns
+    ///
+    /// The number of items that are done and the total number of items.
+    pub fn checklist_progress(&self) -> (usize, usize) {
+        let done = self.checklist.iter().filter(|item| item.done).count();
+        (done, self.checklist.len())
+    }
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
 /// Gamification struct with fields for points, goals, and achievement messages
 pub struct Gamification {
     pub points: u32,
@@ -40,8 +176,47 @@ pub struct Gamification {
     pub daily_reward: u32,
     pub daily_reward_message: String,
     pub weekly_challenge_message: String,
+    #[serde(default)]
+    pub unblock_bonus: bool, // whether completing a task earns a bonus for every task waiting on it
+    #[serde(default)]
+    pub unblock_bonus_points: u32, // the part of the points earned by unblocking tasks
+    #[serde(default)]
+    pub project_goals: BTreeMap<String, u32>, // number of tasks to complete in each project
+    #[serde(default)]
+    pub achievements: Achievements, // the achievement rules and the trophy case
+    #[serde(default)]
+    pub rest_days: Vec<Weekday>, // days of the week that don't break a streak
+    #[serde(skip)]
+    pub streak: Streak, // worked out from the tasks by `TaskStore::evaluate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level_config: Option<LevelConfig>, // the level curve and XP sources loaded by the user, None for the standard ones
+    #[serde(skip)]
+    pub level: Level, // worked out from the ledger and the streak by `TaskStore::evaluate`
+    #[serde(default)]
+    pub best_level: u32, // highest level ever reached, so only new levels are celebrated
+    #[serde(default)]
+    pub scoring: ScoringModel, // how the points of a task are worked out, chosen in the settings
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// ProjectGoal struct with the progress toward the goal of a project
+pub struct ProjectGoal {
+    pub project: String,
+    pub completed: usize, // completed tasks in the project
+    pub goal: u32,
 }
 
+/// Implementation of ProjectGoal with a check whether the goal is reached
+impl ProjectGoal {
+    /// Helper function to check whether enough tasks in the project are completed
+    pub fn reached(&self) -> bool {
+        self.completed >= self.goal as usize
+    }
+}
+
+/// Bonus points for every task that was waiting on a completed task, when the unblock bonus is on
+pub const UNBLOCK_BONUS: u32 = 5;
+
 /// Implementation of Gamification struct with methods to check challenges, daily rewards, and weekly challenges
 impl Gamification {
     /// Helper function to create a new instance of Gamification
@@ -54,111 +229,163 @@ impl Gamification {
             achievement_message: String::new(),
             daily_reward: 0,
             daily_reward_message: String::new(),
-            weekly_challenge_message: String::from("Complete a task every day for a week to earn 100 points!"),
+            weekly_challenge_message: String::from(
+                "Complete a task every day for a week to earn 100 points!",
+            ),
+            unblock_bonus: false,
+            unblock_bonus_points: 0,
+            project_goals: BTreeMap::new(),
+            achievements: Achievements::default(),
+            rest_days: Vec::new(),
+            streak: Streak::default(),
+            level_config: None,
+            level: Level::default(),
+            best_level: 0,
+            scoring: ScoringModel::default(),
         }
     }
 
-    /// Helper function to check the user's progress and display achievement messages
-    /// based on the number of completed tasks and total points
-    /// 
+    /// Helper function to get the level curve and XP sources in use
+    pub fn level_config(&self) -> &LevelConfig {
+        self.level_config
+            .as_ref()
+            .unwrap_or_else(|| levels::standard_config())
+    }
+
+    /// Helper function to check the user's progress and display the highest level reached with
+    /// the current points and completed tasks, as defined by the level rules of the achievements.
+    /// The points are the balance of the points ledger, which is settled before the challenges
+    /// are checked.
+    ///
     /// # Arguments
     /// 
     /// * `tasks` - A slice of Task structs representing the user's tasks
-    pub fn check_challenges(&mut self, tasks: &[Task]) {
-        let bronze_points = 50;
-        let silver_points = 100;
-        let gold_points = 500;
-
-        let completed_tasks = tasks.iter().filter(|task| task.completed).count(); // count the number of completed tasks
-        self.points = tasks.iter().filter(|task| task.completed).map(Task::points).sum(); // calculate the total points
-
-        if self.points >= gold_points && completed_tasks >= self.gold_goal as usize {
-            self.display_achievement("Congrats! You have reached the Gold level!");
-        } else if self.points >= silver_points && completed_tasks >= self.silver_goal as usize {
-            self.display_achievement("Congrats! You have reached the Silver level!");
-        } else if self.points >= bronze_points && completed_tasks >= self.bronze_goal as usize {
-            self.display_achievement("Congrats! You have reached the Bronze level!");
-        } else {
-            self.display_achievement("Keep going! You're progressing toward the next level!");
-        }
+    /// * `today` - The current date, from the app's clock
+    pub fn check_challenges(&mut self, tasks: &[Task], today: NaiveDate) {
+        let progress = Progress::new(tasks, self, today);
+        let message = match self.achievements.highest(AchievementKind::Level, &progress) {
+            Some(level) => level.message().to_string(),
+            None => "Keep going! You're progressing toward the next level!".to_string(),
+        };
+        self.display_achievement(&message);
     }
 
-    /// Helper function to calculate the daily reward based on the number of tasks completed in a day
-    /// and display a message to the user
-    /// 
+    /// Helper function to unlock the trophies and levels reached that aren't unlocked yet
+    ///
     /// # Arguments
-    /// 
+    ///
     /// * `tasks` - A slice of Task structs representing the user's tasks
-    pub fn daily_reward(&mut self, tasks: &[Task]) {
-        // calculate the number of tasks completed within the last day using the completed_date field
-        let daily_tasks: usize = tasks.iter().filter(|task| {
-            if let Some(completed_date) = &task.completed_date {
-                // check if the task was completed within the last day
-                // (for simplicity, assume the date format is "YYYY-MM-DD")
-                completed_date == &chrono::Local::now().format("%Y-%m-%d").to_string()
-            } else {
-                false
-            }
-        }).count();
-
-        // if the user completed 15, 10, or 5 tasks in a day, give them 100, 50, or 25 points, respectively
-        if daily_tasks >= 15 {
-            self.display_daily_reward("Congrats! You completed 15 tasks today!");
-            self.daily_reward = 100;
-        } else if daily_tasks >= 10 {
-            self.display_daily_reward("Congrats! You completed 10 tasks today!");
-            self.daily_reward = 50;
-        } else if daily_tasks >= 5 {
-            self.display_daily_reward("Congrats! You completed 5 tasks today!");
-            self.daily_reward = 25;
-        } else {
-            self.display_daily_reward("Keep going! You're making progress!");
-            self.daily_reward = 0;
-        }
+    /// * `now` - The current time, from the app's clock
+    ///
+    /// # Returns
+    ///
+    /// The number of achievements unlocked.
+    pub fn unlock_achievements(&mut self, tasks: &[Task], now: DateTime<Local>) -> usize {
+        let progress = Progress::new(tasks, self, now.date_naive());
+        self.achievements.unlock(&progress, now)
+    }
+
+    /// Helper function to calculate the unblock bonus: UNBLOCK_BONUS points for every task
+    /// that depends on a completed task
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - A slice of Task structs representing the user's tasks
+    pub fn unblock_bonus_points(tasks: &[Task]) -> u32 {
+        let completed: std::collections::HashSet<TaskId> = tasks
+            .iter()
+            .filter(|task| task.completed)
+            .map(|task| task.id)
+            .collect();
+        let unblocked: usize = tasks
+            .iter()
+            .map(|task| {
+                task.depends_on
+                    .iter()
+                    .filter(|prerequisite| completed.contains(prerequisite))
+                    .count()
+            })
+            .sum();
+        unblocked as u32 * UNBLOCK_BONUS
     }
 
-    /// Helper function to calculate the weekly challenge based on the number of tasks completed each day
-    /// in the last 7 days and display a message to the user
+    /// Helper function to get the progress toward every project goal, in project order
+    ///
+    /// # Arguments
     /// 
+    /// * `tasks` - A slice of Task structs representing the user's tasks
+    pub fn project_goal_progress(&self, tasks: &[Task]) -> Vec<ProjectGoal> {
+        self.project_goals
+            .iter()
+            .map(|(project, &goal)| ProjectGoal {
+                project: project.clone(),
+                completed: tasks
+                    .iter()
+                    .filter(|task| {
+                        task.completed && task.project.as_deref() == Some(project.as_str())
+                    })
+                    .count(),
+                goal,
+            })
+            .collect()
+    }
+
+    /// Helper function to calculate the daily reward from the highest daily rule of the achievements
+    /// reached today, such as completing 5, 10 or 15 tasks, and display a message to the user
+    ///
     /// # Arguments
     /// 
     /// * `tasks` - A slice of Task structs representing the user's tasks
-    pub fn weekly_challenge(&mut self, tasks: &[Task]) {
-        // Get the current date
-        let current_date = chrono::Local::now().format("%Y-%m-%d").to_string();
-    
-        // Get the date 7 days ago
-        let seven_days_ago = chrono::Local::now().checked_sub_signed(chrono::Duration::days(7))
-            .map(|date| date.format("%Y-%m-%d").to_string())
-            .unwrap_or_else(|| "1970-01-01".to_string()); // Default to a valid date if subtraction fails
-    
+    /// * `today` - The current date, from the app's clock
+    pub fn daily_reward(&mut self, tasks: &[Task], today: NaiveDate) {
+        let progress = Progress::new(tasks, self, today);
+        let (message, points) = match self.achievements.highest(AchievementKind::Daily, &progress) {
+            Some(reward) => (reward.message().to_string(), reward.points),
+            None => ("Keep going! You're making progress!".to_string(), 0),
+        };
+        self.display_daily_reward(&message);
+        self.daily_reward = points;
+    }
+
+    /// Helper function to check the weekly challenge based on the number of tasks completed each day
+    /// in the last 7 days and display a message to the user. The bonus points are banked in the ledger.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - A slice of Task structs representing the user's tasks
+    /// * `current_date` - The current date, from the app's clock
+    ///
+    /// # Returns
+    ///
+    /// Whether a task was completed on each of the last 7 days.
+    pub fn weekly_challenge(&mut self, tasks: &[Task], current_date: NaiveDate) -> bool {
         // Get the number of tasks completed each day in the last 7 days
-        let mut tasks_completed_each_day = vec![0; 7];
-    
+        let mut tasks_completed_each_day = [0; 7];
+
         for task in tasks {
-            if let Some(completed_date) = &task.completed_date {
+            if let Some(completed_date) = task.completed_date {
                 // Check if the task was completed within the last 7 days
-                if completed_date >= &seven_days_ago && completed_date <= &current_date {
-                    // Try parsing the current date and completed date safely
-                    let current_naive_date = chrono::NaiveDate::parse_from_str(&current_date, "%Y-%m-%d");
-                    let completed_naive_date = chrono::NaiveDate::parse_from_str(completed_date, "%Y-%m-%d");
-    
-                    // If both dates are valid, calculate the difference
-                    if let (Ok(current), Ok(completed)) = (current_naive_date, completed_naive_date) {
-                        let days_diff = current.signed_duration_since(completed).num_days() as usize;
-                        if days_diff < 7 {
-                            tasks_completed_each_day[6 - days_diff] += 1;
-                        }
-                    }
+                let days_diff = current_date
+                    .signed_duration_since(completed_date.date_naive())
+                    .num_days();
+                if (0..7).contains(&days_diff) {
+                    tasks_completed_each_day[6 - days_diff as usize] += 1;
                 }
             }
         }
-    
-        // If the user has completed a task every day for the last 7 days, give them 100 points
-        if tasks_completed_each_day.iter().all(|&count| count > 0) {
-            self.points = 100;
-            self.display_weekly_challenge("Congrats! You completed a task every day for the last week!");
+
+        // If the user has completed a task every day for the last 7 days, they earn the weekly bonus
+        let completed_every_day = tasks_completed_each_day.iter().all(|&count| count > 0);
+        if completed_every_day {
+            self.display_weekly_challenge(
+                "Congrats! You completed a task every day for the last week!",
+            );
+        } else {
+            self.display_weekly_challenge(
+                "Complete a task every day for a week to earn 100 points!",
+            );
         }
+        completed_every_day
     }
     
     
@@ -179,40 +406,66 @@ impl Gamification {
     }
 }
 
+/// Implement the Default trait for Gamification so that it starts with the default goals
+impl Default for Gamification {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::{Duration, Local};
+    use crate::clock::Clock;
+    use crate::ledger::Ledger;
+    use chrono::Duration;
+
+    /// The clock the tests run on, so they give the same result on any day
+    fn clock() -> Clock {
+        Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap())
+    }
+
+    /// The points the ledger pays for the tasks, which are numbered in order
+    fn ledger_points(tasks: &[Task]) -> u32 {
+        let tasks: Vec<Task> = tasks
+            .iter()
+            .zip(1..)
+            .map(|(task, id)| Task {
+                id: TaskId(id),
+                ..task.clone()
+            })
+            .collect();
+        let mut ledger = Ledger::default();
+        ledger.reconcile(&tasks, &Gamification::new(), false, clock().now());
+        ledger.points()
+    }
 
     #[test]
     fn test_task_points() {
         let low_priority_task = Task {
-            name: "Task 1".to_string(),
             description: "A low priority task".to_string(),
-            due_date: "2024-11-23".to_string(),
-            priority: PriorityLevel::Low,
+            due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap())),
             completed: true,
             completed_date: None,
+            ..Task::new("Task 1", PriorityLevel::Low)
         };
         assert_eq!(low_priority_task.points(), 10);
 
         let medium_priority_task = Task {
-            name: "Task 2".to_string(),
             description: "A medium priority task".to_string(),
-            due_date: "2024-11-23".to_string(),
-            priority: PriorityLevel::Medium,
+            due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap())),
             completed: true,
             completed_date: None,
+            ..Task::new("Task 2", PriorityLevel::Medium)
         };
         assert_eq!(medium_priority_task.points(), 20);
 
         let high_priority_task = Task {
-            name: "Task 3".to_string(),
             description: "A high priority task".to_string(),
-            due_date: "2024-11-23".to_string(),
-            priority: PriorityLevel::High,
+            due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap())),
             completed: true,
             completed_date: None,
+            ..Task::new("Task 3", PriorityLevel::High)
         };
         assert_eq!(high_priority_task.points(), 30);
     }
@@ -221,27 +474,25 @@ mod tests {
     fn test_gamification_check_challenges() {
         let tasks = vec![
             Task {
-                name: "Task 1".to_string(),
                 description: "A completed task".to_string(),
-                due_date: "2024-11-23".to_string(),
-                priority: PriorityLevel::Low,
+                due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap())),
                 completed: true,
                 completed_date: None,
+                ..Task::new("Task 1", PriorityLevel::Low)
             },
             Task {
-                name: "Task 2".to_string(),
                 description: "Another completed task".to_string(),
-                due_date: "2024-11-23".to_string(),
-                priority: PriorityLevel::Medium,
+                due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap())),
                 completed: true,
                 completed_date: None,
+                ..Task::new("Task 2", PriorityLevel::Medium)
             },
         ];
 
         let mut gamification = Gamification::new();
-        gamification.check_challenges(&tasks);
+        gamification.check_challenges(&tasks, clock().today());
 
-        assert_eq!(gamification.points, 30); // 10 (Low) + 20 (Medium)
+        assert_eq!(ledger_points(&tasks), 30); // 10 (Low) + 20 (Medium)
         assert_eq!(
             gamification.achievement_message,
             "Keep going! You're progressing toward the next level!"
@@ -250,28 +501,27 @@ mod tests {
 
     #[test]
     fn test_gamification_daily_reward() {
-        let today = Local::now().format("%Y-%m-%d").to_string();
+        let now = clock().now();
+        let today = Some(DueDate::on(now.date_naive()));
         let tasks = vec![
             Task {
-                name: "Task 1".to_string(),
                 description: "Completed today".to_string(),
-                due_date: today.clone(),
-                priority: PriorityLevel::High,
+                due_date: today,
                 completed: true,
-                completed_date: Some(today.clone()),
+                completed_date: Some(now),
+                ..Task::new("Task 1", PriorityLevel::High)
             },
             Task {
-                name: "Task 2".to_string(),
                 description: "Another completed today".to_string(),
-                due_date: today.clone(),
-                priority: PriorityLevel::Medium,
+                due_date: today,
                 completed: true,
-                completed_date: Some(today.clone()),
+                completed_date: Some(now),
+                ..Task::new("Task 2", PriorityLevel::Medium)
             },
         ];
 
         let mut gamification = Gamification::new();
-        gamification.daily_reward(&tasks);
+        gamification.daily_reward(&tasks, clock().today());
 
         assert_eq!(gamification.daily_reward, 0); // Less than 5 tasks
         assert_eq!(
@@ -282,33 +532,31 @@ mod tests {
 
     #[test]
     fn test_gamification_weekly_challenge() {
-        let today = Local::now().format("%Y-%m-%d").to_string();
-
         // Generate tasks completed for each of the last 7 days
         let tasks: Vec<Task> = (0..7)
             .map(|day_offset| {
-                let date = Local::now()
+                let date = clock()
+                    .now()
                     .checked_sub_signed(Duration::days(day_offset))
-                    .unwrap()
-                    .format("%Y-%m-%d")
-                    .to_string();
+                    .unwrap();
 
                 Task {
-                    name: format!("Task {}", day_offset + 1),
                     description: "Completed task".to_string(),
-                    due_date: date.clone(),
-                    priority: PriorityLevel::Medium,
+                    due_date: Some(DueDate::on(date.date_naive())),
                     completed: true,
                     completed_date: Some(date),
+                    ..Task::new(format!("Task {}", day_offset + 1), PriorityLevel::Medium)
                 }
             })
             .collect();
 
         let mut gamification = Gamification::new();
-        gamification.weekly_challenge(&tasks);
+        let completed_every_day = gamification.weekly_challenge(&tasks, clock().today());
 
         // Expecting 100 points because a task was completed each day for the last 7 days
-        assert_eq!(gamification.points, 100);
+        let mut ledger = Ledger::default();
+        ledger.reconcile(&[], &gamification, completed_every_day, clock().now());
+        assert_eq!(ledger.points(), 100);
         assert_eq!(
             gamification.weekly_challenge_message,
             "Congrats! You completed a task every day for the last week!"
@@ -320,27 +568,25 @@ mod tests {
     fn test_gamification_achievements() {
         let tasks = vec![
             Task {
-                name: "Task 1".to_string(),
                 description: "A completed high priority task".to_string(),
-                due_date: "2024-11-23".to_string(),
-                priority: PriorityLevel::High,
+                due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap())),
                 completed: true,
                 completed_date: None,
+                ..Task::new("Task 1", PriorityLevel::High)
             },
             Task {
-                name: "Task 2".to_string(),
                 description: "Another completed high priority task".to_string(),
-                due_date: "2024-11-23".to_string(),
-                priority: PriorityLevel::High,
+                due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap())),
                 completed: true,
                 completed_date: None,
+                ..Task::new("Task 2", PriorityLevel::High)
             },
         ];
 
         let mut gamification = Gamification::new();
-        gamification.check_challenges(&tasks);
+        gamification.check_challenges(&tasks, clock().today());
 
-        assert_eq!(gamification.points, 60); // 30 (High) + 30 (High)
+        assert_eq!(ledger_points(&tasks), 60); // 30 (High) + 30 (High)
         assert_eq!(
             gamification.achievement_message,
             "Keep going! You're progressing toward the next level!"
@@ -349,31 +595,144 @@ mod tests {
 
     #[test]
     fn test_daily_reward_exact_thresholds() {
-        let today = Local::now().format("%Y-%m-%d").to_string();
+        let now = clock().now();
+        let today = Some(DueDate::on(now.date_naive()));
 
         let tasks: Vec<Task> = (0..15)
             .map(|i| Task {
-                name: format!("Task {}", i + 1),
                 description: "Completed today".to_string(),
-                due_date: today.clone(),
-                priority: PriorityLevel::Low,
+                due_date: today,
                 completed: true,
-                completed_date: Some(today.clone()),
+                completed_date: Some(now),
+                ..Task::new(format!("Task {}", i + 1), PriorityLevel::Low)
             })
             .collect();
 
         let mut gamification = Gamification::new();
 
         // Test for 5 tasks
-        gamification.daily_reward(&tasks[0..5]);
+        gamification.daily_reward(&tasks[0..5], clock().today());
         assert_eq!(gamification.daily_reward, 25);
 
         // Test for 10 tasks
-        gamification.daily_reward(&tasks[0..10]);
+        gamification.daily_reward(&tasks[0..10], clock().today());
         assert_eq!(gamification.daily_reward, 50);
 
         // Test for 15 tasks
-        gamification.daily_reward(&tasks[0..15]);
+        gamification.daily_reward(&tasks[0..15], clock().today());
         assert_eq!(gamification.daily_reward, 100);
     }
+
+    #[test]
+    fn test_checklist_earns_partial_points() {
+        let item = |text: &str, done| ChecklistItem {
+            text: text.to_string(),
+            done,
+        };
+        let mut task = Task {
+            description: "A checklist".to_string(),
+            due_date: None,
+            completed: false,
+            completed_date: None,
+            checklist: vec![
+                item("Passport", true),
+                item("Charger", true),
+                item("Socks", false),
+            ],
+            ..Task::new("Pack for the trip", PriorityLevel::High)
+        };
+        assert_eq!(task.checklist_progress(), (2, 3));
+        assert_eq!(task.earned_points(&ScoringModel::default()), 20); // two thirds of 30
+
+        // Completing the task earns the rest, not more
+        task.completed = true;
+        assert_eq!(task.earned_points(&ScoringModel::default()), 30);
+
+        assert_eq!(ledger_points(&[task]), 30);
+    }
+
+    #[test]
+    fn test_unblock_bonus() {
+        let task = |id: u64, completed: bool, depends_on: Vec<TaskId>| Task {
+            id: TaskId(id),
+            description: String::new(),
+            due_date: None,
+            completed,
+            completed_date: None,
+            depends_on,
+            ..Task::new(format!("Task {id}"), PriorityLevel::Low)
+        };
+        // task 1 unblocks tasks 2 and 3, task 2 is still open
+        let tasks = [
+            task(1, true, vec![]),
+            task(2, false, vec![TaskId(1)]),
+            task(3, false, vec![TaskId(1), TaskId(2)]),
+        ];
+
+        let mut gamification = Gamification::new();
+        let mut ledger = Ledger::default();
+        ledger.reconcile(&tasks, &gamification, false, clock().now());
+        assert_eq!((ledger.points(), ledger.unblock_bonus()), (10, 0));
+
+        gamification.unblock_bonus = true;
+        ledger.reconcile(&tasks, &gamification, false, clock().now());
+        assert_eq!((ledger.points(), ledger.unblock_bonus()), (20, 10));
+    }
+
+    #[test]
+    fn test_project_goal_progress() {
+        let task = |id: u64, completed: bool, project: Option<&str>| Task {
+            id: TaskId(id),
+            description: String::new(),
+            due_date: None,
+            completed,
+            completed_date: None,
+            project: project.map(str::to_string),
+            ..Task::new(format!("Task {id}"), PriorityLevel::Low)
+        };
+        let tasks = [
+            task(1, true, Some("backend")),
+            task(2, false, Some("backend")),
+            task(3, true, Some("garden")),
+            task(4, true, None),
+        ];
+
+        let mut gamification = Gamification::new();
+        gamification.project_goals.insert("backend".to_string(), 2);
+        gamification.project_goals.insert("garden".to_string(), 1);
+        let progress = gamification.project_goal_progress(&tasks);
+        assert_eq!(
+            progress,
+            vec![
+                ProjectGoal {
+                    project: "backend".to_string(),
+                    completed: 1,
+                    goal: 2
+                },
+                ProjectGoal {
+                    project: "garden".to_string(),
+                    completed: 1,
+                    goal: 1
+                },
+            ]
+        );
+        assert!(!progress[0].reached());
+        assert!(progress[1].reached());
+    }
+
+    #[test]
+    fn test_priority_level_parsing() {
+        for (input, priority) in [
+            ("high", PriorityLevel::High),
+            ("H", PriorityLevel::High),
+            ("3", PriorityLevel::High),
+            (" Medium ", PriorityLevel::Medium),
+            ("m", PriorityLevel::Medium),
+            ("1", PriorityLevel::Low),
+        ] {
+            assert_eq!(input.parse::<PriorityLevel>(), Ok(priority));
+        }
+        assert!("urgent".parse::<PriorityLevel>().is_err());
+        assert!("4".parse::<PriorityLevel>().is_err());
+    }
 }
@@ -0,0 +1,416 @@
+//! Undo and redo.
+//!
+//! Every change to the tasks, goals or saved filters is recorded as a named command together
+//! with a patch that turns the store back into what it was before the command ran. Undoing a
+//! command applies the patch and moves the command to the redo stack with the patch that undoes
+//! the undo, so undo and redo restore a state exactly, down to completion dates and the points
+//! they earned. Patches only hold the parts of the store a command changed, which keeps a saved
+//! history small.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::store::TaskStore;
+
+/// The most commands kept for undoing; older ones are forgotten
+pub const MAX_HISTORY: usize = 50;
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+/// Patch enum with the changes that turn one saved store into another
+enum Patch {
+    Replace {
+        value: Value,
+    }, // a new value
+    Fields {
+        fields: BTreeMap<String, Patch>,
+    }, // changes to some fields of an object
+    Splice {
+        at: usize,
+        remove: usize,
+        insert: Vec<Value>,
+    }, // items removed from and added to a list
+}
+
+/// Implementation of Patch with methods to find and apply the changes between two values
+impl Patch {
+    /// Helper function to find the patch that turns `from` into `to`
+    fn diff(from: &Value, to: &Value) -> Patch {
+        match (from, to) {
+            // objects with the same fields only keep the fields that changed
+            (Value::Object(from), Value::Object(to))
+                if from.len() == to.len() && from.keys().all(|key| to.contains_key(key)) =>
+            {
+                let fields = to
+                    .iter()
+                    .filter(|&(key, value)| from[key] != *value)
+                    .map(|(key, value)| (key.clone(), Patch::diff(&from[key], value)))
+                    .collect();
+                Patch::Fields { fields }
+            }
+            // lists only keep the items between the ones they start and end with in common
+            (Value::Array(from), Value::Array(to)) => {
+                let start = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+                let end = from[start..]
+                    .iter()
+                    .rev()
+                    .zip(to[start..].iter().rev())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                Patch::Splice {
+                    at: start,
+                    remove: from.len() - start - end,
+                    insert: to[start..to.len() - end].to_vec(),
+                }
+            }
+            _ => Patch::Replace { value: to.clone() },
+        }
+    }
+
+    /// Helper function to apply the patch to the value it was found from
+    fn apply(&self, value: &mut Value) {
+        match (self, value) {
+            (Patch::Fields { fields }, Value::Object(object)) => {
+                for (key, patch) in fields {
+                    match (object.get_mut(key), patch) {
+                        (Some(field), patch) => patch.apply(field),
+                        (None, Patch::Replace { value }) => {
+                            object.insert(key.clone(), value.clone());
+                        }
+                        (None, _) => {}
+                    }
+                }
+            }
+            (Patch::Splice { at, remove, insert }, Value::Array(items)) => {
+                let at = (*at).min(items.len());
+                let end = (at + remove).min(items.len());
+                items.splice(at..end, insert.iter().cloned());
+            }
+            (Patch::Replace { value: new }, value) => *value = new.clone(),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+/// A command that can be undone or redone, with the patch that returns the store to how it was
+pub struct Change {
+    pub description: String, // what the command did, such as `Complete task "Write report"`
+    patch: Patch,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+/// History struct with the commands that can be undone and redone
+pub struct History {
+    undo: Vec<Change>, // oldest first
+    redo: Vec<Change>, // most recently undone last
+    persist: bool,     // whether the commands are saved, so undo survives restarts
+    #[serde(skip)]
+    coalescing: bool, // whether the last command absorbs the next one with the same description
+}
+
+/// The saved form of a History, which leaves out the commands unless they are kept
+#[derive(serde::Serialize)]
+struct SavedHistory<'a> {
+    undo: &'a [Change],
+    redo: &'a [Change],
+    persist: bool,
+}
+
+impl serde::Serialize for History {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (undo, redo): (&[Change], &[Change]) = if self.persist {
+            (&self.undo, &self.redo)
+        } else {
+            (&[], &[])
+        };
+        SavedHistory {
+            undo,
+            redo,
+            persist: self.persist,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Helper function to find the patch that turns the store `from` into the store `to`
+fn diff(from: &TaskStore, to: &TaskStore) -> Option<Patch> {
+    Some(Patch::diff(
+        &serde_json::to_value(from).ok()?,
+        &serde_json::to_value(to).ok()?,
+    ))
+}
+
+/// Helper function to apply a patch to the store
+///
+/// # Returns
+///
+/// The patched store and the patch that turns it back into the store it was made from, or None
+/// if the patched store can't be read.
+fn restore(store: &TaskStore, patch: &Patch) -> Option<(TaskStore, Patch)> {
+    let current = serde_json::to_value(store).ok()?;
+    let mut restored = current.clone();
+    patch.apply(&mut restored);
+    let reverse = Patch::diff(&restored, &current);
+    let mut restored: TaskStore = serde_json::from_value(restored).ok()?;
+    // the clock isn't saved, so the restored store keeps reading the time from the same one
+    restored.set_clock(store.clock());
+    Some((restored, reverse))
+}
+
+/// Implementation of History with methods to record, undo and redo commands
+impl History {
+    /// Helper function to record a command that changed the store
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - What the command did, shown next to Undo and Redo
+    /// * `before` - The store as it was before the command ran
+    /// * `after` - The store as the command left it
+    pub fn record(
+        &mut self,
+        description: impl Into<String>,
+        before: &TaskStore,
+        after: &TaskStore,
+    ) {
+        let Some(patch) = diff(after, before) else {
+            return;
+        };
+        self.undo.push(Change {
+            description: description.into(),
+            patch,
+        });
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.coalescing = false;
+    }
+
+    /// Helper function to record a command that is repeated many times in a row, such as
+    /// dragging a goal to a new value. A run of commands with the same description is undone
+    /// in one step.
+    pub fn record_coalescing(
+        &mut self,
+        description: impl Into<String>,
+        before: &TaskStore,
+        after: &TaskStore,
+    ) {
+        let description = description.into();
+        let repeated = self.coalescing
+            && self
+                .undo
+                .last()
+                .is_some_and(|change| change.description == description);
+        // the last command's patch leads from `before` to the store the run started from
+        let start = self
+            .undo
+            .last()
+            .filter(|_| repeated)
+            .and_then(|change| restore(before, &change.patch));
+        match start.and_then(|(start, _)| diff(after, &start)) {
+            Some(patch) => self.undo.last_mut().unwrap().patch = patch,
+            None => self.record(description, before, after),
+        }
+        self.redo.clear();
+        self.coalescing = true;
+    }
+
+    /// Helper function to undo the last command
+    ///
+    /// # Returns
+    ///
+    /// The description of the undone command, or None if there is nothing to undo.
+    pub fn undo(&mut self, store: &mut TaskStore) -> Option<String> {
+        let (restored, patch) = restore(store, &self.undo.last()?.patch)?;
+        let description = self.undo.pop()?.description;
+        self.coalescing = false;
+        *store = restored;
+        self.redo.push(Change {
+            description: description.clone(),
+            patch,
+        });
+        Some(description)
+    }
+
+    /// Helper function to redo the last undone command
+    ///
+    /// # Returns
+    ///
+    /// The description of the redone command, or None if there is nothing to redo.
+    pub fn redo(&mut self, store: &mut TaskStore) -> Option<String> {
+        let (restored, patch) = restore(store, &self.redo.last()?.patch)?;
+        let description = self.redo.pop()?.description;
+        self.coalescing = false;
+        *store = restored;
+        self.undo.push(Change {
+            description: description.clone(),
+            patch,
+        });
+        Some(description)
+    }
+
+    /// Helper function to get the description of the command Undo would undo
+    pub fn next_undo(&self) -> Option<&str> {
+        self.undo.last().map(|change| change.description.as_str())
+    }
+
+    /// Helper function to get the description of the command Redo would redo
+    pub fn next_redo(&self) -> Option<&str> {
+        self.redo.last().map(|change| change.description.as_str())
+    }
+
+    /// Helper function to get the commands that can be undone, most recent first
+    pub fn undoable(&self) -> impl Iterator<Item = &str> {
+        self.undo
+            .iter()
+            .rev()
+            .map(|change| change.description.as_str())
+    }
+
+    /// Helper function to check whether the commands are saved, so undo survives restarts
+    pub fn is_persistent(&self) -> bool {
+        self.persist
+    }
+
+    /// Helper function to choose whether the commands are saved
+    pub fn set_persistent(&mut self, persist: bool) {
+        self.persist = persist;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamification::PriorityLevel;
+    use crate::store::NewTask;
+    use crate::task_list::TaskId;
+
+    fn new_task(name: &str) -> NewTask {
+        NewTask {
+            name: name.to_string(),
+            description: String::new(),
+            due_date: None,
+            priority: PriorityLevel::Low,
+            recurrence: None,
+        }
+    }
+
+    fn names(store: &TaskStore) -> Vec<String> {
+        store
+            .tasks()
+            .iter()
+            .map(|task| {
+                format!(
+                    "{}{}",
+                    task.name,
+                    if task.completed { " (done)" } else { "" }
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_undo_and_redo_restore_the_store() {
+        let mut store = TaskStore::new();
+        let mut history = History::default();
+        let before = store.clone();
+        store.create(new_task("Write report")).unwrap();
+        history.record("Add task", &before, &store);
+        let before = store.clone();
+        store.complete(TaskId(1)).unwrap();
+        history.record("Complete task", &before, &store);
+        let completed_date = store.task(TaskId(1)).unwrap().completed_date;
+
+        assert_eq!(history.undo(&mut store).as_deref(), Some("Complete task"));
+        assert_eq!(names(&store), ["Write report"]);
+        assert_eq!(history.next_redo(), Some("Complete task"));
+        assert_eq!(history.redo(&mut store).as_deref(), Some("Complete task"));
+        assert_eq!(names(&store), ["Write report (done)"]);
+        // redoing restores the original completion time instead of completing the task again
+        assert_eq!(
+            store.task(TaskId(1)).unwrap().completed_date,
+            completed_date
+        );
+
+        history.undo(&mut store);
+        history.undo(&mut store);
+        assert!(names(&store).is_empty());
+        assert_eq!(history.undo(&mut store), None);
+
+        // a new command forgets what was undone
+        let before = store.clone();
+        store.clear();
+        history.record("Clear all tasks", &before, &store);
+        assert_eq!(history.next_redo(), None);
+        assert_eq!(history.undoable().collect::<Vec<_>>(), ["Clear all tasks"]);
+    }
+
+    #[test]
+    fn test_coalescing_and_limit() {
+        let mut store = TaskStore::new();
+        let mut history = History::default();
+        for bronze in 2..5 {
+            let before = store.clone();
+            store.set_goals(bronze, 10, 20).unwrap();
+            history.record_coalescing("Change goals", &before, &store);
+        }
+        assert_eq!(history.undoable().count(), 1);
+        history.undo(&mut store);
+        assert_eq!(
+            store.gamification().bronze_goal,
+            TaskStore::new().gamification().bronze_goal
+        );
+
+        for _ in 0..MAX_HISTORY + 5 {
+            history.record("Add task", &store, &store);
+        }
+        assert_eq!(history.undoable().count(), MAX_HISTORY);
+    }
+
+    #[test]
+    fn test_history_is_only_saved_when_kept() {
+        let mut history = History::default();
+        history.record("Add task", &TaskStore::new(), &TaskStore::new());
+        let saved: History =
+            serde_json::from_str(&serde_json::to_string(&history).unwrap()).unwrap();
+        assert_eq!(saved.next_undo(), None);
+
+        history.set_persistent(true);
+        let saved: History =
+            serde_json::from_str(&serde_json::to_string(&history).unwrap()).unwrap();
+        assert_eq!(saved.next_undo(), Some("Add task"));
+        assert!(saved.is_persistent());
+    }
+
+    #[test]
+    fn test_commands_only_keep_what_they_changed() {
+        let mut store = TaskStore::new();
+        for name in ["Write report", "Call the bank", "Water the plants"] {
+            store.create(new_task(name)).unwrap();
+        }
+        let mut history = History::default();
+        history.set_persistent(true);
+        let before = store.clone();
+        store.complete(TaskId(2)).unwrap();
+        history.record("Complete task", &before, &store);
+        let completed = serde_json::to_value(&store).unwrap();
+
+        // the saved command holds the completed task, not the other tasks
+        let saved = serde_json::to_string(&history).unwrap();
+        assert!(saved.contains("Call the bank"));
+        assert!(!saved.contains("Write report") && !saved.contains("Water the plants"));
+
+        // a saved history still undoes and redoes the command
+        let mut history: History = serde_json::from_str(&saved).unwrap();
+        history.undo(&mut store);
+        assert_eq!(
+            names(&store),
+            ["Write report", "Call the bank", "Water the plants"]
+        );
+        history.redo(&mut store);
+        assert_eq!(serde_json::to_value(&store).unwrap(), completed);
+    }
+}
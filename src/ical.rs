@@ -0,0 +1,613 @@
+//! iCalendar (RFC 5545) export and import.
+//!
+//! Tasks are exported either as VTODO components, which task-aware calendar clients show as
+//! to-dos, or as VEVENT components on their due date for clients that only show events. VTODO
+//! components from any calendar can be imported as tasks.
+//!
+//! Due dates without a time are written as `VALUE=DATE` dates, times with a UTC offset are
+//! converted to UTC, and times without an offset are written as floating local times. A time
+//! with a `TZID` parameter is read as a local time, since no time zone database is bundled.
+//! Repeating tasks carry their rule as an `RRULE`, see [`crate::recurrence`] for the supported subset.
+//! Priorities use the RFC 5545 scale where 1 is the highest and 9 the lowest, see
+//! [`priority_to_ical`] and [`priority_from_ical`].
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::dates::{start_of_day, DueDate};
+use crate::gamification::{PriorityLevel, Task};
+use crate::recurrence::Recurrence;
+use crate::store::TaskStore;
+use crate::transfer::{duplicate_key, ImportSummary};
+
+/// Longest content line, in bytes, before it is folded onto a continuation line
+const MAX_LINE_LENGTH: usize = 75;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Kind of calendar component tasks are exported as
+pub enum IcsComponent {
+    #[default]
+    Todo, // VTODO for every task
+    Event, // VEVENT on the due date, for tasks that have one
+}
+
+/// IcsTodos struct with the tasks read from the VTODO components of a calendar
+pub struct IcsTodos {
+    pub tasks: Vec<Task>,
+    pub skipped: Vec<String>, // VTODO components that could not be read, and why
+}
+
+/// Helper function to map a priority level onto the iCalendar scale, where 1 is the highest
+/// priority and 9 the lowest. The values are the middle of the ranges read by priority_from_ical.
+pub fn priority_to_ical(priority: PriorityLevel) -> u8 {
+    match priority {
+        PriorityLevel::High => 1,
+        PriorityLevel::Medium => 5,
+        PriorityLevel::Low => 9,
+    }
+}
+
+/// Helper function to map an iCalendar priority onto a priority level, following the
+/// ranges RFC 5545 suggests: 1-4 is high, 5 is medium and 6-9 is low.
+/// 0 means the priority is undefined and maps to low, like a task added without a priority.
+pub fn priority_from_ical(priority: u8) -> PriorityLevel {
+    match priority {
+        1..=4 => PriorityLevel::High,
+        5 => PriorityLevel::Medium,
+        _ => PriorityLevel::Low,
+    }
+}
+
+/// Helper function to export tasks as an iCalendar file
+///
+/// # Arguments
+///
+/// * `store` - The store holding the tasks
+/// * `component` - Whether to write every task as a VTODO or each task with a due date as a VEVENT
+pub fn export(store: &TaskStore, component: IcsComponent) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//TaskHero//TaskHero//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    let stamp = format_utc(Utc::now());
+    for task in store.tasks() {
+        let name = match component {
+            IcsComponent::Todo => "VTODO",
+            IcsComponent::Event if task.due_date.is_some() => "VEVENT",
+            IcsComponent::Event => continue,
+        };
+        lines.push(format!("BEGIN:{name}"));
+        lines.push(format!("UID:taskhero-task-{}@taskhero", task.id.0));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        lines.push(format!("SUMMARY:{}", escape_text(&task.name)));
+        if !task.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&task.description)));
+        }
+        if let Some(due_date) = task.due_date {
+            let property = if component == IcsComponent::Todo {
+                "DUE"
+            } else {
+                "DTSTART"
+            };
+            lines.push(format!("{property}{}", format_due_date(due_date)));
+        }
+        if let Some(recurrence) = &task.recurrence {
+            lines.push(format!("RRULE:{}", recurrence.to_rrule()));
+        }
+        lines.push(format!("PRIORITY:{}", priority_to_ical(task.priority)));
+        if component == IcsComponent::Todo {
+            lines.push(format!(
+                "STATUS:{}",
+                if task.completed {
+                    "COMPLETED"
+                } else {
+                    "NEEDS-ACTION"
+                }
+            ));
+            if let Some(completed_date) = task.completed_date {
+                lines.push(format!(
+                    "COMPLETED:{}",
+                    format_utc(completed_date.with_timezone(&Utc))
+                ));
+            }
+        }
+        lines.push(format!("END:{name}"));
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+/// Helper function to read the VTODO components of an iCalendar file as tasks
+///
+/// # Returns
+///
+/// The tasks and the components that were skipped, or an error if the file is not an iCalendar file.
+pub fn parse_todos(text: &str) -> Result<IcsTodos, String> {
+    let mut todos = IcsTodos {
+        tasks: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut components: Vec<String> = Vec::new(); // names of the components we are inside of
+    let mut todo: Option<Vec<ContentLine>> = None;
+    let mut todo_count = 0;
+
+    for (number, line) in unfold_lines(text).into_iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line = ContentLine::parse(&line)
+            .ok_or_else(|| format!("line {} is not an iCalendar content line", number + 1))?;
+        match line.name.as_str() {
+            "BEGIN" => {
+                let component = line.value.to_ascii_uppercase();
+                if components.is_empty() && component != "VCALENDAR" {
+                    return Err("the file does not start with BEGIN:VCALENDAR".to_string());
+                }
+                // properties of components nested in a VTODO, such as VALARM, are not the task's
+                if component == "VTODO" && todo.is_none() {
+                    todo = Some(Vec::new());
+                    todo_count += 1;
+                }
+                components.push(component);
+            }
+            "END" => {
+                let component = line.value.to_ascii_uppercase();
+                if components.pop().as_deref() != Some(component.as_str()) {
+                    return Err(format!("END:{component} does not match the open component"));
+                }
+                if component == "VTODO" && !components.contains(&"VTODO".to_string()) {
+                    let properties = todo.take().unwrap_or_default();
+                    match parse_todo(&properties) {
+                        Ok(task) => todos.tasks.push(task),
+                        Err(message) => {
+                            todos
+                                .skipped
+                                .push(describe_todo(todo_count, &properties, &message))
+                        }
+                    }
+                }
+            }
+            _ => {
+                if let Some(properties) = &mut todo {
+                    if components.last().map(String::as_str) == Some("VTODO") {
+                        properties.push(line);
+                    }
+                }
+            }
+        }
+    }
+    if !components.is_empty() {
+        return Err(format!("the file ends inside {}", components.join(" > ")));
+    }
+    if todo_count == 0 && text.trim().is_empty() {
+        return Err("the file is empty".to_string());
+    }
+    Ok(todos)
+}
+
+/// Helper function to add imported tasks to the store, skipping tasks that already exist
+/// (the same name and due date)
+pub fn import(store: &mut TaskStore, todos: IcsTodos) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    let mut existing: HashSet<_> = store.tasks().iter().map(duplicate_key).collect();
+    for task in todos.tasks {
+        if existing.insert(duplicate_key(&task)) {
+            store.insert(task);
+            summary.added += 1;
+        } else {
+            summary.duplicates += 1;
+        }
+    }
+    store.evaluate();
+    summary
+}
+
+/// A content line such as `DUE;VALUE=DATE:20241123`
+struct ContentLine {
+    name: String,                  // upper case
+    params: Vec<(String, String)>, // parameter names are upper case
+    value: String,
+}
+
+/// Implementation of ContentLine with methods to parse a line and read its parameters
+impl ContentLine {
+    /// Helper function to split a content line into its name, parameters and value
+    fn parse(line: &str) -> Option<Self> {
+        // the value starts at the first colon that is not inside a quoted parameter value
+        let mut in_quotes = false;
+        let colon = line.char_indices().find_map(|(index, c)| match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                None
+            }
+            ':' if !in_quotes => Some(index),
+            _ => None,
+        })?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+        let mut parts = head.split(';');
+        let name = parts.next()?.trim().to_ascii_uppercase();
+        if name.is_empty() {
+            return None;
+        }
+        let params = parts
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| {
+                (
+                    key.trim().to_ascii_uppercase(),
+                    value.trim_matches('"').to_string(),
+                )
+            })
+            .collect();
+        Some(ContentLine {
+            name,
+            params,
+            value: value.to_string(),
+        })
+    }
+
+    /// Helper function to get the value of a parameter
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Helper function to describe a skipped VTODO by its position and UID or summary
+fn describe_todo(number: usize, properties: &[ContentLine], message: &str) -> String {
+    let find = |name: &str| {
+        properties
+            .iter()
+            .find(|line| line.name == name)
+            .map(|line| unescape_text(&line.value))
+    };
+    match find("SUMMARY").or_else(|| find("UID")) {
+        Some(label) => format!("VTODO {number} ({label:?}): {message}"),
+        None => format!("VTODO {number}: {message}"),
+    }
+}
+
+/// Helper function to read a task from the properties of a VTODO
+fn parse_todo(properties: &[ContentLine]) -> Result<Task, String> {
+    let find = |name: &str| properties.iter().find(|line| line.name == name);
+
+    let name = find("SUMMARY")
+        .map(|line| unescape_text(&line.value))
+        .unwrap_or_default();
+    if name.trim().is_empty() {
+        return Err("it has no SUMMARY".to_string());
+    }
+    let description = find("DESCRIPTION")
+        .map(|line| unescape_text(&line.value))
+        .unwrap_or_default();
+    let due_date = find("DUE").map(parse_due_date).transpose()?;
+    let priority = match find("PRIORITY") {
+        Some(line) => priority_from_ical(
+            line.value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid PRIORITY {:?}", line.value))?,
+        ),
+        None => PriorityLevel::Low,
+    };
+    let completed_date = find("COMPLETED")
+        .map(|line| parse_date_time(line).map(|date| date.with_timezone(&Local)))
+        .transpose()?;
+    let recurrence = find("RRULE")
+        .map(|line| {
+            Recurrence::from_rrule(&line.value).map_err(|err| format!("unsupported RRULE: {err}"))
+        })
+        .transpose()?;
+    let completed = completed_date.is_some()
+        || find("STATUS").is_some_and(|line| line.value.trim().eq_ignore_ascii_case("COMPLETED"))
+        || find("PERCENT-COMPLETE").is_some_and(|line| line.value.trim() == "100");
+
+    Ok(Task {
+        description,
+        due_date,
+        completed,
+        completed_date,
+        recurrence,
+        ..Task::new(name, priority) // the ID is assigned when the task is added
+    })
+}
+
+/// Helper function to read a DUE property as a due date
+fn parse_due_date(line: &ContentLine) -> Result<DueDate, String> {
+    let value = line.value.trim();
+    if line
+        .param("VALUE")
+        .is_some_and(|kind| kind.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8
+    {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(DueDate::on)
+            .map_err(|_| format!("invalid DUE date {value:?}"));
+    }
+    let (naive, utc) =
+        parse_naive_date_time(value).ok_or_else(|| format!("invalid DUE time {value:?}"))?;
+    Ok(DueDate {
+        date: naive.date(),
+        time: Some(naive.time()),
+        utc_offset_minutes: utc.then_some(0),
+    })
+}
+
+/// Helper function to read a date-time property, such as COMPLETED, as a moment in time
+fn parse_date_time(line: &ContentLine) -> Result<DateTime<Utc>, String> {
+    let value = line.value.trim();
+    if value.len() == 8 {
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+            return Ok(start_of_day(date).with_timezone(&Utc));
+        }
+    }
+    let (naive, utc) = parse_naive_date_time(value)
+        .ok_or_else(|| format!("invalid {} time {value:?}", line.name))?;
+    Ok(if utc {
+        Utc.from_utc_datetime(&naive)
+    } else {
+        Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .unwrap_or_else(|| Local.from_utc_datetime(&naive))
+            .with_timezone(&Utc)
+    })
+}
+
+/// Helper function to parse `20241123T143000` or `20241123T143000Z`
+///
+/// # Returns
+///
+/// The date and time, and whether it is in UTC.
+fn parse_naive_date_time(value: &str) -> Option<(NaiveDateTime, bool)> {
+    let (value, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|naive| (naive, utc))
+}
+
+/// Helper function to format a due date as the parameters and value of a DUE or DTSTART property
+fn format_due_date(due_date: DueDate) -> String {
+    match (due_date.time, due_date.utc_offset_minutes) {
+        (None, _) => format!(";VALUE=DATE:{}", due_date.date.format("%Y%m%d")),
+        (Some(time), None) => format!(":{}", due_date.date.and_time(time).format("%Y%m%dT%H%M%S")),
+        (Some(_), Some(_)) => format!(":{}", format_utc(due_date.to_local().with_timezone(&Utc))),
+    }
+}
+
+/// Helper function to format a moment as an iCalendar UTC date-time
+fn format_utc(moment: DateTime<Utc>) -> String {
+    moment.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Helper function to escape text for a TEXT property value
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Helper function to undo the escaping of a TEXT property value
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Helper function to fold a content line into lines of at most MAX_LINE_LENGTH bytes,
+/// without splitting a UTF-8 character, each ending in CRLF
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1; // the leading space
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Helper function to join folded continuation lines back onto the line they belong to
+fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::NewTask;
+
+    fn add(store: &mut TaskStore, name: &str, due_date: &str, priority: PriorityLevel) {
+        store
+            .create(NewTask {
+                name: name.to_string(),
+                description: String::new(),
+                due_date: crate::dates::parse_due_date(due_date, store.clock().today()).ok(),
+                priority,
+                recurrence: None,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_priority_mapping() {
+        for priority in [
+            PriorityLevel::Low,
+            PriorityLevel::Medium,
+            PriorityLevel::High,
+        ] {
+            assert_eq!(priority_from_ical(priority_to_ical(priority)), priority);
+        }
+        let mapped: Vec<_> = (0..=9).map(priority_from_ical).collect();
+        use PriorityLevel::*;
+        assert_eq!(
+            mapped,
+            [Low, High, High, High, High, Medium, Low, Low, Low, Low]
+        );
+    }
+
+    #[test]
+    fn test_todo_round_trip() {
+        let mut store = TaskStore::new();
+        add(
+            &mut store,
+            "Write report; draft, then final",
+            "2024-11-23",
+            PriorityLevel::High,
+        );
+        add(
+            &mut store,
+            "Call",
+            "2024-11-24 09:15",
+            PriorityLevel::Medium,
+        );
+        add(
+            &mut store,
+            "Standup",
+            "2024-11-25 14:30 +02:00",
+            PriorityLevel::Low,
+        );
+        add(&mut store, "Someday", "", PriorityLevel::Low);
+        let ids = store.tasks().ids();
+        store
+            .update(
+                ids[0],
+                crate::store::TaskUpdate {
+                    description: Some(format!("Line one\nLine \\two {}", "é".repeat(60))),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.complete(ids[1]).unwrap();
+        store
+            .update(
+                ids[2],
+                crate::store::TaskUpdate {
+                    recurrence: Some("weekly on monday, friday, 4 times".parse().ok()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let ics = export(&store, IcsComponent::Todo);
+        assert!(ics.lines().all(|line| line.len() <= MAX_LINE_LENGTH + 1)); // + the CR
+        assert!(ics.contains("DUE;VALUE=DATE:20241123\r\n"));
+        assert!(ics.contains("DUE:20241125T123000Z\r\n"));
+        assert!(ics.contains("PRIORITY:1\r\n"));
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=MO,FR;COUNT=4\r\n"));
+
+        let todos = parse_todos(&ics).unwrap();
+        assert!(todos.skipped.is_empty());
+        assert_eq!(todos.tasks.len(), 4);
+        for (imported, original) in todos.tasks.iter().zip(store.tasks()) {
+            assert_eq!(imported.name, original.name);
+            assert_eq!(imported.description, original.description);
+            assert_eq!(imported.priority, original.priority);
+            assert_eq!(imported.completed, original.completed);
+            assert_eq!(imported.recurrence, original.recurrence);
+            assert_eq!(
+                imported.due_date.map(|due| due.to_local()),
+                original.due_date.map(|due| due.to_local())
+            );
+            assert_eq!(
+                imported.completed_date.map(|date| date.timestamp()),
+                original.completed_date.map(|date| date.timestamp())
+            );
+        }
+        // Exporting what was imported gives the same calendar, apart from the UIDs and stamps
+        let mut reimported = TaskStore::new();
+        assert_eq!(import(&mut reimported, todos).added, 4);
+        let strip = |ics: &str| {
+            ics.lines()
+                .filter(|line| !line.starts_with("DTSTAMP"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        assert_eq!(strip(&export(&reimported, IcsComponent::Todo)), strip(&ics));
+        // Importing the same tasks again only finds duplicates
+        assert_eq!(
+            import(&mut reimported, parse_todos(&ics).unwrap()).duplicates,
+            4
+        );
+    }
+
+    #[test]
+    fn test_events_only_include_tasks_with_due_dates() {
+        let mut store = TaskStore::new();
+        add(
+            &mut store,
+            "Write report",
+            "2024-11-23",
+            PriorityLevel::High,
+        );
+        add(&mut store, "Someday", "", PriorityLevel::Low);
+        let ics = export(&store, IcsComponent::Event);
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20241123\r\n"));
+        // Events are not imported as tasks
+        assert!(parse_todos(&ics).unwrap().tasks.is_empty());
+    }
+
+    #[test]
+    fn test_import_from_other_clients() {
+        let ics = "BEGIN:VCALENDAR\nVERSION:2.0\nBEGIN:VTODO\nUID:1\nSUMMARY:Pay rent\nDUE;TZID=\"Europe/Berlin\":20241201T090000\n\
+                   PRIORITY:3\nSTATUS:COMPLETED\nBEGIN:VALARM\nACTION:DISPLAY\nSUMMARY:Alarm text\nEND:VALARM\nEND:VTODO\n\
+                   BEGIN:VTODO\nUID:no-summary\nEND:VTODO\nBEGIN:VTODO\nSUMMARY:Long\n  name\nPRIORITY:high\nEND:VTODO\nEND:VCALENDAR\n";
+        let todos = parse_todos(ics).unwrap();
+        assert_eq!(todos.tasks.len(), 1);
+        let task = &todos.tasks[0];
+        assert_eq!(task.name, "Pay rent");
+        assert_eq!(task.priority, PriorityLevel::High);
+        assert!(task.completed && task.completed_date.is_none());
+        assert_eq!(
+            task.due_date,
+            crate::dates::parse_due_date(
+                "2024-12-01 09:00",
+                NaiveDate::from_ymd_opt(2024, 12, 1).unwrap()
+            )
+            .ok()
+        );
+        assert_eq!(
+            todos.skipped,
+            [
+                "VTODO 2 (\"no-summary\"): it has no SUMMARY",
+                "VTODO 3 (\"Long name\"): invalid PRIORITY \"high\""
+            ]
+        );
+
+        assert!(parse_todos("BEGIN:VTODO\nEND:VTODO\n").is_err());
+        assert!(parse_todos("BEGIN:VCALENDAR\nBEGIN:VTODO\nEND:VCALENDAR\n").is_err());
+    }
+}
@@ -0,0 +1,846 @@
+//! The append-only event journal.
+//!
+//! Every change to the tasks, goals and saved filters is recorded as events, such as a task
+//! being created, edited, completed or deleted, or a goal changing, each with the time it
+//! happened. Events are only ever added, never changed or removed, so the journal keeps the
+//! facts a snapshot of the tasks loses: when a task was created, each time its priority
+//! changed, and every completion, including those of tasks that were reopened or deleted since.
+//!
+//! The tasks, goals and saved filters are rebuilt from the journal with [`Journal::replay`];
+//! the points and messages of the gamification system are then computed from the tasks as
+//! usual. Events are worked out by comparing the store before and after a change, so every
+//! change is journaled the same way whether it comes from a form, an import, a sync or undo.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Local, NaiveDate, Weekday};
+
+use crate::achievements::{Achievement, Unlocked};
+use crate::gamification::{Gamification, Task};
+use crate::ledger::{Ledger, Transaction};
+use crate::levels::LevelConfig;
+use crate::query::TaskQuery;
+use crate::scoring::{Score, ScoringModel};
+use crate::store::TaskStore;
+use crate::streaks;
+use crate::tags;
+use crate::task_list::{TaskId, TaskList};
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type")]
+/// A change recorded in the journal
+pub enum Event {
+    TaskCreated {
+        task: Task,
+        position: usize,
+    }, // position in the task list
+    TaskEdited {
+        task: Task,
+    }, // the task after the edit; completion is left to TaskCompleted
+    TaskCompleted {
+        id: TaskId,
+        completed_at: Option<DateTime<Local>>,
+        #[serde(default)]
+        score: Option<Score>, // the score paid for the task, missing in journals saved before scores were kept
+    },
+    TaskUncompleted {
+        id: TaskId,
+    },
+    TaskDeleted {
+        id: TaskId,
+    },
+    TasksReordered {
+        order: Vec<TaskId>,
+    }, // such as after the tasks were replaced by an import
+    GoalChanged {
+        bronze: u32,
+        silver: u32,
+        gold: u32,
+    },
+    ProjectGoalChanged {
+        project: String,
+        goal: u32,
+    }, // a goal of 0 removes the project's goal
+    UnblockBonusChanged {
+        enabled: bool,
+    },
+    FilterSaved {
+        name: String,
+        query: TaskQuery,
+    },
+    FilterRemoved {
+        name: String,
+    },
+    RewardGranted {
+        reward: String,
+        points: u32,
+    }, // a level or daily reward; points are 0 for a level
+    PointsRecorded {
+        transaction: Transaction,
+    }, // a transaction added to the points ledger
+    AchievementUnlocked {
+        unlocked: Unlocked,
+    }, // a trophy or level added to the trophy case
+    AchievementRulesChanged {
+        rules: Option<Vec<Achievement>>,
+    }, // None goes back to the standard rules
+    RestDaysChanged {
+        days: Vec<Weekday>,
+    },
+    LevelConfigChanged {
+        config: Option<LevelConfig>,
+    }, // None goes back to the standard level curve
+    LevelReached {
+        level: u32,
+    }, // a level reached for the first time
+    ScoringChanged {
+        scoring: ScoringModel,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// An event and the time it happened
+pub struct Entry {
+    pub at: DateTime<Local>,
+    pub event: Event,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A line of the journal described in words
+pub struct LogLine {
+    pub at: DateTime<Local>,
+    pub task: Option<TaskId>, // the task the line is about, if any
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+/// Journal struct with every event recorded so far, oldest first
+pub struct Journal {
+    entries: Vec<Entry>,
+}
+
+/// Implementation of Journal with methods to record events and rebuild the state from them
+impl Journal {
+    /// Helper function to get every entry, oldest first
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Helper function to check whether nothing has been recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Helper function to record an event
+    pub fn push(&mut self, event: Event, at: DateTime<Local>) {
+        self.entries.push(Entry { at, event });
+    }
+
+    /// Helper function to record the events that turn one state of the store into another
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - The store before the change
+    /// * `after` - The store after the change
+    /// * `at` - When the change happened
+    ///
+    /// # Returns
+    ///
+    /// The number of events recorded, 0 when nothing changed.
+    pub fn record(&mut self, before: &TaskStore, after: &TaskStore, at: DateTime<Local>) -> usize {
+        let events = changes(before, after);
+        let count = events.len();
+        self.entries
+            .extend(events.into_iter().map(|event| Entry { at, event }));
+        count
+    }
+
+    /// Helper function to rebuild the tasks, goals and saved filters from the events. The points
+    /// and messages of the gamification system are left for `TaskStore::evaluate` to compute.
+    pub fn replay(&self) -> TaskStore {
+        let mut tasks: Vec<Task> = Vec::new();
+        let mut next_id = 0;
+        let mut gamification = Gamification::new();
+        let mut filters = BTreeMap::new();
+        let mut ledger = Ledger::default();
+        for entry in &self.entries {
+            match &entry.event {
+                Event::TaskCreated { task, position } => {
+                    next_id = next_id.max(task.id.0 + 1);
+                    tasks.retain(|other| other.id != task.id);
+                    tasks.insert((*position).min(tasks.len()), task.clone());
+                }
+                Event::TaskEdited { task } => {
+                    if let Some(own) = tasks.iter_mut().find(|own| own.id == task.id) {
+                        *own = Task {
+                            completed: own.completed,
+                            completed_date: own.completed_date,
+                            ..task.clone()
+                        };
+                    }
+                }
+                Event::TaskCompleted {
+                    id,
+                    completed_at,
+                    score,
+                } => {
+                    if let Some(task) = tasks.iter_mut().find(|task| task.id == *id) {
+                        task.completed = true;
+                        task.completed_date = *completed_at;
+                        task.score = score.clone();
+                    }
+                }
+                Event::TaskUncompleted { id } => {
+                    if let Some(task) = tasks.iter_mut().find(|task| task.id == *id) {
+                        task.completed = false;
+                        task.completed_date = None;
+                        task.score = None;
+                    }
+                }
+                Event::TaskDeleted { id } => tasks.retain(|task| task.id != *id),
+                Event::TasksReordered { order } => {
+                    tasks.sort_by_key(|task| {
+                        order
+                            .iter()
+                            .position(|id| *id == task.id)
+                            .unwrap_or(order.len())
+                    });
+                }
+                Event::GoalChanged {
+                    bronze,
+                    silver,
+                    gold,
+                } => {
+                    (
+                        gamification.bronze_goal,
+                        gamification.silver_goal,
+                        gamification.gold_goal,
+                    ) = (*bronze, *silver, *gold);
+                }
+                Event::ProjectGoalChanged { project, goal: 0 } => {
+                    gamification.project_goals.remove(project);
+                }
+                Event::ProjectGoalChanged { project, goal } => {
+                    gamification.project_goals.insert(project.clone(), *goal);
+                }
+                Event::UnblockBonusChanged { enabled } => gamification.unblock_bonus = *enabled,
+                Event::FilterSaved { name, query } => {
+                    filters.insert(name.clone(), query.clone());
+                }
+                Event::FilterRemoved { name } => {
+                    filters.remove(name);
+                }
+                Event::RewardGranted { .. } => {} // rewards follow from the tasks
+                Event::PointsRecorded { transaction } => ledger.push(transaction.clone()),
+                Event::AchievementUnlocked { unlocked } => {
+                    gamification.achievements.push(unlocked.clone())
+                }
+                Event::AchievementRulesChanged { rules } => {
+                    gamification.achievements.set_custom_rules(rules.clone())
+                }
+                Event::RestDaysChanged { days } => gamification.rest_days = days.clone(),
+                Event::LevelConfigChanged { config } => gamification.level_config = config.clone(),
+                Event::LevelReached { level } => {
+                    gamification.best_level = gamification.best_level.max(*level)
+                }
+                Event::ScoringChanged { scoring } => gamification.scoring = scoring.clone(),
+            }
+        }
+        TaskStore::from_parts(
+            TaskList::with_next_id(tasks, next_id),
+            gamification,
+            filters,
+            ledger,
+        )
+    }
+
+    /// Helper function to describe the journal in words
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - Only describe the events of this task, or every event for None
+    ///
+    /// # Returns
+    ///
+    /// A line for every event, oldest first. An edit gets a line for every field it changed.
+    pub fn log(&self, task: Option<TaskId>) -> Vec<LogLine> {
+        let mut known: HashMap<TaskId, Task> = HashMap::new();
+        let mut lines = Vec::new();
+        for entry in &self.entries {
+            let about = match &entry.event {
+                Event::TaskCreated { task, .. } | Event::TaskEdited { task } => Some(task.id),
+                Event::TaskCompleted { id, .. }
+                | Event::TaskUncompleted { id }
+                | Event::TaskDeleted { id } => Some(*id),
+                _ => None,
+            };
+            let name = |id: &TaskId, known: &HashMap<TaskId, Task>| {
+                known.get(id).map_or(format!("task {id}"), |task| {
+                    format!("task {id} {:?}", task.name)
+                })
+            };
+            let texts = match &entry.event {
+                Event::TaskCreated { task, .. } => {
+                    vec![format!("Created task {} {:?}", task.id, task.name)]
+                }
+                Event::TaskEdited { task } => match known.get(&task.id) {
+                    Some(old) => describe_edit(old, task)
+                        .into_iter()
+                        .map(|change| format!("{}: {change}", name(&task.id, &known)))
+                        .collect(),
+                    None => vec![format!("Edited {}", name(&task.id, &known))],
+                },
+                Event::TaskCompleted { id, .. } => vec![format!("Completed {}", name(id, &known))],
+                Event::TaskUncompleted { id } => vec![format!("Reopened {}", name(id, &known))],
+                Event::TaskDeleted { id } => vec![format!("Deleted {}", name(id, &known))],
+                Event::TasksReordered { .. } => vec!["Reordered the tasks".to_string()],
+                Event::GoalChanged {
+                    bronze,
+                    silver,
+                    gold,
+                } => vec![format!(
+                    "Goals changed to {bronze}, {silver} and {gold} tasks"
+                )],
+                Event::ProjectGoalChanged { project, goal: 0 } => {
+                    vec![format!("Removed the goal of project {project}")]
+                }
+                Event::ProjectGoalChanged { project, goal } => {
+                    vec![format!("Goal of project {project} set to {goal} tasks")]
+                }
+                Event::UnblockBonusChanged { enabled } => {
+                    vec![format!(
+                        "Turned {} the unblock bonus",
+                        if *enabled { "on" } else { "off" }
+                    )]
+                }
+                Event::FilterSaved { name, query } => {
+                    vec![format!("Saved filter {name:?}: {query}")]
+                }
+                Event::FilterRemoved { name } => vec![format!("Deleted filter {name:?}")],
+                Event::RewardGranted { reward, points: 0 } => vec![reward.clone()],
+                Event::RewardGranted { reward, points } => {
+                    vec![format!("{reward} (+{points} points)")]
+                }
+                Event::PointsRecorded { transaction } => vec![format!(
+                    "{:+} points: {}",
+                    transaction.amount, transaction.reason
+                )],
+                Event::AchievementUnlocked { unlocked } => {
+                    vec![format!("Unlocked achievement {:?}", unlocked.name)]
+                }
+                Event::AchievementRulesChanged { rules: Some(rules) } => {
+                    vec![format!("Loaded {} achievement rule(s)", rules.len())]
+                }
+                Event::AchievementRulesChanged { rules: None } => {
+                    vec!["Went back to the standard achievement rules".to_string()]
+                }
+                Event::RestDaysChanged { days } => vec![format!(
+                    "Rest days changed to {}",
+                    streaks::format_rest_days(days)
+                )],
+                Event::LevelConfigChanged { config: Some(_) } => {
+                    vec!["Changed the level curve".to_string()]
+                }
+                Event::LevelConfigChanged { config: None } => {
+                    vec!["Went back to the standard level curve".to_string()]
+                }
+                Event::LevelReached { level } => vec![format!("Reached level {level}")],
+                Event::ScoringChanged { scoring } => vec![format!(
+                    "Changed the scoring, using the {} formula",
+                    scoring.formula
+                )],
+            };
+            match &entry.event {
+                Event::TaskCreated { task, .. } | Event::TaskEdited { task } => {
+                    let completion = known
+                        .get(&task.id)
+                        .map_or((task.completed, task.completed_date), |old| {
+                            (old.completed, old.completed_date)
+                        });
+                    known.insert(
+                        task.id,
+                        Task {
+                            completed: completion.0,
+                            completed_date: completion.1,
+                            ..task.clone()
+                        },
+                    );
+                }
+                Event::TaskCompleted {
+                    id, completed_at, ..
+                } => {
+                    if let Some(task) = known.get_mut(id) {
+                        (task.completed, task.completed_date) = (true, *completed_at);
+                    }
+                }
+                Event::TaskUncompleted { id } => {
+                    if let Some(task) = known.get_mut(id) {
+                        (task.completed, task.completed_date) = (false, None);
+                    }
+                }
+                _ => {}
+            }
+            if task.map_or(true, |task| about == Some(task)) {
+                lines.extend(texts.into_iter().map(|text| LogLine {
+                    at: entry.at,
+                    task: about,
+                    text,
+                }));
+            }
+        }
+        lines
+    }
+
+    /// Helper function to count the completions of every day. Every completion counts, including
+    /// those of tasks that were reopened or deleted since and each completion of a reopened task.
+    pub fn completions_by_day(&self) -> BTreeMap<NaiveDate, usize> {
+        let mut days = BTreeMap::new();
+        for entry in &self.entries {
+            if let Event::TaskCompleted { completed_at, .. } = &entry.event {
+                let day = completed_at.unwrap_or(entry.at).date_naive();
+                *days.entry(day).or_insert(0) += 1;
+            }
+        }
+        days
+    }
+}
+
+/// Helper function to work out the events that turn one state of the store into another
+fn changes(before: &TaskStore, after: &TaskStore) -> Vec<Event> {
+    let mut events = Vec::new();
+    for task in before.tasks() {
+        if !after.tasks().contains(task.id) {
+            events.push(Event::TaskDeleted { id: task.id });
+        }
+    }
+    for (position, task) in after.tasks().iter().enumerate() {
+        let Some(old) = before.tasks().get(task.id) else {
+            events.push(Event::TaskCreated {
+                task: task.clone(),
+                position,
+            });
+            continue;
+        };
+        // the score is kept on completion, so it changes with the completion or with an edit of the points
+        let without_completion = |task: &Task| Task {
+            completed: false,
+            completed_date: None,
+            score: None,
+            ..task.clone()
+        };
+        if without_completion(old) != without_completion(task) {
+            events.push(Event::TaskEdited { task: task.clone() });
+        }
+        if (old.completed, old.completed_date) != (task.completed, task.completed_date) {
+            events.push(if task.completed {
+                Event::TaskCompleted {
+                    id: task.id,
+                    completed_at: task.completed_date,
+                    score: task.score.clone(),
+                }
+            } else {
+                Event::TaskUncompleted { id: task.id }
+            });
+        }
+    }
+    // the events above keep the order of the tasks, unless the tasks were replaced in another order
+    let mut order: Vec<TaskId> = before
+        .tasks()
+        .ids()
+        .into_iter()
+        .filter(|id| after.tasks().contains(*id))
+        .collect();
+    for (position, task) in after.tasks().iter().enumerate() {
+        if !before.tasks().contains(task.id) {
+            order.insert(position.min(order.len()), task.id);
+        }
+    }
+    if order != after.tasks().ids() {
+        events.push(Event::TasksReordered {
+            order: after.tasks().ids(),
+        });
+    }
+
+    let (old, new) = (before.gamification(), after.gamification());
+    if (old.bronze_goal, old.silver_goal, old.gold_goal)
+        != (new.bronze_goal, new.silver_goal, new.gold_goal)
+    {
+        events.push(Event::GoalChanged {
+            bronze: new.bronze_goal,
+            silver: new.silver_goal,
+            gold: new.gold_goal,
+        });
+    }
+    for project in old
+        .project_goals
+        .keys()
+        .filter(|project| !new.project_goals.contains_key(*project))
+    {
+        events.push(Event::ProjectGoalChanged {
+            project: project.clone(),
+            goal: 0,
+        });
+    }
+    for (project, &goal) in &new.project_goals {
+        if old.project_goals.get(project) != Some(&goal) {
+            events.push(Event::ProjectGoalChanged {
+                project: project.clone(),
+                goal,
+            });
+        }
+    }
+    if old.unblock_bonus != new.unblock_bonus {
+        events.push(Event::UnblockBonusChanged {
+            enabled: new.unblock_bonus,
+        });
+    }
+    if old.scoring != new.scoring {
+        events.push(Event::ScoringChanged {
+            scoring: new.scoring.clone(),
+        });
+    }
+    if old.level_config != new.level_config {
+        events.push(Event::LevelConfigChanged {
+            config: new.level_config.clone(),
+        });
+    }
+    if new.best_level > old.best_level {
+        events.push(Event::LevelReached {
+            level: new.best_level,
+        });
+    }
+    if old.rest_days != new.rest_days {
+        events.push(Event::RestDaysChanged {
+            days: new.rest_days.clone(),
+        });
+    }
+    if old.achievements.custom_rules() != new.achievements.custom_rules() {
+        events.push(Event::AchievementRulesChanged {
+            rules: new.achievements.custom_rules().map(<[Achievement]>::to_vec),
+        });
+    }
+    // like the ledger, achievements are only ever unlocked
+    for unlocked in new
+        .achievements
+        .unlocked()
+        .iter()
+        .skip(old.achievements.unlocked().len())
+    {
+        events.push(Event::AchievementUnlocked {
+            unlocked: unlocked.clone(),
+        });
+    }
+
+    // the ledger only grows, so the new transactions are the ones after the old ones
+    for transaction in after
+        .ledger()
+        .transactions()
+        .iter()
+        .skip(before.ledger().len())
+    {
+        events.push(Event::PointsRecorded {
+            transaction: transaction.clone(),
+        });
+    }
+
+    for name in before
+        .filters()
+        .keys()
+        .filter(|name| !after.filters().contains_key(*name))
+    {
+        events.push(Event::FilterRemoved { name: name.clone() });
+    }
+    for (name, query) in after.filters() {
+        if before.filters().get(name) != Some(query) {
+            events.push(Event::FilterSaved {
+                name: name.clone(),
+                query: query.clone(),
+            });
+        }
+    }
+    events
+}
+
+/// Helper function to describe the fields an edit changed
+fn describe_edit(old: &Task, new: &Task) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+        changes.push(format!("renamed to {:?}", new.name));
+    }
+    if old.description != new.description {
+        changes.push("description changed".to_string());
+    }
+    if old.due_date != new.due_date {
+        changes.push(match new.due_date {
+            Some(due_date) => format!("due date changed to {due_date}"),
+            None => "due date removed".to_string(),
+        });
+    }
+    if old.priority != new.priority {
+        changes.push(format!(
+            "priority changed from {:?} to {:?}",
+            old.priority, new.priority
+        ));
+    }
+    if old.effort != new.effort {
+        changes.push(match new.effort {
+            Some(effort) => format!("effort estimated at {effort} h"),
+            None => "effort estimate removed".to_string(),
+        });
+    }
+    if old.difficulty != new.difficulty {
+        changes.push(format!(
+            "difficulty changed from {} to {}",
+            old.difficulty, new.difficulty
+        ));
+    }
+    if old.points_override != new.points_override {
+        changes.push(match new.points_override {
+            Some(points) => format!("points set to {points} by hand"),
+            None => "points left to the scoring formula".to_string(),
+        });
+    }
+    if old.recurrence != new.recurrence {
+        changes.push(match &new.recurrence {
+            Some(recurrence) => format!("repeats {recurrence}"),
+            None => "stopped repeating".to_string(),
+        });
+    }
+    if old.project != new.project {
+        changes.push(match &new.project {
+            Some(project) => format!("moved to project {project}"),
+            None => "taken out of its project".to_string(),
+        });
+    }
+    if old.tags != new.tags {
+        changes.push(if new.tags.is_empty() {
+            "tags removed".to_string()
+        } else {
+            format!("tags changed to {}", tags::format_tags(&new.tags))
+        });
+    }
+    if old.parent != new.parent {
+        changes.push(match new.parent {
+            Some(parent) => format!("moved under task {parent}"),
+            None => "moved to the top level".to_string(),
+        });
+    }
+    if old.checklist != new.checklist {
+        let (done, total) = new.checklist_progress();
+        changes.push(format!("checklist changed ({done}/{total} done)"));
+    }
+    if old.depends_on != new.depends_on {
+        let prerequisites: Vec<String> = new.depends_on.iter().map(TaskId::to_string).collect();
+        changes.push(if prerequisites.is_empty() {
+            "no longer waits for other tasks".to_string()
+        } else {
+            format!("waits for {}", prerequisites.join(", "))
+        });
+    }
+    if old.auto_complete != new.auto_complete {
+        let state = if new.auto_complete { "on" } else { "off" };
+        changes.push(format!(
+            "completing once its subtasks and checklist are done turned {state}"
+        ));
+    }
+    if changes.is_empty() {
+        changes.push("edited".to_string());
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::DueDate;
+    use crate::gamification::PriorityLevel;
+    use crate::recurrence::Recurrence;
+    use crate::store::{NewTask, TaskUpdate};
+    use chrono::TimeZone;
+
+    fn new_task(name: &str, recurrence: Option<Recurrence>) -> NewTask {
+        NewTask {
+            name: name.to_string(),
+            description: String::new(),
+            due_date: Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap())),
+            priority: PriorityLevel::Low,
+            recurrence,
+        }
+    }
+
+    /// Helper function to compare what the journal rebuilds, leaving out the computed points and messages
+    fn rebuilt(store: &TaskStore) -> serde_json::Value {
+        let gamification = store.gamification();
+        serde_json::json!({
+            "tasks": store.tasks().as_slice(),
+            "goals": [gamification.bronze_goal, gamification.silver_goal, gamification.gold_goal],
+            "project_goals": gamification.project_goals,
+            "unblock_bonus": gamification.unblock_bonus,
+            "filters": store.filters(),
+            "ledger": store.ledger(),
+            "achievements": gamification.achievements,
+            "rest_days": gamification.rest_days,
+            "best_level": gamification.best_level,
+            "scoring": gamification.scoring,
+        })
+    }
+
+    /// Helper function to change the store and journal the change
+    fn change(
+        store: &mut TaskStore,
+        journal: &mut Journal,
+        at: DateTime<Local>,
+        change: impl FnOnce(&mut TaskStore),
+    ) {
+        let before = store.clone();
+        change(store);
+        journal.record(&before, store, at);
+    }
+
+    #[test]
+    fn test_replay_rebuilds_the_store() {
+        let at = Local.with_ymd_and_hms(2024, 11, 20, 9, 0, 0).unwrap();
+        let mut store = TaskStore::new();
+        let mut journal = Journal::default();
+        change(&mut store, &mut journal, at, |store| {
+            store.create(new_task("Write report", None)).unwrap();
+            store
+                .create(new_task(
+                    "Standup",
+                    Recurrence::parse_optional("daily").unwrap(),
+                ))
+                .unwrap();
+            store.create(new_task("Plan sprint", None)).unwrap();
+        });
+        change(&mut store, &mut journal, at, |store| {
+            store
+                .update(
+                    TaskId(1),
+                    TaskUpdate {
+                        priority: Some(PriorityLevel::High),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            store.add_dependency(TaskId(3), TaskId(1)).unwrap();
+        });
+        // completing a repeating task adds its next occurrence, and checking the rewards banks its points
+        change(&mut store, &mut journal, at, |store| {
+            store.complete(TaskId(2)).unwrap();
+            store.evaluate();
+        });
+        let deleted = store.clone();
+        change(&mut store, &mut journal, at, |store| {
+            store.delete(TaskId(1)).unwrap();
+        });
+        // undoing the delete puts the task back in its place
+        change(&mut store, &mut journal, at, |store| *store = deleted);
+        change(&mut store, &mut journal, at, |store| {
+            store.set_goals(3, 6, 9).unwrap();
+            store.set_project_goal("Garden", 4).unwrap();
+            store.set_unblock_bonus(true);
+            store
+                .save_filter(
+                    "Open",
+                    TaskQuery {
+                        text: "open".to_string(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            store.uncomplete(TaskId(2)).unwrap();
+        });
+        // replacing the tasks, such as by an import, can change their order
+        change(&mut store, &mut journal, at, |store| {
+            let mut tasks = store.tasks().as_slice().to_vec();
+            tasks.reverse();
+            store.replace_tasks(tasks);
+        });
+        // nothing changed, so nothing is recorded
+        assert_eq!(journal.record(&store.clone(), &store, at), 0);
+
+        assert_eq!(rebuilt(&journal.replay()), rebuilt(&store));
+        assert_eq!(
+            journal.replay().tasks().ids(),
+            [TaskId(5), TaskId(6), TaskId(7), TaskId(8)]
+        );
+
+        // the journal survives saving and loading
+        let saved: Journal =
+            serde_json::from_str(&serde_json::to_string(&journal).unwrap()).unwrap();
+        assert_eq!(saved.entries(), journal.entries());
+    }
+
+    #[test]
+    fn test_log_describes_the_history_of_a_task() {
+        let at = Local.with_ymd_and_hms(2024, 11, 20, 9, 0, 0).unwrap();
+        let mut store = TaskStore::new();
+        let mut journal = Journal::default();
+        change(&mut store, &mut journal, at, |store| {
+            store.create(new_task("Write report", None)).unwrap();
+            store.create(new_task("Plan sprint", None)).unwrap();
+        });
+        change(&mut store, &mut journal, at, |store| {
+            store
+                .update(
+                    TaskId(1),
+                    TaskUpdate {
+                        priority: Some(PriorityLevel::High),
+                        tags: Some(vec!["work".to_string()]),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        });
+        change(&mut store, &mut journal, at, |store| {
+            store.set_completed(TaskId(1), true).unwrap()
+        });
+        change(&mut store, &mut journal, at, |store| {
+            store.set_completed(TaskId(1), false).unwrap()
+        });
+        change(&mut store, &mut journal, at, |store| {
+            store.delete(TaskId(1)).unwrap();
+        });
+        let texts: Vec<String> = journal
+            .log(Some(TaskId(1)))
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "Created task #1 \"Write report\"",
+                "task #1 \"Write report\": priority changed from Low to High",
+                "task #1 \"Write report\": tags changed to #work",
+                "Completed task #1 \"Write report\"",
+                "Reopened task #1 \"Write report\"",
+                "Deleted task #1 \"Write report\"",
+            ]
+        );
+        assert_eq!(journal.log(None).len(), 7);
+    }
+
+    #[test]
+    fn test_completions_by_day_keeps_reopened_and_deleted_tasks() {
+        let day = |day| Local.with_ymd_and_hms(2024, 11, day, 9, 0, 0).unwrap();
+        let mut journal = Journal::default();
+        for (id, completed_at) in [(1, day(18)), (2, day(18)), (1, day(19))] {
+            journal.push(
+                Event::TaskCompleted {
+                    id: TaskId(id),
+                    completed_at: Some(completed_at),
+                    score: None,
+                },
+                completed_at,
+            );
+        }
+        journal.push(Event::TaskDeleted { id: TaskId(2) }, day(19));
+        let days: Vec<(u32, usize)> = journal
+            .completions_by_day()
+            .into_iter()
+            .map(|(date, count)| (chrono::Datelike::day(&date), count))
+            .collect();
+        assert_eq!(days, [(18, 2), (19, 1)]);
+    }
+}
@@ -0,0 +1,510 @@
+//! The points ledger.
+//!
+//! Points are banked as transactions instead of being worked out from the tasks every time:
+//! completing a task, checking off checklist items, the unblock bonus, the daily reward and
+//! the weekly bonus each record a transaction with the points, a reason and the time, and the
+//! points shown everywhere are the balance of the ledger. Transactions are never removed, so a
+//! daily reward stays earned after the day is over, and reopening a task records a penalty
+//! taking its points back rather than erasing the award.
+//!
+//! Every transaction has a key naming what it is for, such as a task or a day, and
+//! [`Ledger::reconcile`] only records the difference between what a key should be worth and what
+//! it has been paid so far. Checking the tasks any number of times therefore grants every reward
+//! exactly once.
+
+use chrono::{DateTime, Duration, Local, NaiveDate};
+
+use crate::gamification::{Gamification, Task, UNBLOCK_BONUS};
+use crate::task_list::TaskId;
+
+/// Points for completing a task every day for a week
+pub const WEEKLY_BONUS: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+/// What a transaction was recorded for
+pub enum TransactionKind {
+    Completion,   // completing a task or checking off its checklist items
+    UnblockBonus, // completing a task others were waiting for
+    DailyReward, // reaching a daily reward of the achievement rules, such as completing 5 tasks in a day
+    WeeklyBonus, // completing a task every day for a week
+    Penalty,     // taking back points, such as when a task is reopened
+    Adjustment,  // changed by hand
+    StreakFreeze, // spent on a streak freeze
+}
+
+impl std::fmt::Display for TransactionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TransactionKind::Completion => "Completion",
+            TransactionKind::UnblockBonus => "Unblock bonus",
+            TransactionKind::DailyReward => "Daily reward",
+            TransactionKind::WeeklyBonus => "Weekly bonus",
+            TransactionKind::Penalty => "Penalty",
+            TransactionKind::Adjustment => "Adjustment",
+            TransactionKind::StreakFreeze => "Streak freeze",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// A change to the points, with why and when it happened
+pub struct Transaction {
+    pub at: DateTime<Local>,
+    pub kind: TransactionKind,
+    pub amount: i64,    // negative for penalties
+    pub reason: String, // such as `Completed "Write report"`
+    pub key: String,    // what the points are for, such as `task #3` or `daily 2024-11-20`
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+/// Ledger struct with every transaction recorded so far, oldest first
+pub struct Ledger {
+    transactions: Vec<Transaction>,
+}
+
+/// Implementation of Ledger with methods to record transactions and work out the balance
+impl Ledger {
+    /// Helper function to get every transaction, oldest first
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    /// Helper function to get the number of transactions
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Helper function to check whether nothing has been recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Helper function to get the balance of the ledger, which can be negative after penalties
+    pub fn balance(&self) -> i64 {
+        self.transactions
+            .iter()
+            .map(|transaction| transaction.amount)
+            .sum()
+    }
+
+    /// Helper function to get the points to show, which never go below 0
+    pub fn points(&self) -> u32 {
+        self.balance().clamp(0, u32::MAX as i64) as u32
+    }
+
+    /// Helper function to get the points paid for a key so far
+    pub fn paid(&self, key: &str) -> i64 {
+        self.transactions
+            .iter()
+            .filter(|transaction| transaction.key == key)
+            .map(|transaction| transaction.amount)
+            .sum()
+    }
+
+    /// Helper function to record a transaction as it is
+    pub fn push(&mut self, transaction: Transaction) {
+        self.transactions.push(transaction);
+    }
+
+    /// Helper function to get the points earned by unblocking tasks that are still banked
+    pub fn unblock_bonus(&self) -> u32 {
+        let paid: i64 = self
+            .transactions
+            .iter()
+            .filter(|transaction| transaction.key.starts_with("unblock "))
+            .map(|transaction| transaction.amount)
+            .sum();
+        paid.clamp(0, u32::MAX as i64) as u32
+    }
+
+    /// Helper function to award points for a key once; awarding the same key again records nothing
+    ///
+    /// # Returns
+    ///
+    /// Whether the points were awarded.
+    pub fn award(
+        &mut self,
+        key: &str,
+        kind: TransactionKind,
+        amount: u32,
+        reason: impl Into<String>,
+        at: DateTime<Local>,
+    ) -> bool {
+        if amount == 0
+            || self
+                .transactions
+                .iter()
+                .any(|transaction| transaction.key == key)
+        {
+            return false;
+        }
+        self.push(Transaction {
+            at,
+            kind,
+            amount: amount as i64,
+            reason: reason.into(),
+            key: key.to_string(),
+        });
+        true
+    }
+
+    /// Helper function to change the points by hand
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The points to add, or to take away when negative
+    /// * `reason` - Why the points were changed
+    /// * `at` - When the points were changed
+    pub fn adjust(&mut self, amount: i64, reason: impl Into<String>, at: DateTime<Local>) {
+        let key = format!("adjustment {}", self.transactions.len() + 1);
+        self.push(Transaction {
+            at,
+            kind: TransactionKind::Adjustment,
+            amount,
+            reason: reason.into(),
+            key,
+        });
+    }
+
+    /// Helper function to move the points paid for a task to the task that replaced it, such as
+    /// when the tasks are replaced by an import, so a task that arrives completed isn't paid again
+    ///
+    /// # Returns
+    ///
+    /// The number of transactions recorded.
+    pub fn transfer(&mut self, from: TaskId, to: TaskId, at: DateTime<Local>) -> usize {
+        let before = self.transactions.len();
+        let keys = [
+            (task_key(from), task_key(to), TransactionKind::Completion),
+            (
+                unblock_key(from),
+                unblock_key(to),
+                TransactionKind::UnblockBonus,
+            ),
+        ];
+        for (from_key, to_key, kind) in keys {
+            let paid = self.paid(&from_key);
+            if paid != 0 {
+                self.push(Transaction {
+                    at,
+                    kind,
+                    amount: -paid,
+                    reason: format!("Moved to task {to}"),
+                    key: from_key,
+                });
+                self.push(Transaction {
+                    at,
+                    kind,
+                    amount: paid,
+                    reason: format!("Moved from task {from}"),
+                    key: to_key,
+                });
+            }
+        }
+        self.transactions.len() - before
+    }
+
+    /// Helper function to spend points, such as on a streak freeze
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - What the points are spent on
+    /// * `amount` - The points to spend
+    /// * `reason` - Why the points were spent
+    /// * `at` - When the points were spent
+    pub fn spend(
+        &mut self,
+        kind: TransactionKind,
+        amount: u32,
+        reason: impl Into<String>,
+        at: DateTime<Local>,
+    ) {
+        let key = format!("spent {}", self.transactions.len() + 1);
+        self.push(Transaction {
+            at,
+            kind,
+            amount: -(amount as i64),
+            reason: reason.into(),
+            key,
+        });
+    }
+
+    /// Helper function to bring the points paid for a key to what it is worth now
+    ///
+    /// # Returns
+    ///
+    /// Whether a transaction was recorded.
+    fn settle(
+        &mut self,
+        key: String,
+        worth: i64,
+        kind: TransactionKind,
+        reason: impl FnOnce(i64) -> String,
+        at: DateTime<Local>,
+    ) -> bool {
+        let change = worth - self.paid(&key);
+        if change == 0 {
+            return false;
+        }
+        let kind = if change < 0 {
+            TransactionKind::Penalty
+        } else {
+            kind
+        };
+        self.push(Transaction {
+            at,
+            kind,
+            amount: change,
+            reason: reason(change),
+            key,
+        });
+        true
+    }
+
+    /// Helper function to record the points earned or lost since the ledger was last reconciled
+    ///
+    /// Every task is worth the points it has earned, so completing it awards them and reopening
+    /// it takes them back. A deleted task keeps the points it earned. The daily reward and the
+    /// weekly bonus are banked: once earned they are kept, even if tasks are reopened later.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The user's tasks
+    /// * `gamification` - The gamification state, with the daily reward and the unblock bonus setting
+    /// * `completed_every_day` - Whether a task was completed on each of the last 7 days
+    /// * `now` - The current time, from the app's clock
+    ///
+    /// # Returns
+    ///
+    /// The number of transactions recorded.
+    pub fn reconcile(
+        &mut self,
+        tasks: &[Task],
+        gamification: &Gamification,
+        completed_every_day: bool,
+        now: DateTime<Local>,
+    ) -> usize {
+        let before = self.transactions.len();
+        for task in tasks {
+            let worth = task.earned_points(&gamification.scoring) as i64;
+            let reason = |change: i64| match (change > 0, task.completed) {
+                (true, true) => format!("Completed {:?}", task.name),
+                (true, false) => format!("Checked off items of {:?}", task.name),
+                (false, false) if worth == 0 => format!("Reopened {:?}", task.name),
+                (false, _) => format!("Lowered the points of {:?}", task.name), // such as a cheaper scoring
+            };
+            self.settle(
+                task_key(task.id),
+                worth,
+                TransactionKind::Completion,
+                reason,
+                now,
+            );
+        }
+
+        if gamification.unblock_bonus
+            || self
+                .transactions
+                .iter()
+                .any(|transaction| transaction.kind == TransactionKind::UnblockBonus)
+        {
+            let completed: std::collections::HashSet<TaskId> = tasks
+                .iter()
+                .filter(|task| task.completed)
+                .map(|task| task.id)
+                .collect();
+            for task in tasks {
+                let unblocked = task
+                    .depends_on
+                    .iter()
+                    .filter(|prerequisite| completed.contains(prerequisite))
+                    .count() as i64;
+                let worth = if gamification.unblock_bonus {
+                    unblocked * UNBLOCK_BONUS as i64
+                } else {
+                    0
+                };
+                let reason = |change: i64| {
+                    if change > 0 {
+                        format!("Unblocked {:?}", task.name)
+                    } else {
+                        format!("{:?} is waiting again", task.name)
+                    }
+                };
+                self.settle(
+                    unblock_key(task.id),
+                    worth,
+                    TransactionKind::UnblockBonus,
+                    reason,
+                    now,
+                );
+            }
+        }
+
+        // the daily reward only goes up during the day, by the difference to the next tier
+        let today = now.date_naive();
+        let key = format!("daily {today}");
+        let worth = self.paid(&key).max(gamification.daily_reward as i64);
+        self.settle(
+            key,
+            worth,
+            TransactionKind::DailyReward,
+            |_| gamification.daily_reward_message.clone(),
+            now,
+        );
+
+        // one bonus per week, so a streak earns it again every seventh day
+        if completed_every_day && !self.weekly_bonus_since(today - Duration::days(6)) {
+            let reason = "Completed a task every day for the last week";
+            self.award(
+                &format!("weekly {today}"),
+                TransactionKind::WeeklyBonus,
+                WEEKLY_BONUS,
+                reason,
+                now,
+            );
+        }
+        self.transactions.len() - before
+    }
+
+    /// Helper function to check whether a weekly bonus was earned on or after a date
+    fn weekly_bonus_since(&self, date: NaiveDate) -> bool {
+        self.transactions.iter().any(|transaction| {
+            transaction.kind == TransactionKind::WeeklyBonus && transaction.at.date_naive() >= date
+        })
+    }
+}
+
+/// Helper function to get the key of the points of a task
+fn task_key(id: TaskId) -> String {
+    format!("task {id}")
+}
+
+/// Helper function to get the key of the unblock bonus of a task
+fn unblock_key(id: TaskId) -> String {
+    format!("unblock {id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::gamification::PriorityLevel;
+    use crate::store::{NewTask, TaskStore};
+
+    fn new_task(name: &str) -> NewTask {
+        NewTask {
+            name: name.to_string(),
+            description: String::new(),
+            due_date: None,
+            priority: PriorityLevel::Low,
+            recurrence: None,
+        }
+    }
+
+    #[test]
+    fn test_rewards_are_granted_once() {
+        let mut clock = Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap());
+        let mut store = TaskStore::new();
+        store.set_clock(clock);
+        let ids: Vec<TaskId> = (1..=5)
+            .map(|n| store.create(new_task(&format!("Task {n}"))).unwrap())
+            .collect();
+        for &id in &ids {
+            store.complete(id).unwrap();
+        }
+        // checking again and again changes nothing
+        for _ in 0..3 {
+            store.evaluate();
+        }
+        let kinds: Vec<TransactionKind> = store
+            .ledger()
+            .transactions()
+            .iter()
+            .map(|transaction| transaction.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                [TransactionKind::Completion; 5].as_slice(),
+                &[TransactionKind::DailyReward]
+            ]
+            .concat()
+        );
+        assert_eq!(store.gamification().points, 50 + 25);
+
+        // reopening a task takes its points back, but the daily reward stays banked
+        store.uncomplete(ids[0]).unwrap();
+        store.evaluate();
+        let penalty = store.ledger().transactions().last().unwrap();
+        assert_eq!(
+            (penalty.kind, penalty.amount, penalty.reason.as_str()),
+            (TransactionKind::Penalty, -10, "Reopened \"Task 1\"")
+        );
+        assert_eq!(store.gamification().points, 40 + 25);
+        // completing it again earns its points again, once
+        store.complete(ids[0]).unwrap();
+        store.evaluate();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 50 + 25);
+
+        // a deleted task keeps its points, and so does the day after
+        store.delete(ids[1]).unwrap();
+        clock.advance(Duration::days(1));
+        store.set_clock(clock);
+        store.evaluate();
+        assert_eq!(store.gamification().points, 50 + 25);
+        assert_eq!(store.ledger().len(), 8);
+    }
+
+    #[test]
+    fn test_weekly_bonus_and_adjustments() {
+        let mut clock = Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap());
+        let mut store = TaskStore::new();
+        for day in 0..14 {
+            store.set_clock(clock);
+            let id = store.create(new_task(&format!("Day {day}"))).unwrap();
+            store.complete(id).unwrap();
+            store.evaluate();
+            clock.advance(Duration::days(1));
+        }
+        // a streak of two weeks earns the bonus twice, on the 7th and the 14th day
+        let bonuses: Vec<NaiveDate> = store
+            .ledger()
+            .transactions()
+            .iter()
+            .filter(|transaction| transaction.kind == TransactionKind::WeeklyBonus)
+            .map(|transaction| transaction.at.date_naive())
+            .collect();
+        assert_eq!(
+            bonuses,
+            [
+                NaiveDate::from_ymd_opt(2024, 11, 26).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 12, 3).unwrap()
+            ]
+        );
+        assert_eq!(store.gamification().points, 14 * 10 + 2 * WEEKLY_BONUS);
+
+        let mut ledger = Ledger::default();
+        ledger.adjust(-30, "Skipped the gym", clock.now());
+        assert_eq!((ledger.balance(), ledger.points()), (-30, 0));
+        ledger.adjust(50, "Helped a friend move", clock.now());
+        assert_eq!(ledger.points(), 20);
+        assert!(ledger.award(
+            "bonus",
+            TransactionKind::Adjustment,
+            5,
+            "Bonus",
+            clock.now()
+        ));
+        assert!(!ledger.award(
+            "bonus",
+            TransactionKind::Adjustment,
+            5,
+            "Bonus",
+            clock.now()
+        ));
+        assert_eq!(ledger.points(), 25);
+    }
+}
@@ -0,0 +1,323 @@
+//! XP and levels.
+//!
+//! XP is earned from the points banked in the ledger and from streaks, and it only measures
+//! progress: spending points on a streak freeze doesn't cost any XP. Every level takes more XP
+//! than the one before it, following a level curve. How much XP each source is worth and the
+//! curve are a [`LevelConfig`], read from `levels.json` and replaceable by a JSON file in the same
+//! format, so the levels can be tuned without changing the code.
+
+use std::sync::OnceLock;
+
+use crate::ledger::{Ledger, TransactionKind};
+use crate::streaks::Streak;
+
+/// Highest level there is, so a curve that barely grows still ends somewhere
+pub const MAX_LEVEL: u32 = 1000;
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// How much XP each level takes
+pub enum Curve {
+    Linear { per_level: u32 },               // every level takes the same XP
+    Polynomial { base: u32, exponent: f64 }, // level n starts at base * (n - 1)^exponent XP
+    Exponential { base: u32, growth: f64 }, // level 2 takes base XP, and every level growth times the one before
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// How much XP each source is worth
+pub struct XpWeights {
+    pub task_points: f64, // XP per point earned by completing tasks, including penalties and adjustments
+    pub bonuses: f64, // XP per point of the unblock bonus, the daily reward and the weekly bonus
+    pub streak_day: u32, // XP for every day of the longest streak
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// LevelConfig struct with the level curve and the XP sources
+pub struct LevelConfig {
+    pub curve: Curve,
+    pub xp: XpWeights,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The level reached with some XP, and the progress toward the next one
+pub struct Level {
+    pub level: u32, // starting at 1
+    pub xp: u64,    // total XP
+    pub start: u64, // XP the level starts at
+    pub next: u64,  // XP the next level starts at, the same as `start` at the highest level
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Errors returned when a level configuration can't be loaded
+pub enum LevelConfigError {
+    Json(String),          // the file is not valid JSON or not a level configuration
+    Invalid(&'static str), // the curve or the weights can't be used
+}
+
+impl std::fmt::Display for LevelConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelConfigError::Json(message) => {
+                write!(f, "The level configuration is not valid: {message}")
+            }
+            LevelConfigError::Invalid(message) => {
+                write!(f, "The level configuration can't be used: {message}.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LevelConfigError {}
+
+/// Implementation of Curve with the XP each level starts at
+impl Curve {
+    /// Helper function to get the total XP a level starts at; level 1 starts at 0
+    pub fn threshold(&self, level: u32) -> u64 {
+        let steps = level.saturating_sub(1);
+        match *self {
+            Curve::Linear { per_level } => steps as u64 * per_level as u64,
+            Curve::Polynomial { base, exponent } => {
+                (base as f64 * (steps as f64).powf(exponent)).round() as u64
+            }
+            Curve::Exponential { base, growth } => {
+                // the sum of a geometric series, which doesn't grow at all with a growth of 1
+                let levels = if growth > 1.0 {
+                    (growth.powi(steps as i32) - 1.0) / (growth - 1.0)
+                } else {
+                    steps as f64
+                };
+                (base as f64 * levels).round() as u64
+            }
+        }
+    }
+
+    /// Helper function to check that every level takes more XP than 0, and not less than the one before
+    fn validate(&self) -> Result<(), LevelConfigError> {
+        let valid = match *self {
+            Curve::Linear { per_level } => per_level > 0,
+            Curve::Polynomial { base, exponent } => base > 0 && exponent >= 1.0,
+            Curve::Exponential { base, growth } => base > 0 && growth >= 1.0,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(LevelConfigError::Invalid("the base and the XP per level have to be above 0, and the exponent and growth at least 1"))
+        }
+    }
+}
+
+/// Implementation of LevelConfig with methods to work out the XP and the level
+impl LevelConfig {
+    /// Helper function to work out the XP earned
+    ///
+    /// # Arguments
+    ///
+    /// * `ledger` - The points ledger, with the points earned from tasks and bonuses
+    /// * `streak` - The streak, whose longest run earns XP for every day
+    pub fn xp(&self, ledger: &Ledger, streak: &Streak) -> u64 {
+        let (mut task_points, mut bonuses) = (0, 0);
+        for transaction in ledger.transactions() {
+            match transaction.kind {
+                TransactionKind::Completion
+                | TransactionKind::Penalty
+                | TransactionKind::Adjustment => task_points += transaction.amount,
+                TransactionKind::UnblockBonus
+                | TransactionKind::DailyReward
+                | TransactionKind::WeeklyBonus => bonuses += transaction.amount,
+                TransactionKind::StreakFreeze => {} // spending points isn't losing progress
+            }
+        }
+        let xp = task_points as f64 * self.xp.task_points
+            + bonuses as f64 * self.xp.bonuses
+            + streak.longest as f64 * self.xp.streak_day as f64;
+        xp.max(0.0).round() as u64
+    }
+
+    /// Helper function to get the level reached with some XP
+    pub fn level(&self, xp: u64) -> Level {
+        let mut level = 1;
+        while level < MAX_LEVEL && self.curve.threshold(level + 1) <= xp {
+            level += 1;
+        }
+        let start = self.curve.threshold(level);
+        let next = if level < MAX_LEVEL {
+            self.curve.threshold(level + 1)
+        } else {
+            start
+        };
+        Level {
+            level,
+            xp,
+            start,
+            next,
+        }
+    }
+}
+
+/// Implementation of Level with the progress toward the next level
+impl Level {
+    /// Helper function to get the share of the XP toward the next level earned so far, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.next <= self.start {
+            1.0
+        } else {
+            (self.xp - self.start) as f32 / (self.next - self.start) as f32
+        }
+    }
+}
+
+/// Helper function to get the standard configuration, read from `levels.json`
+pub fn standard_config() -> &'static LevelConfig {
+    static CONFIG: OnceLock<LevelConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        parse_config(include_str!("levels.json"))
+            .expect("the standard level configuration is valid")
+    })
+}
+
+/// Helper function to read a level configuration from JSON
+///
+/// # Arguments
+///
+/// * `json` - A level configuration, in the format of `levels.json`
+///
+/// # Returns
+///
+/// The configuration, or an error if the JSON is invalid or the curve doesn't grow.
+pub fn parse_config(json: &str) -> Result<LevelConfig, LevelConfigError> {
+    let config: LevelConfig =
+        serde_json::from_str(json).map_err(|err| LevelConfigError::Json(err.to_string()))?;
+    config.curve.validate()?;
+    if config.xp.task_points < 0.0 || config.xp.bonuses < 0.0 {
+        return Err(LevelConfigError::Invalid("XP weights can't be negative"));
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::gamification::PriorityLevel;
+    use crate::store::{NewTask, TaskStore};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_curves() {
+        let linear = Curve::Linear { per_level: 100 };
+        assert_eq!(
+            (1..=4)
+                .map(|level| linear.threshold(level))
+                .collect::<Vec<_>>(),
+            [0, 100, 200, 300]
+        );
+        let polynomial = Curve::Polynomial {
+            base: 100,
+            exponent: 1.5,
+        };
+        assert_eq!(
+            (1..=5)
+                .map(|level| polynomial.threshold(level))
+                .collect::<Vec<_>>(),
+            [0, 100, 283, 520, 800]
+        );
+        let exponential = Curve::Exponential {
+            base: 100,
+            growth: 2.0,
+        };
+        assert_eq!(
+            (1..=5)
+                .map(|level| exponential.threshold(level))
+                .collect::<Vec<_>>(),
+            [0, 100, 300, 700, 1500]
+        );
+        assert_eq!(
+            Curve::Exponential {
+                base: 50,
+                growth: 1.0
+            }
+            .threshold(3),
+            100
+        );
+        assert_eq!(standard_config().curve, polynomial);
+    }
+
+    #[test]
+    fn test_levels() {
+        let config = LevelConfig {
+            curve: Curve::Linear { per_level: 100 },
+            xp: XpWeights {
+                task_points: 1.0,
+                bonuses: 0.5,
+                streak_day: 5,
+            },
+        };
+        assert_eq!(
+            config.level(0),
+            Level {
+                level: 1,
+                xp: 0,
+                start: 0,
+                next: 100
+            }
+        );
+        assert_eq!(config.level(99).level, 1);
+        assert_eq!(config.level(100).level, 2);
+        assert_eq!(config.level(250).progress(), 0.5);
+        assert_eq!(config.level(u64::MAX).level, MAX_LEVEL);
+        assert_eq!(config.level(u64::MAX).progress(), 1.0);
+
+        let now = Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap()).now();
+        let mut ledger = Ledger::default();
+        ledger.award("task #1", TransactionKind::Completion, 30, "Completed", now);
+        ledger.award(
+            "daily 2024-11-20",
+            TransactionKind::DailyReward,
+            25,
+            "5 tasks",
+            now,
+        );
+        ledger.spend(
+            TransactionKind::StreakFreeze,
+            50,
+            "Bought a streak freeze",
+            now,
+        );
+        assert_eq!(config.xp(&ledger, &Streak::default()), 30 + 13); // half of the 25 points, rounded
+
+        // every day of the longest streak earns XP too
+        let mut store = TaskStore::new();
+        let mut clock = Clock::fixed_on(now.date_naive());
+        for name in ["Plan", "Write", "Review"] {
+            store.set_clock(clock);
+            let id = store
+                .create(NewTask {
+                    name: name.to_string(),
+                    description: String::new(),
+                    due_date: None,
+                    priority: PriorityLevel::Low,
+                    recurrence: None,
+                })
+                .unwrap();
+            store.complete(id).unwrap();
+            clock.advance(chrono::Duration::days(1));
+        }
+        let streak = Streak::track(store.tasks().as_slice(), &[], &[], clock.today());
+        assert_eq!(config.xp(&ledger, &streak), 30 + 13 + 3 * 5);
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let json = r#"{"curve": {"type": "linear", "per_level": 0}, "xp": {"task_points": 1, "bonuses": 1, "streak_day": 0}}"#;
+        assert!(matches!(
+            parse_config(json),
+            Err(LevelConfigError::Invalid(_))
+        ));
+        assert!(matches!(parse_config("[]"), Err(LevelConfigError::Json(_))));
+        let json = json.replace("\"per_level\": 0", "\"per_level\": 250");
+        assert_eq!(
+            parse_config(&json).unwrap().curve,
+            Curve::Linear { per_level: 250 }
+        );
+    }
+}
@@ -1,5 +1,28 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+pub mod achievements;
 pub mod app;
+pub mod clock;
+pub mod csv_transfer;
+pub mod dates;
 pub mod gamification;
-pub use app::TemplateApp;
\ No newline at end of file
+pub mod history;
+pub mod ical;
+pub mod journal;
+pub mod ledger;
+pub mod levels;
+pub mod query;
+pub mod query_language;
+pub mod recurrence;
+pub mod report;
+pub mod scoring;
+pub mod storage;
+pub mod store;
+pub mod streaks;
+pub mod tags;
+pub mod task_list;
+pub mod todotxt;
+pub mod transfer;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+pub use app::TemplateApp;
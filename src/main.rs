@@ -7,7 +7,7 @@ fn main() -> eframe::Result<()> {
     };
 
     eframe::run_native(
-        "TaskHero",
+        task_hero::storage::APP_ID,
         native_options,
         Box::new(|cc| Ok(Box::new(task_hero::TemplateApp::new(cc)))),
     )
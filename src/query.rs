@@ -0,0 +1,577 @@
+//! Searching, filtering and sorting tasks.
+//!
+//! A [`TaskQuery`] combines free text, filters and a sort order. The GUI query bar, the CLI
+//! `list` and `report` commands and saved filters all use it, so a query lists the same tasks
+//! wherever it is run. The text is written in the [query language](crate::query_language),
+//! so it can be a plain search or an expression such as `priority>=medium and due<+3d`.
+
+use std::cmp::{Ordering, Reverse};
+
+use chrono::NaiveDate;
+
+use crate::gamification::{PriorityLevel, Task};
+use crate::query_language::{Expr, QueryError};
+use crate::scoring::ScoringModel;
+use crate::store::TaskStore;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+/// Which tasks a query lists by their state
+pub enum Status {
+    #[default]
+    All,
+    Open, // not completed
+    Completed,
+    Ready,   // not completed and not waiting for another task
+    Blocked, // not completed and waiting for another task
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+/// How a query orders the tasks it lists
+pub enum SortKey {
+    #[default]
+    Tree, // the task list order, with subtasks under their parent
+    DueDate,  // soonest first, tasks without a due date last
+    Priority, // highest first
+    Created,  // oldest first
+    Points,   // most points first
+}
+
+/// Implementation of Status with its name, for menus and the command line
+impl Status {
+    /// Every status, in menu order
+    pub const ALL: [Status; 5] = [
+        Status::All,
+        Status::Open,
+        Status::Completed,
+        Status::Ready,
+        Status::Blocked,
+    ];
+
+    /// Helper function to get the name of the status
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::All => "all",
+            Status::Open => "open",
+            Status::Completed => "completed",
+            Status::Ready => "ready",
+            Status::Blocked => "blocked",
+        }
+    }
+
+    /// Helper function to check whether a task is in the state
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The store holding the task, to tell whether it waits for other tasks
+    /// * `task` - The task to check
+    pub fn matches(self, store: &TaskStore, task: &Task) -> bool {
+        match self {
+            Status::All => true,
+            Status::Open => !task.completed,
+            Status::Completed => task.completed,
+            Status::Ready => store.is_ready(task.id),
+            Status::Blocked => !task.completed && store.is_blocked(task.id),
+        }
+    }
+}
+
+impl std::str::FromStr for Status {
+    type Err = String;
+
+    /// Helper function to parse a status by its name in any case
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        Status::ALL
+            .into_iter()
+            .find(|status| status.name() == input)
+            .ok_or_else(|| {
+                format!("unknown status {input:?}, expected all, open, completed, ready or blocked")
+            })
+    }
+}
+
+/// Implementation of SortKey with its name, for menus and the command line
+impl SortKey {
+    /// Every sort key, in menu order
+    pub const ALL: [SortKey; 5] = [
+        SortKey::Tree,
+        SortKey::DueDate,
+        SortKey::Priority,
+        SortKey::Created,
+        SortKey::Points,
+    ];
+
+    /// Helper function to get the name of the sort key
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Tree => "tree",
+            SortKey::DueDate => "due",
+            SortKey::Priority => "priority",
+            SortKey::Created => "created",
+            SortKey::Points => "points",
+        }
+    }
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    /// Helper function to parse a sort key by its name in any case
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        SortKey::ALL
+            .into_iter()
+            .find(|key| key.name() == input)
+            .ok_or_else(|| {
+                format!(
+                    "unknown sort key {input:?}, expected tree, due, priority, created or points"
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+/// TaskQuery struct with the text, filters and sort order that choose the tasks to list.
+/// The default query lists every task in the task list order.
+pub struct TaskQuery {
+    pub text: String, // a query language expression; text it can't parse is matched as a plain search
+    pub status: Status,
+    pub priority: Option<PriorityLevel>,
+    pub tag: Option<String>,
+    pub project: Option<String>,
+    pub due_before: Option<NaiveDate>, // due on an earlier day; tasks without a due date don't match
+    pub due_after: Option<NaiveDate>,  // due on a later day; tasks without a due date don't match
+    pub overdue: bool,                 // not completed and due before today
+    pub sort: SortKey,
+    pub reverse: bool, // reverse the sort order
+}
+
+/// Implementation of TaskQuery with methods to match and list tasks
+impl TaskQuery {
+    /// Helper function to check whether the query filters out any task, as opposed to only sorting them
+    pub fn is_filtering(&self) -> bool {
+        let sorting = TaskQuery {
+            sort: self.sort,
+            reverse: self.reverse,
+            ..TaskQuery::default()
+        };
+        *self != sorting
+    }
+
+    /// Helper function to parse the text of the query as an expression
+    ///
+    /// # Arguments
+    ///
+    /// * `today` - The day relative dates such as `+3d` count from
+    ///
+    /// # Returns
+    ///
+    /// The expression, None for an empty text, or the error that makes the text a plain search.
+    pub fn expression(&self, today: NaiveDate) -> Result<Option<Expr>, QueryError> {
+        Expr::parse(&self.text, today)
+    }
+
+    /// Helper function to check whether a task matches the text and filters of the query
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The store holding the task, to tell whether it waits for other tasks
+    /// * `task` - The task to check
+    /// * `today` - The current date, for the overdue filter and relative dates
+    pub fn matches(&self, store: &TaskStore, task: &Task, today: NaiveDate) -> bool {
+        self.matches_expression(&self.expression(today), store, task, today)
+    }
+
+    /// Helper function to check whether a task matches the query, with its text already parsed
+    fn matches_expression(
+        &self,
+        expression: &Result<Option<Expr>, QueryError>,
+        store: &TaskStore,
+        task: &Task,
+        today: NaiveDate,
+    ) -> bool {
+        let text_matches = match expression {
+            Ok(Some(expr)) => expr.matches(store, task, today),
+            Ok(None) => true,
+            Err(_) => {
+                let text = self.text.trim().to_lowercase();
+                task.name.to_lowercase().contains(&text)
+                    || task.description.to_lowercase().contains(&text)
+            }
+        };
+        if !text_matches {
+            return false;
+        }
+        let due_date = task.due_date.map(|due_date| due_date.local_date());
+        self.status.matches(store, task)
+            && self
+                .priority
+                .map_or(true, |priority| task.priority == priority)
+            && self.tag.as_ref().map_or(true, |tag| {
+                let tag = tag.trim().trim_start_matches('#');
+                task.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+            })
+            && self.project.as_ref().map_or(true, |project| {
+                task.project.as_deref() == Some(project.trim())
+            })
+            && self
+                .due_before
+                .map_or(true, |before| due_date.is_some_and(|due| due < before))
+            && self
+                .due_after
+                .map_or(true, |after| due_date.is_some_and(|due| due > after))
+            && (!self.overdue || (!task.completed && due_date.is_some_and(|due| due < today)))
+    }
+
+    /// Helper function to list the tasks that match the query, in its sort order
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The store holding the tasks
+    /// * `today` - The current date, for the overdue filter
+    ///
+    /// # Returns
+    ///
+    /// Each task with its depth under other tasks. Only the tree order, not reversed, nests
+    /// subtasks, so every other order lists the tasks at depth 0.
+    pub fn run<'a>(&self, store: &'a TaskStore, today: NaiveDate) -> Vec<(usize, &'a Task)> {
+        let expression = self.expression(today);
+        let mut tasks: Vec<(usize, &Task)> = store
+            .tasks()
+            .tree()
+            .into_iter()
+            .filter(|(_, task)| self.matches_expression(&expression, store, task, today))
+            .collect();
+        if self.sort != SortKey::Tree {
+            tasks.sort_by(|(_, a), (_, b)| self.compare(&store.gamification().scoring, a, b));
+        }
+        if self.sort != SortKey::Tree || self.reverse {
+            for (depth, _) in &mut tasks {
+                *depth = 0;
+            }
+        }
+        if self.reverse {
+            tasks.reverse();
+        }
+        tasks
+    }
+
+    /// Helper function to list the tasks that match the query, without their depth
+    pub fn tasks<'a>(&self, store: &'a TaskStore, today: NaiveDate) -> Vec<&'a Task> {
+        self.run(store, today)
+            .into_iter()
+            .map(|(_, task)| task)
+            .collect()
+    }
+
+    /// Helper function to order two tasks by the sort key, breaking ties by creation order;
+    /// points are worked out by the scoring model
+    fn compare(&self, scoring: &ScoringModel, a: &Task, b: &Task) -> Ordering {
+        let by_key = match self.sort {
+            SortKey::Tree | SortKey::Created => Ordering::Equal,
+            // Tasks without a due date come after every due date
+            SortKey::DueDate => {
+                let due = |task: &Task| {
+                    (
+                        task.due_date.is_none(),
+                        task.due_date.map(|due_date| due_date.to_local()),
+                    )
+                };
+                due(a).cmp(&due(b))
+            }
+            SortKey::Priority => Reverse(a.priority as u8).cmp(&Reverse(b.priority as u8)),
+            SortKey::Points => {
+                Reverse(a.score_with(scoring).points).cmp(&Reverse(b.score_with(scoring).points))
+            }
+        };
+        // IDs are assigned in the order the tasks were created
+        by_key.then(a.id.cmp(&b.id))
+    }
+}
+
+impl std::fmt::Display for TaskQuery {
+    /// Helper function to describe the query in words, such as
+    /// `"report", open, High priority, sorted by due`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if !self.text.trim().is_empty() {
+            parts.push(format!("{:?}", self.text.trim()));
+        }
+        if self.status != Status::All {
+            parts.push(self.status.name().to_string());
+        }
+        if let Some(priority) = self.priority {
+            parts.push(format!("{priority:?} priority"));
+        }
+        if let Some(tag) = &self.tag {
+            parts.push(format!("#{}", tag.trim_start_matches('#')));
+        }
+        if let Some(project) = &self.project {
+            parts.push(format!("project {project}"));
+        }
+        if let Some(after) = self.due_after {
+            parts.push(format!("due after {after}"));
+        }
+        if let Some(before) = self.due_before {
+            parts.push(format!("due before {before}"));
+        }
+        if self.overdue {
+            parts.push("overdue".to_string());
+        }
+        if parts.is_empty() {
+            parts.push("every task".to_string());
+        }
+        if self.sort != SortKey::Tree || self.reverse {
+            parts.push(format!(
+                "sorted by {}{}",
+                self.sort.name(),
+                if self.reverse { ", reversed" } else { "" }
+            ));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::parse_due_date;
+    use crate::store::{NewTask, TaskUpdate};
+    use crate::task_list::TaskId;
+
+    fn store() -> TaskStore {
+        let mut store = TaskStore::new();
+        for (name, description, due_date, priority) in [
+            (
+                "Write report",
+                "Quarterly numbers",
+                "2024-11-18",
+                PriorityLevel::Medium,
+            ),
+            (
+                "Email team",
+                "About the report",
+                "2024-11-25",
+                PriorityLevel::High,
+            ),
+            ("Plan sprint", "", "", PriorityLevel::Low),
+            ("Fix login", "", "2024-11-22", PriorityLevel::High),
+        ] {
+            store
+                .create(NewTask {
+                    name: name.to_string(),
+                    description: description.to_string(),
+                    due_date: parse_due_date(due_date, store.clock().today()).ok(),
+                    priority,
+                    recurrence: None,
+                })
+                .unwrap();
+        }
+        store
+            .update(
+                TaskId(4),
+                TaskUpdate {
+                    tags: Some(vec!["backend".to_string()]),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.add_dependency(TaskId(3), TaskId(2)).unwrap();
+        store.complete(TaskId(1)).unwrap();
+        store
+    }
+
+    fn ids(query: &TaskQuery, store: &TaskStore) -> Vec<u64> {
+        query
+            .tasks(store, NaiveDate::from_ymd_opt(2024, 11, 20).unwrap())
+            .iter()
+            .map(|task| task.id.0)
+            .collect()
+    }
+
+    #[test]
+    fn test_text_and_filters() {
+        let store = store();
+        assert_eq!(ids(&TaskQuery::default(), &store), [1, 2, 3, 4]);
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    text: "REPORT".to_string(),
+                    ..Default::default()
+                },
+                &store
+            ),
+            [1, 2]
+        );
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    status: Status::Open,
+                    ..Default::default()
+                },
+                &store
+            ),
+            [2, 3, 4]
+        );
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    status: Status::Ready,
+                    ..Default::default()
+                },
+                &store
+            ),
+            [2, 4]
+        );
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    status: Status::Blocked,
+                    ..Default::default()
+                },
+                &store
+            ),
+            [3]
+        );
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    priority: Some(PriorityLevel::High),
+                    ..Default::default()
+                },
+                &store
+            ),
+            [2, 4]
+        );
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    tag: Some("#Backend".to_string()),
+                    ..Default::default()
+                },
+                &store
+            ),
+            [4]
+        );
+
+        let before = NaiveDate::from_ymd_opt(2024, 11, 22);
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    due_before: before,
+                    ..Default::default()
+                },
+                &store
+            ),
+            [1]
+        );
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    due_after: before,
+                    ..Default::default()
+                },
+                &store
+            ),
+            [2]
+        );
+        // the completed report was due before today but is not overdue
+        assert!(ids(
+            &TaskQuery {
+                overdue: true,
+                ..Default::default()
+            },
+            &store
+        )
+        .is_empty());
+        assert!(TaskQuery {
+            overdue: true,
+            ..Default::default()
+        }
+        .is_filtering());
+        assert!(!TaskQuery {
+            sort: SortKey::Points,
+            reverse: true,
+            ..Default::default()
+        }
+        .is_filtering());
+    }
+
+    #[test]
+    fn test_expressions_in_the_text() {
+        let store = store();
+        let text = |text: &str| TaskQuery {
+            text: text.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(
+                &text("priority>=medium and due<+3d and not completed"),
+                &store
+            ),
+            [4]
+        );
+        assert_eq!(ids(&text("tag:backend or blocked"), &store), [3, 4]);
+        // filters still apply on top of the expression
+        assert_eq!(
+            ids(
+                &TaskQuery {
+                    status: Status::Open,
+                    ..text("report")
+                },
+                &store
+            ),
+            [2]
+        );
+
+        // text that isn't a valid expression is searched for as it is
+        let plain = text("numbers (Q4");
+        assert!(plain
+            .expression(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap())
+            .is_err());
+        assert!(ids(&plain, &store).is_empty());
+        assert_eq!(ids(&text("quarterly num"), &store), [1]);
+        assert!(ids(&text("report:"), &store).is_empty());
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let store = store();
+        let sorted = |sort, reverse| {
+            ids(
+                &TaskQuery {
+                    sort,
+                    reverse,
+                    ..Default::default()
+                },
+                &store,
+            )
+        };
+        assert_eq!(sorted(SortKey::DueDate, false), [1, 4, 2, 3]);
+        assert_eq!(sorted(SortKey::Priority, false), [2, 4, 1, 3]);
+        assert_eq!(sorted(SortKey::Created, true), [4, 3, 2, 1]);
+        assert_eq!(sorted(SortKey::Points, false), [2, 4, 1, 3]);
+        assert_eq!("Due".parse(), Ok(SortKey::DueDate));
+        assert!("size".parse::<SortKey>().is_err());
+        assert_eq!(" READY ".parse(), Ok(Status::Ready));
+    }
+
+    #[test]
+    fn test_description() {
+        assert_eq!(TaskQuery::default().to_string(), "every task");
+        let query = TaskQuery {
+            text: " report ".to_string(),
+            status: Status::Open,
+            priority: Some(PriorityLevel::High),
+            tag: Some("backend".to_string()),
+            sort: SortKey::DueDate,
+            reverse: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            query.to_string(),
+            "\"report\", open, High priority, #backend, sorted by due, reversed"
+        );
+    }
+}
@@ -0,0 +1,695 @@
+//! A small query language for tasks.
+//!
+//! ```text
+//! priority>=medium and due<+3d and not completed and tag:backend
+//! (project:website or tag:urgent) and not blocked
+//! "weekly report" points>20
+//! ```
+//!
+//! - `field:value` and `field OP value` compare a task field, where OP is one of
+//!   `=`, `!=`, `<`, `<=`, `>` and `>=`. `:` means `=`.
+//! - `priority` takes low, medium or high (or 1-3), `points` a number, and `due` a date:
+//!   `today`, `tomorrow`, `yesterday`, a relative date such as `+3d`, `-1w` or `+2m` (days,
+//!   weeks, months), or a date such as `2024-11-23`. `due:none` matches tasks without a due
+//!   date; every other `due` comparison skips them.
+//! - `tag`, `project` and `status` (all, open, completed, ready, blocked) take a name, and
+//!   `name`, `description` and `text` match tasks whose name, description or either
+//!   contains the value. These fields only support `:`, `=` and `!=`.
+//! - `completed` (or `done`), `open`, `ready`, `blocked`, `overdue` and `repeating` on their
+//!   own match tasks in that state.
+//! - Any other word, or text in double quotes, matches tasks whose name or description
+//!   contains it, so plain searches work too.
+//! - Terms next to each other must all match, as with `and`. `or`, `not` and parentheses
+//!   combine them, with `not` binding tightest and `or` loosest.
+//!
+//! Relative dates are resolved against the day the query is run, so a saved query such as
+//! `due<+3d` always looks three days ahead.
+
+use chrono::{Duration, Months, NaiveDate};
+
+use crate::dates::parse_due_date;
+use crate::gamification::{PriorityLevel, Task};
+use crate::query::Status;
+use crate::store::TaskStore;
+
+/// A short summary of the query language, for the search bar and the command line help
+pub const QUERY_HINT: &str = "Words search task names and descriptions. Expressions such as \
+    `priority>=medium and due<+3d and not completed and tag:backend` filter by \
+    priority, due, points, tag, project, status, name or description, and \
+    completed, open, ready, blocked, overdue and repeating match task states. \
+    Combine terms with and, or, not and parentheses.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An error in a query, with the column (counting characters from 1) where it was found
+pub struct QueryError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at column {})", self.message, self.column)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The comparison of a `field OP value` term
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// Implementation of Comparison with a method to apply it to two values
+impl Comparison {
+    /// Helper function to compare a task's value with the value in the query
+    fn holds<T: Ord>(self, value: T, wanted: T) -> bool {
+        match self {
+            Comparison::Equal => value == wanted,
+            Comparison::NotEqual => value != wanted,
+            Comparison::Less => value < wanted,
+            Comparison::LessOrEqual => value <= wanted,
+            Comparison::Greater => value > wanted,
+            Comparison::GreaterOrEqual => value >= wanted,
+        }
+    }
+
+    /// Helper function to get the operator as written in a query
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A parsed query
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Text(String),        // name or description contains, without regard to case
+    Name(String),        // name contains
+    Description(String), // description contains
+    Tag(String),
+    Project(String),
+    Status(Status),
+    Priority(Comparison, PriorityLevel),
+    Points(Comparison, u32),
+    Due(Comparison, NaiveDate),
+    NoDueDate,
+    Overdue,
+    Repeating,
+}
+
+/// Implementation of Expr with the parser and the evaluator
+impl Expr {
+    /// Helper function to parse a query
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The query text
+    /// * `today` - The day relative dates such as `+3d` count from
+    ///
+    /// # Returns
+    ///
+    /// The query, None for an empty query, or the first error in it.
+    pub fn parse(input: &str, today: NaiveDate) -> Result<Option<Expr>, QueryError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end: input.chars().count() + 1,
+            today,
+        };
+        let expr = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(Some(expr)),
+            Some(token) if token.kind == TokenKind::Close => {
+                Err(token.error("unexpected `)` without a matching `(`"))
+            }
+            Some(token) => Err(token.error(format!("unexpected {}", token.describe()))),
+        }
+    }
+
+    /// Helper function to check whether a task matches the query
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The store holding the task, to tell whether it waits for other tasks
+    /// * `task` - The task to check
+    /// * `today` - The current date, for `overdue`
+    pub fn matches(&self, store: &TaskStore, task: &Task, today: NaiveDate) -> bool {
+        let contains =
+            |text: &str, wanted: &str| text.to_lowercase().contains(&wanted.to_lowercase());
+        let due_date = task.due_date.map(|due_date| due_date.local_date());
+        match self {
+            Expr::And(a, b) => a.matches(store, task, today) && b.matches(store, task, today),
+            Expr::Or(a, b) => a.matches(store, task, today) || b.matches(store, task, today),
+            Expr::Not(expr) => !expr.matches(store, task, today),
+            Expr::Text(text) => contains(&task.name, text) || contains(&task.description, text),
+            Expr::Name(text) => contains(&task.name, text),
+            Expr::Description(text) => contains(&task.description, text),
+            Expr::Tag(tag) => task.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)),
+            Expr::Project(project) => task
+                .project
+                .as_ref()
+                .is_some_and(|own| own.eq_ignore_ascii_case(project)),
+            Expr::Status(status) => status.matches(store, task),
+            Expr::Priority(comparison, priority) => {
+                comparison.holds(task.priority as u8, *priority as u8)
+            }
+            Expr::Points(comparison, points) => comparison.holds(
+                task.score_with(&store.gamification().scoring).points,
+                *points,
+            ),
+            Expr::Due(comparison, date) => due_date.is_some_and(|due| comparison.holds(due, *date)),
+            Expr::NoDueDate => task.due_date.is_none(),
+            Expr::Overdue => !task.completed && due_date.is_some_and(|due| due < today),
+            Expr::Repeating => task.recurrence.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The kinds of token a query is made of
+enum TokenKind {
+    Word(String),
+    Quoted(String),
+    Operator(Comparison),
+    Colon,
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+/// A token of a query and the column it starts at
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+/// Implementation of Token with helpers for error messages
+impl Token {
+    /// Helper function to create an error pointing at the token
+    fn error(&self, message: impl Into<String>) -> QueryError {
+        QueryError {
+            column: self.column,
+            message: message.into(),
+        }
+    }
+
+    /// Helper function to describe the token in an error message
+    fn describe(&self) -> String {
+        match &self.kind {
+            TokenKind::Word(word) => format!("`{word}`"),
+            TokenKind::Quoted(text) => format!("\"{text}\""),
+            TokenKind::Operator(comparison) => format!("`{}`", comparison.symbol()),
+            TokenKind::Colon => "`:`".to_string(),
+            TokenKind::Open => "`(`".to_string(),
+            TokenKind::Close => "`)`".to_string(),
+        }
+    }
+
+    /// Helper function to check whether the token is a keyword such as `and`, in any case
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.kind, TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+/// Helper function to split a query into tokens
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let column = index + 1;
+        let c = chars[index];
+        let next = chars.get(index + 1).copied();
+        let (kind, length) = match c {
+            c if c.is_whitespace() => {
+                index += 1;
+                continue;
+            }
+            '(' => (TokenKind::Open, 1),
+            ')' => (TokenKind::Close, 1),
+            ':' => (TokenKind::Colon, 1),
+            '=' => (TokenKind::Operator(Comparison::Equal), 1),
+            '!' if next == Some('=') => (TokenKind::Operator(Comparison::NotEqual), 2),
+            '!' => {
+                return Err(QueryError {
+                    column,
+                    message: "expected `!=`; use `not` to negate a term".to_string(),
+                })
+            }
+            '<' if next == Some('=') => (TokenKind::Operator(Comparison::LessOrEqual), 2),
+            '<' => (TokenKind::Operator(Comparison::Less), 1),
+            '>' if next == Some('=') => (TokenKind::Operator(Comparison::GreaterOrEqual), 2),
+            '>' => (TokenKind::Operator(Comparison::Greater), 1),
+            '"' => {
+                let length =
+                    chars[index + 1..]
+                        .iter()
+                        .position(|&c| c == '"')
+                        .ok_or(QueryError {
+                            column,
+                            message: "missing the closing `\"`".to_string(),
+                        })?;
+                let text: String = chars[index + 1..index + 1 + length].iter().collect();
+                (TokenKind::Quoted(text), length + 2)
+            }
+            _ => {
+                let length = chars[index..]
+                    .iter()
+                    .position(|&c| c.is_whitespace() || "():=!<>\"".contains(c))
+                    .unwrap_or(chars.len() - index);
+                (
+                    TokenKind::Word(chars[index..index + length].iter().collect()),
+                    length,
+                )
+            }
+        };
+        tokens.push(Token { kind, column });
+        index += length;
+    }
+    Ok(tokens)
+}
+
+/// Fields that can be compared, as listed in error messages
+const FIELDS: &str = "priority, due, points, tag, project, status, name, description or text";
+
+/// Recursive descent parser over the tokens of a query
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    end: usize, // column just after the query, for errors at its end
+    today: NaiveDate,
+}
+
+/// Implementation of Parser with a method for each level of precedence
+impl Parser {
+    /// Helper function to look at the next token without taking it
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// Helper function to take the next token
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Helper function to take the next token if it is a keyword
+    fn take_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|token| token.is_keyword(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    /// Helper function to create an error at the end of the query
+    fn error_at_end(&self, message: impl Into<String>) -> QueryError {
+        QueryError {
+            column: self.end,
+            message: message.into(),
+        }
+    }
+
+    /// Helper function to parse terms joined by `or`
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_and()?;
+        while self.take_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    /// Helper function to parse terms joined by `and`, or written next to each other
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_not()?;
+        while self.take_keyword("and")
+            || self
+                .peek()
+                .is_some_and(|token| token.kind != TokenKind::Close && !token.is_keyword("or"))
+        {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    /// Helper function to parse a term with any number of `not`s in front of it
+    fn parse_not(&mut self) -> Result<Expr, QueryError> {
+        if self.take_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_term()
+    }
+
+    /// Helper function to parse a single term: a comparison, a state, a search word or a
+    /// query in parentheses
+    fn parse_term(&mut self) -> Result<Expr, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(self.error_at_end("the query ends where a term was expected"));
+        };
+        match token.kind.clone() {
+            TokenKind::Open => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::Close,
+                        ..
+                    }) => Ok(expr),
+                    Some(other) => {
+                        Err(other.error(format!("expected `)` but found {}", other.describe())))
+                    }
+                    None => Err(token.error("this `(` is never closed")),
+                }
+            }
+            TokenKind::Quoted(text) => Ok(Expr::Text(text)),
+            TokenKind::Word(word) => {
+                let comparison = match self.peek().map(|next| &next.kind) {
+                    Some(TokenKind::Colon) => Some(Comparison::Equal),
+                    Some(TokenKind::Operator(comparison)) => Some(*comparison),
+                    _ => None,
+                };
+                match comparison {
+                    Some(comparison) => {
+                        let operator = self.next().expect("peeked");
+                        self.parse_comparison(&token, &word, &operator, comparison)
+                    }
+                    None if ["and", "or", "not"]
+                        .iter()
+                        .any(|keyword| word.eq_ignore_ascii_case(keyword)) =>
+                    {
+                        Err(token.error(format!("`{word}` needs a term on both sides")))
+                    }
+                    None => Ok(match word.to_lowercase().as_str() {
+                        "completed" | "done" => Expr::Status(Status::Completed),
+                        "open" => Expr::Status(Status::Open),
+                        "ready" => Expr::Status(Status::Ready),
+                        "blocked" => Expr::Status(Status::Blocked),
+                        "overdue" => Expr::Overdue,
+                        "repeating" => Expr::Repeating,
+                        _ => Expr::Text(word),
+                    }),
+                }
+            }
+            TokenKind::Close => Err(token.error("unexpected `)` without a matching `(`")),
+            TokenKind::Operator(_) | TokenKind::Colon => Err(token.error(format!(
+                "{} needs a field in front of it, such as priority or due",
+                token.describe()
+            ))),
+        }
+    }
+
+    /// Helper function to parse the value of a `field OP value` term
+    fn parse_comparison(
+        &mut self,
+        field: &Token,
+        name: &str,
+        operator: &Token,
+        comparison: Comparison,
+    ) -> Result<Expr, QueryError> {
+        let (value, value_token) = match self.next() {
+            Some(
+                token @ Token {
+                    kind: TokenKind::Word(_) | TokenKind::Quoted(_),
+                    ..
+                },
+            ) => {
+                let (TokenKind::Word(value) | TokenKind::Quoted(value)) = &token.kind else {
+                    unreachable!()
+                };
+                (value.clone(), token)
+            }
+            Some(other) => {
+                return Err(other.error(format!(
+                    "expected a value after `{name}{}` but found {}",
+                    operator.describe().trim_matches('`'),
+                    other.describe()
+                )))
+            }
+            None => {
+                return Err(self.error_at_end(format!(
+                    "expected a value after `{name}{}`",
+                    operator.describe().trim_matches('`')
+                )))
+            }
+        };
+
+        let text_only = |expr: fn(String) -> Expr| match comparison {
+            Comparison::Equal => Ok(expr(value.clone())),
+            Comparison::NotEqual => Ok(Expr::Not(Box::new(expr(value.clone())))),
+            _ => Err(operator.error(format!(
+                "`{name}` can't be compared with `{}`, only with `:`, `=` or `!=`",
+                comparison.symbol()
+            ))),
+        };
+        match name.to_lowercase().as_str() {
+            "priority" => {
+                let priority: PriorityLevel = value.parse().map_err(|_| {
+                    value_token.error(format!(
+                        "unknown priority `{value}`, expected low, medium or high"
+                    ))
+                })?;
+                Ok(Expr::Priority(comparison, priority))
+            }
+            "points" => {
+                let points = value.parse().map_err(|_| {
+                    value_token.error(format!("`{value}` is not a number of points"))
+                })?;
+                Ok(Expr::Points(comparison, points))
+            }
+            "due" if value.eq_ignore_ascii_case("none") => match comparison {
+                Comparison::Equal => Ok(Expr::NoDueDate),
+                Comparison::NotEqual => Ok(Expr::Not(Box::new(Expr::NoDueDate))),
+                _ => Err(operator.error("`due:none` can only be compared with `:`, `=` or `!=`")),
+            },
+            "due" => {
+                let date = parse_query_date(&value, self.today).ok_or_else(|| {
+                    value_token.error(format!("unknown date `{value}`, expected today, tomorrow, +3d, -1w, +2m or a date such as 2024-11-23"))
+                })?;
+                Ok(Expr::Due(comparison, date))
+            }
+            "status" => {
+                let status: Status = value
+                    .parse()
+                    .map_err(|err: String| value_token.error(err))?;
+                match comparison {
+                    Comparison::Equal => Ok(Expr::Status(status)),
+                    Comparison::NotEqual => Ok(Expr::Not(Box::new(Expr::Status(status)))),
+                    _ => Err(operator.error(format!(
+                        "`{name}` can't be compared with `{}`, only with `:`, `=` or `!=`",
+                        comparison.symbol()
+                    ))),
+                }
+            }
+            "tag" => text_only(|value| Expr::Tag(value.trim_start_matches('#').to_string())),
+            "project" => text_only(Expr::Project),
+            "name" => text_only(Expr::Name),
+            "description" | "desc" => text_only(Expr::Description),
+            "text" => text_only(Expr::Text),
+            _ => Err(field.error(format!("unknown field `{name}`, expected {FIELDS}"))),
+        }
+    }
+}
+
+/// Helper function to parse the date of a `due` comparison: a relative date such as `+3d`,
+/// `-1w` or `+2m`, or anything the due date parser understands
+fn parse_query_date(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Some(sign @ ('+' | '-')) = value.chars().next() {
+        let unit = value.chars().last()?;
+        let amount: u32 = value[1..value.len() - unit.len_utf8()].parse().ok()?;
+        let forward = sign == '+';
+        return match unit.to_ascii_lowercase() {
+            'd' | 'w' => {
+                let days = Duration::days(
+                    i64::from(amount)
+                        * if unit.eq_ignore_ascii_case(&'w') {
+                            7
+                        } else {
+                            1
+                        },
+                );
+                if forward {
+                    today.checked_add_signed(days)
+                } else {
+                    today.checked_sub_signed(days)
+                }
+            }
+            'm' if forward => today.checked_add_months(Months::new(amount)),
+            'm' => today.checked_sub_months(Months::new(amount)),
+            _ => None,
+        };
+    }
+    parse_due_date(value, today)
+        .ok()
+        .map(|due_date| due_date.local_date())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::parse_due_date;
+    use crate::store::{NewTask, TaskUpdate};
+    use crate::task_list::TaskId;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 11, 20).unwrap()
+    }
+
+    fn store() -> TaskStore {
+        let mut store = TaskStore::new();
+        for (name, due_date, priority, tags) in [
+            ("Write report", "2024-11-18", PriorityLevel::Medium, vec![]),
+            (
+                "Fix login",
+                "2024-11-22",
+                PriorityLevel::High,
+                vec!["backend"],
+            ),
+            ("Plan sprint", "", PriorityLevel::Low, vec!["backend"]),
+            ("Email team", "2024-12-01", PriorityLevel::High, vec![]),
+        ] {
+            let id = store
+                .create(NewTask {
+                    name: name.to_string(),
+                    description: String::new(),
+                    due_date: parse_due_date(due_date, store.clock().today()).ok(),
+                    priority,
+                    recurrence: None,
+                })
+                .unwrap();
+            let tags = tags.into_iter().map(str::to_string).collect();
+            store
+                .update(
+                    id,
+                    TaskUpdate {
+                        tags: Some(tags),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+        store.complete(TaskId(4)).unwrap();
+        store
+    }
+
+    fn ids(query: &str) -> Vec<u64> {
+        let store = store();
+        let expr = Expr::parse(query, today()).unwrap().unwrap();
+        store
+            .tasks()
+            .iter()
+            .filter(|task| expr.matches(&store, task, today()))
+            .map(|task| task.id.0)
+            .collect()
+    }
+
+    fn error(query: &str) -> String {
+        Expr::parse(query, today()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(
+            ids("priority>=medium and due<+3d and not completed and tag:backend"),
+            [2]
+        );
+        assert_eq!(
+            ids("priority>=medium and due<+3d and not completed"),
+            [1, 2]
+        );
+        assert_eq!(ids("tag:backend or overdue"), [1, 2, 3]);
+        assert_eq!(ids("(tag:backend or overdue) priority!=low"), [1, 2]);
+        assert_eq!(ids("not not done"), [4]);
+        assert_eq!(ids("due:none"), [3]);
+        assert_eq!(ids("due>=today due<=+2w"), [2, 4]);
+        assert_eq!(ids("due=2024-11-22"), [2]);
+        assert_eq!(ids("due<-1d"), [1]);
+        assert_eq!(ids("points>10 open"), [1, 2]);
+        assert_eq!(ids("REPORT"), [1]);
+        assert_eq!(ids("\"fix log\" or name:email"), [2, 4]);
+        assert_eq!(ids("status:ready tag!=backend"), [1]);
+        assert_eq!(Expr::parse("  ", today()), Ok(None));
+    }
+
+    #[test]
+    fn test_errors_point_at_the_problem() {
+        assert_eq!(error("priorty>=high"), "unknown field `priorty`, expected priority, due, points, tag, project, status, name, description or text (at column 1)");
+        assert_eq!(
+            error("priority>=urgent"),
+            "unknown priority `urgent`, expected low, medium or high (at column 11)"
+        );
+        assert_eq!(error("due<soon"), "unknown date `soon`, expected today, tomorrow, +3d, -1w, +2m or a date such as 2024-11-23 (at column 5)");
+        assert_eq!(
+            error("priority>="),
+            "expected a value after `priority>=` (at column 11)"
+        );
+        assert_eq!(
+            error("(tag:a or tag:b"),
+            "this `(` is never closed (at column 1)"
+        );
+        assert_eq!(
+            error("tag:a)"),
+            "unexpected `)` without a matching `(` (at column 6)"
+        );
+        assert_eq!(
+            error("tag<a"),
+            "`tag` can't be compared with `<`, only with `:`, `=` or `!=` (at column 4)"
+        );
+        assert_eq!(
+            error("open and"),
+            "the query ends where a term was expected (at column 9)"
+        );
+        assert_eq!(
+            error("!done"),
+            "expected `!=`; use `not` to negate a term (at column 1)"
+        );
+        assert_eq!(
+            error("name:\"unfinished"),
+            "missing the closing `\"` (at column 6)"
+        );
+        assert_eq!(
+            error(">= 3"),
+            "`>=` needs a field in front of it, such as priority or due (at column 1)"
+        );
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        assert_eq!(
+            parse_query_date("+3d", today()),
+            NaiveDate::from_ymd_opt(2024, 11, 23)
+        );
+        assert_eq!(
+            parse_query_date("-1w", today()),
+            NaiveDate::from_ymd_opt(2024, 11, 13)
+        );
+        assert_eq!(
+            parse_query_date("+2m", today()),
+            NaiveDate::from_ymd_opt(2025, 1, 20)
+        );
+        assert_eq!(
+            parse_query_date("tomorrow", today()),
+            NaiveDate::from_ymd_opt(2024, 11, 21)
+        );
+        assert_eq!(parse_query_date("+3x", today()), None);
+        assert_eq!(parse_query_date("+d", today()), None);
+    }
+}
@@ -0,0 +1,873 @@
+//! Recurrence rules for repeating tasks.
+//!
+//! A rule is a subset of the iCalendar RRULE (RFC 5545): `FREQ` (daily, weekly, monthly or
+//! yearly), `INTERVAL`, `BYDAY` (with an ordinal such as `2TU` or `-1FR` for monthly rules),
+//! `BYMONTHDAY`, `COUNT` and `UNTIL`. Rules can also be typed in words, such as `weekdays`,
+//! `every 2 weeks on monday, friday` or `monthly on the last friday`, which is how they are shown.
+//!
+//! When a repeating task is completed it stays completed, so it keeps earning its points and
+//! counting toward the daily reward and weekly challenge, and the next occurrence is added as a
+//! new task with the due date rolled forward. `COUNT` is the number of occurrences left,
+//! including the current one.
+
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+
+/// The most periods searched for the next occurrence, so a rule that can never occur again
+/// (such as the 31st of February) doesn't loop forever
+const MAX_PERIODS: u32 = 1000;
+
+/// Weekdays in RRULE order, with their two-letter RRULE codes
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "MO"),
+    (Weekday::Tue, "TU"),
+    (Weekday::Wed, "WE"),
+    (Weekday::Thu, "TH"),
+    (Weekday::Fri, "FR"),
+    (Weekday::Sat, "SA"),
+    (Weekday::Sun, "SU"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How often a rule repeats, before its interval is applied
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Recurrence struct with a rule for when a repeating task is due next
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32, // every N days, weeks, months or years
+    pub weekdays: Vec<(Option<i32>, Weekday)>, // BYDAY, with the week of the month for monthly rules (-1 is the last)
+    pub month_days: Vec<i32>, // BYMONTHDAY, negative days count from the end of the month
+    pub count: Option<u32>,   // occurrences left, including the current one
+    pub until: Option<NaiveDate>, // no occurrence is due after this date
+}
+
+/// Implementation of Recurrence with the common rules, RRULE conversion and the next occurrence
+impl Recurrence {
+    /// Helper function to create a rule repeating every `interval` periods of a frequency
+    pub fn every(interval: u32, frequency: Frequency) -> Self {
+        Recurrence {
+            frequency,
+            interval: interval.max(1),
+            weekdays: Vec::new(),
+            month_days: Vec::new(),
+            count: None,
+            until: None,
+        }
+    }
+
+    /// Helper function to create a rule repeating every day
+    pub fn daily() -> Self {
+        Self::every(1, Frequency::Daily)
+    }
+
+    /// Helper function to create a rule repeating Monday to Friday
+    pub fn weekdays() -> Self {
+        Recurrence {
+            weekdays: WEEKDAYS[..5]
+                .iter()
+                .map(|&(weekday, _)| (None, weekday))
+                .collect(),
+            ..Self::every(1, Frequency::Weekly)
+        }
+    }
+
+    /// Helper function to create a rule repeating on a day of every month, where -1 is the last day
+    pub fn monthly_on_day(day: i32) -> Self {
+        Recurrence {
+            month_days: vec![day],
+            ..Self::every(1, Frequency::Monthly)
+        }
+    }
+
+    /// Helper function to create a rule repeating on a weekday of every month, such as the
+    /// second Tuesday (`2`) or the last Friday (`-1`)
+    pub fn monthly_on_weekday(week: i32, weekday: Weekday) -> Self {
+        Recurrence {
+            weekdays: vec![(Some(week), weekday)],
+            ..Self::every(1, Frequency::Monthly)
+        }
+    }
+
+    /// Helper function to parse a repeat rule typed into a form, where an empty field means
+    /// the task doesn't repeat
+    pub fn parse_optional(input: &str) -> Result<Option<Self>, String> {
+        if input.trim().is_empty() {
+            Ok(None)
+        } else {
+            input.parse().map(Some)
+        }
+    }
+
+    /// Helper function to read an RRULE value such as `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR`,
+    /// with or without the `RRULE:` prefix
+    pub fn from_rrule(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let rule = input
+            .strip_prefix("RRULE:")
+            .or_else(|| input.strip_prefix("rrule:"))
+            .unwrap_or(input);
+        let mut frequency = None;
+        let mut recurrence = Self::daily();
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected KEY=VALUE, got {part:?}"))?;
+            let invalid = || format!("invalid {key} {value:?}");
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => {
+                            return Err(format!(
+                            "unsupported FREQ {value:?}, expected DAILY, WEEKLY, MONTHLY or YEARLY"
+                        ))
+                        }
+                    })
+                }
+                "INTERVAL" => {
+                    recurrence.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|&interval| interval > 0)
+                        .ok_or_else(invalid)?
+                }
+                "BYDAY" => {
+                    recurrence.weekdays = value
+                        .split(',')
+                        .map(parse_rrule_weekday)
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                "BYMONTHDAY" => {
+                    recurrence.month_days = value
+                        .split(',')
+                        .map(|day| {
+                            day.parse()
+                                .ok()
+                                .filter(|day: &i32| (1..=31).contains(&day.abs()))
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                "COUNT" => {
+                    recurrence.count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|&count| count > 0)
+                            .ok_or_else(invalid)?,
+                    )
+                }
+                "UNTIL" => {
+                    // only the date of an UNTIL time is kept, since tasks are due on days
+                    let date = value.get(..8).ok_or_else(invalid)?;
+                    recurrence.until =
+                        Some(NaiveDate::parse_from_str(date, "%Y%m%d").map_err(|_| invalid())?);
+                }
+                "WKST" if value.eq_ignore_ascii_case("MO") => {}
+                _ => return Err(format!("unsupported RRULE part {part:?}")),
+            }
+        }
+        recurrence.frequency = frequency.ok_or("the rule has no FREQ")?;
+        recurrence.check()?;
+        Ok(recurrence)
+    }
+
+    /// Helper function to write the rule as an RRULE value, without the `RRULE:` prefix
+    pub fn to_rrule(&self) -> String {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        let mut parts = vec![format!("FREQ={frequency}")];
+        if self.interval > 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+        if !self.weekdays.is_empty() {
+            let days: Vec<String> = self
+                .weekdays
+                .iter()
+                .map(|&(week, weekday)| {
+                    format!(
+                        "{}{}",
+                        week.map(|week| week.to_string()).unwrap_or_default(),
+                        weekday_code(weekday)
+                    )
+                })
+                .collect();
+            parts.push(format!("BYDAY={}", days.join(",")));
+        }
+        if !self.month_days.is_empty() {
+            let days: Vec<String> = self.month_days.iter().map(i32::to_string).collect();
+            parts.push(format!("BYMONTHDAY={}", days.join(",")));
+        }
+        if let Some(count) = self.count {
+            parts.push(format!("COUNT={count}"));
+        }
+        if let Some(until) = self.until {
+            parts.push(format!("UNTIL={}", until.format("%Y%m%d")));
+        }
+        parts.join(";")
+    }
+
+    /// Helper function to find the first occurrence after a date, ignoring the count
+    ///
+    /// # Returns
+    ///
+    /// The date, or `None` if the rule has no occurrence after it.
+    pub fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        for period in 0..MAX_PERIODS {
+            let mut candidates = self.candidates(date, period)?;
+            candidates.sort();
+            if let Some(next) = candidates.into_iter().find(|&candidate| candidate > date) {
+                return match self.until {
+                    Some(until) if next > until => None,
+                    _ => Some(next),
+                };
+            }
+        }
+        None
+    }
+
+    /// Helper function to get the next occurrence of a repeating task that was completed
+    ///
+    /// The next occurrence is the first one after the current due date that is not before
+    /// `today`, so a task completed late doesn't come back already overdue. A task without a due
+    /// date repeats from the day it was completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `due_date` - The due date of the completed occurrence
+    /// * `today` - The day it was completed
+    ///
+    /// # Returns
+    ///
+    /// The due date of the next occurrence and the rule it repeats with, or `None` if the rule
+    /// has no more occurrences.
+    pub fn next(
+        &self,
+        due_date: Option<crate::dates::DueDate>,
+        today: NaiveDate,
+    ) -> Option<(crate::dates::DueDate, Recurrence)> {
+        if self.count.is_some_and(|count| count <= 1) {
+            return None;
+        }
+        let from = due_date.map_or(today, |due_date| due_date.date);
+        let mut next = self.next_after(from)?;
+        for _ in 0..MAX_PERIODS {
+            if next >= today {
+                break;
+            }
+            next = self.next_after(next)?;
+        }
+
+        let mut rule = self.clone();
+        rule.count = self.count.map(|count| count - 1);
+        if rule.frequency == Frequency::Monthly
+            && rule.weekdays.is_empty()
+            && rule.month_days.is_empty()
+        {
+            // pin the day of the month, so a task due on the 31st isn't due on the 28th from February on
+            rule.month_days = vec![from.day() as i32];
+        }
+        let due_date = match due_date {
+            Some(due_date) => crate::dates::DueDate {
+                date: next,
+                ..due_date
+            },
+            None => crate::dates::DueDate::on(next),
+        };
+        Some((due_date, rule))
+    }
+
+    /// Helper function to check that the parts of a rule fit its frequency
+    fn check(&self) -> Result<(), String> {
+        let has_week = self.weekdays.iter().any(|(week, _)| week.is_some());
+        match self.frequency {
+            Frequency::Daily | Frequency::Weekly if !self.month_days.is_empty() => {
+                Err("BYMONTHDAY needs a MONTHLY rule".to_string())
+            }
+            Frequency::Daily | Frequency::Weekly if has_week => Err(
+                "a weekday with a week of the month, such as 2TU, needs a MONTHLY rule".to_string(),
+            ),
+            Frequency::Monthly
+                if self
+                    .weekdays
+                    .iter()
+                    .any(|&(week, _)| week.is_some_and(|week| week == 0 || week.abs() > 5)) =>
+            {
+                Err("the week of the month must be 1 to 5 or -1 to -5".to_string())
+            }
+            Frequency::Yearly if !self.weekdays.is_empty() || !self.month_days.is_empty() => Err(
+                "YEARLY rules repeat on the due date and can't have BYDAY or BYMONTHDAY"
+                    .to_string(),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Helper function to list the dates a rule occurs on in one period after the one holding `date`
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - The date the search starts from, which also gives the day for rules without BYDAY or BYMONTHDAY
+    /// * `period` - How many intervals after the period holding `date` to look in
+    fn candidates(&self, date: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let steps = period.checked_mul(self.interval)?;
+        let matches_weekday = |day: NaiveDate| {
+            self.weekdays.is_empty()
+                || self
+                    .weekdays
+                    .iter()
+                    .any(|&(_, weekday)| weekday == day.weekday())
+        };
+        Some(match self.frequency {
+            Frequency::Daily => {
+                let day = date.checked_add_signed(Duration::days(steps.into()))?;
+                if matches_weekday(day) {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday().into());
+                let monday = monday.checked_add_signed(Duration::weeks(steps.into()))?;
+                if self.weekdays.is_empty() {
+                    vec![monday + Duration::days(date.weekday().num_days_from_monday().into())]
+                } else {
+                    self.weekdays
+                        .iter()
+                        .map(|&(_, weekday)| {
+                            monday + Duration::days(weekday.num_days_from_monday().into())
+                        })
+                        .collect()
+                }
+            }
+            Frequency::Monthly => {
+                let first = date.with_day(1)?.checked_add_months(Months::new(steps))?;
+                let length = days_in_month(first);
+                let mut days: Vec<NaiveDate> = self
+                    .month_days
+                    .iter()
+                    .filter_map(|&day| {
+                        // days past the end of a short month fall on its last day
+                        let day = if day > 0 {
+                            day.min(length)
+                        } else {
+                            length + day + 1
+                        };
+                        (day >= 1).then(|| first.with_day(day as u32)).flatten()
+                    })
+                    .collect();
+                for &(week, weekday) in &self.weekdays {
+                    let in_month = (0..length as u32)
+                        .map(|offset| first + Duration::days(offset.into()))
+                        .filter(|day| day.weekday() == weekday);
+                    match week {
+                        None => days.extend(in_month),
+                        Some(week) if week > 0 => {
+                            days.extend(in_month.skip(week as usize - 1).take(1))
+                        }
+                        Some(week) => days.extend(
+                            in_month
+                                .collect::<Vec<_>>()
+                                .into_iter()
+                                .rev()
+                                .nth((-week) as usize - 1),
+                        ),
+                    }
+                }
+                if self.month_days.is_empty() && self.weekdays.is_empty() {
+                    days.push(first.with_day((date.day() as i32).min(length) as u32)?);
+                }
+                days
+            }
+            Frequency::Yearly => {
+                let first = date
+                    .with_day(1)?
+                    .checked_add_months(Months::new(steps.checked_mul(12)?))?;
+                vec![first.with_day(date.day().min(days_in_month(first) as u32))?]
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for Recurrence {
+    /// Format the rule in words, such as `every 2 weeks on monday, friday, 3 times`.
+    /// Rules that can't be said in words are written as `RRULE:...`, so the text always parses back.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(base) = self.describe() else {
+            return write!(f, "RRULE:{}", self.to_rrule());
+        };
+        write!(f, "{base}")?;
+        if let Some(count) = self.count {
+            write!(f, ", {count} times")?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ", until {}", until.format("%Y-%m-%d"))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Recurrence {
+    type Err = String;
+
+    /// Parse a rule in words, as written by Display, or an RRULE
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err("Please enter how often the task repeats.".to_string());
+        }
+        if input.to_ascii_uppercase().starts_with("RRULE:")
+            || input.to_ascii_uppercase().starts_with("FREQ=")
+        {
+            return Self::from_rrule(input);
+        }
+        parse_words(&input.to_lowercase()).ok_or_else(|| {
+            format!(
+                "Could not understand the repeat rule \"{input}\". Try daily, weekdays, weekly on monday, every 2 weeks, \
+                 monthly on the 15th, monthly on the last friday, yearly or an RRULE such as FREQ=WEEKLY;BYDAY=MO,TH."
+            )
+        })
+    }
+}
+
+impl serde::Serialize for Recurrence {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_rrule())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Recurrence {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rule = String::deserialize(deserializer)?;
+        Self::from_rrule(&rule).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Implementation of Recurrence with the helpers for writing a rule in words
+impl Recurrence {
+    /// Helper function to describe the rule without its count and end date
+    ///
+    /// # Returns
+    ///
+    /// The description, or `None` if the rule can't be said in words.
+    fn describe(&self) -> Option<String> {
+        let (unit, adverb) = match self.frequency {
+            Frequency::Daily => ("day", "daily"),
+            Frequency::Weekly => ("week", "weekly"),
+            Frequency::Monthly => ("month", "monthly"),
+            Frequency::Yearly => ("year", "yearly"),
+        };
+        let base = match self.interval {
+            1 => adverb.to_string(),
+            interval => format!("every {interval} {unit}s"),
+        };
+        match self.frequency {
+            Frequency::Daily | Frequency::Yearly if self.weekdays.is_empty() => Some(base),
+            Frequency::Daily => None,
+            Frequency::Weekly if *self == Self::weekdays() || self.weekdays.is_empty() => {
+                Some(if self.weekdays.is_empty() {
+                    base
+                } else {
+                    "weekdays".to_string()
+                })
+            }
+            Frequency::Weekly => {
+                let days: Vec<&str> = self
+                    .weekdays
+                    .iter()
+                    .map(|&(_, weekday)| weekday_name(weekday))
+                    .collect();
+                Some(format!("{base} on {}", days.join(", ")))
+            }
+            Frequency::Monthly if self.weekdays.iter().any(|(week, _)| week.is_none()) => None,
+            Frequency::Monthly if self.month_days.is_empty() && self.weekdays.is_empty() => {
+                Some(base)
+            }
+            Frequency::Monthly => {
+                let mut days = Vec::new();
+                for &day in &self.month_days {
+                    days.push(match day {
+                        -1 => "the last day".to_string(),
+                        day if day > 0 => format!("the {}", ordinal(day)),
+                        _ => return None,
+                    });
+                }
+                for &(week, weekday) in &self.weekdays {
+                    let week = match week? {
+                        -1 => "last".to_string(),
+                        week if week > 0 => ordinal(week),
+                        _ => return None,
+                    };
+                    days.push(format!("the {week} {}", weekday_name(weekday)));
+                }
+                Some(format!("{base} on {}", days.join(", ")))
+            }
+            Frequency::Yearly => None,
+        }
+    }
+}
+
+/// Helper function to read a rule in words, which the caller has lower-cased
+fn parse_words(input: &str) -> Option<Recurrence> {
+    // the count and end date come last, after commas
+    let mut rest = input.trim();
+    let mut until = None;
+    let mut count = None;
+    if let Some((head, tail)) = rest.rsplit_once(',') {
+        if let Some(date) = tail.trim().strip_prefix("until ") {
+            until = Some(NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?);
+            rest = head.trim();
+        }
+    }
+    if let Some((head, tail)) = rest.rsplit_once(',') {
+        if let Some(times) = tail
+            .trim()
+            .strip_suffix(" times")
+            .or_else(|| tail.trim().strip_suffix(" time"))
+        {
+            count = Some(times.trim().parse().ok().filter(|&count: &u32| count > 0)?);
+            rest = head.trim();
+        }
+    }
+
+    let (base, on) = match rest.split_once(" on ") {
+        Some((base, on)) => (base.trim(), Some(on.trim())),
+        None => (rest, None),
+    };
+    // "every monday" or "every tuesday and thursday"
+    if let Some(days) = base.strip_prefix("every ").and_then(parse_weekday_list) {
+        if on.is_some() {
+            return None;
+        }
+        return Some(Recurrence {
+            weekdays: days.into_iter().map(|weekday| (None, weekday)).collect(),
+            count,
+            until,
+            ..Recurrence::every(1, Frequency::Weekly)
+        });
+    }
+    let mut words = base.split_whitespace();
+    let mut recurrence = match (words.next()?, words.next(), words.next(), words.next()) {
+        ("daily", None, _, _) => Recurrence::daily(),
+        ("weekly", None, _, _) => Recurrence::every(1, Frequency::Weekly),
+        ("monthly", None, _, _) => Recurrence::every(1, Frequency::Monthly),
+        ("yearly" | "annually", None, _, _) => Recurrence::every(1, Frequency::Yearly),
+        ("weekdays", None, _, _) => Recurrence::weekdays(),
+        ("every", Some("weekday"), None, _) => Recurrence::weekdays(),
+        ("every", Some(unit), None, _) => Recurrence::every(1, parse_unit(unit)?),
+        ("every", Some(interval), Some(unit), None) => {
+            Recurrence::every(interval.parse().ok().filter(|&n| n > 0)?, parse_unit(unit)?)
+        }
+        _ => return None,
+    };
+    if let Some(on) = on {
+        match recurrence.frequency {
+            Frequency::Weekly => {
+                recurrence.weekdays = parse_weekday_list(on)?
+                    .into_iter()
+                    .map(|weekday| (None, weekday))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                for item in on.split(',').flat_map(|item| item.split(" and ")) {
+                    let item = item.trim();
+                    let item = item.strip_prefix("the ").unwrap_or(item);
+                    let item = item.strip_prefix("day ").unwrap_or(item);
+                    match item.split_whitespace().collect::<Vec<_>>()[..] {
+                        ["last", "day"] => recurrence.month_days.push(-1),
+                        [day] => recurrence
+                            .month_days
+                            .push(parse_ordinal(day).filter(|day| *day <= 31)?),
+                        [week, weekday] => {
+                            let week = if week == "last" {
+                                -1
+                            } else {
+                                parse_ordinal(week).filter(|week| *week <= 5)?
+                            };
+                            recurrence
+                                .weekdays
+                                .push((Some(week), parse_weekday(weekday)?));
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            Frequency::Daily | Frequency::Yearly => return None,
+        }
+    }
+    recurrence.count = count;
+    recurrence.until = until;
+    Some(recurrence)
+}
+
+/// Helper function to read a unit such as "days" or "week"
+fn parse_unit(unit: &str) -> Option<Frequency> {
+    match unit.trim_end_matches('s') {
+        "day" => Some(Frequency::Daily),
+        "week" => Some(Frequency::Weekly),
+        "month" => Some(Frequency::Monthly),
+        "year" => Some(Frequency::Yearly),
+        _ => None,
+    }
+}
+
+/// Helper function to read weekdays separated by commas or "and"
+fn parse_weekday_list(input: &str) -> Option<Vec<Weekday>> {
+    input
+        .split(',')
+        .flat_map(|item| item.split(" and "))
+        .map(|item| parse_weekday(item.trim()))
+        .collect()
+}
+
+/// Helper function to read a weekday name or abbreviation
+fn parse_weekday(input: &str) -> Option<Weekday> {
+    let input = input.trim().trim_end_matches('s'); // "mondays"
+    WEEKDAYS
+        .iter()
+        .map(|&(weekday, _)| weekday)
+        .find(|&weekday| {
+            let name = weekday_name(weekday);
+            input.len() >= 2 && name.starts_with(input) || name.trim_end_matches('s') == input
+        })
+}
+
+/// Helper function to read an ordinal such as "2nd" or a plain number
+fn parse_ordinal(input: &str) -> Option<i32> {
+    let number = input.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &input[number.len()..];
+    let number: i32 = number.parse().ok().filter(|&number| number > 0)?;
+    match suffix {
+        "" => Some(number),
+        _ if suffix == &ordinal(number)[number.to_string().len()..] => Some(number),
+        _ => None,
+    }
+}
+
+/// Helper function to read an RRULE weekday such as `MO`, `2TU` or `-1FR`
+fn parse_rrule_weekday(input: &str) -> Option<(Option<i32>, Weekday)> {
+    let input = input.trim().to_ascii_uppercase();
+    let (week, code) = input.split_at(input.len().checked_sub(2)?);
+    let weekday = WEEKDAYS.iter().find(|&&(_, name)| name == code)?.0;
+    let week = match week {
+        "" => None,
+        week => Some(
+            week.trim_start_matches('+')
+                .parse()
+                .ok()
+                .filter(|&week: &i32| week != 0)?,
+        ),
+    };
+    Some((week, weekday))
+}
+
+/// Helper function to get the RRULE code of a weekday
+fn weekday_code(weekday: Weekday) -> &'static str {
+    WEEKDAYS
+        .iter()
+        .find(|&&(day, _)| day == weekday)
+        .map_or("MO", |&(_, code)| code)
+}
+
+/// Helper function to get the name of a weekday
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+/// Helper function to write a number as an ordinal, such as 1st, 2nd or 11th
+fn ordinal(number: i32) -> String {
+    let suffix = match (number % 10, number % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{number}{suffix}")
+}
+
+/// Helper function to get the number of days in the month of a date
+fn days_in_month(date: NaiveDate) -> i32 {
+    let first = date.with_day(1).expect("every month has a first day");
+    let next = first
+        .checked_add_months(Months::new(1))
+        .unwrap_or(NaiveDate::MAX);
+    next.signed_duration_since(first).num_days() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::{parse_due_date, DueDate};
+
+    fn date(input: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(input, "%Y-%m-%d").unwrap()
+    }
+
+    /// Helper function to list the next few occurrences after a date
+    fn occurrences(rule: &str, from: &str, n: usize) -> Vec<String> {
+        let rule: Recurrence = rule.parse().unwrap();
+        std::iter::successors(rule.next_after(date(from)), |&day| rule.next_after(day))
+            .take(n)
+            .map(|day| day.format("%Y-%m-%d").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_next_occurrences() {
+        // 2024-11-22 is a Friday
+        assert_eq!(
+            occurrences("daily", "2024-11-22", 2),
+            ["2024-11-23", "2024-11-24"]
+        );
+        assert_eq!(
+            occurrences("weekdays", "2024-11-22", 3),
+            ["2024-11-25", "2024-11-26", "2024-11-27"]
+        );
+        assert_eq!(
+            occurrences("every 3 days", "2024-11-22", 2),
+            ["2024-11-25", "2024-11-28"]
+        );
+        assert_eq!(
+            occurrences("every 2 weeks", "2024-11-22", 2),
+            ["2024-12-06", "2024-12-20"]
+        );
+        assert_eq!(
+            occurrences("every 2 weeks on monday, friday", "2024-11-22", 3),
+            ["2024-12-02", "2024-12-06", "2024-12-16"]
+        );
+        assert_eq!(
+            occurrences("every tuesday and thursday", "2024-11-22", 2),
+            ["2024-11-26", "2024-11-28"]
+        );
+        assert_eq!(
+            occurrences("monthly on the 31st", "2024-01-31", 3),
+            ["2024-02-29", "2024-03-31", "2024-04-30"]
+        );
+        assert_eq!(
+            occurrences("monthly on the last day", "2024-11-22", 2),
+            ["2024-11-30", "2024-12-31"]
+        );
+        assert_eq!(
+            occurrences("monthly on the 2nd tuesday", "2024-11-22", 2),
+            ["2024-12-10", "2025-01-14"]
+        );
+        assert_eq!(
+            occurrences("monthly on the last friday", "2024-11-22", 2),
+            ["2024-11-29", "2024-12-27"]
+        );
+        assert_eq!(
+            occurrences("yearly", "2024-02-29", 2),
+            ["2025-02-28", "2026-02-28"]
+        );
+        assert_eq!(
+            occurrences("FREQ=MONTHLY;INTERVAL=3;BYMONTHDAY=1,15", "2024-11-22", 3),
+            ["2025-02-01", "2025-02-15", "2025-05-01"]
+        );
+        assert_eq!(
+            occurrences("daily, until 2024-11-23", "2024-11-22", 3),
+            ["2024-11-23"]
+        );
+    }
+
+    #[test]
+    fn test_words_and_rrule_round_trip() {
+        for text in [
+            "daily",
+            "weekdays",
+            "weekly on monday, thursday",
+            "every 2 weeks",
+            "monthly on the 15th, the last day",
+            "monthly on the 2nd tuesday",
+            "every 3 years",
+            "daily, 5 times, until 2025-01-31",
+            "RRULE:FREQ=DAILY;BYDAY=SA,SU",
+        ] {
+            let rule: Recurrence = text.parse().unwrap();
+            assert_eq!(rule.to_string(), text);
+            assert_eq!(Recurrence::from_rrule(&rule.to_rrule()).unwrap(), rule);
+        }
+        assert_eq!(
+            "Every Monday".parse::<Recurrence>().unwrap().to_rrule(),
+            "FREQ=WEEKLY;BYDAY=MO"
+        );
+        assert_eq!(
+            "monthly on day 1".parse(),
+            Ok(Recurrence::monthly_on_day(1))
+        );
+        assert_eq!(
+            Recurrence::from_rrule("RRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3")
+                .unwrap()
+                .to_string(),
+            "monthly on the last friday, 3 times"
+        );
+
+        assert!("sometimes".parse::<Recurrence>().is_err());
+        assert!("every 0 days".parse::<Recurrence>().is_err());
+        assert!("monthly on the 32nd".parse::<Recurrence>().is_err());
+        assert!(Recurrence::from_rrule("FREQ=HOURLY").is_err());
+        assert!(Recurrence::from_rrule("FREQ=WEEKLY;BYDAY=2TU").is_err());
+        assert!(Recurrence::from_rrule("FREQ=DAILY;BYSETPOS=1").is_err());
+    }
+
+    #[test]
+    fn test_next_rolls_forward_from_the_due_date() {
+        let due = parse_due_date("2024-11-22 09:00", date("2024-11-22")).unwrap();
+        let (next, rule) = Recurrence::daily()
+            .next(Some(due), date("2024-11-22"))
+            .unwrap();
+        assert_eq!(
+            next,
+            parse_due_date("2024-11-23 09:00", date("2024-11-22")).unwrap()
+        );
+        assert_eq!(rule, Recurrence::daily());
+
+        // completed late, the next occurrence is not already overdue
+        let (next, _) = Recurrence::daily()
+            .next(Some(due), date("2024-11-25"))
+            .unwrap();
+        assert_eq!(next.date, date("2024-11-25"));
+
+        // a plain monthly rule keeps the day of the month
+        let (next, rule) = Recurrence::every(1, Frequency::Monthly)
+            .next(Some(DueDate::on(date("2024-01-31"))), date("2024-01-31"))
+            .unwrap();
+        assert_eq!(next.date, date("2024-02-29"));
+        assert_eq!(
+            rule.next(Some(next), date("2024-02-29")).unwrap().0.date,
+            date("2024-03-31")
+        );
+
+        // the count runs out
+        let rule = Recurrence {
+            count: Some(2),
+            ..Recurrence::daily()
+        };
+        let (next, rule) = rule.next(None, date("2024-11-22")).unwrap();
+        assert_eq!((next.date, rule.count), (date("2024-11-23"), Some(1)));
+        assert!(rule.next(Some(next), date("2024-11-23")).is_none());
+    }
+}
@@ -0,0 +1,212 @@
+use chrono::NaiveDate;
+
+use crate::gamification::{PriorityLevel, Task};
+use crate::scoring::ScoringModel;
+use crate::tags;
+
+#[derive(Debug, Clone, PartialEq)]
+/// TaskReport struct with the metrics shown in the Tasks Report
+pub struct TaskReport {
+    pub total_tasks: usize,
+    pub completed_tasks: usize,
+    pub incomplete_tasks: usize,
+    pub low_priority_tasks: usize,
+    pub medium_priority_tasks: usize,
+    pub high_priority_tasks: usize,
+    pub total_points: u32,
+    pub average_priority: f32,
+    pub most_common_priority: PriorityLevel,
+    pub upcoming_tasks: usize,
+    pub projects: Vec<Breakdown>, // one per project, in project order
+    pub tags: Vec<Breakdown>,     // one per tag, in tag order
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Breakdown struct with the metrics of the tasks in one project or with one tag
+pub struct Breakdown {
+    pub name: String,
+    pub total_tasks: usize,
+    pub completed_tasks: usize,
+    pub total_points: u32,
+}
+
+/// Implementation of Breakdown with a method to compute the metrics of some of the tasks
+impl Breakdown {
+    /// Helper function to compute the metrics of the tasks that belong to a group
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the project or tag
+    /// * `tasks` - A slice of Task structs representing the user's tasks
+    /// * `scoring` - The scoring model that works out the points of a task
+    /// * `belongs` - Whether a task belongs to the group
+    fn new(
+        name: &str,
+        tasks: &[Task],
+        scoring: &ScoringModel,
+        belongs: impl Fn(&Task) -> bool,
+    ) -> Self {
+        let grouped: Vec<&Task> = tasks.iter().filter(|task| belongs(task)).collect();
+        Breakdown {
+            name: name.to_string(),
+            total_tasks: grouped.len(),
+            completed_tasks: grouped.iter().filter(|task| task.completed).count(),
+            total_points: grouped.iter().map(|task| task.earned_points(scoring)).sum(),
+        }
+    }
+
+    /// Helper function to express the completed tasks as a percentage of the group's tasks
+    pub fn completed_percentage(&self) -> f32 {
+        if self.total_tasks > 0 {
+            (self.completed_tasks as f32 / self.total_tasks as f32) * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Implementation of TaskReport with a method to compute the metrics from a list of tasks
+impl TaskReport {
+    /// Helper function to compute the report metrics
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - A slice of Task structs representing the user's tasks
+    /// * `scoring` - The scoring model that works out the points of a task
+    /// * `today` - The current date, used to count upcoming tasks
+    pub fn new(tasks: &[Task], scoring: &ScoringModel, today: NaiveDate) -> Self {
+        let total_tasks = tasks.len();
+        let completed_tasks = tasks.iter().filter(|task| task.completed).count();
+
+        // Count the tasks of each priority level
+        let count_priority = |priority| {
+            tasks
+                .iter()
+                .filter(|task| task.priority == priority)
+                .count()
+        };
+        let low_priority_tasks = count_priority(PriorityLevel::Low);
+        let medium_priority_tasks = count_priority(PriorityLevel::Medium);
+        let high_priority_tasks = count_priority(PriorityLevel::High);
+
+        let total_points = tasks.iter().map(|task| task.earned_points(scoring)).sum();
+
+        let total_priority: u32 = tasks.iter().map(|task| task.priority as u32).sum();
+        let average_priority = if total_tasks > 0 {
+            total_priority as f32 / total_tasks as f32
+        } else {
+            0.0
+        };
+
+        // Ties go to the lower priority level
+        let most_common_priority = if low_priority_tasks >= medium_priority_tasks
+            && low_priority_tasks >= high_priority_tasks
+        {
+            PriorityLevel::Low
+        } else if medium_priority_tasks >= high_priority_tasks {
+            PriorityLevel::Medium
+        } else {
+            PriorityLevel::High
+        };
+
+        // Upcoming tasks have a due date after today; tasks without a due date are not upcoming
+        let upcoming_tasks = tasks
+            .iter()
+            .filter(|task| {
+                task.due_date
+                    .is_some_and(|due_date| due_date.local_date() > today)
+            })
+            .count();
+
+        let projects = tags::all_projects(tasks)
+            .iter()
+            .map(|project| {
+                Breakdown::new(project, tasks, scoring, |task| {
+                    task.project.as_ref() == Some(project)
+                })
+            })
+            .collect();
+        let tags = tags::all_tags(tasks)
+            .iter()
+            .map(|tag| {
+                Breakdown::new(tag, tasks, scoring, |task| {
+                    task.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+                })
+            })
+            .collect();
+
+        TaskReport {
+            total_tasks,
+            completed_tasks,
+            incomplete_tasks: total_tasks - completed_tasks,
+            low_priority_tasks,
+            medium_priority_tasks,
+            high_priority_tasks,
+            total_points,
+            average_priority,
+            most_common_priority,
+            upcoming_tasks,
+            projects,
+            tags,
+        }
+    }
+
+    /// Helper function to format the metrics as the labelled lines shown in the Tasks Report,
+    /// and a line for every project and tag
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Total Tasks: {}", self.total_tasks),
+            format!(
+                "Completed Tasks: {} ({:.2}%)",
+                self.completed_tasks,
+                self.percentage(self.completed_tasks)
+            ),
+            format!(
+                "Low Priority Tasks: {} ({:.2}%)",
+                self.low_priority_tasks,
+                self.percentage(self.low_priority_tasks)
+            ),
+            format!(
+                "Medium Priority Tasks: {} ({:.2}%)",
+                self.medium_priority_tasks,
+                self.percentage(self.medium_priority_tasks)
+            ),
+            format!(
+                "High Priority Tasks: {} ({:.2}%)",
+                self.high_priority_tasks,
+                self.percentage(self.high_priority_tasks)
+            ),
+            format!("Total Points Earned: {}", self.total_points),
+            format!(
+                "Incomplete Tasks: {} ({:.2}%)",
+                self.incomplete_tasks,
+                self.percentage(self.incomplete_tasks)
+            ),
+            format!("Average Task Priority: {:.2}", self.average_priority),
+            format!("Most Common Task Priority: {:?}", self.most_common_priority),
+            format!("Upcoming Tasks: {}", self.upcoming_tasks),
+        ];
+        for (kind, breakdowns) in [("Project", &self.projects), ("Tag", &self.tags)] {
+            lines.extend(breakdowns.iter().map(|breakdown| {
+                format!(
+                    "{kind} {}: {}/{} completed ({:.2}%), {} points",
+                    breakdown.name,
+                    breakdown.completed_tasks,
+                    breakdown.total_tasks,
+                    breakdown.completed_percentage(),
+                    breakdown.total_points
+                )
+            }));
+        }
+        lines
+    }
+
+    /// Helper function to express a count as a percentage of all tasks
+    pub fn percentage(&self, count: usize) -> f32 {
+        if self.total_tasks > 0 {
+            (count as f32 / self.total_tasks as f32) * 100.0
+        } else {
+            0.0
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! Task scoring.
+//!
+//! How many points a task is worth is worked out by a [`ScoringModel`], chosen in the settings.
+//! The priority formula gives every task 10, 20 or 30 points by its priority. The weighted formula
+//! starts from those points, adds points for every hour of estimated effort, and multiplies the
+//! result by the task's difficulty, by whether it was completed before or after the day it was
+//! due, and by the multipliers of its tags. Whatever the formula, a task can have its points set
+//! by hand instead. Every step is kept in a [`Score`], so the task details can show how the points
+//! of a task were worked out. The score of a task is kept when it is completed, so changing the
+//! scoring later doesn't change the points already paid for it.
+
+use std::collections::BTreeMap;
+
+use crate::gamification::Task;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+/// How hard a task is, estimated by the user
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+/// The formula the points of a task are worked out with
+pub enum Formula {
+    #[default]
+    Priority, // 10, 20 or 30 points by priority
+    Weighted, // the priority points with the effort, difficulty, timing and tag multipliers
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+/// ScoringModel struct with the formula and the weights of the weighted formula
+pub struct ScoringModel {
+    pub formula: Formula,
+    pub effort_points: u32,    // points for every hour of estimated effort
+    pub early_multiplier: f64, // for a task completed before the day it was due
+    pub late_multiplier: f64,  // for a task completed after the day it was due
+    pub tag_multipliers: BTreeMap<String, f64>, // by tag, matched in any case
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+/// What a step of a score does to the points
+pub enum Effect {
+    Base(u32),     // the points the score starts from
+    Add(u32),      // points added
+    Multiply(f64), // the points so far multiplied
+    Replace(u32),  // the points set by hand, whatever the steps before
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// A step of a score, such as "Hard difficulty ×1.5"
+pub struct ScoreStep {
+    pub label: String,
+    pub effect: Effect,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// The points a task is worth, and the steps they were worked out in
+pub struct Score {
+    pub points: u32,
+    pub steps: Vec<ScoreStep>,
+}
+
+/// Implementation of Difficulty with the multiplier of the weighted formula
+impl Difficulty {
+    /// Helper function to get how much the points are multiplied by
+    pub fn multiplier(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+        }
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = String;
+
+    /// Helper function to parse a difficulty in any case, such as "hard"
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!(
+                "unknown difficulty {input:?}, expected easy, normal or hard"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Formula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::str::FromStr for Formula {
+    type Err = String;
+
+    /// Helper function to parse a formula in any case, such as "weighted"
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "priority" => Ok(Formula::Priority),
+            "weighted" => Ok(Formula::Weighted),
+            _ => Err(format!(
+                "unknown formula {input:?}, expected priority or weighted"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Effect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Effect::Base(points) => write!(f, "{points}"),
+            Effect::Add(points) => write!(f, "+{points}"),
+            Effect::Multiply(multiplier) => write!(f, "×{multiplier}"),
+            Effect::Replace(points) => write!(f, "= {points}"),
+        }
+    }
+}
+
+/// Implement the Default trait for ScoringModel so that it starts with the priority formula
+impl Default for ScoringModel {
+    fn default() -> Self {
+        ScoringModel {
+            formula: Formula::Priority,
+            effort_points: 5,
+            early_multiplier: 1.25,
+            late_multiplier: 0.75,
+            tag_multipliers: BTreeMap::new(),
+        }
+    }
+}
+
+/// Implementation of ScoringModel with methods to score a task
+impl ScoringModel {
+    /// Helper function to work out the points a task is worth and how
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task to score; its completion date only counts once it is completed
+    pub fn score(&self, task: &Task) -> Score {
+        let mut steps = vec![ScoreStep {
+            label: format!("{:?} priority", task.priority),
+            effect: Effect::Base(task.points()),
+        }];
+        let mut points = task.points() as f64;
+        if self.formula == Formula::Weighted {
+            if let Some(hours) = task.effort.filter(|hours| *hours > 0) {
+                points += hours.saturating_mul(self.effort_points) as f64;
+                steps.push(ScoreStep {
+                    label: format!("{hours} h of effort"),
+                    effect: Effect::Add(hours.saturating_mul(self.effort_points)),
+                });
+            }
+            let mut multiply = |label: String, multiplier: f64| {
+                points *= multiplier;
+                steps.push(ScoreStep {
+                    label,
+                    effect: Effect::Multiply(multiplier),
+                });
+            };
+            if task.difficulty != Difficulty::Normal {
+                multiply(
+                    format!("{} difficulty", task.difficulty),
+                    task.difficulty.multiplier(),
+                );
+            }
+            if let (Some(due_date), Some(completed_date), true) =
+                (task.due_date, task.completed_date, task.completed)
+            {
+                let (due, completed) = (due_date.local_date(), completed_date.date_naive());
+                if completed < due {
+                    multiply("Completed early".to_string(), self.early_multiplier);
+                } else if completed > due {
+                    multiply("Completed late".to_string(), self.late_multiplier);
+                }
+            }
+            for tag in &task.tags {
+                if let Some(multiplier) = self.tag_multiplier(tag) {
+                    multiply(format!("Tag {tag}"), multiplier);
+                }
+            }
+        }
+        let mut points = points.max(0.0).round() as u32;
+        if let Some(fixed) = task.points_override {
+            points = fixed;
+            steps.push(ScoreStep {
+                label: "Set by hand".to_string(),
+                effect: Effect::Replace(fixed),
+            });
+        }
+        Score { points, steps }
+    }
+
+    /// Helper function to get the points a task is worth
+    pub fn points(&self, task: &Task) -> u32 {
+        self.score(task).points
+    }
+
+    /// Helper function to get the multiplier of a tag, matched in any case
+    pub fn tag_multiplier(&self, tag: &str) -> Option<f64> {
+        self.tag_multipliers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+            .map(|(_, multiplier)| *multiplier)
+    }
+
+    /// Helper function to set the multiplier of a tag; a multiplier of 1 removes it
+    pub fn set_tag_multiplier(&mut self, tag: &str, multiplier: f64) {
+        self.tag_multipliers
+            .retain(|name, _| !name.eq_ignore_ascii_case(tag));
+        if multiplier != 1.0 {
+            self.tag_multipliers.insert(tag.to_string(), multiplier);
+        }
+    }
+}
+
+/// Helper function to parse a tag multiplier, such as "urgent=2"
+///
+/// # Returns
+///
+/// The tag and its multiplier, or an error if there is no tag or the multiplier is invalid.
+pub fn parse_tag_multiplier(input: &str) -> Result<(String, f64), String> {
+    let (tag, multiplier) = input
+        .split_once('=')
+        .ok_or_else(|| format!("expected TAG=MULTIPLIER, such as urgent=2, not {input:?}"))?;
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("the tag of a multiplier can't be empty".to_string());
+    }
+    Ok((tag.to_string(), parse_multiplier(multiplier)?))
+}
+
+/// Helper function to parse a multiplier, a number of at least 0 such as "1.5"
+pub fn parse_multiplier(input: &str) -> Result<f64, String> {
+    match input.trim().parse::<f64>() {
+        Ok(multiplier) if multiplier >= 0.0 && multiplier.is_finite() => Ok(multiplier),
+        _ => Err(format!(
+            "a multiplier has to be a number of at least 0, not {input:?}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::dates::DueDate;
+    use crate::gamification::PriorityLevel;
+    use crate::task_list::TaskId;
+    use chrono::{Duration, NaiveDate};
+
+    /// Helper function to get a high priority task due on a day and completed on another
+    fn task(due: NaiveDate, completed: NaiveDate) -> Task {
+        Task {
+            id: TaskId(1),
+            due_date: Some(DueDate::on(due)),
+            completed: true,
+            completed_date: Some(Clock::fixed_on(completed).now()),
+            tags: vec!["Urgent".to_string()],
+            effort: Some(2),
+            difficulty: Difficulty::Hard,
+            ..Task::new("Write report", PriorityLevel::High)
+        }
+    }
+
+    #[test]
+    fn test_formulas() {
+        let today = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap();
+        let mut task = task(today, today);
+        let mut model = ScoringModel::default();
+        assert_eq!(
+            model.score(&task),
+            Score {
+                points: 30,
+                steps: vec![ScoreStep {
+                    label: "High priority".to_string(),
+                    effect: Effect::Base(30)
+                }]
+            }
+        );
+
+        // (30 + 2 * 5) * 1.5, then twice as much for the tag
+        model.formula = Formula::Weighted;
+        model.set_tag_multiplier("urgent", 2.0);
+        let score = model.score(&task);
+        assert_eq!(score.points, 120);
+        let steps: Vec<String> = score
+            .steps
+            .iter()
+            .map(|step| format!("{} {}", step.label, step.effect))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                "High priority 30",
+                "2 h of effort +10",
+                "Hard difficulty ×1.5",
+                "Tag Urgent ×2"
+            ]
+        );
+
+        // completed a day early or late
+        task.completed_date = Some(Clock::fixed_on(today - Duration::days(1)).now());
+        assert_eq!(model.points(&task), 150);
+        task.completed_date = Some(Clock::fixed_on(today + Duration::days(1)).now());
+        assert_eq!(model.points(&task), 90);
+        // timing doesn't count before the task is completed
+        task.completed = false;
+        assert_eq!(model.points(&task), 120);
+
+        // points set by hand win over every formula
+        task.points_override = Some(7);
+        assert_eq!(model.points(&task), 7);
+        assert_eq!(
+            ScoringModel::default()
+                .score(&task)
+                .steps
+                .last()
+                .unwrap()
+                .effect,
+            Effect::Replace(7)
+        );
+        model.set_tag_multiplier("URGENT", 1.0);
+        assert!(model.tag_multipliers.is_empty());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_tag_multiplier(" urgent = 2.5 "),
+            Ok(("urgent".to_string(), 2.5))
+        );
+        assert!(parse_tag_multiplier("urgent").is_err());
+        assert!(parse_tag_multiplier("=2").is_err());
+        assert!(parse_tag_multiplier("urgent=-1").is_err());
+        assert_eq!("Hard".parse(), Ok(Difficulty::Hard));
+        assert_eq!(" weighted".parse(), Ok(Formula::Weighted));
+        assert!("heavy".parse::<Difficulty>().is_err());
+    }
+}
@@ -0,0 +1,741 @@
+//! Versioned persistence of the app state.
+//!
+//! The app state is saved as a JSON envelope `{"version": n, "state": {...}}` under [`STATE_KEY`].
+//! Loading upgrades older versions one step at a time through [`MIGRATIONS`], so that a change
+//! to `Task` or `Gamification` never silently resets a user's history. Data that can't be read
+//! is kept under a backup key instead of being discarded.
+//!
+//! Version history:
+//!
+//! * 0 - RON under `eframe::APP_KEY`, tasks stored as a bare list with free-form string dates
+//! * 1 - RON under `eframe::APP_KEY`, tasks stored with persistent IDs
+//! * 2 - RON under `eframe::APP_KEY`, typed due dates and completion timestamps
+//! * 3 - JSON envelope under [`STATE_KEY`]
+//! * 4 - tasks and gamification moved into `store`
+//!
+//! Versions 0 to 2 carry no version number. They are read through frozen copies of the old
+//! structs in [`legacy`], converted to version 3 and then continue through the migration chain.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// App ID the GUI runs under, which also names the directory eframe persists to
+pub const APP_ID: &str = "TaskHero";
+
+/// Storage key of the versioned app state
+pub const STATE_KEY: &str = "task_hero_state";
+
+/// Prefix of the storage keys that unreadable app states are copied to
+pub const BACKUP_KEY_PREFIX: &str = "task_hero_backup";
+
+/// Version of the envelope written by this build
+pub const CURRENT_VERSION: u32 = 4;
+
+/// First version that was saved in a JSON envelope. Older app states are unversioned RON.
+const FIRST_ENVELOPE_VERSION: u32 = 3;
+
+/// A migration upgrades the JSON form of the app state by exactly one version
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Migrations in order, `MIGRATIONS[i]` upgrades version `FIRST_ENVELOPE_VERSION + i` to the next one
+const MIGRATIONS: &[Migration] = &[v3_to_v4];
+
+/// Helper function to move the tasks and gamification state of a version 3 app state into the
+/// `store` object owned by `TaskStore`
+fn v3_to_v4(state: &mut Value) -> Result<(), String> {
+    let app = state
+        .as_object_mut()
+        .ok_or("the app state is not an object")?;
+    let mut store = serde_json::Map::new();
+    for key in ["tasks", "gamification"] {
+        if let Some(value) = app.remove(key) {
+            store.insert(key.to_string(), value);
+        }
+    }
+    app.insert("store".to_string(), Value::Object(store));
+    Ok(())
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+/// The on-disk envelope around the app state
+struct Envelope {
+    version: u32,
+    state: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Errors that can occur while loading a saved app state
+pub enum LoadError {
+    /// The saved data is not valid JSON/RON or doesn't match the expected shape
+    Corrupt(String),
+    /// The data was saved by a newer version of TaskHero
+    UnsupportedVersion(u32),
+    /// Upgrading from the given version failed
+    Migration { from: u32, message: String },
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Corrupt(message) => write!(f, "the saved data could not be read: {message}"),
+            LoadError::UnsupportedVersion(version) => write!(
+                f,
+                "the saved data is from a newer version of TaskHero (format {version}, this build reads up to {CURRENT_VERSION})"
+            ),
+            LoadError::Migration { from, message } => {
+                write!(f, "the saved data could not be upgraded from format {from}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A copy of saved data that could not be loaded, to be written back under its own key
+pub struct Backup {
+    pub key: String,
+    pub blob: String,
+}
+
+/// The result of loading the app state from storage
+pub struct Loaded<T> {
+    pub state: T,
+    /// Message for the user when the saved data could not be loaded
+    pub warning: Option<String>,
+    /// Unreadable data that should be kept, see [`write_backup`]
+    pub backup: Option<Backup>,
+}
+
+/// Helper function to encode the app state in the current envelope format
+pub fn encode<T: Serialize>(state: &T) -> Result<String, serde_json::Error> {
+    let envelope = Envelope {
+        version: CURRENT_VERSION,
+        state: serde_json::to_value(state)?,
+    };
+    serde_json::to_string(&envelope)
+}
+
+/// Helper function to decode an app state saved in any envelope version
+pub fn decode<T: DeserializeOwned>(blob: &str) -> Result<T, LoadError> {
+    let envelope: Envelope =
+        serde_json::from_str(blob).map_err(|err| LoadError::Corrupt(err.to_string()))?;
+    let state = migrate(envelope.version, envelope.state, MIGRATIONS)?;
+    serde_json::from_value(state).map_err(|err| LoadError::Corrupt(err.to_string()))
+}
+
+/// Helper function to decode an unversioned RON app state saved under `eframe::APP_KEY`
+/// (versions 0 to 2) and run it through the migration chain
+pub fn decode_legacy<T: DeserializeOwned>(ron_blob: &str) -> Result<T, LoadError> {
+    let state: legacy::AppState =
+        ron::from_str(ron_blob).map_err(|err| LoadError::Corrupt(err.to_string()))?;
+    let state = migrate(FIRST_ENVELOPE_VERSION, state.into_v3(), MIGRATIONS)?;
+    serde_json::from_value(state).map_err(|err| LoadError::Corrupt(err.to_string()))
+}
+
+/// Helper function to upgrade the JSON form of the app state to `CURRENT_VERSION`
+///
+/// # Arguments
+///
+/// * `version` - The version the state was saved with
+/// * `state` - The saved state
+/// * `migrations` - The migration chain, starting at `FIRST_ENVELOPE_VERSION`
+fn migrate(version: u32, mut state: Value, migrations: &[Migration]) -> Result<Value, LoadError> {
+    let current_version = FIRST_ENVELOPE_VERSION + migrations.len() as u32;
+    if version > current_version {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+    if version < FIRST_ENVELOPE_VERSION {
+        return Err(LoadError::Corrupt(format!(
+            "unknown envelope version {version}"
+        )));
+    }
+    for (from, migration) in
+        (version..).zip(&migrations[(version - FIRST_ENVELOPE_VERSION) as usize..])
+    {
+        migration(&mut state).map_err(|message| LoadError::Migration { from, message })?;
+    }
+    Ok(state)
+}
+
+/// Helper function to load the app state from storage, falling back to the default state
+/// when nothing was saved yet or the saved data can't be read
+///
+/// # Arguments
+///
+/// * `storage` - The eframe storage to read from
+pub fn load<T: DeserializeOwned + Default>(storage: &dyn eframe::Storage) -> Loaded<T> {
+    let (key, result) = if let Some(blob) = storage.get_string(STATE_KEY) {
+        (STATE_KEY, decode(&blob).map_err(|err| (err, blob)))
+    } else if let Some(blob) = storage.get_string(eframe::APP_KEY) {
+        (
+            eframe::APP_KEY,
+            decode_legacy(&blob).map_err(|err| (err, blob)),
+        )
+    } else {
+        return Loaded {
+            state: T::default(),
+            warning: None,
+            backup: None,
+        };
+    };
+
+    match result {
+        Ok(state) => Loaded {
+            state,
+            warning: None,
+            backup: None,
+        },
+        Err((err, blob)) => {
+            let backup = Backup {
+                key: format!(
+                    "{BACKUP_KEY_PREFIX}_{}",
+                    chrono::Local::now().format("%Y%m%d_%H%M%S")
+                ),
+                blob,
+            };
+            log::error!(
+                "Failed to load app state from {key:?}: {err}. Keeping a copy under {:?}",
+                backup.key
+            );
+            Loaded {
+                state: T::default(),
+                warning: Some(format!(
+                    "Your saved tasks could not be loaded because {err}. A copy of the data was kept under \"{}\".",
+                    backup.key
+                )),
+                backup: Some(backup),
+            }
+        }
+    }
+}
+
+/// Helper function to write a backup of unreadable data back to storage
+pub fn write_backup(storage: &mut dyn eframe::Storage, backup: &Backup) {
+    storage.set_string(&backup.key, backup.blob.clone());
+}
+
+/// Helper function to save the app state to storage in the current envelope format
+pub fn save<T: Serialize>(storage: &mut dyn eframe::Storage, state: &T) {
+    match encode(state) {
+        Ok(blob) => storage.set_string(STATE_KEY, blob),
+        Err(err) => log::error!("Failed to save app state: {err}"),
+    }
+}
+
+/// Frozen copies of the structs saved as RON before the app state was versioned.
+/// These must not change, they describe data that already exists on users' machines.
+mod legacy {
+    use serde::de::{self, Deserializer, Visitor};
+    use serde_json::{json, Value};
+
+    #[derive(serde::Deserialize)]
+    pub struct AppState {
+        #[serde(default)]
+        tasks: Tasks,
+        #[serde(default)]
+        selected_task: Option<u64>,
+        #[serde(default)]
+        gamification: Gamification,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Gamification {
+        bronze_goal: u32,
+        silver_goal: u32,
+        gold_goal: u32,
+    }
+
+    impl Default for Gamification {
+        fn default() -> Self {
+            Gamification {
+                bronze_goal: 5,
+                silver_goal: 10,
+                gold_goal: 20,
+            }
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Task {
+        #[serde(default)]
+        id: u64, // version 1 and later
+        name: String,
+        description: String,
+        #[serde(default, deserialize_with = "optional_string")]
+        due_date: Option<String>, // a bare string before version 2
+        priority: Priority,
+        completed: bool,
+        #[serde(default, deserialize_with = "optional_string")]
+        completed_date: Option<String>, // `YYYY-MM-DD` before version 2
+    }
+
+    #[derive(Clone, Copy, serde::Deserialize)]
+    enum Priority {
+        Low,
+        Medium,
+        High,
+    }
+
+    #[derive(Default)]
+    /// Tasks were a bare list in version 0 and `(tasks: [...], next_id: n)` afterwards
+    struct Tasks {
+        tasks: Vec<Task>,
+        next_id: Option<u64>,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(field_identifier, rename_all = "snake_case")]
+    enum TasksField {
+        Tasks,
+        NextId,
+        #[serde(other)]
+        Other,
+    }
+
+    impl<'de> serde::Deserialize<'de> for Tasks {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct TasksVisitor;
+
+            impl<'de> Visitor<'de> for TasksVisitor {
+                type Value = Tasks;
+
+                fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    formatter.write_str("a list of tasks")
+                }
+
+                fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Tasks, A::Error> {
+                    let mut tasks = Vec::new();
+                    while let Some(task) = seq.next_element()? {
+                        tasks.push(task);
+                    }
+                    Ok(Tasks {
+                        tasks,
+                        next_id: None,
+                    })
+                }
+
+                fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Tasks, A::Error> {
+                    let mut result = Tasks::default();
+                    while let Some(field) = map.next_key()? {
+                        match field {
+                            TasksField::Tasks => result.tasks = map.next_value()?,
+                            TasksField::NextId => result.next_id = Some(map.next_value()?),
+                            TasksField::Other => {
+                                map.next_value::<de::IgnoredAny>()?;
+                            }
+                        }
+                    }
+                    Ok(result)
+                }
+            }
+
+            deserializer.deserialize_any(TasksVisitor)
+        }
+    }
+
+    /// Helper function to read a string saved either bare or as `Some("...")`/`None`
+    fn optional_string<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<String>, D::Error> {
+        struct OptionalStringVisitor;
+
+        impl<'de> Visitor<'de> for OptionalStringVisitor {
+            type Value = Option<String>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str("an optional string")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(Some(value.to_string()))
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                deserializer.deserialize_any(self)
+            }
+        }
+
+        deserializer.deserialize_any(OptionalStringVisitor)
+    }
+
+    impl AppState {
+        /// Helper function to convert the legacy state into the JSON form of version 3
+        pub fn into_v3(self) -> Value {
+            // In version 0 the selection was a position in the list, which can't be mapped to an ID
+            let selected_task = self.tasks.next_id.and(self.selected_task);
+            let tasks: Vec<Value> = self.tasks.tasks.into_iter().map(Task::into_v3).collect();
+            json!({
+                "tasks": { "tasks": tasks, "next_id": self.tasks.next_id.unwrap_or(0) },
+                "selected_task": selected_task,
+                "gamification": {
+                    "points": 0,
+                    "bronze_goal": self.gamification.bronze_goal,
+                    "silver_goal": self.gamification.silver_goal,
+                    "gold_goal": self.gamification.gold_goal,
+                    "achievement_message": "",
+                    "daily_reward": 0,
+                    "daily_reward_message": "",
+                    "weekly_challenge_message": "Complete a task every day for a week to earn 100 points!",
+                },
+            })
+        }
+    }
+
+    impl Task {
+        /// Helper function to convert a legacy task into the JSON form of version 3.
+        /// Due dates that can't be understood are moved into the description so nothing is lost.
+        fn into_v3(self) -> Value {
+            // there is no simulated date yet while a file is loaded, so relative dates use the real one
+            let today = crate::clock::Clock::System.today();
+            let mut description = self.description;
+            let due_date = self
+                .due_date
+                .filter(|due| !due.trim().is_empty())
+                .and_then(|due| match crate::dates::parse_due_date(&due, today) {
+                    Ok(parsed) => Some(parsed.to_string()),
+                    Err(_) => {
+                        description = format!("{description} (Due: {due})");
+                        None
+                    }
+                });
+            let completed_date = self
+                .completed_date
+                .and_then(|completed| crate::dates::parse_completed_date(&completed, today))
+                .map(|completed| completed.to_rfc3339());
+            let priority = match self.priority {
+                Priority::Low => "Low",
+                Priority::Medium => "Medium",
+                Priority::High => "High",
+            };
+            json!({
+                "id": self.id,
+                "name": self.name,
+                "description": description,
+                "due_date": due_date,
+                "priority": priority,
+                "completed": self.completed,
+                "completed_date": completed_date,
+            })
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Storage backed by the same `app.ron` file eframe persists the GUI state to, so that tools
+/// running outside the GUI (such as the `taskhero` CLI) read and write the user's real tasks.
+/// The file holds a RON map from storage keys to strings; keys other than the ones TaskHero
+/// uses (window positions, egui memory) are kept as they are.
+pub struct FileStorage {
+    path: std::path::PathBuf,
+    kv: std::collections::HashMap<String, String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Implementation of FileStorage with methods to open and write the storage file
+impl FileStorage {
+    /// Helper function to open the storage file the GUI uses
+    ///
+    /// # Returns
+    ///
+    /// The storage, or an error if there is no data directory for this platform or the file can't be read.
+    pub fn open_default() -> std::io::Result<Self> {
+        let dir = eframe::storage_dir(APP_ID).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no data directory found for this platform",
+            )
+        })?;
+        Self::open(&dir)
+    }
+
+    /// Helper function to open the `app.ron` storage file in a directory.
+    /// A missing file is treated as empty storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory holding `app.ron`
+    pub fn open(dir: &std::path::Path) -> std::io::Result<Self> {
+        let path = dir.join("app.ron");
+        let kv = match std::fs::read_to_string(&path) {
+            Ok(contents) => ron::from_str(&contents).map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: {err}", path.display()),
+                )
+            })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Default::default(),
+            Err(err) => return Err(err),
+        };
+        Ok(FileStorage { path, kv })
+    }
+
+    /// Helper function to get the path of the storage file
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Helper function to write the storage to disk.
+    /// The file is written next to the old one and then renamed over it, so an interrupted
+    /// write never leaves a truncated file behind.
+    pub fn write(&self) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let contents = ron::ser::to_string_pretty(&self.kv, Default::default())
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        let temp_path = self.path.with_extension("ron.tmp");
+        std::fs::write(&temp_path, contents)?;
+        std::fs::rename(&temp_path, &self.path)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl eframe::Storage for FileStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.kv.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.kv.insert(key.to_string(), value);
+    }
+
+    fn flush(&mut self) {
+        if let Err(err) = self.write() {
+            log::warn!("Failed to write {}: {err}", self.path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::{parse_due_date, DueDate};
+    use crate::TemplateApp;
+    use chrono::NaiveDate;
+    use eframe::Storage;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    /// In-memory storage for testing
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn legacy_storage(ron_blob: &str) -> MemoryStorage {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(eframe::APP_KEY, ron_blob.to_string());
+        storage
+    }
+
+    #[test]
+    fn test_load_v0_baseline_fixture() {
+        let storage = legacy_storage(include_str!("../fixtures/storage/v0_baseline.ron"));
+        let loaded = load::<TemplateApp>(&storage);
+        assert_eq!(loaded.warning, None);
+
+        let tasks = loaded.state.store().tasks();
+        assert_eq!(tasks.len(), 3);
+        let names: Vec<_> = tasks.iter().map(|task| task.name.as_str()).collect();
+        assert_eq!(names, ["Write report", "Email team", "Plan sprint"]);
+        assert!(tasks.ids().iter().all(|id| id.is_assigned()));
+
+        let report = &tasks.as_slice()[0];
+        assert_eq!(
+            report.due_date,
+            Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 23).unwrap()))
+        );
+        assert_eq!(
+            report.completed_date.map(|date| date.date_naive()),
+            NaiveDate::from_ymd_opt(2024, 11, 22)
+        );
+        // "11.30.2024" was the format the Upcoming Tasks metric expected
+        assert_eq!(
+            tasks.as_slice()[1].due_date,
+            Some(DueDate::on(NaiveDate::from_ymd_opt(2024, 11, 30).unwrap()))
+        );
+        // Free-form text that is not a date is kept in the description rather than failing the load
+        assert_eq!(tasks.as_slice()[2].due_date, None);
+        assert_eq!(
+            tasks.as_slice()[2].description,
+            "Next two weeks (Due: sometime next week)"
+        );
+        assert_eq!(loaded.state.store().gamification().gold_goal, 25);
+    }
+
+    #[test]
+    fn test_load_v1_task_ids_fixture() {
+        let storage = legacy_storage(include_str!("../fixtures/storage/v1_task_ids.ron"));
+        let loaded = load::<TemplateApp>(&storage);
+        assert_eq!(loaded.warning, None);
+
+        let tasks = loaded.state.store().tasks();
+        assert_eq!(
+            tasks.ids().iter().map(|id| id.0).collect::<Vec<_>>(),
+            [2, 5]
+        );
+        assert_eq!(
+            tasks.get(crate::task_list::TaskId(5)).unwrap().name,
+            "Email team"
+        );
+    }
+
+    #[test]
+    fn test_load_v2_typed_dates_fixture() {
+        let storage = legacy_storage(include_str!("../fixtures/storage/v2_typed_dates.ron"));
+        let loaded = load::<TemplateApp>(&storage);
+        assert_eq!(loaded.warning, None);
+
+        let task = &loaded.state.store().tasks().as_slice()[0];
+        assert_eq!(
+            task.due_date,
+            parse_due_date(
+                "2024-11-23 14:30 +02:00",
+                NaiveDate::from_ymd_opt(2024, 11, 23).unwrap()
+            )
+            .ok()
+        );
+        assert!(task.completed_date.is_some());
+    }
+
+    #[test]
+    fn test_load_v3_envelope_fixture() {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(
+            STATE_KEY,
+            include_str!("../fixtures/storage/v3_envelope.json").to_string(),
+        );
+        let loaded = load::<TemplateApp>(&storage);
+        assert_eq!(loaded.warning, None);
+        assert_eq!(loaded.state.store().tasks().len(), 2);
+        assert_eq!(loaded.state.store().gamification().bronze_goal, 3);
+    }
+
+    #[test]
+    fn test_load_v4_store_fixture() {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(
+            STATE_KEY,
+            include_str!("../fixtures/storage/v4_store.json").to_string(),
+        );
+        let loaded = load::<TemplateApp>(&storage);
+        assert_eq!(loaded.warning, None);
+        assert_eq!(loaded.state.store().tasks().len(), 2);
+        assert_eq!(loaded.state.store().gamification().silver_goal, 6);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let mut storage = legacy_storage(include_str!("../fixtures/storage/v0_baseline.ron"));
+        let app = load::<TemplateApp>(&storage).state;
+        save(&mut storage, &app);
+
+        let reloaded = load::<TemplateApp>(&storage).state;
+        assert_eq!(reloaded.store().tasks().ids(), app.store().tasks().ids());
+        let envelope: Envelope =
+            serde_json::from_str(&storage.get_string(STATE_KEY).unwrap()).unwrap();
+        assert_eq!(envelope.version, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_unreadable_data_is_backed_up() {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(STATE_KEY, "{\"version\": 3, \"state\": [1, 2".to_string());
+        let loaded = load::<TemplateApp>(&storage);
+
+        assert!(loaded.state.store().tasks().is_empty());
+        assert!(loaded.warning.is_some());
+        let backup = loaded.backup.unwrap();
+        assert!(backup.key.starts_with(BACKUP_KEY_PREFIX));
+
+        write_backup(&mut storage, &backup);
+        assert_eq!(
+            storage.get_string(&backup.key).as_deref(),
+            Some("{\"version\": 3, \"state\": [1, 2")
+        );
+
+        // a due date that can't be understood is backed up with the rest of the state instead of being dropped
+        let blob = include_str!("../fixtures/storage/v3_envelope.json")
+            .replace("2024-11-23 14:30 +02:00", "whenever");
+        storage.set_string(STATE_KEY, blob.clone());
+        let loaded = load::<TemplateApp>(&storage);
+        assert!(loaded.state.store().tasks().is_empty());
+        assert_eq!(loaded.backup.unwrap().blob, blob);
+    }
+
+    #[test]
+    fn test_file_storage_keeps_other_keys() {
+        let dir =
+            std::env::temp_dir().join(format!("task_hero_file_storage_{}", std::process::id()));
+        let mut storage = FileStorage::open(&dir).unwrap();
+        assert_eq!(storage.get_string(STATE_KEY), None);
+        storage.set_string("egui", "window state".to_string());
+        save(&mut storage, &TemplateApp::default());
+        storage.write().unwrap();
+
+        let reopened = FileStorage::open(&dir).unwrap();
+        assert_eq!(reopened.get_string("egui").as_deref(), Some("window state"));
+        assert_eq!(load::<TemplateApp>(&reopened).warning, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_newer_versions_are_not_loaded() {
+        let blob = format!("{{\"version\": {}, \"state\": {{}}}}", CURRENT_VERSION + 1);
+        assert_eq!(
+            decode::<Value>(&blob).unwrap_err(),
+            LoadError::UnsupportedVersion(CURRENT_VERSION + 1)
+        );
+    }
+
+    #[test]
+    fn test_migrations_run_in_order() {
+        fn add_a(state: &mut Value) -> Result<(), String> {
+            state["steps"]
+                .as_array_mut()
+                .ok_or("missing steps")?
+                .push("a".into());
+            Ok(())
+        }
+        fn add_b(state: &mut Value) -> Result<(), String> {
+            state["steps"]
+                .as_array_mut()
+                .ok_or("missing steps")?
+                .push("b".into());
+            Ok(())
+        }
+        let chain: &[Migration] = &[add_a, add_b];
+
+        let state = serde_json::json!({ "steps": [] });
+        let migrated = migrate(FIRST_ENVELOPE_VERSION, state.clone(), chain).unwrap();
+        assert_eq!(migrated, serde_json::json!({ "steps": ["a", "b"] }));
+
+        let migrated = migrate(FIRST_ENVELOPE_VERSION + 1, state.clone(), chain).unwrap();
+        assert_eq!(migrated, serde_json::json!({ "steps": ["b"] }));
+
+        assert_eq!(
+            migrate(FIRST_ENVELOPE_VERSION, serde_json::json!({}), chain).unwrap_err(),
+            LoadError::Migration {
+                from: FIRST_ENVELOPE_VERSION,
+                message: "missing steps".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,1577 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{NaiveDate, Weekday};
+
+use crate::achievements::Achievement;
+use crate::clock::Clock;
+use crate::dates::DueDate;
+use crate::gamification::{ChecklistItem, Gamification, PriorityLevel, Task};
+use crate::ledger::{Ledger, TransactionKind};
+use crate::levels::LevelConfig;
+use crate::query::TaskQuery;
+use crate::recurrence::Recurrence;
+use crate::report::TaskReport;
+use crate::scoring::{Difficulty, ScoringModel};
+use crate::streaks::{self, Streak};
+use crate::tags;
+use crate::task_list::{TaskId, TaskList};
+use crate::transfer::duplicate_key;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Errors returned by TaskStore operations
+pub enum StoreError {
+    TaskNotFound(TaskId),
+    EmptyName,
+    InvalidGoals { bronze: u32, silver: u32, gold: u32 },
+    OpenSubtasks(TaskId),
+    InvalidParent { id: TaskId, parent: TaskId },
+    EmptyChecklistItem,
+    ChecklistItemNotFound(TaskId, usize),
+    DependencyCycle { id: TaskId, prerequisite: TaskId },
+    EmptyProject,
+    EmptyFilterName,
+    InvalidAdjustment,
+    NotEnoughPoints { needed: u32, points: u32 },
+    TooManyFreezes,
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::TaskNotFound(id) => write!(f, "There is no task {id}."),
+            StoreError::EmptyName => write!(f, "A task needs a name."),
+            StoreError::InvalidGoals {
+                bronze,
+                silver,
+                gold,
+            } => write!(
+                f,
+                "Goals must increase from bronze to gold (got {bronze}/{silver}/{gold})."
+            ),
+            StoreError::OpenSubtasks(id) => {
+                write!(f, "Task {id} still has open subtasks or checklist items.")
+            }
+            StoreError::InvalidParent { id, parent } => {
+                write!(f, "Task {id} can't be a subtask of {parent}.")
+            }
+            StoreError::EmptyChecklistItem => write!(f, "A checklist item needs some text."),
+            StoreError::ChecklistItemNotFound(id, index) => {
+                write!(f, "Task {id} has no checklist item {}.", index + 1)
+            }
+            StoreError::DependencyCycle { id, prerequisite } if id == prerequisite => {
+                write!(f, "Task {id} can't depend on itself.")
+            }
+            StoreError::DependencyCycle { id, prerequisite } => {
+                write!(f, "Task {id} can't depend on {prerequisite}, since {prerequisite} already waits for {id}.")
+            }
+            StoreError::EmptyProject => write!(f, "A project needs a name."),
+            StoreError::EmptyFilterName => write!(f, "A saved filter needs a name."),
+            StoreError::InvalidAdjustment => write!(
+                f,
+                "An adjustment needs a number of points other than 0 and a reason."
+            ),
+            StoreError::NotEnoughPoints { needed, points } => {
+                write!(f, "This needs {needed} points, but you have {points}.")
+            }
+            StoreError::TooManyFreezes => write!(
+                f,
+                "You already hold {} streak freezes, the most you can.",
+                streaks::MAX_FREEZES
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+#[derive(Debug, Clone)]
+/// NewTask struct with the fields needed to create a task
+pub struct NewTask {
+    pub name: String,
+    pub description: String,
+    pub due_date: Option<DueDate>,
+    pub priority: PriorityLevel,
+    pub recurrence: Option<Recurrence>,
+}
+
+#[derive(Debug, Clone, Default)]
+/// TaskUpdate struct with the fields to change on a task; `None` leaves a field unchanged
+pub struct TaskUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub due_date: Option<Option<DueDate>>,
+    pub priority: Option<PriorityLevel>,
+    pub tags: Option<Vec<String>>,
+    pub project: Option<Option<String>>,
+    pub recurrence: Option<Option<Recurrence>>,
+    pub auto_complete: Option<bool>,
+    pub effort: Option<Option<u32>>,
+    pub difficulty: Option<Difficulty>,
+    pub points_override: Option<Option<u32>>,
+}
+
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+/// TaskStore struct that owns the tasks and the gamification state.
+/// This is the UI-independent API that the GUI, the CLI and tests all use to
+/// create, update, complete, delete and query tasks.
+pub struct TaskStore {
+    tasks: TaskList,
+    gamification: Gamification,
+    filters: BTreeMap<String, TaskQuery>, // saved filters by name
+    ledger: Ledger, // every change to the points, from which the points are worked out
+    #[serde(skip)]
+    clock: Clock, // where completion times and the daily reward get the time from
+}
+
+/// Implementation of TaskStore with the task operations and gamification evaluation
+impl TaskStore {
+    /// Helper function to create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Helper function to build a store from its parts, such as when rebuilding it from the journal
+    pub fn from_parts(
+        tasks: TaskList,
+        gamification: Gamification,
+        filters: BTreeMap<String, TaskQuery>,
+        ledger: Ledger,
+    ) -> Self {
+        TaskStore {
+            tasks,
+            gamification,
+            filters,
+            ledger,
+            clock: Clock::default(),
+        }
+    }
+
+    /// Helper function to get the clock the store reads the time from
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// Helper function to choose the clock the store reads the time from, such as a fixed clock in tests
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// Helper function to get the points ledger
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    /// Helper function to put back the points ledger and the unlocked achievements of another
+    /// state of the store, such as after undo restored an earlier state. Points and achievements
+    /// are never taken back, so they are carried over instead.
+    pub fn keep_earned(&mut self, other: &TaskStore) {
+        self.ledger = other.ledger.clone();
+        self.gamification
+            .achievements
+            .set_unlocked(other.gamification.achievements.unlocked().to_vec());
+        self.gamification.best_level = other.gamification.best_level;
+    }
+
+    /// Helper function to replace the level curve and XP sources, or to go back to the standard ones with None.
+    /// Levels already reached aren't celebrated again.
+    pub fn set_level_config(&mut self, config: Option<LevelConfig>) {
+        self.gamification.level_config = config;
+        self.evaluate();
+    }
+
+    /// Helper function to replace the achievement rules, or to go back to the standard rules with None
+    pub fn set_achievement_rules(&mut self, rules: Option<Vec<Achievement>>) {
+        self.gamification.achievements.set_custom_rules(rules);
+    }
+
+    /// Helper function to change the points by hand
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The points to add, or to take away when negative
+    /// * `reason` - Why the points were changed
+    pub fn adjust_points(&mut self, amount: i64, reason: &str) -> Result<(), StoreError> {
+        let reason = reason.trim();
+        if amount == 0 || reason.is_empty() {
+            return Err(StoreError::InvalidAdjustment);
+        }
+        self.ledger.adjust(amount, reason, self.clock.now());
+        self.evaluate();
+        Ok(())
+    }
+
+    /// Helper function to choose the days of the week that don't break a streak
+    pub fn set_rest_days(&mut self, mut days: Vec<Weekday>) {
+        days.sort_by_key(Weekday::num_days_from_monday);
+        days.dedup();
+        self.gamification.rest_days = days;
+        self.evaluate();
+    }
+
+    /// Helper function to spend points on a streak freeze, which protects the streak on a day
+    /// without a completion
+    pub fn buy_streak_freeze(&mut self) -> Result<(), StoreError> {
+        self.evaluate();
+        let points = self.gamification.points;
+        if self.gamification.streak.freezes >= streaks::MAX_FREEZES {
+            return Err(StoreError::TooManyFreezes);
+        }
+        if points < streaks::FREEZE_COST {
+            return Err(StoreError::NotEnoughPoints {
+                needed: streaks::FREEZE_COST,
+                points,
+            });
+        }
+        self.ledger.spend(
+            TransactionKind::StreakFreeze,
+            streaks::FREEZE_COST,
+            "Bought a streak freeze",
+            self.clock.now(),
+        );
+        self.evaluate();
+        Ok(())
+    }
+
+    /// Helper function to get all tasks
+    pub fn tasks(&self) -> &TaskList {
+        &self.tasks
+    }
+
+    /// Helper function to look up a task by ID
+    pub fn task(&self, id: TaskId) -> Option<&Task> {
+        self.tasks.get(id)
+    }
+
+    /// Helper function to get the gamification state
+    pub fn gamification(&self) -> &Gamification {
+        &self.gamification
+    }
+
+    /// Helper function to look up a task by ID for changing it
+    fn task_mut(&mut self, id: TaskId) -> Result<&mut Task, StoreError> {
+        self.tasks.get_mut(id).ok_or(StoreError::TaskNotFound(id))
+    }
+
+    /// Helper function to create a task
+    ///
+    /// # Returns
+    ///
+    /// The ID of the new task, or an error if the name is empty.
+    pub fn create(&mut self, new_task: NewTask) -> Result<TaskId, StoreError> {
+        if new_task.name.trim().is_empty() {
+            return Err(StoreError::EmptyName);
+        }
+        Ok(self.tasks.push(Task {
+            description: new_task.description,
+            due_date: new_task.due_date,
+            recurrence: new_task.recurrence,
+            ..Task::new(new_task.name, new_task.priority)
+        }))
+    }
+
+    /// Helper function to create a subtask. A completed parent is reopened, since it now
+    /// has an open subtask.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - The ID of the task to add the subtask to
+    /// * `new_task` - The fields of the subtask
+    pub fn create_subtask(
+        &mut self,
+        parent: TaskId,
+        new_task: NewTask,
+    ) -> Result<TaskId, StoreError> {
+        if !self.tasks.contains(parent) {
+            return Err(StoreError::TaskNotFound(parent));
+        }
+        let id = self.create(new_task)?;
+        self.task_mut(id)?.parent = Some(parent);
+        self.settle(Some(parent));
+        Ok(id)
+    }
+
+    /// Helper function to move a task under another task, or to the top level with `None`.
+    /// A task can't be moved under itself or one of its own subtasks.
+    pub fn set_parent(&mut self, id: TaskId, parent: Option<TaskId>) -> Result<(), StoreError> {
+        let old_parent = self.task_mut(id)?.parent;
+        if let Some(parent) = parent {
+            if !self.tasks.contains(parent) {
+                return Err(StoreError::TaskNotFound(parent));
+            }
+            if parent == id || self.descendants(id).contains(&parent) {
+                return Err(StoreError::InvalidParent { id, parent });
+            }
+        }
+        self.task_mut(id)?.parent = parent;
+        self.settle(old_parent);
+        self.settle(parent);
+        Ok(())
+    }
+
+    /// Helper function to get the IDs of a task's subtasks, their subtasks, and so on
+    pub fn descendants(&self, id: TaskId) -> Vec<TaskId> {
+        let mut descendants = Vec::new();
+        let mut seen = HashSet::from([id]);
+        let mut pending = vec![id];
+        while let Some(next) = pending.pop() {
+            for child in self.tasks.children(next) {
+                if seen.insert(child.id) {
+                    descendants.push(child.id);
+                    pending.push(child.id);
+                }
+            }
+        }
+        descendants
+    }
+
+    /// Helper function to get the IDs of a task's parent, its parent, and so on up to the top level
+    pub fn ancestors(&self, id: TaskId) -> Vec<TaskId> {
+        let mut ancestors = Vec::new();
+        let mut next = self.tasks.get(id).and_then(|task| task.parent);
+        while let Some(parent) = next.filter(|&parent| {
+            parent != id && !ancestors.contains(&parent) && self.tasks.contains(parent)
+        }) {
+            ancestors.push(parent);
+            next = self.tasks.get(parent).and_then(|task| task.parent);
+        }
+        ancestors
+    }
+
+    /// Helper function to check whether all of a task's direct subtasks and checklist items are done.
+    /// A task without subtasks or checklist items has nothing holding it back.
+    pub fn children_done(&self, id: TaskId) -> bool {
+        let checklist_done = self
+            .tasks
+            .get(id)
+            .is_some_and(|task| task.checklist.iter().all(|item| item.done));
+        checklist_done && self.tasks.children(id).all(|child| child.completed)
+    }
+
+    /// Helper function to check whether a task has subtasks or checklist items
+    pub fn has_children(&self, id: TaskId) -> bool {
+        self.tasks
+            .get(id)
+            .is_some_and(|task| !task.checklist.is_empty())
+            || self.tasks.children(id).next().is_some()
+    }
+
+    /// Helper function to make a task wait for another task to be completed.
+    /// Adding a dependency that already exists changes nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task that has to wait
+    /// * `prerequisite` - The ID of the task it waits for
+    ///
+    /// # Returns
+    ///
+    /// An error if either task doesn't exist, or if the prerequisite already waits for the task,
+    /// directly or through other tasks, since neither could ever start.
+    pub fn add_dependency(&mut self, id: TaskId, prerequisite: TaskId) -> Result<(), StoreError> {
+        if !self.tasks.contains(prerequisite) {
+            return Err(StoreError::TaskNotFound(prerequisite));
+        }
+        self.task_mut(id)?;
+        if prerequisite == id || self.waits_for(prerequisite, id) {
+            return Err(StoreError::DependencyCycle { id, prerequisite });
+        }
+        let task = self.task_mut(id)?;
+        if !task.depends_on.contains(&prerequisite) {
+            task.depends_on.push(prerequisite);
+        }
+        Ok(())
+    }
+
+    /// Helper function to stop a task waiting for another task
+    ///
+    /// # Returns
+    ///
+    /// Whether the task was waiting for it.
+    pub fn remove_dependency(
+        &mut self,
+        id: TaskId,
+        prerequisite: TaskId,
+    ) -> Result<bool, StoreError> {
+        let task = self.task_mut(id)?;
+        let before = task.depends_on.len();
+        task.depends_on
+            .retain(|&candidate| candidate != prerequisite);
+        Ok(task.depends_on.len() != before)
+    }
+
+    /// Helper function to check whether a task waits for another task, directly or through other tasks
+    fn waits_for(&self, id: TaskId, prerequisite: TaskId) -> bool {
+        let mut seen = HashSet::from([id]);
+        let mut pending = vec![id];
+        while let Some(next) = pending.pop() {
+            for &candidate in self
+                .tasks
+                .get(next)
+                .map_or(&[][..], |task| &task.depends_on)
+            {
+                if candidate == prerequisite {
+                    return true;
+                }
+                if seen.insert(candidate) {
+                    pending.push(candidate);
+                }
+            }
+        }
+        false
+    }
+
+    /// Helper function to get the prerequisites of a task that are not completed yet.
+    /// Prerequisites that have been deleted no longer block anything.
+    pub fn blocked_by(&self, id: TaskId) -> Vec<TaskId> {
+        let Some(task) = self.tasks.get(id) else {
+            return Vec::new();
+        };
+        task.depends_on
+            .iter()
+            .copied()
+            .filter(|&prerequisite| {
+                self.tasks
+                    .get(prerequisite)
+                    .is_some_and(|task| !task.completed)
+            })
+            .collect()
+    }
+
+    /// Helper function to check whether a task is waiting for an open prerequisite
+    pub fn is_blocked(&self, id: TaskId) -> bool {
+        !self.blocked_by(id).is_empty()
+    }
+
+    /// Helper function to check whether a task is ready to work on: not completed and not blocked
+    pub fn is_ready(&self, id: TaskId) -> bool {
+        self.tasks.get(id).is_some_and(|task| !task.completed) && !self.is_blocked(id)
+    }
+
+    /// Helper function to get the IDs of the tasks that wait for a task, in insertion order
+    pub fn dependents(&self, id: TaskId) -> Vec<TaskId> {
+        self.tasks
+            .iter()
+            .filter(|task| task.depends_on.contains(&id))
+            .map(|task| task.id)
+            .collect()
+    }
+
+    /// Helper function to add an existing task, such as one read from an export file, keeping its
+    /// completion state and dates. The task is given a new ID.
+    pub fn insert(&mut self, task: Task) -> TaskId {
+        self.tasks.push(task)
+    }
+
+    /// Helper function to replace every task with the given ones, which are given fresh IDs. A task
+    /// replacing one with the same name and due date takes over the points that one was paid, so
+    /// a task that arrives completed doesn't earn its points twice.
+    pub fn replace_tasks(&mut self, tasks: Vec<Task>) {
+        let mut replaced: Vec<_> = self
+            .tasks
+            .iter()
+            .map(|task| (task.id, duplicate_key(task)))
+            .collect();
+        self.tasks.replace_all(tasks);
+        let now = self.clock.now();
+        for task in self.tasks.iter() {
+            if let Some(index) = replaced
+                .iter()
+                .position(|(_, key)| *key == duplicate_key(task))
+            {
+                let (id, _) = replaced.remove(index);
+                self.ledger.transfer(id, task.id, now);
+            }
+        }
+    }
+
+    /// Helper function to change the fields of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to change
+    /// * `update` - The fields to change
+    pub fn update(&mut self, id: TaskId, update: TaskUpdate) -> Result<(), StoreError> {
+        if update
+            .name
+            .as_ref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            return Err(StoreError::EmptyName);
+        }
+        let rescore = update.points_override.is_some();
+        let task = self.task_mut(id)?;
+        if let Some(name) = update.name {
+            task.name = name;
+        }
+        if let Some(description) = update.description {
+            task.description = description;
+        }
+        if let Some(due_date) = update.due_date {
+            task.due_date = due_date;
+        }
+        if let Some(priority) = update.priority {
+            task.priority = priority;
+        }
+        if let Some(tags) = update.tags {
+            task.tags = tags::normalize_tags(tags);
+        }
+        if let Some(project) = update.project {
+            task.project = tags::normalize_project(project);
+        }
+        if let Some(recurrence) = update.recurrence {
+            task.recurrence = recurrence;
+        }
+        if let Some(effort) = update.effort {
+            task.effort = effort;
+        }
+        if let Some(difficulty) = update.difficulty {
+            task.difficulty = difficulty;
+        }
+        if let Some(points_override) = update.points_override {
+            task.points_override = points_override;
+        }
+        if let Some(auto_complete) = update.auto_complete {
+            task.auto_complete = auto_complete;
+            self.settle(Some(id));
+        }
+        // points set by hand change the points paid for a completed task too
+        if rescore {
+            self.keep_score(id);
+        }
+        Ok(())
+    }
+
+    /// Helper function to keep the score a completed task gets with the current scoring model,
+    /// so later changes to the scoring don't change the points paid for it
+    fn keep_score(&mut self, id: TaskId) {
+        if let Some(task) = self.tasks.get_mut(id).filter(|task| task.completed) {
+            task.score = Some(self.gamification.scoring.score(task));
+        }
+    }
+
+    /// Helper function to mark a task as completed or not completed.
+    /// Completion is a transition: the full completion time is recorded once, when an open task
+    /// becomes completed, and cleared when the task is reopened, so completing it again records
+    /// the new time. Setting the state a task already has changes nothing, so a task completed
+    /// last week still counts for last week however often the app checks or redraws it.
+    pub fn set_completed(&mut self, id: TaskId, completed: bool) -> Result<(), StoreError> {
+        if completed {
+            self.complete(id).map(|_| ())
+        } else {
+            self.uncomplete(id)
+        }
+    }
+
+    /// Helper function to mark a task as completed.
+    /// A task can only be completed once all its subtasks and checklist items are done, and
+    /// completing the last open subtask of an auto-completing parent completes the parent too.
+    /// Completing a repeating task adds its next occurrence, which takes over the recurrence
+    /// rule, so reopening and completing the task again doesn't add another one.
+    ///
+    /// # Returns
+    ///
+    /// The ID of the next occurrence, if one was added.
+    pub fn complete(&mut self, id: TaskId) -> Result<Option<TaskId>, StoreError> {
+        let task = self.task_mut(id)?;
+        if task.completed {
+            return Ok(None);
+        }
+        if !self.children_done(id) {
+            return Err(StoreError::OpenSubtasks(id));
+        }
+        let next = self.mark_completed(id);
+        let parent = self.tasks.get(id).and_then(|task| task.parent);
+        self.settle(parent);
+        Ok(next)
+    }
+
+    /// Helper function to record a task as completed and add the next occurrence of a repeating task.
+    /// The next occurrence starts with its checklist unchecked; subtasks stay with the completed task.
+    fn mark_completed(&mut self, id: TaskId) -> Option<TaskId> {
+        let now = self.clock.now();
+        let task = self.tasks.get_mut(id)?;
+        task.completed = true;
+        task.completed_date = Some(now);
+        task.score = Some(self.gamification.scoring.score(task));
+
+        let recurrence = task.recurrence.take()?;
+        let (due_date, recurrence) = recurrence.next(task.due_date, now.date_naive())?;
+        let next = Task {
+            id: TaskId::default(), // assigned by the task list
+            due_date: Some(due_date),
+            completed: false,
+            completed_date: None,
+            score: None,
+            recurrence: Some(recurrence),
+            checklist: task
+                .checklist
+                .iter()
+                .map(|item| ChecklistItem {
+                    done: false,
+                    ..item.clone()
+                })
+                .collect(),
+            ..task.clone()
+        };
+        Some(self.tasks.push(next))
+    }
+
+    /// Helper function to mark a completed task as not completed, clearing its completion time.
+    /// Completed parents of the task are reopened too. Reopening an open task changes nothing,
+    /// not even its parents: those are left as they are, since a parent with an open subtask has
+    /// already been reopened when the subtask was added or reopened.
+    pub fn uncomplete(&mut self, id: TaskId) -> Result<(), StoreError> {
+        let task = self.task_mut(id)?;
+        if !task.completed {
+            return Ok(());
+        }
+        task.completed = false;
+        task.completed_date = None;
+        task.score = None;
+        let parent = task.parent;
+        self.settle(parent);
+        Ok(())
+    }
+
+    /// Helper function to bring a task and its parents in line with their subtasks and checklists:
+    /// a completed task with open children is reopened, and an auto-completing task whose
+    /// children are all done is completed
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The ID of the first task to check, after which its parents are checked in turn
+    fn settle(&mut self, start: Option<TaskId>) {
+        let mut seen = HashSet::new();
+        let mut next = start;
+        while let Some(id) = next {
+            if !seen.insert(id) {
+                break;
+            }
+            let done = self.children_done(id);
+            let has_children = self.has_children(id);
+            let Some(task) = self.tasks.get_mut(id) else {
+                break;
+            };
+            if task.completed && !done {
+                task.completed = false;
+                task.completed_date = None;
+                task.score = None;
+            } else if !task.completed && done && has_children && task.auto_complete {
+                self.mark_completed(id);
+            }
+            next = self.tasks.get(id).and_then(|task| task.parent);
+        }
+    }
+
+    /// Helper function to delete a task together with all its subtasks
+    ///
+    /// # Returns
+    ///
+    /// The deleted task.
+    pub fn delete(&mut self, id: TaskId) -> Result<Task, StoreError> {
+        let descendants = self.descendants(id);
+        let task = self.tasks.remove(id).ok_or(StoreError::TaskNotFound(id))?;
+        for descendant in &descendants {
+            self.tasks.remove(*descendant);
+        }
+        // nothing can wait for a deleted task
+        for other in self.tasks.iter_mut() {
+            other
+                .depends_on
+                .retain(|prerequisite| *prerequisite != id && !descendants.contains(prerequisite));
+        }
+        self.settle(task.parent);
+        Ok(task)
+    }
+
+    /// Helper function to add an item to a task's checklist. A completed task is reopened.
+    pub fn add_checklist_item(&mut self, id: TaskId, text: &str) -> Result<(), StoreError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(StoreError::EmptyChecklistItem);
+        }
+        self.task_mut(id)?.checklist.push(ChecklistItem {
+            text: text.to_string(),
+            done: false,
+        });
+        self.settle(Some(id));
+        Ok(())
+    }
+
+    /// Helper function to check or uncheck a checklist item
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task with the checklist
+    /// * `index` - The position of the item in the checklist, starting at 0
+    /// * `done` - Whether the item is done
+    pub fn set_checklist_item(
+        &mut self,
+        id: TaskId,
+        index: usize,
+        done: bool,
+    ) -> Result<(), StoreError> {
+        let item = self
+            .task_mut(id)?
+            .checklist
+            .get_mut(index)
+            .ok_or(StoreError::ChecklistItemNotFound(id, index))?;
+        item.done = done;
+        self.settle(Some(id));
+        Ok(())
+    }
+
+    /// Helper function to remove an item from a task's checklist
+    ///
+    /// # Returns
+    ///
+    /// The removed item.
+    pub fn remove_checklist_item(
+        &mut self,
+        id: TaskId,
+        index: usize,
+    ) -> Result<ChecklistItem, StoreError> {
+        let task = self.task_mut(id)?;
+        if index >= task.checklist.len() {
+            return Err(StoreError::ChecklistItemNotFound(id, index));
+        }
+        let item = task.checklist.remove(index);
+        self.settle(Some(id));
+        Ok(item)
+    }
+
+    /// Helper function to delete every task
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    /// Helper function to find the tasks matching a condition, in insertion order
+    pub fn query<'a>(
+        &'a self,
+        predicate: impl Fn(&Task) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Task> + 'a {
+        self.tasks.iter().filter(move |task| predicate(task))
+    }
+
+    /// Helper function to compute the Tasks Report metrics
+    pub fn report(&self, today: NaiveDate) -> TaskReport {
+        TaskReport::new(self.tasks.as_slice(), &self.gamification.scoring, today)
+    }
+
+    /// Helper function to compute the Tasks Report metrics of the tasks that match a query
+    pub fn report_matching(&self, query: &TaskQuery, today: NaiveDate) -> TaskReport {
+        let tasks: Vec<Task> = query.tasks(self, today).into_iter().cloned().collect();
+        TaskReport::new(&tasks, &self.gamification.scoring, today)
+    }
+
+    /// Helper function to get the saved filters by name
+    pub fn filters(&self) -> &BTreeMap<String, TaskQuery> {
+        &self.filters
+    }
+
+    /// Helper function to save a query under a name, replacing a filter saved under the same name
+    pub fn save_filter(&mut self, name: &str, query: TaskQuery) -> Result<(), StoreError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(StoreError::EmptyFilterName);
+        }
+        self.filters.insert(name.to_string(), query);
+        Ok(())
+    }
+
+    /// Helper function to delete a saved filter
+    ///
+    /// # Returns
+    ///
+    /// The deleted filter, or None if there was no filter with the name.
+    pub fn remove_filter(&mut self, name: &str) -> Option<TaskQuery> {
+        self.filters.remove(name.trim())
+    }
+
+    /// Helper function to change the bronze, silver and gold task goals.
+    /// The goals must be at least 1 and strictly increasing.
+    pub fn set_goals(&mut self, bronze: u32, silver: u32, gold: u32) -> Result<(), StoreError> {
+        if bronze == 0 || bronze >= silver || silver >= gold {
+            return Err(StoreError::InvalidGoals {
+                bronze,
+                silver,
+                gold,
+            });
+        }
+        self.gamification.bronze_goal = bronze;
+        self.gamification.silver_goal = silver;
+        self.gamification.gold_goal = gold;
+        Ok(())
+    }
+
+    /// Helper function to turn the bonus for completing tasks that others wait for on or off
+    pub fn set_unblock_bonus(&mut self, enabled: bool) {
+        self.gamification.unblock_bonus = enabled;
+    }
+
+    /// Helper function to choose how the points of a task are worked out.
+    /// Tasks already completed keep the points they were paid, but the checked off items of open
+    /// tasks are re-priced: the ledger pays the difference when they are worth more with the new
+    /// scoring, and takes it back when they are worth less.
+    pub fn set_scoring(&mut self, scoring: ScoringModel) {
+        self.gamification.scoring = scoring;
+        self.evaluate();
+    }
+
+    /// Helper function to set the number of tasks to complete in a project.
+    /// A goal of 0 removes the project's goal.
+    pub fn set_project_goal(&mut self, project: &str, goal: u32) -> Result<(), StoreError> {
+        let project =
+            tags::normalize_project(Some(project.to_string())).ok_or(StoreError::EmptyProject)?;
+        if goal == 0 {
+            self.gamification.project_goals.remove(&project);
+        } else {
+            self.gamification.project_goals.insert(project, goal);
+        }
+        Ok(())
+    }
+
+    /// Helper function to update the achievements, the daily reward and the weekly challenge from the
+    /// tasks, and to bank the points earned since the last check in the ledger. The points are the
+    /// balance of the ledger.
+    pub fn evaluate(&mut self) {
+        // completed tasks that weren't scored yet, such as imported ones, keep the score they get now
+        for task in self.tasks.iter_mut() {
+            if !task.completed {
+                task.score = None;
+            } else if task.score.is_none() {
+                task.score = Some(self.gamification.scoring.score(task));
+            }
+        }
+        let tasks = self.tasks.as_slice();
+        let today = self.clock.today();
+        let freezes_bought: Vec<NaiveDate> = self
+            .ledger
+            .transactions()
+            .iter()
+            .filter(|transaction| transaction.kind == TransactionKind::StreakFreeze)
+            .map(|transaction| transaction.at.date_naive())
+            .collect();
+        self.gamification.streak =
+            Streak::track(tasks, &freezes_bought, &self.gamification.rest_days, today);
+        // check the daily goal and the weekly challenge, which the ledger pays; a daily rule on the
+        // points sees the balance as the ledger was last settled
+        self.gamification.daily_reward(tasks, today);
+        let completed_every_day = self.gamification.weekly_challenge(tasks, today);
+        self.ledger.reconcile(
+            tasks,
+            &self.gamification,
+            completed_every_day,
+            self.clock.now(),
+        );
+        // the points are the balance of the ledger, so the challenges are checked once it is settled
+        self.gamification.points = self.ledger.points();
+        self.gamification.unblock_bonus_points = self.ledger.unblock_bonus();
+        let config = self.gamification.level_config();
+        let level = config.level(config.xp(&self.ledger, &self.gamification.streak));
+        self.gamification.level = level;
+        self.gamification.best_level = self.gamification.best_level.max(level.level);
+        self.gamification.check_challenges(tasks, today); // Use gamification system to check challenges
+        self.gamification
+            .unlock_achievements(tasks, self.clock.now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::Formula;
+    use chrono::Duration;
+
+    /// The clock the tests run on, so they give the same result on any day
+    fn clock() -> Clock {
+        Clock::fixed_on(NaiveDate::from_ymd_opt(2024, 11, 20).unwrap())
+    }
+
+    fn new_task(name: &str, priority: PriorityLevel) -> NewTask {
+        NewTask {
+            name: name.to_string(),
+            description: "A task".to_string(),
+            due_date: None,
+            priority,
+            recurrence: None,
+        }
+    }
+
+    #[test]
+    fn test_create_update_delete() {
+        let mut store = TaskStore::new();
+        assert_eq!(
+            store.create(new_task("  ", PriorityLevel::Low)),
+            Err(StoreError::EmptyName)
+        );
+
+        let id = store
+            .create(new_task("Task 1", PriorityLevel::Low))
+            .unwrap();
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    name: Some("Renamed".to_string()),
+                    priority: Some(PriorityLevel::High),
+                    due_date: Some(Some(DueDate::on(
+                        NaiveDate::from_ymd_opt(2024, 11, 23).unwrap(),
+                    ))),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let task = store.task(id).unwrap();
+        assert_eq!(task.name, "Renamed");
+        assert_eq!(task.description, "A task");
+        assert_eq!(task.priority, PriorityLevel::High);
+        assert!(task.due_date.is_some());
+
+        assert_eq!(store.delete(id).unwrap().name, "Renamed");
+        assert!(
+            matches!(store.delete(id), Err(StoreError::TaskNotFound(missing)) if missing == id)
+        );
+        assert_eq!(store.complete(id), Err(StoreError::TaskNotFound(id)));
+    }
+
+    #[test]
+    fn test_complete_records_time_once() {
+        let mut store = TaskStore::new();
+        let id = store
+            .create(new_task("Task 1", PriorityLevel::Medium))
+            .unwrap();
+
+        store.complete(id).unwrap();
+        let completed_date = store.task(id).unwrap().completed_date;
+        assert!(completed_date.is_some());
+
+        // Completing again keeps the original completion time
+        store.complete(id).unwrap();
+        assert_eq!(store.task(id).unwrap().completed_date, completed_date);
+
+        store.uncomplete(id).unwrap();
+        let task = store.task(id).unwrap();
+        assert!(!task.completed);
+        assert_eq!(task.completed_date, None);
+    }
+
+    #[test]
+    fn test_completion_transitions() {
+        let mut store = TaskStore::new();
+        let mut clock = clock();
+        store.set_clock(clock);
+        let id = store
+            .create(new_task("Task 1", PriorityLevel::Medium))
+            .unwrap();
+
+        // the full time of the transition is recorded, not the start of the day
+        store.set_completed(id, true).unwrap();
+        let first = store.task(id).unwrap().completed_date.unwrap();
+        assert_eq!(first, clock.now());
+        clock.advance(Duration::days(1));
+        store.set_clock(clock);
+
+        // nothing but a transition touches the time: checking the rewards, setting the same state
+        // again, editing the task or reopening an open task
+        store.evaluate();
+        store.set_completed(id, true).unwrap();
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    name: Some("Renamed".to_string()),
+                    priority: Some(PriorityLevel::High),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.evaluate();
+        assert_eq!(store.task(id).unwrap().completed_date, Some(first));
+        let open = store
+            .create(new_task("Task 2", PriorityLevel::Low))
+            .unwrap();
+        store.uncomplete(open).unwrap();
+        assert_eq!(store.task(open).unwrap().completed_date, None);
+
+        // reopening clears the time and completing again records a new one
+        store.set_completed(id, false).unwrap();
+        assert_eq!(store.task(id).unwrap().completed_date, None);
+        store.set_completed(id, true).unwrap();
+        assert_eq!(store.task(id).unwrap().completed_date, Some(clock.now()));
+    }
+
+    #[test]
+    fn test_old_completions_keep_their_day() {
+        let mut store = TaskStore::new();
+        let mut clock = clock();
+        store.set_clock(clock);
+        let ids: Vec<TaskId> = (0..5)
+            .map(|n| {
+                store
+                    .create(new_task(&format!("Task {n}"), PriorityLevel::Low))
+                    .unwrap()
+            })
+            .collect();
+        for &id in &ids {
+            store.complete(id).unwrap();
+        }
+        store.evaluate();
+        assert_eq!(store.gamification().daily_reward, 25);
+
+        // a week later the tasks still count for the day they were completed, however often they are checked again
+        let completed_at = clock.now();
+        clock.advance(Duration::days(7));
+        store.set_clock(clock);
+        for _ in 0..3 {
+            for &id in &ids {
+                store.set_completed(id, true).unwrap();
+            }
+            store.evaluate();
+        }
+        assert_eq!(store.gamification().daily_reward, 0);
+        assert!(ids
+            .iter()
+            .all(|&id| store.task(id).unwrap().completed_date == Some(completed_at)));
+    }
+
+    #[test]
+    fn test_reopening_a_subtask_reopens_its_parent() {
+        let mut store = TaskStore::new();
+        let trip = store
+            .create(new_task("Plan the trip", PriorityLevel::High))
+            .unwrap();
+        store
+            .update(
+                trip,
+                TaskUpdate {
+                    auto_complete: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let flights = store
+            .create_subtask(trip, new_task("Book flights", PriorityLevel::Medium))
+            .unwrap();
+        store.complete(flights).unwrap();
+        let completed_date = store.task(trip).unwrap().completed_date;
+        assert!(completed_date.is_some());
+
+        // completing the subtask again doesn't complete the parent again
+        store.complete(flights).unwrap();
+        assert_eq!(store.task(trip).unwrap().completed_date, completed_date);
+
+        store.uncomplete(flights).unwrap();
+        let parent = store.task(trip).unwrap();
+        assert!(!parent.completed && parent.completed_date.is_none());
+        store.complete(flights).unwrap();
+        assert!(store.task(trip).unwrap().completed_date >= completed_date);
+    }
+
+    #[test]
+    fn test_evaluate_awards_points() {
+        let mut store = TaskStore::new();
+        let low = store
+            .create(new_task("Task 1", PriorityLevel::Low))
+            .unwrap();
+        let high = store
+            .create(new_task("Task 2", PriorityLevel::High))
+            .unwrap();
+        store
+            .create(new_task("Task 3", PriorityLevel::High))
+            .unwrap();
+        store.complete(low).unwrap();
+        store.complete(high).unwrap();
+
+        store.evaluate();
+        assert_eq!(store.gamification().points, 40);
+        assert_eq!(
+            store.gamification().achievement_message,
+            "Keep going! You're progressing toward the next level!"
+        );
+        assert_eq!(store.query(|task| !task.completed).count(), 1);
+    }
+
+    #[test]
+    fn test_set_scoring_settles_the_points() {
+        let mut store = TaskStore::new();
+        let done = store
+            .create(new_task("Task 1", PriorityLevel::High))
+            .unwrap();
+        store.complete(done).unwrap();
+        let id = store.create(new_task("Pack", PriorityLevel::High)).unwrap();
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    difficulty: Some(Difficulty::Hard),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.add_checklist_item(id, "Passport").unwrap();
+        store.add_checklist_item(id, "Charger").unwrap();
+        store.set_checklist_item(id, 0, true).unwrap();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 30 + 15);
+
+        // the checked off items are worth more with the new formula, the completed task keeps its points
+        store.set_scoring(ScoringModel {
+            formula: Formula::Weighted,
+            ..Default::default()
+        });
+        let transaction = store.ledger().transactions().last().unwrap();
+        assert_eq!(
+            (transaction.amount, transaction.reason.as_str()),
+            (7, "Checked off items of \"Pack\"")
+        );
+        assert_eq!(store.ledger().paid(&format!("task {id}")), 22);
+        assert_eq!(store.ledger().paid(&format!("task {done}")), 30);
+        assert_eq!(store.gamification().points, 30 + 22);
+
+        // and worth less again with the old one, which takes the difference back
+        store.set_scoring(ScoringModel::default());
+        let transaction = store.ledger().transactions().last().unwrap();
+        assert_eq!(
+            (
+                transaction.kind,
+                transaction.amount,
+                transaction.reason.as_str()
+            ),
+            (
+                TransactionKind::Penalty,
+                -7,
+                "Lowered the points of \"Pack\""
+            )
+        );
+        assert_eq!(store.ledger().paid(&format!("task {id}")), 15);
+        assert_eq!(store.gamification().points, 30 + 15);
+    }
+
+    #[test]
+    fn test_completed_tasks_keep_their_score() {
+        let mut store = TaskStore::new();
+        store.set_scoring(ScoringModel {
+            formula: Formula::Weighted,
+            ..Default::default()
+        });
+        let id = store
+            .create(new_task("Write the report", PriorityLevel::High))
+            .unwrap();
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    difficulty: Some(Difficulty::Hard),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.complete(id).unwrap();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 45);
+        let paid = store
+            .task(id)
+            .unwrap()
+            .score
+            .clone()
+            .expect("a completed task keeps its score");
+        assert_eq!((paid.points, paid.steps.len()), (45, 2));
+
+        // changing the scoring doesn't change the points already paid, or how they were worked out
+        store.set_scoring(ScoringModel::default());
+        store.evaluate();
+        assert_eq!(store.gamification().points, 45);
+        assert_eq!(store.ledger().paid(&format!("task {id}")), 45);
+        assert_eq!(store.task(id).unwrap().score, Some(paid));
+
+        // points set by hand settle the difference, and clearing them scores the task with the current model
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    points_override: Some(Some(50)),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 50);
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    points_override: Some(None),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 30);
+
+        // a reopened task is scored again when it is completed again
+        store.set_scoring(ScoringModel {
+            formula: Formula::Weighted,
+            ..Default::default()
+        });
+        store.uncomplete(id).unwrap();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 0);
+        store.complete(id).unwrap();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 45);
+    }
+
+    #[test]
+    fn test_repeating_task_credits_every_completion() {
+        let mut store = TaskStore::new();
+        let mut clock = clock();
+        store.set_clock(clock);
+        let mut id = store
+            .create(NewTask {
+                due_date: Some(DueDate::on(clock.today())),
+                recurrence: Some(Recurrence::daily()),
+                ..new_task("Standup notes", PriorityLevel::Low)
+            })
+            .unwrap();
+        for _ in 0..7 {
+            id = store
+                .complete(id)
+                .unwrap()
+                .expect("a daily task always has a next occurrence");
+        }
+        let next = store.task(id).unwrap();
+        assert!(!next.completed && next.recurrence.is_some());
+        assert_eq!(
+            next.due_date.unwrap().date,
+            clock.today() + Duration::days(7)
+        );
+
+        // every completed occurrence earns its points and counts toward the daily reward, which is banked too
+        store.evaluate();
+        assert_eq!(store.tasks().len(), 8);
+        assert_eq!(store.gamification().points, 70 + 25);
+        assert_eq!(store.gamification().daily_reward, 25);
+
+        // completing an occurrence on each of seven days wins the weekly challenge
+        let mut store = TaskStore::new();
+        store.set_clock(clock);
+        let mut id = store
+            .create(NewTask {
+                recurrence: Some(Recurrence::daily()),
+                ..new_task("Standup notes", PriorityLevel::Low)
+            })
+            .unwrap();
+        for _ in 0..7 {
+            id = store
+                .complete(id)
+                .unwrap()
+                .expect("a daily task always has a next occurrence");
+            clock.advance(Duration::days(1));
+            store.set_clock(clock);
+        }
+        let mut gamification = Gamification::new();
+        gamification.weekly_challenge(store.tasks().as_slice(), clock.today() - Duration::days(1));
+        assert_eq!(
+            gamification.weekly_challenge_message,
+            "Congrats! You completed a task every day for the last week!"
+        );
+        // a day later the first of them is more than a week ago
+        let mut gamification = Gamification::new();
+        gamification.weekly_challenge(store.tasks().as_slice(), clock.today());
+        assert_ne!(
+            gamification.weekly_challenge_message,
+            "Congrats! You completed a task every day for the last week!"
+        );
+    }
+
+    #[test]
+    fn test_subtasks_gate_or_derive_parent_completion() {
+        let mut store = TaskStore::new();
+        let trip = store
+            .create(new_task("Plan the trip", PriorityLevel::High))
+            .unwrap();
+        let flights = store
+            .create_subtask(trip, new_task("Book flights", PriorityLevel::Medium))
+            .unwrap();
+        let prices = store
+            .create_subtask(flights, new_task("Compare prices", PriorityLevel::Low))
+            .unwrap();
+
+        // A parent can't be completed while a subtask is open
+        assert_eq!(
+            store.complete(flights),
+            Err(StoreError::OpenSubtasks(flights))
+        );
+        store.complete(prices).unwrap();
+        store.complete(flights).unwrap();
+        assert!(!store.task(trip).unwrap().completed);
+
+        // An auto-completing parent follows its subtasks, both ways
+        store
+            .update(
+                trip,
+                TaskUpdate {
+                    auto_complete: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(store.task(trip).unwrap().completed);
+        store.uncomplete(prices).unwrap();
+        assert!(!store.task(flights).unwrap().completed && !store.task(trip).unwrap().completed);
+
+        // Each completed subtask earns its own points
+        store.evaluate();
+        assert_eq!(store.gamification().points, 0);
+        store.complete(prices).unwrap();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 10);
+
+        // Subtasks can't form a cycle, and deleting a task deletes its subtasks
+        assert_eq!(
+            store.set_parent(trip, Some(prices)),
+            Err(StoreError::InvalidParent {
+                id: trip,
+                parent: prices
+            })
+        );
+        assert_eq!(store.descendants(trip), vec![flights, prices]);
+        store.delete(flights).unwrap();
+        assert!(store.tasks().len() == 1 && store.task(prices).is_none());
+    }
+
+    #[test]
+    fn test_checklist_items() {
+        let mut store = TaskStore::new();
+        let id = store
+            .create(new_task("Pack", PriorityLevel::Medium))
+            .unwrap();
+        assert_eq!(
+            store.add_checklist_item(id, "  "),
+            Err(StoreError::EmptyChecklistItem)
+        );
+        store.add_checklist_item(id, "Passport").unwrap();
+        store.add_checklist_item(id, "Charger").unwrap();
+        assert_eq!(
+            store.set_checklist_item(id, 2, true),
+            Err(StoreError::ChecklistItemNotFound(id, 2))
+        );
+
+        store.set_checklist_item(id, 0, true).unwrap();
+        assert_eq!(store.complete(id), Err(StoreError::OpenSubtasks(id)));
+        store.evaluate();
+        assert_eq!(store.gamification().points, 10);
+
+        // Removing the last open item lets an auto-completing task complete itself
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    auto_complete: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(store.remove_checklist_item(id, 1).unwrap().text, "Charger");
+        assert!(store.task(id).unwrap().completed);
+
+        // A new item reopens it
+        store.add_checklist_item(id, "Socks").unwrap();
+        assert!(!store.task(id).unwrap().completed);
+    }
+
+    #[test]
+    fn test_dependencies_block_until_completed() {
+        let mut store = TaskStore::new();
+        let design = store
+            .create(new_task("Design", PriorityLevel::Medium))
+            .unwrap();
+        let build = store
+            .create(new_task("Build", PriorityLevel::High))
+            .unwrap();
+        let ship = store.create(new_task("Ship", PriorityLevel::Low)).unwrap();
+        store.add_dependency(build, design).unwrap();
+        store.add_dependency(ship, build).unwrap();
+        store.add_dependency(ship, build).unwrap();
+        assert_eq!(store.task(ship).unwrap().depends_on, [build]);
+
+        // a dependency that closes a loop is refused
+        assert_eq!(
+            store.add_dependency(design, ship),
+            Err(StoreError::DependencyCycle {
+                id: design,
+                prerequisite: ship
+            })
+        );
+        assert_eq!(
+            store.add_dependency(design, design),
+            Err(StoreError::DependencyCycle {
+                id: design,
+                prerequisite: design
+            })
+        );
+
+        assert_eq!(store.blocked_by(ship), [build]);
+        assert!(store.is_ready(design) && !store.is_ready(build));
+        store.complete(design).unwrap();
+        assert!(store.is_ready(build));
+        assert_eq!(store.dependents(build), [ship]);
+
+        // the optional bonus pays for every task a completed task was holding up
+        store.set_unblock_bonus(true);
+        store.evaluate();
+        assert_eq!(
+            store.gamification().points,
+            20 + crate::gamification::UNBLOCK_BONUS
+        );
+
+        // deleting a prerequisite unblocks the tasks waiting for it
+        store.delete(build).unwrap();
+        assert!(store.task(ship).unwrap().depends_on.is_empty());
+        assert!(store
+            .remove_dependency(ship, build)
+            .is_ok_and(|removed| !removed));
+    }
+
+    #[test]
+    fn test_set_goals_validates_order() {
+        let mut store = TaskStore::new();
+        assert!(store.set_goals(2, 4, 8).is_ok());
+        assert_eq!(store.gamification().silver_goal, 4);
+        assert_eq!(
+            store.set_goals(4, 4, 8),
+            Err(StoreError::InvalidGoals {
+                bronze: 4,
+                silver: 4,
+                gold: 8
+            })
+        );
+        assert_eq!(store.gamification().bronze_goal, 2);
+    }
+
+    #[test]
+    fn test_projects_tags_and_project_goals() {
+        let mut store = TaskStore::new();
+        let id = store
+            .create(new_task("Task 1", PriorityLevel::Low))
+            .unwrap();
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    tags: Some(vec![
+                        " backend".to_string(),
+                        "Backend".to_string(),
+                        String::new(),
+                        "urgent".to_string(),
+                    ]),
+                    project: Some(Some(" Work ".to_string())),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(store.task(id).unwrap().tags, ["backend", "urgent"]);
+        assert_eq!(store.task(id).unwrap().project.as_deref(), Some("Work"));
+
+        assert_eq!(
+            store.set_project_goal(" ", 3),
+            Err(StoreError::EmptyProject)
+        );
+        store.set_project_goal("Work", 1).unwrap();
+        store.complete(id).unwrap();
+        let progress = store
+            .gamification()
+            .project_goal_progress(store.tasks().as_slice());
+        assert!(progress[0].reached());
+        store.set_project_goal("Work", 0).unwrap();
+        assert!(store.gamification().project_goals.is_empty());
+
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    project: Some(Some(String::new())),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(store.task(id).unwrap().project, None);
+    }
+
+    #[test]
+    fn test_saved_filters() {
+        let mut store = TaskStore::new();
+        store
+            .create(new_task("Task 1", PriorityLevel::High))
+            .unwrap();
+        store
+            .create(new_task("Task 2", PriorityLevel::Low))
+            .unwrap();
+        let high = TaskQuery {
+            priority: Some(PriorityLevel::High),
+            ..Default::default()
+        };
+        assert_eq!(
+            store.save_filter("  ", high.clone()),
+            Err(StoreError::EmptyFilterName)
+        );
+        store.save_filter(" Urgent ", high.clone()).unwrap();
+        assert_eq!(store.filters().get("Urgent"), Some(&high));
+
+        let today = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap();
+        let report = store.report_matching(&store.filters()["Urgent"], today);
+        assert_eq!((report.total_tasks, report.high_priority_tasks), (1, 1));
+
+        // saved filters are kept with the tasks
+        let saved = serde_json::to_string(&store).unwrap();
+        let mut loaded: TaskStore = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.remove_filter("Urgent"), Some(high));
+        assert_eq!(loaded.remove_filter("Urgent"), None);
+    }
+
+    #[test]
+    fn test_report_metrics() {
+        let mut store = TaskStore::new();
+        let today = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap();
+        let done = store
+            .create(new_task("Task 1", PriorityLevel::High))
+            .unwrap();
+        let upcoming = store
+            .create(new_task("Task 2", PriorityLevel::Low))
+            .unwrap();
+        store
+            .create(new_task("Task 3", PriorityLevel::Low))
+            .unwrap();
+        store.complete(done).unwrap();
+        store
+            .update(
+                upcoming,
+                TaskUpdate {
+                    due_date: Some(Some(DueDate::on(
+                        NaiveDate::from_ymd_opt(2024, 11, 23).unwrap(),
+                    ))),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let report = store.report(today);
+        assert_eq!(
+            (
+                report.total_tasks,
+                report.completed_tasks,
+                report.incomplete_tasks
+            ),
+            (3, 1, 2)
+        );
+        assert_eq!(report.total_points, 30);
+        assert_eq!(report.most_common_priority, PriorityLevel::Low);
+        assert_eq!(report.upcoming_tasks, 1);
+        assert!((report.average_priority - 5.0 / 3.0).abs() < f32::EPSILON);
+        assert!((report.percentage(report.completed_tasks) - 100.0 / 3.0).abs() < 0.001);
+        assert_eq!(TaskStore::new().report(today).percentage(0), 0.0);
+    }
+}
@@ -0,0 +1,273 @@
+//! Streak tracking.
+//!
+//! A streak is the number of days in a row a task was completed on. Rest days, such as
+//! weekends, neither count toward a streak nor break it. A day without a completion can also be
+//! protected by a streak freeze: freezes are bought with points, at most [`MAX_FREEZES`] held at
+//! a time, and one is used up automatically on every day that would have broken the streak. A
+//! freeze only protects days from the day it was bought on, so buying one can't repair a streak
+//! that is already broken.
+//!
+//! The streak is worked out from the completed tasks, the rest days and the freezes bought in the
+//! points ledger, so it never needs to be saved, and it can't get out of step with the tasks.
+
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use crate::gamification::Task;
+
+/// Points a streak freeze costs
+pub const FREEZE_COST: u32 = 50;
+
+/// Most streak freezes that can be held at a time
+pub const MAX_FREEZES: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What a day of the streak calendar was
+pub enum DayStatus {
+    Completed, // a task was completed
+    Frozen,    // missed, but protected by a streak freeze
+    Rest,      // a rest day without a completion
+    Missed,    // a day without a completion that broke the streak, or that no streak was running on
+    Upcoming,  // today without a completion yet, or a day still to come
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Streak struct with the current and longest streak and the days they were worked out from
+pub struct Streak {
+    pub current: u32,               // days with a completion in the current streak
+    pub longest: u32,               // days with a completion in the longest streak ever
+    pub start: Option<NaiveDate>,   // first day of the current streak
+    pub freezes: u32,               // streak freezes held and not used yet
+    completed: BTreeSet<NaiveDate>, // days a task was completed on
+    frozen: BTreeSet<NaiveDate>,    // days protected by a streak freeze
+    rest_days: Vec<Weekday>,
+    today: Option<NaiveDate>,
+}
+
+/// Implementation of Streak with methods to work it out and to describe the days of a calendar
+impl Streak {
+    /// Helper function to work out the streak
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - A slice of Task structs representing the user's tasks
+    /// * `freezes_bought` - The day every streak freeze was bought on
+    /// * `rest_days` - Days of the week that don't break a streak
+    /// * `today` - The current date, from the app's clock
+    pub fn track(
+        tasks: &[Task],
+        freezes_bought: &[NaiveDate],
+        rest_days: &[Weekday],
+        today: NaiveDate,
+    ) -> Self {
+        let completed: BTreeSet<NaiveDate> = tasks
+            .iter()
+            .filter_map(|task| task.completed_date)
+            .map(|date| date.date_naive())
+            .filter(|day| *day <= today)
+            .collect();
+        let mut bought: Vec<NaiveDate> = freezes_bought.to_vec();
+        bought.sort();
+
+        let mut streak = Streak {
+            completed,
+            rest_days: rest_days.to_vec(),
+            today: Some(today),
+            ..Streak::default()
+        };
+        let (mut available, mut next_bought) = (0, 0);
+        let mut day = streak
+            .completed
+            .first()
+            .copied()
+            .unwrap_or(today)
+            .min(bought.first().copied().unwrap_or(today));
+        while day <= today {
+            while next_bought < bought.len() && bought[next_bought] <= day {
+                available += 1;
+                next_bought += 1;
+            }
+            if streak.completed.contains(&day) {
+                streak.start = streak.start.or(Some(day));
+                streak.current += 1;
+            } else if streak.is_rest_day(day) || day == today {
+                // neither counts nor breaks the streak, and today isn't over yet
+            } else if streak.current > 0 && available > 0 {
+                available -= 1;
+                streak.frozen.insert(day);
+            } else {
+                streak.current = 0;
+                streak.start = None;
+            }
+            streak.longest = streak.longest.max(streak.current);
+            day += Duration::days(1);
+        }
+        streak.freezes = available + (bought.len() - next_bought) as u32;
+        streak
+    }
+
+    /// Helper function to check whether a day is one of the rest days
+    pub fn is_rest_day(&self, day: NaiveDate) -> bool {
+        self.rest_days.contains(&day.weekday())
+    }
+
+    /// Helper function to get the days protected by a streak freeze, oldest first
+    pub fn frozen_days(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.frozen.iter().copied()
+    }
+
+    /// Helper function to describe a day of the streak calendar
+    pub fn status(&self, day: NaiveDate) -> DayStatus {
+        if self.completed.contains(&day) {
+            DayStatus::Completed
+        } else if self.frozen.contains(&day) {
+            DayStatus::Frozen
+        } else if self.today.map_or(true, |today| day >= today) {
+            DayStatus::Upcoming
+        } else if self.is_rest_day(day) {
+            DayStatus::Rest
+        } else {
+            DayStatus::Missed
+        }
+    }
+
+    /// Helper function to get the days of the calendar of the last weeks, a week per row from Monday
+    ///
+    /// # Arguments
+    ///
+    /// * `weeks` - The number of weeks to show, the last one being the current week
+    pub fn calendar(&self, weeks: u32) -> Vec<Vec<(NaiveDate, DayStatus)>> {
+        let Some(today) = self.today else {
+            return Vec::new();
+        };
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        (0..weeks as i64)
+            .rev()
+            .map(|week| {
+                let start = monday - Duration::weeks(week);
+                (0..7)
+                    .map(|offset| start + Duration::days(offset))
+                    .map(|day| (day, self.status(day)))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Helper function to parse a list of weekdays separated by commas, such as "sat,sun"
+///
+/// # Returns
+///
+/// The weekdays in order from Monday, or an error naming the first one that isn't a weekday.
+pub fn parse_rest_days(input: &str) -> Result<Vec<Weekday>, String> {
+    let mut days = Vec::new();
+    for name in input
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let day: Weekday = name
+            .parse()
+            .map_err(|_| format!("unknown weekday {name:?}, expected mon, tue, ... or sun"))?;
+        if !days.contains(&day) {
+            days.push(day);
+        }
+    }
+    days.sort_by_key(Weekday::num_days_from_monday);
+    Ok(days)
+}
+
+/// Helper function to describe rest days, such as "Sat, Sun"
+pub fn format_rest_days(days: &[Weekday]) -> String {
+    if days.is_empty() {
+        return "none".to_string();
+    }
+    days.iter()
+        .map(Weekday::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::gamification::PriorityLevel;
+    use crate::task_list::TaskId;
+
+    /// Helper function to get tasks completed the given numbers of days before today
+    fn completed(today: NaiveDate, days_ago: &[i64]) -> Vec<Task> {
+        days_ago
+            .iter()
+            .map(|&days| Task {
+                id: TaskId(days as u64),
+                completed: true,
+                completed_date: Some(Clock::fixed_on(today - Duration::days(days)).now()),
+                ..Task::new(format!("Task {days}"), PriorityLevel::Low)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_current_and_longest_streak() {
+        let today = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(); // a Wednesday
+                                                                    // 4 days in a row long ago, then the last 2 days; today isn't over, so it doesn't break the streak
+        let tasks = completed(today, &[1, 2, 10, 11, 12, 13]);
+        let streak = Streak::track(&tasks, &[], &[], today);
+        assert_eq!(
+            (streak.current, streak.longest, streak.start),
+            (2, 4, Some(today - Duration::days(2)))
+        );
+        assert_eq!(streak.status(today), DayStatus::Upcoming);
+        assert_eq!(streak.status(today - Duration::days(3)), DayStatus::Missed);
+
+        // completing a task today adds to the streak
+        let tasks = completed(today, &[0, 1, 2]);
+        assert_eq!(Streak::track(&tasks, &[], &[], today).current, 3);
+        assert_eq!(
+            Streak::track(&[], &[], &[], today),
+            Streak {
+                rest_days: Vec::new(),
+                today: Some(today),
+                ..Streak::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_rest_days_and_freezes() {
+        let today = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(); // a Wednesday
+                                                                    // nothing on the weekend of the 16th and 17th
+        let tasks = completed(today, &[0, 1, 2, 5, 6]);
+        assert_eq!(Streak::track(&tasks, &[], &[], today).current, 3);
+        let weekends = parse_rest_days("sat, Sun").unwrap();
+        assert_eq!(weekends, [Weekday::Sat, Weekday::Sun]);
+        let streak = Streak::track(&tasks, &[], &weekends, today);
+        assert_eq!(streak.current, 5);
+        assert_eq!(streak.status(today - Duration::days(3)), DayStatus::Rest);
+
+        // a freeze bought before the weekend covers one of its days, two cover both
+        let bought = today - Duration::days(7);
+        let streak = Streak::track(&tasks, &[bought], &[], today);
+        assert_eq!((streak.current, streak.freezes), (3, 0));
+        let streak = Streak::track(&tasks, &[bought, bought, today], &[], today);
+        assert_eq!(
+            (streak.current, streak.freezes, streak.frozen_days().count()),
+            (5, 1, 2)
+        );
+        assert_eq!(streak.status(today - Duration::days(4)), DayStatus::Frozen);
+        // a freeze bought after the missed days doesn't repair the streak
+        assert_eq!(
+            Streak::track(&tasks, &[today - Duration::days(1)], &[], today).current,
+            3
+        );
+
+        let calendar = streak.calendar(2);
+        assert_eq!(calendar.len(), 2);
+        assert_eq!(calendar[1][2], (today, DayStatus::Completed));
+        assert_eq!(calendar[0][0].0.weekday(), Weekday::Mon);
+        assert!(parse_rest_days("someday").is_err());
+        assert_eq!(format_rest_days(&weekends), "Sat, Sun");
+    }
+}
@@ -0,0 +1,252 @@
+//! Tags and projects for organizing tasks.
+//!
+//! A task has any number of tags and at most one project. Tags are compared without regard to
+//! case, so "Backend" and "backend" are the same tag, and the first spelling is kept. Projects
+//! are compared exactly, after trimming.
+
+use std::collections::BTreeMap;
+
+use crate::gamification::Task;
+
+/// The most suggestions offered while typing a tag or project
+pub const MAX_SUGGESTIONS: usize = 5;
+
+/// Helper function to split text into tags, separated by commas or spaces.
+/// A leading `#` is dropped, so "#backend, urgent" gives "backend" and "urgent".
+pub fn parse_tags(text: &str) -> Vec<String> {
+    normalize_tags(
+        text.split(|c: char| c == ',' || c.is_whitespace())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Helper function to trim tags, drop their leading `#`, and drop empty and repeated ones
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#');
+        if !tag.is_empty()
+            && !normalized
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(tag))
+        {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// Helper function to trim a project name, treating an empty one as no project
+pub fn normalize_project(project: Option<String>) -> Option<String> {
+    project
+        .map(|project| project.trim().to_string())
+        .filter(|project| !project.is_empty())
+}
+
+/// Helper function to write tags for display, with a `#` in front of each tag
+/// that isn't a todo.txt context
+pub fn format_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| {
+            if tag.starts_with('@') {
+                tag.clone()
+            } else {
+                format!("#{tag}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Helper function to get every tag used by the tasks, sorted without regard to case
+pub fn all_tags(tasks: &[Task]) -> Vec<String> {
+    let mut tags = normalize_tags(
+        tasks
+            .iter()
+            .flat_map(|task| task.tags.iter().cloned())
+            .collect(),
+    );
+    tags.sort_by_key(|tag| tag.to_lowercase());
+    tags
+}
+
+/// Helper function to get every project used by the tasks, sorted
+pub fn all_projects(tasks: &[Task]) -> Vec<String> {
+    let mut projects: Vec<String> = tasks
+        .iter()
+        .filter_map(|task| task.project.clone())
+        .collect();
+    projects.sort();
+    projects.dedup();
+    projects
+}
+
+/// Helper function to suggest known names that start with what has been typed so far
+///
+/// # Arguments
+///
+/// * `typed` - The start of the name, matched without regard to case
+/// * `known` - The names to choose from
+/// * `exclude` - Names that shouldn't be suggested, such as tags the task already has
+///
+/// # Returns
+///
+/// At most MAX_SUGGESTIONS names, in the order of `known`. Nothing is suggested for a name
+/// that has been typed out completely.
+pub fn suggest<'a>(typed: &str, known: &'a [String], exclude: &[String]) -> Vec<&'a str> {
+    let typed = typed.trim().trim_start_matches('#').to_lowercase();
+    known
+        .iter()
+        .filter(|name| name.to_lowercase().starts_with(&typed) && name.to_lowercase() != typed)
+        .filter(|name| {
+            !exclude
+                .iter()
+                .any(|excluded| excluded.eq_ignore_ascii_case(name))
+        })
+        .take(MAX_SUGGESTIONS)
+        .map(String::as_str)
+        .collect()
+}
+
+/// Helper function to split comma separated tags being typed into the finished tags
+/// and the tag still being typed
+pub fn split_last_tag(text: &str) -> (Vec<String>, &str) {
+    match text.rfind(',') {
+        Some(comma) => (parse_tags(&text[..comma]), text[comma + 1..].trim_start()),
+        None => (Vec::new(), text.trim_start()),
+    }
+}
+
+/// Helper function to replace the tag still being typed with a suggestion
+///
+/// # Returns
+///
+/// The tags as comma separated text, ready for the next tag to be typed.
+pub fn complete_last_tag(text: &str, suggestion: &str) -> String {
+    let (mut tags, _) = split_last_tag(text);
+    tags.push(suggestion.to_string());
+    format!("{}, ", normalize_tags(tags).join(", "))
+}
+
+/// The tasks of one project, with their depth in the task tree, or of no project with `None`
+pub type ProjectGroup<'a> = (Option<&'a str>, Vec<(usize, &'a Task)>);
+
+/// Helper function to group a task tree by project. Subtasks stay with their top-level task,
+/// so a group is always a complete part of the tree.
+///
+/// # Returns
+///
+/// The groups in project order, with the tasks without a project last.
+pub fn group_by_project<'a>(tree: Vec<(usize, &'a Task)>) -> Vec<ProjectGroup<'a>> {
+    let mut groups: BTreeMap<&'a str, Vec<(usize, &'a Task)>> = BTreeMap::new();
+    let mut ungrouped = Vec::new();
+    let mut project = None;
+    for (depth, task) in tree {
+        if depth == 0 {
+            project = task.project.as_deref();
+        }
+        match project {
+            Some(project) => groups.entry(project).or_default().push((depth, task)),
+            None => ungrouped.push((depth, task)),
+        }
+    }
+
+    let mut grouped: Vec<_> = groups
+        .into_iter()
+        .map(|(project, tasks)| (Some(project), tasks))
+        .collect();
+    if !ungrouped.is_empty() {
+        grouped.push((None, ungrouped));
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamification::PriorityLevel;
+    use crate::task_list::TaskId;
+
+    fn task(id: u64, parent: Option<u64>, project: Option<&str>, tags: &[&str]) -> Task {
+        Task {
+            id: TaskId(id),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            parent: parent.map(TaskId),
+            project: project.map(str::to_string),
+            ..Task::new(format!("Task {id}"), PriorityLevel::Low)
+        }
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse_tags("#backend, urgent  Backend,,@office"),
+            vec!["backend", "urgent", "@office"]
+        );
+        assert!(parse_tags(" , ").is_empty());
+        assert_eq!(
+            normalize_project(Some("  garden ".to_string())),
+            Some("garden".to_string())
+        );
+        assert_eq!(normalize_project(Some("  ".to_string())), None);
+        assert_eq!(
+            format_tags(&["backend".to_string(), "@office".to_string()]),
+            "#backend @office"
+        );
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let tasks = [
+            task(1, None, Some("garden"), &["backend", "Bug"]),
+            task(2, None, Some("backyard"), &["urgent", "bug"]),
+        ];
+        let tags = all_tags(&tasks);
+        assert_eq!(tags, vec!["backend", "Bug", "urgent"]);
+        assert_eq!(all_projects(&tasks), vec!["backyard", "garden"]);
+
+        assert_eq!(suggest("b", &tags, &[]), vec!["backend", "Bug"]);
+        assert_eq!(suggest("#BA", &tags, &[]), vec!["backend"]);
+        assert_eq!(suggest("b", &tags, &["bug".to_string()]), vec!["backend"]);
+        assert!(suggest("backend", &tags, &[]).is_empty());
+
+        assert_eq!(
+            split_last_tag("backend, ur"),
+            (vec!["backend".to_string()], "ur")
+        );
+        assert_eq!(
+            complete_last_tag("backend, ur", "urgent"),
+            "backend, urgent, "
+        );
+        assert_eq!(complete_last_tag("ba", "backend"), "backend, ");
+    }
+
+    #[test]
+    fn test_group_by_project_keeps_subtasks_with_their_parent() {
+        let tasks = [
+            task(1, None, None, &[]),
+            task(2, None, Some("garden"), &[]),
+            task(3, Some(2), Some("kitchen"), &[]),
+            task(4, None, Some("backend"), &[]),
+        ];
+        let tree = vec![
+            (0, &tasks[0]),
+            (0, &tasks[1]),
+            (1, &tasks[2]),
+            (0, &tasks[3]),
+        ];
+        let groups: Vec<(Option<&str>, Vec<u64>)> = group_by_project(tree)
+            .into_iter()
+            .map(|(project, tasks)| (project, tasks.iter().map(|(_, task)| task.id.0).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                (Some("backend"), vec![4]),
+                (Some("garden"), vec![2, 3]),
+                (None, vec![1])
+            ]
+        );
+    }
+}
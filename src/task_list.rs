@@ -0,0 +1,469 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::gamification::Task;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[serde(transparent)]
+/// Persistent, unique identifier for a task.
+/// `TaskId(0)` is reserved for tasks that have not been assigned an ID yet.
+pub struct TaskId(pub u64);
+
+impl TaskId {
+    /// Helper function to check whether the ID has been assigned by a TaskList
+    pub fn is_assigned(&self) -> bool {
+        self.0 != 0
+    }
+}
+
+impl std::fmt::Display for TaskId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl std::str::FromStr for TaskId {
+    type Err = String;
+
+    /// Helper function to parse a task ID written as "3" or "#3"
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let digits = input.trim().trim_start_matches('#');
+        match digits.parse() {
+            Ok(id) if id != 0 => Ok(TaskId(id)),
+            _ => Err(format!("invalid task ID {input:?}")),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+/// On-disk form of a TaskList
+struct TaskListRef<'a> {
+    tasks: &'a [Task],
+    next_id: u64,
+}
+
+#[derive(Clone, Default)]
+/// TaskList struct that owns the tasks and looks them up by TaskId.
+/// All selection, editing, and cross-references between tasks should go through a TaskId
+/// rather than a position in the list, since positions change when tasks are removed.
+pub struct TaskList {
+    tasks: Vec<Task>,
+    next_id: u64,
+    index: HashMap<TaskId, usize>, // TaskId -> position in `tasks`
+}
+
+/// Implementation of TaskList with methods to add, look up, and remove tasks by ID
+impl TaskList {
+    /// Helper function to create a new, empty TaskList
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Helper function to build a TaskList from tasks that may be missing IDs
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The tasks to store. Tasks without an ID, or with an ID that is already
+    ///   taken by an earlier task, are given a fresh ID.
+    pub fn from_tasks(tasks: Vec<Task>) -> Self {
+        Self::with_next_id(tasks, 0)
+    }
+
+    /// Helper function to restore a TaskList, making sure every task has a unique ID
+    /// and that `next_id` never hands out an ID that is already in use
+    pub fn with_next_id(tasks: Vec<Task>, next_id: u64) -> Self {
+        let max_id = tasks.iter().map(|task| task.id.0).max().unwrap_or(0);
+        let mut list = TaskList {
+            tasks,
+            next_id: next_id.max(max_id + 1),
+            index: HashMap::new(),
+        };
+
+        for position in 0..list.tasks.len() {
+            let id = list.tasks[position].id;
+            if !id.is_assigned() || list.index.contains_key(&id) {
+                let fresh_id = list.allocate_id();
+                list.tasks[position].id = fresh_id;
+            }
+            list.index.insert(list.tasks[position].id, position);
+        }
+        list
+    }
+
+    /// Helper function to hand out the next unused ID
+    fn allocate_id(&mut self) -> TaskId {
+        self.next_id = self.next_id.max(1);
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Helper function to rebuild the ID index after tasks have moved
+    fn rebuild_index(&mut self) {
+        self.index = self
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(position, task)| (task.id, position))
+            .collect();
+    }
+
+    /// Helper function to add a task to the list, assigning it a new ID
+    ///
+    /// # Returns
+    ///
+    /// The ID given to the task.
+    pub fn push(&mut self, mut task: Task) -> TaskId {
+        let id = self.allocate_id();
+        task.id = id;
+        self.index.insert(id, self.tasks.len());
+        self.tasks.push(task);
+        id
+    }
+
+    /// Helper function to look up a task by ID
+    pub fn get(&self, id: TaskId) -> Option<&Task> {
+        self.index.get(&id).map(|&position| &self.tasks[position])
+    }
+
+    /// Helper function to look up a task by ID for editing
+    pub fn get_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.index
+            .get(&id)
+            .map(|&position| &mut self.tasks[position])
+    }
+
+    /// Helper function to check whether a task with the given ID exists
+    pub fn contains(&self, id: TaskId) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    /// Helper function to remove a task by ID
+    ///
+    /// # Returns
+    ///
+    /// The removed task, or None if no task has that ID.
+    pub fn remove(&mut self, id: TaskId) -> Option<Task> {
+        let position = self.index.remove(&id)?;
+        let task = self.tasks.remove(position);
+        self.rebuild_index();
+        Some(task)
+    }
+
+    /// Helper function to remove every task. IDs are not reused afterwards.
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+        self.index.clear();
+    }
+
+    /// Helper function to replace every task. The tasks are given fresh IDs, with their parents
+    /// and prerequisites renumbered along with them, so IDs handed out before the replacement are
+    /// never reused. Parents and prerequisites that are not among the tasks are dropped.
+    pub fn replace_all(&mut self, tasks: Vec<Task>) {
+        let mut list = TaskList {
+            tasks: Vec::with_capacity(tasks.len()),
+            next_id: self.next_id,
+            index: HashMap::new(),
+        };
+        let mut new_ids = HashMap::new(); // ID of the replacing task -> its fresh ID
+        for task in tasks {
+            let old_id = task.id;
+            let id = list.push(task);
+            if old_id.is_assigned() {
+                new_ids.entry(old_id).or_insert(id);
+            }
+        }
+        for task in &mut list.tasks {
+            task.parent = task.parent.and_then(|parent| new_ids.get(&parent).copied());
+            task.depends_on = task
+                .depends_on
+                .iter()
+                .filter_map(|prerequisite| new_ids.get(prerequisite).copied())
+                .collect();
+        }
+        *self = list;
+    }
+
+    /// Helper function to get the IDs of all tasks in insertion order
+    pub fn ids(&self) -> Vec<TaskId> {
+        self.tasks.iter().map(|task| task.id).collect()
+    }
+
+    /// Helper function to iterate over the direct subtasks of a task, in insertion order
+    pub fn children(&self, id: TaskId) -> impl Iterator<Item = &Task> + '_ {
+        self.tasks
+            .iter()
+            .filter(move |task| task.parent == Some(id) && task.id != id)
+    }
+
+    /// Helper function to list the tasks as a tree, with every subtask right after its parent
+    /// and siblings in insertion order
+    ///
+    /// # Returns
+    ///
+    /// Each task with its depth, 0 for top-level tasks. Subtasks whose parent no longer exists,
+    /// and tasks caught in a cycle of parents, are listed as top-level tasks.
+    pub fn tree(&self) -> Vec<(usize, &Task)> {
+        let mut children: HashMap<TaskId, Vec<&Task>> = HashMap::new();
+        let mut roots = Vec::new();
+        for task in &self.tasks {
+            match task
+                .parent
+                .filter(|&parent| parent != task.id && self.contains(parent))
+            {
+                Some(parent) => children.entry(parent).or_default().push(task),
+                None => roots.push(task),
+            }
+        }
+
+        let mut tree = Vec::with_capacity(self.tasks.len());
+        let mut visited = HashSet::new();
+        let mut stack: Vec<(usize, &Task)> =
+            roots.into_iter().rev().map(|task| (0, task)).collect();
+        loop {
+            while let Some((depth, task)) = stack.pop() {
+                if !visited.insert(task.id) {
+                    continue;
+                }
+                tree.push((depth, task));
+                if let Some(subtasks) = children.get(&task.id) {
+                    stack.extend(subtasks.iter().rev().map(|&subtask| (depth + 1, subtask)));
+                }
+            }
+            // Tasks in a cycle of parents can't be reached from a top-level task
+            match self.tasks.iter().find(|task| !visited.contains(&task.id)) {
+                Some(task) => stack.push((0, task)),
+                None => break,
+            }
+        }
+        tree
+    }
+
+    /// Helper function to iterate over the tasks in insertion order
+    pub fn iter(&self) -> std::slice::Iter<'_, Task> {
+        self.tasks.iter()
+    }
+
+    /// Helper function to iterate mutably over the tasks in insertion order.
+    /// Task IDs must not be changed through this iterator.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Task> {
+        self.tasks.iter_mut()
+    }
+
+    /// Helper function to view the tasks as a slice, e.g. for the gamification checks
+    pub fn as_slice(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// Helper function to get the number of tasks
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Helper function to check whether there are no tasks
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a TaskList {
+    type Item = &'a Task;
+    type IntoIter = std::slice::Iter<'a, Task>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl serde::Serialize for TaskList {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TaskListRef {
+            tasks: &self.tasks,
+            next_id: self.next_id,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TaskList {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TaskListVisitor)
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+/// Field names of the on-disk form of a TaskList
+enum TaskListField {
+    Tasks,
+    NextId,
+    #[serde(other)]
+    Other,
+}
+
+/// Visitor that accepts both the current `(tasks: [...], next_id: n)` form
+/// and the bare list of tasks saved before tasks had IDs
+struct TaskListVisitor;
+
+impl<'de> serde::de::Visitor<'de> for TaskListVisitor {
+    type Value = TaskList;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("a task list or a sequence of tasks")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // Migrate app states saved before tasks had IDs
+        let mut tasks = Vec::new();
+        while let Some(task) = seq.next_element::<Task>()? {
+            tasks.push(task);
+        }
+        Ok(TaskList::from_tasks(tasks))
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut tasks = None;
+        let mut next_id = 0;
+        while let Some(field) = map.next_key::<TaskListField>()? {
+            match field {
+                TaskListField::Tasks => tasks = Some(map.next_value::<Vec<Task>>()?),
+                TaskListField::NextId => next_id = map.next_value()?,
+                TaskListField::Other => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        let tasks = tasks.ok_or_else(|| serde::de::Error::missing_field("tasks"))?;
+        Ok(TaskList::with_next_id(tasks, next_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamification::PriorityLevel;
+
+    fn task(name: &str) -> Task {
+        Task {
+            description: "A task".to_string(),
+            ..Task::new(name, PriorityLevel::Low)
+        }
+    }
+
+    #[test]
+    fn test_ids_are_unique_and_not_reused() {
+        let mut list = TaskList::new();
+        let first = list.push(task("Task 1"));
+        let second = list.push(task("Task 2"));
+        assert_ne!(first, second);
+        assert!(first.is_assigned());
+
+        list.clear();
+        let third = list.push(task("Task 3"));
+        assert!(third != first && third != second);
+    }
+
+    #[test]
+    fn test_lookup_survives_removal() {
+        let mut list = TaskList::new();
+        let first = list.push(task("Task 1"));
+        let second = list.push(task("Task 2"));
+        let third = list.push(task("Task 3"));
+
+        assert_eq!(
+            list.remove(first).map(|task| task.name),
+            Some("Task 1".to_string())
+        );
+        assert!(list.get(first).is_none());
+        assert_eq!(list.get(second).unwrap().name, "Task 2");
+        assert_eq!(list.get(third).unwrap().name, "Task 3");
+        assert_eq!(list.ids(), vec![second, third]);
+    }
+
+    #[test]
+    fn test_tree_puts_subtasks_under_their_parent() {
+        let mut list = TaskList::new();
+        let trip = list.push(task("Plan the trip"));
+        let groceries = list.push(task("Groceries"));
+        let flights = list.push(Task {
+            parent: Some(trip),
+            ..task("Book flights")
+        });
+        list.push(Task {
+            parent: Some(flights),
+            ..task("Compare prices")
+        });
+        list.push(Task {
+            parent: Some(trip),
+            ..task("Book hotel")
+        });
+        list.push(Task {
+            parent: Some(TaskId(99)),
+            ..task("Orphan")
+        });
+
+        let tree: Vec<(usize, &str)> = list
+            .tree()
+            .into_iter()
+            .map(|(depth, task)| (depth, task.name.as_str()))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![
+                (0, "Plan the trip"),
+                (1, "Book flights"),
+                (2, "Compare prices"),
+                (1, "Book hotel"),
+                (0, "Groceries"),
+                (0, "Orphan")
+            ]
+        );
+        assert_eq!(list.children(trip).count(), 2);
+
+        // A cycle of parents still lists every task once
+        list.get_mut(trip).unwrap().parent = Some(flights);
+        list.get_mut(groceries).unwrap().parent = Some(groceries);
+        assert_eq!(list.tree().len(), list.len());
+    }
+
+    #[test]
+    fn test_legacy_state_without_ids_is_migrated() {
+        let legacy = r#"[
+            (name: "Task 1", description: "A", due_date: "2024-11-23", priority: Low, completed: false, completed_date: None),
+            (name: "Task 2", description: "B", due_date: "2024-11-23", priority: High, completed: true, completed_date: Some("2024-11-23")),
+        ]"#;
+        let list: TaskList = ron::from_str(legacy).unwrap();
+
+        assert_eq!(list.len(), 2);
+        let ids = list.ids();
+        assert!(ids.iter().all(TaskId::is_assigned));
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(list.get(ids[1]).unwrap().name, "Task 2");
+    }
+
+    #[test]
+    fn test_round_trip_keeps_ids_and_counter() {
+        let mut list = TaskList::new();
+        let first = list.push(task("Task 1"));
+        let second = list.push(task("Task 2"));
+        list.remove(second);
+
+        let restored: TaskList = ron::from_str(&ron::to_string(&list).unwrap()).unwrap();
+        assert_eq!(restored.ids(), vec![first]);
+
+        // The removed ID must not be handed out again after a reload
+        let mut restored = restored;
+        assert_ne!(restored.push(task("Task 3")), second);
+    }
+}
@@ -0,0 +1,722 @@
+//! todo.txt format support.
+//!
+//! Each task is one line, following the conventions of <http://todotxt.org>:
+//!
+//! ```text
+//! (A) Write report +backend @office due:2024-11-23 taskhero:3
+//! x 2024-11-20 Call the bank +home due:2024-11-19T14:30 pri:B taskhero:4
+//! ```
+//!
+//! - `(A)`, `(B)` and `(C)` are the high, medium and low priorities. Letters after C are read as
+//!   low, and a line without a priority is low. Completed tasks keep their priority as `pri:A`.
+//! - `x 2024-11-20` marks a completed task and the date it was completed.
+//! - `due:` holds the due date, with an optional time and UTC offset (`2024-11-23T14:30+02:00`).
+//! - `+project` and `@context` words become tags. Projects are stored without their `+` and
+//!   contexts keep their `@`, so both are written back the way they were read.
+//! - `project:garden` is the TaskHero project of the task. It has its own key because `+project`
+//!   words are already read as tags. Spaces in project names are written as underscores.
+//! - `taskhero:3` is the ID of the task in TaskHero, which lets a synced file be matched to the
+//!   tasks it came from. Other `key:value` pairs are kept as part of the task name.
+//! - A word of the task name that would be read as one of the above, such as `+1` or `due:soon`,
+//!   is written with a `\` in front (`\+1`), which is dropped when the line is read.
+//!
+//! Task descriptions have no place in todo.txt. They are not exported, and syncing a file keeps
+//! the descriptions of the tasks it updates.
+
+use std::collections::HashSet;
+
+use chrono::{NaiveDate, NaiveTime, Timelike};
+
+use crate::dates::{start_of_day, DueDate};
+use crate::gamification::{PriorityLevel, Task};
+use crate::store::{StoreError, TaskStore, TaskUpdate};
+use crate::task_list::TaskId;
+use crate::transfer::{duplicate_key, ImportSummary};
+
+/// Key of the key:value pair holding the TaskHero ID of a task
+const ID_KEY: &str = "taskhero";
+
+/// Key of the key:value pair holding the project of a task
+const PROJECT_KEY: &str = "project";
+
+/// Word enum with what a word of a todo.txt line is read as, after the completion or priority
+enum Word<'a> {
+    Tag(&'a str),     // a `+project` without its `+`, or an `@context`
+    Project(&'a str), // the value of `project:`
+    Due(&'a str),     // the value of `due:`
+    Priority(char),   // the letter of `pri:`
+    Id(&'a str),      // the value of `taskhero:`
+    Name(&'a str),    // part of the task name
+}
+
+/// TodoTxtLine struct with a task read from a line and the TaskHero ID written on it, if any
+pub struct TodoTxtLine {
+    pub id: Option<TaskId>,
+    pub task: Task,
+}
+
+/// TodoTxtFile struct with the tasks read from a todo.txt file
+pub struct TodoTxtFile {
+    pub lines: Vec<TodoTxtLine>,
+    pub skipped: Vec<String>, // lines that could not be read, and why
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// SyncSummary struct describing what syncing a todo.txt file changed
+pub struct SyncSummary {
+    pub added: usize,   // lines without a matching task
+    pub updated: usize, // tasks whose line changed
+    pub removed: usize, // tasks without a line
+}
+
+impl std::fmt::Display for SyncSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Added {} task(s), updated {}, removed {}.",
+            self.added, self.updated, self.removed
+        )
+    }
+}
+
+/// Helper function to map a priority level onto a todo.txt priority letter
+pub fn priority_to_letter(priority: PriorityLevel) -> char {
+    match priority {
+        PriorityLevel::High => 'A',
+        PriorityLevel::Medium => 'B',
+        PriorityLevel::Low => 'C',
+    }
+}
+
+/// Helper function to map a todo.txt priority letter onto a priority level.
+/// Letters after C are less urgent than C and map to low as well.
+pub fn priority_from_letter(letter: char) -> PriorityLevel {
+    match letter {
+        'A' => PriorityLevel::High,
+        'B' => PriorityLevel::Medium,
+        _ => PriorityLevel::Low,
+    }
+}
+
+/// Helper function to write a task as a todo.txt line, without a line break
+pub fn format_task(task: &Task) -> String {
+    let mut words = Vec::new();
+    if let Some(completed_date) = task.completed_date {
+        words.push(format!("x {}", completed_date.format("%Y-%m-%d")));
+    } else if task.completed {
+        words.push("x".to_string());
+    } else {
+        words.push(format!("({})", priority_to_letter(task.priority)));
+    }
+    let name: Vec<_> = task
+        .name
+        .split_whitespace()
+        .enumerate()
+        .map(|(index, word)| escape_word(word, index == 0))
+        .collect();
+    words.push(name.join(" "));
+    for tag in &task.tags {
+        if tag.starts_with('@') {
+            words.push(tag.clone());
+        } else {
+            words.push(format!("+{tag}"));
+        }
+    }
+    if let Some(project) = &task.project {
+        words.push(format!(
+            "{PROJECT_KEY}:{}",
+            project.split_whitespace().collect::<Vec<_>>().join("_")
+        ));
+    }
+    if let Some(due_date) = task.due_date {
+        words.push(format!("due:{}", format_due_date(due_date)));
+    }
+    if task.completed {
+        words.push(format!("pri:{}", priority_to_letter(task.priority)));
+    }
+    if task.id != TaskId::default() {
+        words.push(format!("{ID_KEY}:{}", task.id.0));
+    }
+    words.join(" ")
+}
+
+/// Helper function to export every task as a todo.txt file
+pub fn export(store: &TaskStore) -> String {
+    store
+        .tasks()
+        .iter()
+        .map(|task| format_task(task) + "\n")
+        .collect()
+}
+
+/// Helper function to read a todo.txt line as a task
+///
+/// # Returns
+///
+/// The task, `None` for a blank line, or an error if the line has no task text.
+pub fn parse_line(line: &str) -> Result<Option<TodoTxtLine>, String> {
+    let mut words = line.split_whitespace().peekable();
+    if words.peek().is_none() {
+        return Ok(None);
+    }
+
+    let mut task = Task::new(String::new(), PriorityLevel::Low); // the ID is assigned when the task is added
+    if words.next_if_eq(&"x").is_some() {
+        task.completed = true;
+        if let Some(date) = words.peek().and_then(|word| parse_date(word)) {
+            words.next();
+            task.completed_date = Some(start_of_day(date));
+        }
+    } else if let Some(letter) = words.peek().and_then(|word| parse_priority(word)) {
+        words.next();
+        task.priority = priority_from_letter(letter);
+    }
+    // the creation date, which TaskHero doesn't keep
+    words.next_if(|word| parse_date(word).is_some());
+
+    let mut id = None;
+    let mut name = Vec::new();
+    for word in words {
+        match read_word(word) {
+            Word::Tag(tag) => task.tags.push(tag.to_string()),
+            Word::Project(value) => task.project = Some(value.to_string()),
+            Word::Due(value) => {
+                task.due_date = Some(
+                    parse_due_date(value).ok_or_else(|| format!("invalid due date {value:?}"))?,
+                );
+            }
+            Word::Priority(letter) => {
+                task.priority = priority_from_letter(letter.to_ascii_uppercase())
+            }
+            Word::Id(value) => {
+                id = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid {ID_KEY} ID {value:?}"))?,
+                )
+            }
+            Word::Name(word) => name.push(word),
+        }
+    }
+    task.name = name.join(" ");
+    if task.name.is_empty() {
+        return Err("the task has no text".to_string());
+    }
+    Ok(Some(TodoTxtLine { id, task }))
+}
+
+/// Helper function to read every task of a todo.txt file
+pub fn parse(text: &str) -> TodoTxtFile {
+    let mut file = TodoTxtFile {
+        lines: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for (number, line) in text.lines().enumerate() {
+        match parse_line(line) {
+            Ok(Some(line)) => file.lines.push(line),
+            Ok(None) => {}
+            Err(message) => file.skipped.push(format!("line {}: {message}", number + 1)),
+        }
+    }
+    file
+}
+
+/// Helper function to add the tasks of a todo.txt file to the store, skipping tasks that
+/// already exist (the same name and due date)
+pub fn import(store: &mut TaskStore, file: TodoTxtFile) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    let mut existing: HashSet<_> = store.tasks().iter().map(duplicate_key).collect();
+    for line in file.lines {
+        if existing.insert(duplicate_key(&line.task)) {
+            store.insert(line.task);
+            summary.added += 1;
+        } else {
+            summary.duplicates += 1;
+        }
+    }
+    store.evaluate();
+    summary
+}
+
+/// Helper function to apply the changes made to a todo.txt file to the store
+///
+/// Lines are matched to tasks by their `taskhero:` ID, or else by name and due date. Matched
+/// tasks take the name, due date, priority, tags, project and completion of their line and keep their
+/// description. Tasks completed in the file are completed now, so they earn their points like
+/// any other completion. Lines without a task are added.
+///
+/// # Arguments
+///
+/// * `store` - The store to change
+/// * `file` - The todo.txt file
+/// * `remove_missing` - Whether to delete the tasks without a line, which is right when the file
+///   was written from the store and lines have been deleted since
+///
+/// # Returns
+///
+/// What the sync changed, or an error listing the lines that could not be read. Nothing is
+/// changed when a line can't be read, since its task would otherwise be deleted.
+pub fn sync(
+    store: &mut TaskStore,
+    file: TodoTxtFile,
+    remove_missing: bool,
+) -> Result<SyncSummary, String> {
+    if !file.skipped.is_empty() {
+        return Err(file.skipped.join("\n"));
+    }
+    let mut summary = SyncSummary::default();
+    let mut unmatched: Vec<TaskId> = store.tasks().ids();
+    let mut matches = Vec::new();
+    let mut new_tasks = Vec::new();
+
+    // match by ID first, so a renamed task is not mistaken for a new one
+    let mut lines = Vec::new();
+    for line in file.lines {
+        match line
+            .id
+            .and_then(|id| unmatched.iter().position(|&candidate| candidate == id))
+        {
+            Some(index) => matches.push((unmatched.remove(index), line.task)),
+            None => lines.push(line.task),
+        }
+    }
+    for task in lines {
+        let key = duplicate_key(&task);
+        let found = unmatched.iter().position(|&id| {
+            store
+                .task(id)
+                .is_some_and(|existing| duplicate_key(existing) == key)
+        });
+        match found {
+            Some(index) => matches.push((unmatched.remove(index), task)),
+            None => new_tasks.push(task),
+        }
+    }
+
+    let mut completions = Vec::new();
+    for (id, task) in matches {
+        let Some(existing) = store.task(id) else {
+            continue;
+        };
+        if existing.name == task.name
+            && existing.due_date == task.due_date
+            && existing.priority == task.priority
+            && existing.tags == task.tags
+            && existing.project == task.project
+            && existing.completed == task.completed
+        {
+            continue;
+        }
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    name: Some(task.name),
+                    due_date: Some(task.due_date),
+                    priority: Some(task.priority),
+                    tags: Some(task.tags),
+                    project: Some(task.project),
+                    ..Default::default()
+                },
+            )
+            .map_err(|err| err.to_string())?;
+        completions.push((id, task.completed));
+        summary.updated += 1;
+    }
+    // complete subtasks before their parents; a parent whose subtasks are still open stays open
+    let order: Vec<TaskId> = store
+        .tasks()
+        .tree()
+        .into_iter()
+        .map(|(_, task)| task.id)
+        .collect();
+    completions.sort_by_key(|(id, _)| {
+        std::cmp::Reverse(order.iter().position(|candidate| candidate == id))
+    });
+    for (id, completed) in completions {
+        match store.set_completed(id, completed) {
+            Ok(()) | Err(StoreError::OpenSubtasks(_)) => {}
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+    for id in unmatched.into_iter().filter(|_| remove_missing) {
+        // subtasks that still have a line outlive their parent
+        let children: Vec<TaskId> = store.tasks().children(id).map(|task| task.id).collect();
+        for child in children {
+            store
+                .set_parent(child, None)
+                .map_err(|err| err.to_string())?;
+        }
+        store.delete(id).map_err(|err| err.to_string())?;
+        summary.removed += 1;
+    }
+    for task in new_tasks {
+        store.insert(task);
+        summary.added += 1;
+    }
+    store.evaluate();
+    Ok(summary)
+}
+
+/// Helper function to tell what a word of a todo.txt line is
+fn read_word(word: &str) -> Word<'_> {
+    if let Some(word) = word.strip_prefix('\\') {
+        Word::Name(word)
+    } else if let Some(tag) = word.strip_prefix('+').filter(|tag| !tag.is_empty()) {
+        Word::Tag(tag)
+    } else if word.len() > 1 && word.starts_with('@') {
+        Word::Tag(word)
+    } else if let Some(value) = word
+        .strip_prefix(PROJECT_KEY)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .filter(|value| !value.is_empty())
+    {
+        Word::Project(value)
+    } else if let Some(value) = word.strip_prefix("due:") {
+        Word::Due(value)
+    } else if let Some(letter) = word
+        .strip_prefix("pri:")
+        .and_then(|value| value.chars().next())
+    {
+        Word::Priority(letter)
+    } else if let Some(value) = word
+        .strip_prefix(ID_KEY)
+        .and_then(|rest| rest.strip_prefix(':'))
+    {
+        Word::Id(value)
+    } else {
+        Word::Name(word)
+    }
+}
+
+/// Helper function to write a word of a task name so that it is read back as the same word.
+/// The first word is also escaped if it looks like a date, which would be read as the creation
+/// or completion date.
+fn escape_word(word: &str, first: bool) -> String {
+    let plain = matches!(read_word(word), Word::Name(read) if read == word);
+    if plain && !(first && parse_date(word).is_some()) {
+        word.to_string()
+    } else {
+        format!("\\{word}")
+    }
+}
+
+/// Helper function to read a `YYYY-MM-DD` date
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+/// Helper function to read a `(A)` priority
+fn parse_priority(word: &str) -> Option<char> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_uppercase() => Some(letter),
+        _ => None,
+    }
+}
+
+/// Helper function to write a due date as a todo.txt value, which can't contain spaces
+fn format_due_date(due_date: DueDate) -> String {
+    let mut value = due_date.date.format("%Y-%m-%d").to_string();
+    if let Some(time) = due_date.time {
+        let format = if time.second() == 0 {
+            "T%H:%M"
+        } else {
+            "T%H:%M:%S"
+        };
+        value.push_str(&time.format(format).to_string());
+        if let Some(minutes) = due_date.utc_offset_minutes {
+            let sign = if minutes < 0 { '-' } else { '+' };
+            value.push_str(&format!(
+                "{sign}{:02}:{:02}",
+                minutes.abs() / 60,
+                minutes.abs() % 60
+            ));
+        }
+    }
+    value
+}
+
+/// Helper function to read a due date written by format_due_date, such as
+/// `2024-11-23`, `2024-11-23T14:30`, `2024-11-23T14:30Z` or `2024-11-23T14:30-05:00`
+fn parse_due_date(value: &str) -> Option<DueDate> {
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    let mut due_date = DueDate::on(parse_date(date)?);
+    let Some(time) = time else {
+        return Some(due_date);
+    };
+    let (time, offset) = match time.find(['+', '-', 'Z']) {
+        Some(index) => time.split_at(index),
+        None => (time, ""),
+    };
+    due_date.time = Some(
+        NaiveTime::parse_from_str(time, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+            .ok()?,
+    );
+    due_date.utc_offset_minutes = match offset {
+        "" => None,
+        "Z" => Some(0),
+        _ => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+            if hours >= 24 || minutes >= 60 {
+                return None;
+            }
+            Some(sign * (hours * 60 + minutes))
+        }
+    };
+    Some(due_date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::NewTask;
+    use chrono::{DateTime, Local};
+
+    /// Helper function to check whether two completion times fall on the same day, which is all todo.txt records
+    fn same_completion_day(a: Option<DateTime<Local>>, b: Option<DateTime<Local>>) -> bool {
+        a.map(|date| date.date_naive()) == b.map(|date| date.date_naive())
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let line = parse_line("x 2026-10-01 2026-09-20 Call +home the bank @phone project:errands due:2026-10-02T14:30+02:00 pri:B t:2026-09-30").unwrap().unwrap();
+        let task = line.task;
+        assert_eq!(line.id, None);
+        assert_eq!(task.name, "Call the bank t:2026-09-30");
+        assert!(task.completed);
+        assert_eq!(
+            task.completed_date,
+            Some(start_of_day(NaiveDate::from_ymd_opt(2026, 10, 1).unwrap()))
+        );
+        assert_eq!(task.priority, PriorityLevel::Medium);
+        assert_eq!(task.tags, ["home", "@phone"]);
+        assert_eq!(task.project.as_deref(), Some("errands"));
+        assert_eq!(
+            task.due_date,
+            crate::dates::parse_due_date(
+                "2026-10-02 14:30 +02:00",
+                NaiveDate::from_ymd_opt(2026, 10, 1).unwrap()
+            )
+            .ok()
+        );
+
+        let task = parse_line("(B) 2026-09-20 Write report taskhero:7")
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.id, Some(TaskId(7)));
+        assert_eq!(task.task.priority, PriorityLevel::Medium);
+        assert!(!task.task.completed);
+        assert_eq!(
+            parse_line("(D) Someday").unwrap().unwrap().task.priority,
+            PriorityLevel::Low
+        );
+        assert_eq!(
+            parse_line("(a) lowercase is text")
+                .unwrap()
+                .unwrap()
+                .task
+                .name,
+            "(a) lowercase is text"
+        );
+
+        assert!(parse_line("   ").unwrap().is_none());
+        assert!(parse_line("(A) +only @tags").is_err());
+        assert!(parse_line("Task due:soon").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut store = TaskStore::new();
+        for (name, due_date, priority) in [
+            ("Write report", "2024-11-23", PriorityLevel::High),
+            ("Call", "2024-11-24 09:15:30", PriorityLevel::Medium),
+            ("Standup", "2024-11-25 14:30 -05:00", PriorityLevel::Low),
+            ("Someday", "", PriorityLevel::Low),
+        ] {
+            store
+                .create(NewTask {
+                    name: name.to_string(),
+                    description: String::new(),
+                    due_date: crate::dates::parse_due_date(due_date, store.clock().today()).ok(),
+                    priority,
+                    recurrence: None,
+                })
+                .unwrap();
+        }
+        let ids = store.tasks().ids();
+        store
+            .update(
+                ids[0],
+                TaskUpdate {
+                    tags: Some(vec!["backend".to_string(), "@office".to_string()]),
+                    project: Some(Some("Q4".to_string())),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.complete(ids[1]).unwrap();
+
+        let text = export(&store);
+        assert!(text.starts_with(
+            "(A) Write report +backend @office project:Q4 due:2024-11-23 taskhero:1\n"
+        ));
+        let file = parse(&text);
+        assert!(file.skipped.is_empty());
+        for (line, task) in file.lines.iter().zip(store.tasks()) {
+            assert_eq!(line.id, Some(task.id));
+            assert_eq!(line.task.name, task.name);
+            assert_eq!(line.task.due_date, task.due_date);
+            assert_eq!(line.task.priority, task.priority);
+            assert_eq!(line.task.tags, task.tags);
+            assert_eq!(line.task.project, task.project);
+            assert_eq!(line.task.completed, task.completed);
+            assert!(same_completion_day(
+                line.task.completed_date,
+                task.completed_date
+            ));
+        }
+
+        // syncing an unchanged file changes nothing, and importing it only finds duplicates
+        assert_eq!(
+            sync(&mut store, parse(&text), true).unwrap(),
+            SyncSummary::default()
+        );
+        assert_eq!(export(&store), text);
+        assert_eq!(import(&mut store, parse(&text)).duplicates, 4);
+    }
+
+    #[test]
+    fn test_name_words_are_escaped() {
+        let mut store = TaskStore::new();
+        for name in [
+            "2024-11-23 Reply to +1 @home project:x due:soon pri:z taskhero:me",
+            "\\server backup +",
+            "x marks the spot",
+        ] {
+            store
+                .create(NewTask {
+                    name: name.to_string(),
+                    description: String::new(),
+                    due_date: None,
+                    priority: PriorityLevel::High,
+                    recurrence: None,
+                })
+                .unwrap();
+        }
+        let text = export(&store);
+        assert!(text.starts_with("(A) \\2024-11-23 Reply to \\+1 \\@home \\project:x \\due:soon \\pri:z \\taskhero:me taskhero:1\n"));
+        let file = parse(&text);
+        assert!(file.skipped.is_empty());
+        for (line, task) in file.lines.iter().zip(store.tasks()) {
+            assert_eq!(line.task.name, task.name);
+            assert!(line.task.tags.is_empty());
+            assert_eq!(line.task.project, None);
+            assert_eq!(line.task.due_date, None);
+            assert_eq!(line.task.priority, PriorityLevel::High);
+        }
+
+        // syncing the exported file leaves the tasks as they are
+        assert_eq!(
+            sync(&mut store, parse(&text), true).unwrap(),
+            SyncSummary::default()
+        );
+        assert_eq!(export(&store), text);
+    }
+
+    #[test]
+    fn test_sync_applies_edits() {
+        let mut store = TaskStore::new();
+        for name in ["Write report", "Call the bank", "Old task"] {
+            store
+                .create(NewTask {
+                    name: name.to_string(),
+                    description: "kept".to_string(),
+                    due_date: None,
+                    priority: PriorityLevel::Low,
+                    recurrence: None,
+                })
+                .unwrap();
+        }
+        let text = "x 2026-10-01 Write the final report pri:A taskhero:1\nCall the bank +home project:errands\nNew task\n";
+        let mut merged = TaskStore::new();
+        merged.replace_tasks(store.tasks().as_slice().to_vec());
+        assert_eq!(
+            sync(&mut merged, parse(text), false).unwrap(),
+            SyncSummary {
+                added: 1,
+                updated: 2,
+                removed: 0
+            }
+        );
+        assert!(merged.task(TaskId(3)).is_some());
+
+        let summary = sync(&mut store, parse(text), true).unwrap();
+        assert_eq!(
+            summary,
+            SyncSummary {
+                added: 1,
+                updated: 2,
+                removed: 1
+            }
+        );
+
+        let report = store.task(TaskId(1)).unwrap();
+        assert_eq!(report.name, "Write the final report");
+        assert_eq!(report.description, "kept");
+        assert!(report.completed && report.completed_date.is_some());
+        assert_eq!(store.gamification().points, 30);
+        assert_eq!(store.task(TaskId(2)).unwrap().tags, ["home"]);
+        assert_eq!(
+            store.task(TaskId(2)).unwrap().project.as_deref(),
+            Some("errands")
+        );
+        assert!(store.task(TaskId(3)).is_none());
+        assert_eq!(store.task(TaskId(4)).unwrap().name, "New task");
+
+        // a line that can't be read would lose its task, so nothing is synced
+        assert_eq!(
+            sync(&mut store, parse("New task due:someday\n"), true).unwrap_err(),
+            "line 1: invalid due date \"someday\""
+        );
+        assert_eq!(store.tasks().len(), 3);
+    }
+
+    #[test]
+    fn test_sync_keeps_subtasks() {
+        let mut store = TaskStore::new();
+        let new_task = |name: &str| NewTask {
+            name: name.to_string(),
+            description: String::new(),
+            due_date: None,
+            priority: PriorityLevel::Low,
+            recurrence: None,
+        };
+        let trip = store.create(new_task("Plan the trip")).unwrap();
+        let flights = store
+            .create_subtask(trip, new_task("Book flights"))
+            .unwrap();
+
+        // the parent is completed after its subtask even though its line comes first
+        let text = "x 2026-10-01 Plan the trip taskhero:1\nx 2026-10-01 Book flights taskhero:2\n";
+        assert_eq!(sync(&mut store, parse(text), true).unwrap().updated, 2);
+        assert!(store.task(trip).unwrap().completed && store.task(flights).unwrap().completed);
+
+        // deleting the parent's line keeps the subtask that still has one
+        let summary = sync(
+            &mut store,
+            parse("x 2026-10-01 Book flights taskhero:2\n"),
+            true,
+        )
+        .unwrap();
+        assert_eq!(summary.removed, 1);
+        assert_eq!(store.task(flights).unwrap().parent, None);
+    }
+}
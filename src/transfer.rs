@@ -0,0 +1,782 @@
+//! JSON export and import of the whole task database.
+//!
+//! An export file holds every task plus the gamification state:
+//!
+//! ```json
+//! {
+//!   "format": "taskhero",
+//!   "version": 1,
+//!   "exported_at": "2024-11-22T18:45:10-05:00",
+//!   "gamification": {
+//!     "points": 30, "bronze_goal": 5, "silver_goal": 10, "gold_goal": 20,
+//!     "achievement_message": "...", "daily_reward": 0,
+//!     "daily_reward_message": "...", "weekly_challenge_message": "..."
+//!   },
+//!   "tasks": [
+//!     {
+//!       "id": 1, "name": "Write report", "description": "Quarterly numbers",
+//!       "due_date": "2024-11-23 14:30", "priority": "High",
+//!       "completed": true, "completed_date": "2024-11-22T18:45:10-05:00"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! On import only `name` is required for a task. `due_date` accepts anything the add form
+//! accepts, `priority` is low, medium or high in any case, `difficulty` is easy, normal or hard
+//! in any case, and `completed_date` is RFC 3339.
+//! A subtask's `parent` and the prerequisites listed in `depends_on` must be the `id`s of other
+//! tasks in the file, and the prerequisites can't wait for each other in a loop. Imported tasks
+//! always get new IDs, so IDs of deleted tasks are never reused, and both a merge and a replace
+//! keep subtasks under their parent and dependencies in place.
+//! From `gamification` only the goals, including `project_goals`, are read; points, rewards and messages are recomputed
+//! from the tasks. Every record is checked before anything is changed, so an import either
+//! applies completely or reports every invalid record and leaves the tasks untouched.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Deserialize as _;
+use serde_json::Value;
+
+use crate::dates::{self, DueDate};
+use crate::gamification::{ChecklistItem, Gamification, PriorityLevel, Task};
+use crate::recurrence::Recurrence;
+use crate::scoring::Difficulty;
+use crate::store::TaskStore;
+use crate::task_list::TaskId;
+
+/// Value of the `format` field of an export file
+pub const FORMAT: &str = "taskhero";
+
+/// Version of the export format written by this build
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How an import combines the file with the existing tasks
+pub enum ImportMode {
+    #[default]
+    Merge, // add the tasks that are not already there, keep the current goals
+    Replace, // replace every task and the goals with the ones in the file
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// ImportSummary struct with what an import changed
+pub struct ImportSummary {
+    pub added: usize,      // tasks added
+    pub duplicates: usize, // tasks skipped because they already exist
+    pub removed: usize,    // tasks removed by a replace
+    pub goals_changed: bool,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Imported {} task(s)", self.added)?;
+        if self.duplicates > 0 {
+            write!(f, ", skipped {} duplicate(s)", self.duplicates)?;
+        }
+        if self.removed > 0 {
+            write!(f, ", replaced {} existing task(s)", self.removed)?;
+        }
+        if self.goals_changed {
+            write!(f, ", updated the goals")?;
+        }
+        write!(f, ".")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A problem with one record of an import file
+pub struct RecordError {
+    pub location: String, // the offending record, such as `tasks[3] (#7 "Write report")`
+    pub message: String,
+}
+
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Errors returned when a file can't be imported
+pub enum ImportError {
+    Json(String),              // the file is not valid JSON
+    Format(String),            // the file is JSON but not a TaskHero export
+    Invalid(Vec<RecordError>), // one or more records are invalid
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Json(message) => write!(f, "The file is not valid JSON: {message}"),
+            ImportError::Format(message) => {
+                write!(f, "The file is not a TaskHero export: {message}")
+            }
+            ImportError::Invalid(errors) => {
+                write!(f, "The file has {} invalid record(s):", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Bronze, silver and gold goals, and the project goals, read from an import file
+type Goals = (u32, u32, u32, BTreeMap<String, u32>);
+
+#[derive(serde::Serialize)]
+/// On-disk form of an export file
+struct ExportFile<'a> {
+    format: &'static str,
+    version: u32,
+    exported_at: DateTime<Local>,
+    gamification: &'a Gamification,
+    tasks: &'a [Task],
+}
+
+#[derive(serde::Deserialize)]
+/// A task as read from an import file, before validation
+struct ImportedTask {
+    #[serde(default)]
+    id: Option<u64>,
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    due_date: Option<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    completed: bool,
+    #[serde(default)]
+    completed_date: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    recurrence: Option<String>,
+    #[serde(default)]
+    parent: Option<u64>,
+    #[serde(default)]
+    checklist: Vec<ChecklistItem>,
+    #[serde(default)]
+    auto_complete: bool,
+    #[serde(default)]
+    depends_on: Vec<u64>,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    effort: Option<u32>,
+    #[serde(default)]
+    difficulty: Option<String>,
+    #[serde(default)]
+    points_override: Option<u32>,
+}
+
+#[derive(serde::Deserialize)]
+/// The goals as read from the gamification record of an import file
+struct ImportedGoals {
+    bronze_goal: u32,
+    silver_goal: u32,
+    gold_goal: u32,
+    #[serde(default)]
+    project_goals: BTreeMap<String, u32>,
+}
+
+/// Helper function to export every task and the gamification state as JSON
+pub fn export(store: &TaskStore) -> String {
+    let file = ExportFile {
+        format: FORMAT,
+        version: VERSION,
+        exported_at: Local::now(),
+        gamification: store.gamification(),
+        tasks: store.tasks().as_slice(),
+    };
+    serde_json::to_string_pretty(&file).expect("tasks can always be serialized")
+}
+
+/// Helper function to import an export file into the store
+///
+/// # Arguments
+///
+/// * `store` - The store to import into
+/// * `json` - The contents of the export file
+/// * `mode` - Whether to merge the file into the existing tasks or replace them
+///
+/// # Returns
+///
+/// What the import changed, or an error that lists every invalid record. Nothing is changed
+/// when an error is returned.
+pub fn import(
+    store: &mut TaskStore,
+    json: &str,
+    mode: ImportMode,
+) -> Result<ImportSummary, ImportError> {
+    let file: Value =
+        serde_json::from_str(json).map_err(|err| ImportError::Json(err.to_string()))?;
+    let (tasks, goals) = parse_file(&file, store.clock().today())?;
+
+    let mut summary = ImportSummary::default();
+    match mode {
+        ImportMode::Replace => {
+            summary.removed = store.tasks().len();
+            summary.added = tasks.len();
+            store.replace_tasks(tasks);
+            if let Some((bronze, silver, gold, project_goals)) = goals {
+                let gamification = store.gamification();
+                summary.goals_changed = (
+                    gamification.bronze_goal,
+                    gamification.silver_goal,
+                    gamification.gold_goal,
+                ) != (bronze, silver, gold)
+                    || gamification.project_goals != project_goals;
+                store
+                    .set_goals(bronze, silver, gold)
+                    .expect("goals were validated");
+                let old_projects: Vec<String> =
+                    store.gamification().project_goals.keys().cloned().collect();
+                for project in old_projects {
+                    store
+                        .set_project_goal(&project, 0)
+                        .expect("goals were validated");
+                }
+                for (project, goal) in project_goals {
+                    store
+                        .set_project_goal(&project, goal)
+                        .expect("goals were validated");
+                }
+            }
+        }
+        ImportMode::Merge => {
+            let mut existing: HashMap<_, _> = store
+                .tasks()
+                .iter()
+                .map(|task| (duplicate_key(task), task.id))
+                .collect();
+            let mut new_ids = HashMap::new(); // ID in the file -> ID in the store
+            let mut subtasks = Vec::new();
+            let mut dependencies = Vec::new();
+            for mut task in tasks {
+                let file_id = task.id;
+                let key = duplicate_key(&task);
+                if let Some(&id) = existing.get(&key) {
+                    new_ids.insert(file_id, id);
+                    summary.duplicates += 1;
+                    continue;
+                }
+                let parent = task.parent.take();
+                let depends_on = std::mem::take(&mut task.depends_on);
+                let id = store.insert(task);
+                dependencies.extend(
+                    depends_on
+                        .into_iter()
+                        .map(|prerequisite| (id, prerequisite)),
+                );
+                existing.insert(key, id);
+                new_ids.insert(file_id, id);
+                if let Some(parent) = parent {
+                    subtasks.push((id, parent));
+                }
+                summary.added += 1;
+            }
+            // Link the subtasks once every parent has its new ID
+            for (id, parent) in subtasks {
+                if let Some(&parent) = new_ids.get(&parent) {
+                    // a cycle of parents in the file leaves the task at the top level
+                    let _ = store.set_parent(id, Some(parent));
+                }
+            }
+            for (id, prerequisite) in dependencies {
+                if let Some(&prerequisite) = new_ids.get(&prerequisite) {
+                    // a task that already exists may close a loop, which is left out
+                    let _ = store.add_dependency(id, prerequisite);
+                }
+            }
+        }
+    }
+    store.evaluate();
+    Ok(summary)
+}
+
+/// Helper function to identify tasks that are the same for duplicate detection:
+/// the same name, ignoring case and surrounding spaces, and the same due date
+pub(crate) fn duplicate_key(task: &Task) -> (String, Option<DueDate>) {
+    (task.name.trim().to_lowercase(), task.due_date)
+}
+
+/// Helper function to check an export file and read its tasks and goals
+fn parse_file(file: &Value, today: NaiveDate) -> Result<(Vec<Task>, Option<Goals>), ImportError> {
+    let object = file
+        .as_object()
+        .ok_or_else(|| ImportError::Format("expected a JSON object".to_string()))?;
+    match object.get("format").and_then(Value::as_str) {
+        Some(FORMAT) => {}
+        _ => {
+            return Err(ImportError::Format(format!(
+                "the \"format\" field must be \"{FORMAT}\""
+            )))
+        }
+    }
+    match object.get("version").and_then(Value::as_u64) {
+        Some(version) if version <= VERSION as u64 => {}
+        Some(version) => {
+            return Err(ImportError::Format(format!(
+                "format version {version} is newer than this build supports ({VERSION})"
+            )))
+        }
+        None => {
+            return Err(ImportError::Format(
+                "the \"version\" field is missing".to_string(),
+            ))
+        }
+    }
+    let records = object
+        .get("tasks")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ImportError::Format("the \"tasks\" field must be a list".to_string()))?;
+
+    let mut errors = Vec::new();
+    let mut tasks = Vec::new();
+    let mut positions = Vec::new(); // index of each task's record
+    let mut seen_ids: HashMap<u64, usize> = HashMap::new();
+    for (index, record) in records.iter().enumerate() {
+        let location = record_location(index, record);
+        match parse_task(record, today) {
+            Ok(task) => {
+                if task.id.is_assigned() {
+                    if let Some(first) = seen_ids.insert(task.id.0, index) {
+                        errors.push(RecordError {
+                            location,
+                            message: format!(
+                                "duplicate id {}, also used by tasks[{first}]",
+                                task.id
+                            ),
+                        });
+                        continue;
+                    }
+                }
+                tasks.push(task);
+                positions.push(index);
+            }
+            Err(message) => errors.push(RecordError { location, message }),
+        }
+    }
+
+    let depends_on: HashMap<TaskId, &[TaskId]> = tasks
+        .iter()
+        .map(|task| (task.id, task.depends_on.as_slice()))
+        .collect();
+    for (task, &index) in tasks.iter().zip(&positions) {
+        if let Some(parent) = task
+            .parent
+            .filter(|parent| !seen_ids.contains_key(&parent.0))
+        {
+            errors.push(RecordError {
+                location: record_location(index, &records[index]),
+                message: format!("parent {parent} is not in the file"),
+            });
+        }
+        for prerequisite in task
+            .depends_on
+            .iter()
+            .filter(|prerequisite| !seen_ids.contains_key(&prerequisite.0))
+        {
+            errors.push(RecordError {
+                location: record_location(index, &records[index]),
+                message: format!("depends_on {prerequisite}, which is not in the file"),
+            });
+        }
+        if waits_for_itself(&depends_on, task.id) {
+            errors.push(RecordError {
+                location: record_location(index, &records[index]),
+                message: "depends_on leads back to the task itself".to_string(),
+            });
+        }
+    }
+
+    let goals = match object.get("gamification") {
+        None | Some(Value::Null) => None,
+        Some(record) => match parse_goals(record) {
+            Ok(goals) => Some(goals),
+            Err(message) => {
+                errors.push(RecordError {
+                    location: "gamification".to_string(),
+                    message,
+                });
+                None
+            }
+        },
+    };
+
+    if errors.is_empty() {
+        Ok((tasks, goals))
+    } else {
+        Err(ImportError::Invalid(errors))
+    }
+}
+
+/// Helper function to check whether a task's prerequisites lead back to the task itself
+///
+/// # Arguments
+///
+/// * `depends_on` - The prerequisites of every task in the file
+/// * `id` - The ID of the task to check
+fn waits_for_itself(depends_on: &HashMap<TaskId, &[TaskId]>, id: TaskId) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    let mut pending = vec![id];
+    while let Some(next) = pending.pop() {
+        for &prerequisite in depends_on.get(&next).copied().unwrap_or_default() {
+            if prerequisite == id {
+                return true;
+            }
+            if seen.insert(prerequisite) {
+                pending.push(prerequisite);
+            }
+        }
+    }
+    false
+}
+
+/// Helper function to describe where a task record is, with its ID and name when it has them
+fn record_location(index: usize, record: &Value) -> String {
+    let mut location = format!("tasks[{index}]");
+    if let Some(id) = record.get("id").and_then(Value::as_u64) {
+        location.push_str(&format!(" (#{id}"));
+        if let Some(name) = record.get("name").and_then(Value::as_str) {
+            location.push_str(&format!(" {name:?}"));
+        }
+        location.push(')');
+    } else if let Some(name) = record.get("name").and_then(Value::as_str) {
+        location.push_str(&format!(" ({name:?})"));
+    }
+    location
+}
+
+/// Helper function to read and validate one task record
+fn parse_task(record: &Value, today: NaiveDate) -> Result<Task, String> {
+    let imported = ImportedTask::deserialize(record).map_err(|err| err.to_string())?;
+    if imported.name.trim().is_empty() {
+        return Err("the name is empty".to_string());
+    }
+    let due_date = match imported.due_date.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(text) => Some(
+            dates::parse_due_date(text, today).map_err(|err| format!("invalid due_date: {err}"))?,
+        ),
+    };
+    let priority = match imported.priority.as_deref() {
+        None => PriorityLevel::Low,
+        Some(text) => text
+            .parse()
+            .map_err(|err| format!("invalid priority: {err}"))?,
+    };
+    let completed_date = match imported.completed_date.as_deref() {
+        None => None,
+        Some(text) => Some(
+            DateTime::parse_from_rfc3339(text)
+                .map_err(|err| format!("invalid completed_date {text:?}: {err}"))?
+                .with_timezone(&Local),
+        ),
+    };
+    let recurrence = match imported.recurrence.as_deref() {
+        None => None,
+        Some(rule) => {
+            Some(Recurrence::from_rrule(rule).map_err(|err| format!("invalid recurrence: {err}"))?)
+        }
+    };
+    let difficulty = match imported.difficulty.as_deref() {
+        None => Difficulty::Normal,
+        Some(text) => text
+            .parse()
+            .map_err(|err| format!("invalid difficulty: {err}"))?,
+    };
+    if completed_date.is_some() && !imported.completed {
+        return Err("completed_date is set but the task is not completed".to_string());
+    }
+
+    Ok(Task {
+        id: TaskId(imported.id.unwrap_or(0)),
+        description: imported.description,
+        due_date,
+        completed: imported.completed,
+        completed_date,
+        tags: imported.tags,
+        recurrence,
+        parent: imported.parent.map(TaskId),
+        checklist: imported.checklist,
+        auto_complete: imported.auto_complete,
+        depends_on: imported.depends_on.into_iter().map(TaskId).collect(),
+        project: crate::tags::normalize_project(imported.project),
+        effort: imported.effort,
+        difficulty,
+        points_override: imported.points_override,
+        ..Task::new(imported.name, priority)
+    })
+}
+
+/// Helper function to read and validate the goals from the gamification record
+fn parse_goals(record: &Value) -> Result<Goals, String> {
+    let goals = ImportedGoals::deserialize(record).map_err(|err| err.to_string())?;
+    if goals.bronze_goal == 0
+        || goals.bronze_goal >= goals.silver_goal
+        || goals.silver_goal >= goals.gold_goal
+    {
+        return Err(format!(
+            "goals must increase from bronze to gold (got {}/{}/{})",
+            goals.bronze_goal, goals.silver_goal, goals.gold_goal
+        ));
+    }
+    if goals
+        .project_goals
+        .keys()
+        .any(|project| project.trim().is_empty())
+    {
+        return Err("a project goal has no project name".to_string());
+    }
+    Ok((
+        goals.bronze_goal,
+        goals.silver_goal,
+        goals.gold_goal,
+        goals.project_goals,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{NewTask, TaskUpdate};
+
+    fn store_with(names: &[&str]) -> TaskStore {
+        let mut store = TaskStore::new();
+        for name in names {
+            store
+                .create(NewTask {
+                    name: name.to_string(),
+                    description: String::new(),
+                    due_date: None,
+                    priority: PriorityLevel::Medium,
+                    recurrence: None,
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_export_and_replace_round_trip() {
+        let mut store = store_with(&["Write report", "Email team"]);
+        let id = store.tasks().ids()[1];
+        store.complete(id).unwrap();
+        store.set_goals(2, 4, 6).unwrap();
+        store
+            .update(
+                id,
+                TaskUpdate {
+                    project: Some(Some("Team".to_string())),
+                    tags: Some(vec!["email".to_string()]),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.set_project_goal("Team", 3).unwrap();
+        let json = export(&store);
+
+        let mut other = store_with(&["Something else"]);
+        other.set_project_goal("Old", 1).unwrap();
+        let summary = import(&mut other, &json, ImportMode::Replace).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 2,
+                duplicates: 0,
+                removed: 1,
+                goals_changed: true
+            }
+        );
+        // the tasks get fresh IDs, so #1 of the replaced task isn't reused
+        assert_eq!(other.tasks().ids(), [TaskId(2), TaskId(3)]);
+        let task = &other.tasks().as_slice()[1];
+        assert_eq!(task.name, "Email team");
+        assert!(task.completed);
+        assert_eq!(
+            task.completed_date.map(|date| date.timestamp()),
+            store
+                .task(id)
+                .unwrap()
+                .completed_date
+                .map(|date| date.timestamp())
+        );
+        assert_eq!(other.gamification().gold_goal, 6);
+        assert_eq!(other.gamification().points, 20);
+        assert_eq!(
+            (task.project.as_deref(), task.tags.as_slice()),
+            (Some("Team"), ["email".to_string()].as_slice())
+        );
+        assert_eq!(
+            other.gamification().project_goals,
+            BTreeMap::from([("Team".to_string(), 3)])
+        );
+    }
+
+    #[test]
+    fn test_replace_doesnt_reuse_paid_ids() {
+        let mut store = store_with(&["Write report"]);
+        store.complete(TaskId(1)).unwrap();
+        store.evaluate();
+        let json = export(&store);
+        assert_eq!(store.gamification().points, 20);
+
+        // the completed #1 in the file replaces #1, which was already paid, so it isn't paid again
+        import(&mut store, &json, ImportMode::Replace).unwrap();
+        assert_eq!(store.tasks().ids(), [TaskId(2)]);
+        assert_eq!(store.gamification().points, 20);
+        assert_eq!(store.ledger().paid("task #2"), 20);
+        import(&mut store, &json, ImportMode::Replace).unwrap();
+        assert_eq!(store.gamification().points, 20);
+        // and reopening it takes back only its own points
+        store.uncomplete(TaskId(3)).unwrap();
+        store.evaluate();
+        assert_eq!(store.gamification().points, 0);
+    }
+
+    #[test]
+    fn test_merge_skips_duplicates() {
+        let json = export(&store_with(&["Write report", "Email team"]));
+        let mut store = store_with(&["  write REPORT "]);
+        let summary = import(&mut store, &json, ImportMode::Merge).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 1,
+                duplicates: 1,
+                removed: 0,
+                goals_changed: false
+            }
+        );
+        assert_eq!(store.tasks().len(), 2);
+        // Merged tasks get fresh IDs instead of the ones in the file
+        assert_eq!(store.tasks().ids(), [TaskId(1), TaskId(2)]);
+    }
+
+    #[test]
+    fn test_merge_keeps_subtasks_under_their_parent() {
+        let mut source = store_with(&["Plan the trip"]);
+        let trip = source.tasks().ids()[0];
+        let flights = source
+            .create_subtask(
+                trip,
+                NewTask {
+                    name: "Book flights".to_string(),
+                    description: String::new(),
+                    due_date: None,
+                    priority: PriorityLevel::Medium,
+                    recurrence: None,
+                },
+            )
+            .unwrap();
+        source
+            .add_checklist_item(flights, "Compare prices")
+            .unwrap();
+        let json = export(&source);
+
+        let mut store = store_with(&["Groceries", "Laundry"]);
+        import(&mut store, &json, ImportMode::Merge).unwrap();
+        let tree: Vec<(usize, String, usize)> = store
+            .tasks()
+            .tree()
+            .into_iter()
+            .map(|(depth, task)| (depth, task.name.clone(), task.checklist.len()))
+            .collect();
+        assert_eq!(
+            tree[2..],
+            [
+                (0, "Plan the trip".to_string(), 0),
+                (1, "Book flights".to_string(), 1)
+            ]
+        );
+
+        // dependencies follow the tasks to their new IDs
+        let mut source = store_with(&["Design", "Build"]);
+        let [design, build] = source.tasks().ids()[..] else {
+            unreachable!()
+        };
+        source.add_dependency(build, design).unwrap();
+        let mut store = store_with(&["Groceries"]);
+        import(&mut store, &export(&source), ImportMode::Merge).unwrap();
+        assert_eq!(store.task(TaskId(3)).unwrap().depends_on, [TaskId(2)]);
+
+        let bad = json.replacen(&format!("\"parent\": {}", trip.0), "\"parent\": 42", 1);
+        let ImportError::Invalid(errors) = import(&mut store, &bad, ImportMode::Merge).unwrap_err()
+        else {
+            panic!("expected record errors");
+        };
+        assert_eq!(errors[0].message, "parent #42 is not in the file");
+    }
+
+    #[test]
+    fn test_invalid_records_are_reported_and_nothing_changes() {
+        let json = r#"{
+            "format": "taskhero", "version": 1,
+            "gamification": { "bronze_goal": 5, "silver_goal": 3, "gold_goal": 20 },
+            "tasks": [
+                { "id": 1, "name": "Fine" },
+                { "id": 7, "name": "Write report", "due_date": "sometime" },
+                { "name": "No priority", "priority": "urgent" },
+                { "id": 1, "name": "Copy" },
+                { "description": "no name" },
+                { "id": 9, "name": "Open", "completed_date": "2024-11-22T18:45:10-05:00" },
+                { "id": 10, "name": "Chicken", "depends_on": [11] },
+                { "id": 11, "name": "Egg", "depends_on": [10, 12] }
+            ]
+        }"#;
+        let mut store = store_with(&["Existing"]);
+        let ImportError::Invalid(errors) =
+            import(&mut store, json, ImportMode::Replace).unwrap_err()
+        else {
+            panic!("expected record errors");
+        };
+        let locations: Vec<_> = errors.iter().map(|error| error.location.as_str()).collect();
+        assert_eq!(
+            locations,
+            [
+                "tasks[1] (#7 \"Write report\")",
+                "tasks[2] (\"No priority\")",
+                "tasks[3] (#1 \"Copy\")",
+                "tasks[4]",
+                "tasks[5] (#9 \"Open\")",
+                "tasks[6] (#10 \"Chicken\")",
+                "tasks[7] (#11 \"Egg\")",
+                "tasks[7] (#11 \"Egg\")",
+                "gamification",
+            ]
+        );
+        assert!(errors[2].message.contains("duplicate id #1"));
+        assert!(errors[3].message.contains("missing field `name`"));
+        assert_eq!(store.tasks().len(), 1);
+    }
+
+    #[test]
+    fn test_other_files_are_rejected() {
+        let mut store = TaskStore::new();
+        assert!(matches!(
+            import(&mut store, "[1, 2", ImportMode::Merge),
+            Err(ImportError::Json(_))
+        ));
+        assert!(matches!(
+            import(&mut store, "{\"tasks\": []}", ImportMode::Merge),
+            Err(ImportError::Format(_))
+        ));
+        assert!(matches!(
+            import(
+                &mut store,
+                "{\"format\": \"taskhero\", \"version\": 2, \"tasks\": []}",
+                ImportMode::Merge
+            ),
+            Err(ImportError::Format(_))
+        ));
+    }
+}
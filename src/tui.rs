@@ -0,0 +1,794 @@
+//! Full-screen terminal UI.
+//!
+//! Mirrors the three panels of the GUI: the task list and task details on the left, the
+//! challenges in the middle and the achievements with bronze/silver/gold progress on the right.
+//! It only needs a terminal, so it also works over SSH on machines without a display.
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::dates;
+use crate::gamification::PriorityLevel;
+use crate::recurrence::Recurrence;
+use crate::store::{NewTask, TaskUpdate};
+use crate::tags;
+use crate::task_list::TaskId;
+use crate::TemplateApp;
+
+/// Labels of the task form fields, in the order Tab moves through them
+const FORM_FIELDS: [&str; 5] = ["Name", "Description", "Due Date", "Repeats", "Priority"];
+
+/// Index of the priority field in the task form
+const PRIORITY_FIELD: usize = 4;
+
+/// Form for adding a task or editing the selected one
+struct TaskForm {
+    editing: Option<TaskId>, // None when adding a new task
+    name: String,
+    description: String,
+    due_date: String,
+    recurrence: String, // empty for a one-off task
+    priority: PriorityLevel,
+    focus: usize,          // index into FORM_FIELDS
+    error: Option<String>, // shown when the form can't be saved
+}
+
+/// Implementation of TaskForm with a method to get the text field that has focus
+impl TaskForm {
+    /// Helper function to get the text field that has focus, if it is not the priority
+    fn focused_text(&mut self) -> Option<&mut String> {
+        match self.focus {
+            0 => Some(&mut self.name),
+            1 => Some(&mut self.description),
+            2 => Some(&mut self.due_date),
+            3 => Some(&mut self.recurrence),
+            _ => None,
+        }
+    }
+}
+
+/// Popups that take over the keyboard
+enum Popup {
+    Form(TaskForm),
+    ConfirmDelete(TaskId),
+    Report,
+}
+
+/// Tui struct with the app state and the state of the terminal UI
+pub struct Tui {
+    app: TemplateApp,
+    selected: usize, // index of the highlighted task in the task list, which shows subtasks under their parent
+    popup: Option<Popup>,
+    status: String,   // message shown in the bottom bar
+    needs_save: bool, // whether the app state changed since it was last saved
+    quit: bool,
+}
+
+/// Implementation of Tui with methods to handle keys and draw the panels
+impl Tui {
+    /// Helper function to create the terminal UI for an app state
+    pub fn new(mut app: TemplateApp) -> Self {
+        app.store_mut().evaluate();
+        Tui {
+            app,
+            selected: 0,
+            popup: None,
+            status: String::new(),
+            needs_save: false,
+            quit: false,
+        }
+    }
+
+    /// Helper function to get the app state
+    pub fn app(&self) -> &TemplateApp {
+        &self.app
+    }
+
+    /// Helper function to get the ID of the highlighted task
+    fn selected_id(&self) -> Option<TaskId> {
+        self.app
+            .store()
+            .tasks()
+            .tree()
+            .get(self.selected)
+            .map(|(_, task)| task.id)
+    }
+
+    /// Helper function to update the achievements after a change and mark the state for saving
+    fn changed(&mut self, status: String) {
+        self.app.update_achievements();
+        self.needs_save = true;
+        self.status = status;
+        let len = self.app.store().tasks().len();
+        self.selected = self.selected.min(len.saturating_sub(1));
+    }
+
+    /// Helper function to handle a key press
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key that was pressed
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+        match self.popup.take() {
+            Some(Popup::Form(form)) => self.handle_form_key(form, key),
+            Some(Popup::ConfirmDelete(id)) => {
+                if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    let name = self
+                        .app
+                        .store()
+                        .task(id)
+                        .map(|task| task.name.clone())
+                        .unwrap_or_default();
+                    if let Ok(task) = self
+                        .app
+                        .apply(format!("Delete task \"{name}\""), |store| store.delete(id))
+                    {
+                        self.changed(format!("Deleted task {id} \"{}\".", task.name));
+                    }
+                } else {
+                    self.status = String::from("Delete cancelled.");
+                }
+            }
+            Some(Popup::Report) => {}
+            None => self.handle_list_key(key),
+        }
+    }
+
+    /// Helper function to handle a key press in the task list
+    fn handle_list_key(&mut self, key: KeyEvent) {
+        let len = self.app.store().tasks().len();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(len.saturating_sub(1))
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => self.selected = len.saturating_sub(1),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(id) = self.selected_id() {
+                    let completed = !self
+                        .app
+                        .store()
+                        .tasks()
+                        .get(id)
+                        .is_some_and(|task| task.completed);
+                    let blocked_by = self.app.store().blocked_by(id);
+                    let name = self
+                        .app
+                        .store()
+                        .task(id)
+                        .map(|task| task.name.clone())
+                        .unwrap_or_default();
+                    let verb = if completed { "Complete" } else { "Reopen" };
+                    match self.app.apply(format!("{verb} task \"{name}\""), |store| {
+                        store.set_completed(id, completed)
+                    }) {
+                        Ok(()) if completed && !blocked_by.is_empty() => {
+                            let ids: Vec<String> =
+                                blocked_by.iter().map(TaskId::to_string).collect();
+                            self.changed(format!(
+                                "Completed task {id}, which was still waiting for {}.",
+                                ids.join(", ")
+                            ));
+                        }
+                        Ok(()) => {
+                            let verb = if completed { "Completed" } else { "Reopened" };
+                            self.changed(format!("{verb} task {id}."));
+                        }
+                        Err(err) => self.status = err.to_string(),
+                    }
+                }
+            }
+            KeyCode::Char('a') => {
+                self.popup = Some(Popup::Form(TaskForm {
+                    editing: None,
+                    name: String::new(),
+                    description: String::new(),
+                    due_date: String::new(),
+                    recurrence: String::new(),
+                    priority: PriorityLevel::Low,
+                    focus: 0,
+                    error: None,
+                }));
+            }
+            KeyCode::Char('e') => {
+                if let Some(task) = self
+                    .selected_id()
+                    .and_then(|id| self.app.store().tasks().get(id))
+                {
+                    self.popup = Some(Popup::Form(TaskForm {
+                        editing: Some(task.id),
+                        name: task.name.clone(),
+                        description: task.description.clone(),
+                        due_date: task
+                            .due_date
+                            .map(|due_date| due_date.to_string())
+                            .unwrap_or_default(),
+                        recurrence: task
+                            .recurrence
+                            .as_ref()
+                            .map(|rule| rule.to_string())
+                            .unwrap_or_default(),
+                        priority: task.priority,
+                        focus: 0,
+                        error: None,
+                    }));
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if let Some(id) = self.selected_id() {
+                    self.popup = Some(Popup::ConfirmDelete(id));
+                }
+            }
+            KeyCode::Char('r') => self.popup = Some(Popup::Report),
+            KeyCode::Char('u') => match self.app.undo() {
+                Some(description) => self.changed(format!("Undid: {description}.")),
+                None => self.status = String::from("Nothing to undo."),
+            },
+            KeyCode::Char('U') => match self.app.redo() {
+                Some(description) => self.changed(format!("Redid: {description}.")),
+                None => self.status = String::from("Nothing to redo."),
+            },
+            _ => {}
+        }
+    }
+
+    /// Helper function to handle a key press in the task form
+    fn handle_form_key(&mut self, mut form: TaskForm, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Tab | KeyCode::Down => form.focus = (form.focus + 1) % FORM_FIELDS.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                form.focus = (form.focus + FORM_FIELDS.len() - 1) % FORM_FIELDS.len()
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if form.focus == PRIORITY_FIELD => {
+                // cycle through the priority levels
+                form.priority = match (form.priority, key.code == KeyCode::Left) {
+                    (PriorityLevel::Low, false) | (PriorityLevel::High, true) => {
+                        PriorityLevel::Medium
+                    }
+                    (PriorityLevel::Medium, false) | (PriorityLevel::Low, true) => {
+                        PriorityLevel::High
+                    }
+                    (PriorityLevel::High, false) | (PriorityLevel::Medium, true) => {
+                        PriorityLevel::Low
+                    }
+                };
+            }
+            KeyCode::Char(c) => {
+                if let Some(text) = form.focused_text() {
+                    text.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = form.focused_text() {
+                    text.pop();
+                }
+            }
+            KeyCode::Enter => match self.save_form(&form) {
+                Ok(status) => {
+                    self.changed(status);
+                    return;
+                }
+                Err(err) => form.error = Some(err),
+            },
+            _ => {}
+        }
+        self.popup = Some(Popup::Form(form));
+    }
+
+    /// Helper function to add or update a task from the task form
+    ///
+    /// # Returns
+    ///
+    /// The status message, or an error to show in the form.
+    fn save_form(&mut self, form: &TaskForm) -> Result<String, String> {
+        let due_date = if form.due_date.trim().is_empty() {
+            None
+        } else {
+            Some(
+                dates::parse_due_date(&form.due_date, self.app.clock().today())
+                    .map_err(|err| err.to_string())?,
+            )
+        };
+        let recurrence = Recurrence::parse_optional(&form.recurrence)?;
+        match form.editing {
+            Some(id) => {
+                let update = TaskUpdate {
+                    name: Some(form.name.clone()),
+                    description: Some(form.description.clone()),
+                    due_date: Some(due_date),
+                    priority: Some(form.priority),
+                    recurrence: Some(recurrence),
+                    ..Default::default()
+                };
+                self.app
+                    .apply(format!("Edit task \"{}\"", form.name), |store| {
+                        store.update(id, update)
+                    })
+                    .map_err(|err| err.to_string())?;
+                Ok(format!("Updated task {id}."))
+            }
+            None => {
+                let new_task = NewTask {
+                    name: form.name.clone(),
+                    description: form.description.clone(),
+                    due_date,
+                    priority: form.priority,
+                    recurrence,
+                };
+                let id = self
+                    .app
+                    .apply(format!("Add task \"{}\"", form.name), |store| {
+                        store.create(new_task)
+                    })
+                    .map_err(|err| err.to_string())?;
+                self.selected = self.app.store().tasks().len() - 1;
+                Ok(format!("Added task {id}."))
+            }
+        }
+    }
+
+    /// Helper function to draw the terminal UI
+    pub fn draw(&self, frame: &mut Frame<'_>) {
+        let [top_bar, panels, bottom_bar] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [left, center, right] = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+        ])
+        .areas(panels);
+
+        frame.render_widget(Line::from("TaskHero").bold().reversed(), top_bar);
+        self.draw_left_panel(frame, left);
+        self.draw_central_panel(frame, center);
+        self.draw_right_panel(frame, right);
+
+        let help =
+            "↑/↓ move  space complete  a add  e edit  d delete  u/U undo/redo  r report  q quit";
+        let bottom = if self.status.is_empty() {
+            help.to_string()
+        } else {
+            format!("{}  |  {help}", self.status)
+        };
+        frame.render_widget(Line::from(bottom).dim(), bottom_bar);
+
+        match &self.popup {
+            Some(Popup::Form(form)) => self.draw_form(frame, form),
+            Some(Popup::ConfirmDelete(id)) => {
+                let area = popup_area(frame.area(), 50, 3);
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(match self.app.store().descendants(*id).len() {
+                        0 => format!("Delete task {id}? (y/n)"),
+                        subtasks => format!("Delete task {id} and {subtasks} subtask(s)? (y/n)"),
+                    })
+                    .block(Block::bordered().title("Confirm")),
+                    area,
+                );
+            }
+            Some(Popup::Report) => {
+                let report = self.app.store().report(self.app.clock().today());
+                let lines: Vec<Line<'_>> = report.lines().into_iter().map(Line::from).collect();
+                let area = popup_area(frame.area(), 50, lines.len() as u16 + 2);
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(lines)
+                        .block(Block::bordered().title("Tasks Report (any key to close)")),
+                    area,
+                );
+            }
+            None => {}
+        }
+    }
+
+    /// Helper function to draw the task list and the details of the highlighted task
+    fn draw_left_panel(&self, frame: &mut Frame<'_>, area: Rect) {
+        let [list_area, details_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(7)]).areas(area);
+        let store = self.app.store();
+        let tasks = store.tasks();
+
+        let items: Vec<ListItem<'_>> = tasks
+            .tree()
+            .into_iter()
+            .map(|(depth, task)| {
+                let (done, total) = task.checklist_progress();
+                let progress = if total > 0 {
+                    format!(" ({done}/{total})")
+                } else {
+                    String::new()
+                };
+                let blocked = if !task.completed && store.is_blocked(task.id) {
+                    " (blocked)"
+                } else {
+                    ""
+                };
+                let item = ListItem::new(format!(
+                    "{}[{}] {}{progress}{blocked}",
+                    "  ".repeat(depth),
+                    if task.completed { "x" } else { " " },
+                    task.name
+                ));
+                if task.completed {
+                    item.dim()
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let mut list_state =
+            ListState::default().with_selected((!tasks.is_empty()).then_some(self.selected));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(format!("Tasks ({})", tasks.len())))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            list_area,
+            &mut list_state,
+        );
+
+        let details = match self.selected_id().and_then(|id| tasks.get(id)) {
+            Some(task) => vec![
+                Line::from({
+                    let mut line = format!("Task {}: {}", task.id, task.name);
+                    if let Some(project) = &task.project {
+                        line.push_str(&format!("  Project: {project}"));
+                    }
+                    if !task.tags.is_empty() {
+                        line.push_str(&format!("  {}", tags::format_tags(&task.tags)));
+                    }
+                    line
+                }),
+                Line::from(format!("Description: {}", task.description)),
+                Line::from(format!(
+                    "Due Date: {}{}",
+                    task.due_date
+                        .map(|due_date| due_date.to_string())
+                        .unwrap_or_else(|| "None".to_string()),
+                    task.recurrence
+                        .as_ref()
+                        .map(|rule| format!(" (repeats {rule})"))
+                        .unwrap_or_default()
+                )),
+                Line::from({
+                    let mut line = format!("Priority: {:?}", task.priority);
+                    let subtasks: Vec<bool> = tasks
+                        .children(task.id)
+                        .map(|subtask| subtask.completed)
+                        .collect();
+                    if !subtasks.is_empty() {
+                        let done = subtasks.iter().filter(|&&completed| completed).count();
+                        line.push_str(&format!("  Subtasks: {done}/{}", subtasks.len()));
+                    }
+                    let (done, total) = task.checklist_progress();
+                    if total > 0 {
+                        line.push_str(&format!("  Checklist: {done}/{total}"));
+                    }
+                    line
+                }),
+                Line::from(match task.completed_date {
+                    Some(completed_date) => {
+                        format!("Completed: {}", completed_date.format("%Y-%m-%d %H:%M"))
+                    }
+                    None => "Not completed".to_string(),
+                }),
+            ],
+            None => vec![Line::from("Press a to add a task.")],
+        };
+        frame.render_widget(
+            Paragraph::new(details)
+                .wrap(Wrap { trim: true })
+                .block(Block::bordered().title("Details")),
+            details_area,
+        );
+    }
+
+    /// Helper function to draw the challenges
+    fn draw_central_panel(&self, frame: &mut Frame<'_>, area: Rect) {
+        let gamification = self.app.store().gamification();
+        let lines = vec![
+            Line::from("Daily Reward").bold(),
+            Line::from(gamification.daily_reward_message.as_str()),
+            Line::from(format!(
+                "Daily Reward Points: {}",
+                gamification.daily_reward
+            )),
+            Line::from(""),
+            Line::from("Weekly Challenge").bold(),
+            Line::from(gamification.weekly_challenge_message.as_str()),
+        ];
+        frame.render_widget(
+            Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+                Block::bordered().title(format!("Challenges (Points: {})", gamification.points)),
+            ),
+            area,
+        );
+    }
+
+    /// Helper function to draw the achievements and the progress toward the goals
+    fn draw_right_panel(&self, frame: &mut Frame<'_>, area: Rect) {
+        let block = Block::bordered().title("Achievements");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [message_area, bronze_area, silver_area, gold_area] = Layout::vertical([
+            Constraint::Min(2),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+        let gamification = self.app.store().gamification();
+        frame.render_widget(
+            Paragraph::new(gamification.achievement_message.as_str()).wrap(Wrap { trim: true }),
+            message_area,
+        );
+
+        let completed_tasks = self.app.store().query(|task| task.completed).count();
+        for (level, goal, color, goal_area) in [
+            (
+                "Bronze",
+                gamification.bronze_goal,
+                Color::Rgb(205, 127, 50),
+                bronze_area,
+            ),
+            ("Silver", gamification.silver_goal, Color::Gray, silver_area),
+            ("Gold", gamification.gold_goal, Color::Yellow, gold_area),
+        ] {
+            let progress = completed_tasks.min(goal as usize);
+            frame.render_widget(
+                Gauge::default()
+                    .gauge_style(Style::new().fg(color))
+                    .ratio(progress as f64 / goal.max(1) as f64)
+                    .label(format!("{level} Goal: {progress}/{goal}")),
+                goal_area,
+            );
+        }
+    }
+
+    /// Helper function to draw the task form
+    fn draw_form(&self, frame: &mut Frame<'_>, form: &TaskForm) {
+        let priority = format!("< {:?} >", form.priority);
+        let values = [
+            form.name.as_str(),
+            form.description.as_str(),
+            form.due_date.as_str(),
+            form.recurrence.as_str(),
+            priority.as_str(),
+        ];
+        let mut lines: Vec<Line<'_>> = FORM_FIELDS
+            .iter()
+            .zip(values)
+            .enumerate()
+            .map(|(index, (label, value))| {
+                let line = Line::from(format!("{label:>12}: {value}"));
+                if index == form.focus {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+        lines.push(Line::from(""));
+        match &form.error {
+            Some(error) => lines.push(Line::from(error.as_str()).red()),
+            None => lines.push(Line::from("Tab next field  Enter save  Esc cancel").dim()),
+        }
+
+        let title = match form.editing {
+            Some(id) => format!("Edit Task {id}"),
+            None => "Add Task".to_string(),
+        };
+        let area = popup_area(frame.area(), 60, lines.len() as u16 + 2);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(title)),
+            area,
+        );
+    }
+}
+
+/// Helper function to center a popup of the given size in an area
+fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+/// Helper function to run the terminal UI until the user quits
+///
+/// # Arguments
+///
+/// * `app` - The app state to show
+/// * `save` - Called with the app state after every change; an error is shown in the bottom bar
+///
+/// # Returns
+///
+/// The final app state.
+pub fn run(
+    app: TemplateApp,
+    save: impl FnMut(&TemplateApp) -> Result<(), String>,
+) -> std::io::Result<TemplateApp> {
+    let mut terminal = ratatui::init(); // restores the terminal on panic
+    let result = event_loop(&mut terminal, Tui::new(app), save);
+    ratatui::restore();
+    result
+}
+
+/// Helper function to draw the terminal UI and handle key presses until the user quits
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    mut tui: Tui,
+    mut save: impl FnMut(&TemplateApp) -> Result<(), String>,
+) -> std::io::Result<TemplateApp> {
+    while !tui.quit {
+        terminal.draw(|frame| tui.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            tui.handle_key(key);
+        }
+        if tui.needs_save {
+            tui.needs_save = false;
+            if let Err(err) = save(&tui.app) {
+                tui.status = format!("Failed to save: {err}");
+            }
+        }
+    }
+    Ok(tui.app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn press(tui: &mut Tui, codes: &[KeyCode]) {
+        for code in codes {
+            tui.handle_key(KeyEvent::from(*code));
+        }
+    }
+
+    fn type_text(tui: &mut Tui, text: &str) {
+        for c in text.chars() {
+            press(tui, &[KeyCode::Char(c)]);
+        }
+    }
+
+    #[test]
+    fn test_add_complete_and_delete_with_keys() {
+        let mut tui = Tui::new(TemplateApp::default());
+        press(&mut tui, &[KeyCode::Char('a')]);
+        type_text(&mut tui, "Write report");
+        press(&mut tui, &[KeyCode::Tab, KeyCode::Tab]);
+        type_text(&mut tui, "someday");
+        press(&mut tui, &[KeyCode::Enter]);
+        // An unparseable due date keeps the form open
+        assert!(tui.app().store().tasks().is_empty());
+        assert!(matches!(&tui.popup, Some(Popup::Form(form)) if form.error.is_some()));
+
+        press(&mut tui, &[KeyCode::Backspace; 7]);
+        press(
+            &mut tui,
+            &[
+                KeyCode::Tab,
+                KeyCode::Tab,
+                KeyCode::Right,
+                KeyCode::Right,
+                KeyCode::Enter,
+            ],
+        );
+        let task = &tui.app().store().tasks().as_slice()[0];
+        assert_eq!(
+            (task.name.as_str(), task.priority),
+            ("Write report", PriorityLevel::High)
+        );
+        assert!(tui.needs_save);
+
+        press(&mut tui, &[KeyCode::Char(' ')]);
+        assert_eq!(tui.app().store().gamification().points, 30);
+
+        press(&mut tui, &[KeyCode::Char('d'), KeyCode::Char('n')]);
+        assert_eq!(tui.app().store().tasks().len(), 1);
+        press(&mut tui, &[KeyCode::Char('d'), KeyCode::Char('y')]);
+        assert!(tui.app().store().tasks().is_empty());
+
+        // undo brings the completed task back with its points, redo deletes it again
+        press(&mut tui, &[KeyCode::Char('u')]);
+        assert_eq!(tui.status, "Undid: Delete task \"Write report\".");
+        assert_eq!(tui.app().store().gamification().points, 30);
+        press(&mut tui, &[KeyCode::Char('u')]);
+        assert!(!tui.app().store().tasks().as_slice()[0].completed);
+        press(&mut tui, &[KeyCode::Char('U'), KeyCode::Char('U')]);
+        assert!(tui.app().store().tasks().is_empty());
+        press(&mut tui, &[KeyCode::Char('U')]);
+        assert_eq!(tui.status, "Nothing to redo.");
+    }
+
+    #[test]
+    fn test_subtasks_follow_their_parent() {
+        let mut app = TemplateApp::default();
+        let new_task = |name: &str| NewTask {
+            name: name.to_string(),
+            description: String::new(),
+            due_date: None,
+            priority: PriorityLevel::Low,
+            recurrence: None,
+        };
+        let trip = app.store_mut().create(new_task("Plan the trip")).unwrap();
+        app.store_mut().create(new_task("Groceries")).unwrap();
+        let flights = app
+            .store_mut()
+            .create_subtask(trip, new_task("Book flights"))
+            .unwrap();
+        let mut tui = Tui::new(app);
+
+        // the parent can't be completed before its subtask, which is listed right under it
+        press(&mut tui, &[KeyCode::Char(' ')]);
+        assert_eq!(
+            tui.status,
+            "Task #1 still has open subtasks or checklist items."
+        );
+        press(&mut tui, &[KeyCode::Down]);
+        assert_eq!(tui.selected_id(), Some(flights));
+        press(
+            &mut tui,
+            &[KeyCode::Char(' '), KeyCode::Up, KeyCode::Char(' ')],
+        );
+        assert!(tui.app().store().task(trip).unwrap().completed);
+    }
+
+    #[test]
+    fn test_draw_shows_the_three_panels() {
+        let mut app = TemplateApp::default();
+        app.store_mut()
+            .create(NewTask {
+                name: "Write report".to_string(),
+                description: "Quarterly numbers".to_string(),
+                due_date: None,
+                priority: PriorityLevel::Medium,
+                recurrence: None,
+            })
+            .unwrap();
+        let tui = Tui::new(app);
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| tui.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        for text in [
+            "[ ] Write report",
+            "Quarterly numbers",
+            "Challenges (Points: 0)",
+            "Bronze Goal: 0/5",
+            "Gold Goal: 0/20",
+        ] {
+            assert!(screen.contains(text), "{text:?} is not on screen");
+        }
+    }
+}